@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
-use strum::Display;
+use strum::{Display, EnumIter};
+
+use crate::config::ExportOptions;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
 pub enum MenuPreview {
@@ -8,6 +12,9 @@ pub enum MenuPreview {
   Constraints,
   Indexes,
   Policies,
+  ForeignKeys,
+  Triggers,
+  RoutineSource,
   Definition,
 }
 
@@ -15,6 +22,8 @@ pub enum MenuPreview {
 pub enum MenuItemKind {
   Table,
   View { materialized: bool },
+  ForeignTable,
+  Routine { procedure: bool },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -24,9 +33,21 @@ pub struct MenuTarget {
   pub kind: MenuItemKind,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize, EnumIter)]
 pub enum ExportFormat {
   CSV,
+  TSV,
+  JSON,
+  Markdown,
+  #[cfg(feature = "parquet")]
+  Parquet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Display, Deserialize)]
+pub enum StatusLevel {
+  Info,
+  Warning,
+  Error,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
@@ -38,11 +59,40 @@ pub enum Action {
   Quit,
   Refresh,
   Error(String),
+  // a message for the status line, which is cleared after a few seconds
+  StatusMessage(String, StatusLevel),
   Help,
+  RequestSwitchConnection,
+  // the name of a connection from the config
+  SwitchConnection(String),
+  RequestUseDatabase,
+  // the name of another database on the connected server
+  UseDatabase(String),
+  NewQueryTab,
+  CloseQueryTab,
+  NextQueryTab,
+  PreviousQueryTab,
+  // the number of a query tab, starting from 1
+  SelectQueryTab(usize),
   SubmitEditorQuery,
+  // like SubmitEditorQuery, but focuses the results once the query finishes
+  SubmitEditorQueryAndFocusData,
   SubmitEditorQueryBypassParser,
+  // shows the plan of the editor's query as a tree, running it too for ANALYZE
+  ExplainEditorQuery,
+  ExplainAnalyzeEditorQuery,
+  ExplainQuery(Vec<String>, bool), // (query_lines, analyze)
+  FormatQuery,
+  // comments the editor's selected lines with `--`, or uncomments them
+  ToggleComment,
+  FormatQueryLines(Vec<String>),
+  FormattedQuery(String),
   Query(Vec<String>, bool, bool), // (query_lines, execution_confirmed, bypass_parser)
   MenuPreview(MenuPreview, MenuTarget), // (preview, target)
+  CopyQualifiedName(MenuTarget),
+  EditRoutine(MenuTarget),
+  // shows the statements that recreate a table
+  ShowCreateTable(MenuTarget),
   QueryToEditor(Vec<String>),
   ClearHistory,
   AbortQuery,
@@ -51,16 +101,173 @@ pub enum Action {
   FocusHistory,
   FocusData,
   FocusFavorites,
+  ToggleLayoutSplit,
+  ToggleZoom,
   CycleFocusForwards,
   CycleFocusBackwards,
   LoadMenu,
+  LoadTableStats,
   CopyData(String),
+  // copies the value under the cursor in the results
+  CopyCell,
+  // copies the values of the row or column under the cursor in the results
+  CopyRow,
+  CopyColumn,
+  // copies a `column IN (...)` condition with the values of the column under the cursor
+  CopyInList,
+  // edits the value under the cursor in the results, with an UPDATE of its row
+  EditCell,
   RequestExportData(i64),
-  ExportData(ExportFormat),
+  ExportData(ExportFormat, ExportOptions),
+  // exports by re-running the query the results came from
+  StreamExportData(ExportFormat, ExportOptions, String),
+  ExportDataProgress(u64),
   ExportDataFinished,
   RequestYankAll(i64),
   YankAll,
+  NextPage,
+  PrevPage,
+  // fetches the next rows of a streamed query, to add to the ones shown
+  LoadMoreRows,
+  RequestCopyAs(i64),
+  // column name, column type, the field names of a composite column, value
+  RequestCellDetail(String, String, Option<Vec<String>>, String),
+  CopyAs(ExportFormat),
   RequestSaveFavorite(Vec<String>),
-  SaveFavorite(String, Vec<String>),
+  // folder, name, query lines
+  SaveFavorite(Option<String>, String, Vec<String>),
   DeleteFavorite(String),
+  RequestRenameFavorite(String),
+  // old name, new name. names include their folder, so this also moves favorites
+  RenameFavorite(String, String),
+  // runs a favorite, asking for the values of any placeholders first
+  RunFavorite(Vec<String>),
+  RequestExportFavorites,
+  RequestImportFavorites,
+  ExportFavorites(PathBuf),
+  ImportFavorites(PathBuf),
+  RequestLoadSqlFile,
+  RequestSaveSqlFile,
+  LoadSqlFile(PathBuf),
+  SaveSqlFile(PathBuf),
+  // the file the editor was just loaded from or saved to
+  SqlFileFinished(PathBuf),
+  // lists the actions that can be run by hand, to pick one to run
+  CommandPalette,
+}
+
+impl Action {
+  /// A name for the action as it's listed in the command palette, or `None`
+  /// for actions that aren't run by hand.
+  pub fn label(&self) -> Option<&'static str> {
+    let label = match self {
+      Action::Quit => "quit",
+      Action::Help => "show keybindings",
+      Action::CommandPalette => "show command palette",
+      Action::AbortQuery => "abort query",
+      Action::RequestSwitchConnection => "switch connection…",
+      Action::RequestUseDatabase => "switch database…",
+      Action::SubmitEditorQuery => "run query",
+      Action::SubmitEditorQueryAndFocusData => "run query and focus results",
+      Action::SubmitEditorQueryBypassParser => "run query without parsing it",
+      Action::ExplainEditorQuery => "explain query",
+      Action::ExplainAnalyzeEditorQuery => "explain analyze query",
+      Action::FormatQuery => "format query",
+      Action::ToggleComment => "toggle comment",
+      Action::RequestLoadSqlFile => "open sql file…",
+      Action::RequestSaveSqlFile => "save sql file…",
+      Action::FocusMenu => "focus menu",
+      Action::FocusEditor => "focus query editor",
+      Action::FocusData => "focus results",
+      Action::FocusHistory => "focus history",
+      Action::FocusFavorites => "focus favorites",
+      Action::CycleFocusForwards => "focus next pane",
+      Action::CycleFocusBackwards => "focus previous pane",
+      Action::NewQueryTab => "new query tab",
+      Action::CloseQueryTab => "close query tab",
+      Action::NextQueryTab => "next query tab",
+      Action::PreviousQueryTab => "previous query tab",
+      Action::ToggleLayoutSplit => "toggle editor beside or above results",
+      Action::ToggleZoom => "toggle zoom",
+      Action::LoadMenu => "reload schemas and tables",
+      Action::CopyCell => "copy cell",
+      Action::CopyRow => "copy row",
+      Action::CopyColumn => "copy column",
+      Action::CopyInList => "copy column as IN list",
+      Action::EditCell => "edit cell…",
+      Action::NextPage => "next page of results",
+      Action::PrevPage => "previous page of results",
+      Action::LoadMoreRows => "load more rows",
+      Action::ClearHistory => "clear query history",
+      Action::RequestExportFavorites => "export favorites…",
+      Action::RequestImportFavorites => "import favorites…",
+      _ => return None,
+    };
+    Some(label)
+  }
+
+  /// The actions the command palette lists, in the order it lists them. Ones
+  /// that need something to go with them, like a connection or a file, open
+  /// the prompt for it.
+  pub fn palette() -> Vec<Action> {
+    vec![
+      Action::SubmitEditorQuery,
+      Action::SubmitEditorQueryAndFocusData,
+      Action::SubmitEditorQueryBypassParser,
+      Action::ExplainEditorQuery,
+      Action::ExplainAnalyzeEditorQuery,
+      Action::AbortQuery,
+      Action::FormatQuery,
+      Action::ToggleComment,
+      Action::RequestLoadSqlFile,
+      Action::RequestSaveSqlFile,
+      Action::RequestSwitchConnection,
+      Action::RequestUseDatabase,
+      Action::LoadMenu,
+      Action::FocusMenu,
+      Action::FocusEditor,
+      Action::FocusData,
+      Action::FocusHistory,
+      Action::FocusFavorites,
+      Action::CycleFocusForwards,
+      Action::CycleFocusBackwards,
+      Action::NewQueryTab,
+      Action::CloseQueryTab,
+      Action::NextQueryTab,
+      Action::PreviousQueryTab,
+      Action::ToggleLayoutSplit,
+      Action::ToggleZoom,
+      Action::CopyCell,
+      Action::CopyRow,
+      Action::CopyColumn,
+      Action::CopyInList,
+      Action::EditCell,
+      Action::NextPage,
+      Action::PrevPage,
+      Action::LoadMoreRows,
+      Action::ClearHistory,
+      Action::RequestExportFavorites,
+      Action::RequestImportFavorites,
+      Action::Help,
+      Action::Quit,
+    ]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_palette_labels() {
+    let palette = Action::palette();
+    assert!(palette.iter().all(|action| action.label().is_some()), "every action in the palette needs a label");
+    let mut labels = palette.iter().filter_map(Action::label).collect::<Vec<_>>();
+    labels.sort();
+    labels.dedup();
+    assert_eq!(labels.len(), palette.len());
+    assert!(!palette.contains(&Action::CommandPalette));
+    assert_eq!(Action::Tick.label(), None);
+    assert_eq!(Action::Render.label(), None);
+  }
 }
@@ -1,7 +1,13 @@
+use std::{
+  collections::VecDeque,
+  path::PathBuf,
+  time::{Duration, Instant},
+};
+
 #[cfg(feature = "arboard")]
 use arboard::Clipboard;
 use color_eyre::eyre::{Result, eyre};
-use crossterm::event::{KeyEvent, MouseEvent, MouseEventKind};
+use crossterm::event::{MouseEvent, MouseEventKind};
 use ratatui::{
   Frame,
   layout::{Constraint, Direction, Layout, Position},
@@ -15,30 +21,88 @@ use strum::IntoEnumIterator;
 use tokio::sync::mpsc::{self};
 
 use crate::{
-  action::{Action, ExportFormat, MenuItemKind, MenuPreview},
+  action::{Action, MenuItemKind, MenuPreview, StatusLevel},
+  chord::PendingKeys,
   cli::{Cli, Driver},
   components::{
     Component, ComponentImpls,
     data::{Data, DataComponent},
-    editor::Editor,
+    editor::{Editor, EditorComponent, ScratchFile},
     favorites::{FavoriteEntries, Favorites},
-    history::History,
+    history::{History, HistoryFile},
     menu::{Menu, MenuComponent},
   },
-  config::Config,
-  database::{self, Database, DbTaskResult, ExecutionType, Rows},
+  config::{Config, EditorAutosave, LayoutSplit, QualifyTables, normalize_key_event},
+  database::{
+    self, ConnectionInfo, Cursor, Database, DbTaskResult, ExecutionType, ExportTask, QueryResultsWithMetadata, Rows,
+    SchemaCatalog, TableStatsTask,
+  },
+  export::{ProgressSink, file_extension, new_file_sink},
   focus::Focus,
+  plan::parse_plan,
   popups::{
-    PopUp, PopUpPayload, confirm_bypass::ConfirmBypass, confirm_export::ConfirmExport, confirm_query::ConfirmQuery,
-    confirm_tx::ConfirmTx, confirm_yank::ConfirmYank, exporting::Exporting, name_favorite::NameFavorite,
+    PopUp, PopUpPayload,
+    cell_detail::CellDetail,
+    command_palette::CommandPalette,
+    confirm_bypass::ConfirmBypass,
+    confirm_close_tab::ConfirmCloseTab,
+    confirm_export::ConfirmExport,
+    confirm_qualify::ConfirmQualify,
+    confirm_query::ConfirmQuery,
+    confirm_restore::ConfirmRestore,
+    confirm_tx::ConfirmTx,
+    confirm_yank::ConfirmYank,
+    copy_as::CopyAs,
+    edit_cell::EditCell,
+    exporting::Exporting,
+    fill_placeholders::FillPlaceholders,
+    import_favorites::ImportFavorites,
+    keybinding_help::KeybindingHelp,
+    name_favorite::NameFavorite,
+    sql_file::{SqlFile, SqlFileMode},
+    switch_connection::{SwitchConnection, SwitchTarget},
   },
+  query_log::{QueryLog, QueryLogEntry},
+  session::{Session, SessionFile},
+  slow_log::{SlowQueryLog, format_duration},
   tui,
   ui::center,
+  utils::{get_export_dir, get_scripts_dir},
 };
 
 pub struct HistoryEntry {
   pub query_lines: Vec<String>,
   pub timestamp: chrono::DateTime<chrono::Local>,
+  // entries loaded from before the driver was recorded don't have one
+  pub driver: Option<Driver>,
+  // consecutive runs of the same query are collapsed into one entry
+  pub run_count: usize,
+  // stats from the last run, once it has finished
+  pub duration: Option<chrono::Duration>,
+  pub row_count: Option<u64>,
+}
+
+// a query that was limited to one page of results when it ran
+#[derive(Clone, Debug)]
+pub struct Page {
+  pub query: String,
+  pub size: u64,
+  pub offset: u64,
+}
+
+// how long a status message is shown, and how long before that it starts to fade
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+const STATUS_FADE: Duration = Duration::from_secs(4);
+// how long the query tabs go unchanged before they're saved to the scratch file
+const SCRATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+// oracle doesn't allow more in an IN list, and longer lists are unwieldy anyway
+const IN_LIST_MAX_VALUES: usize = 1000;
+
+#[derive(Clone, Debug)]
+struct StatusMessage {
+  text: String,
+  level: StatusLevel,
+  shown_at: Instant,
 }
 
 pub struct AppState {
@@ -48,27 +112,109 @@ pub struct AppState {
   pub last_query_start: Option<chrono::DateTime<chrono::Utc>>,
   pub last_query_end: Option<chrono::DateTime<chrono::Utc>>,
   pub query_task_running: bool,
+  pub page: Option<Page>,
+  // a query whose rows are streamed in as the results are scrolled
+  pub cursor: Option<Cursor>,
+  // whether the focused pane is shown full screen
+  pub zoomed: bool,
+  // whether destructive statements are confirmed before they run. starts
+  // from the setting, and can be turned off for the rest of the session
+  pub confirm_destructive: bool,
+  // what the app is connected to, and the connection's tag from the config
+  pub connection: ConnectionInfo,
+  pub connection_tag: Option<String>,
 }
 
 pub struct Components<'a> {
   pub menu: Box<dyn MenuComponent<'a>>,
-  pub editor: Box<dyn Component>,
+  pub editor: Box<dyn EditorComponent>,
   pub history: Box<dyn Component>,
   pub data: Box<dyn DataComponent<'a>>,
   pub favorites: Box<dyn Component>,
 }
 
+// the editor and results of a query tab that isn't shown. the shown tab's
+// are in `Components` and `AppState`, and they're swapped when switching.
+pub struct QueryTab {
+  editor: Box<dyn EditorComponent>,
+  data: Box<dyn DataComponent<'static>>,
+  page: Option<Page>,
+  cursor: Option<Cursor>,
+  last_query_start: Option<chrono::DateTime<chrono::Utc>>,
+  last_query_end: Option<chrono::DateTime<chrono::Utc>>,
+  last_sql_file: Option<PathBuf>,
+}
+
+impl QueryTab {
+  fn new() -> Self {
+    Self {
+      editor: Box::new(Editor::new()),
+      data: Box::new(Data::new()),
+      page: None,
+      cursor: None,
+      last_query_start: None,
+      last_query_end: None,
+      last_sql_file: None,
+    }
+  }
+}
+
 pub struct App {
   pub mouse_mode_override: Option<bool>,
+  // the name of the connection from the config that's in use, if one is
+  pub connection_name: Option<String>,
   pub config: Config,
   pub components: Components<'static>,
   pub should_quit: bool,
-  pub last_tick_key_events: Vec<KeyEvent>,
+  // keys typed so far towards a binding of several keys
+  pub pending_keys: PendingKeys,
   pub last_frame_mouse_event: Option<MouseEvent>,
   pub state: AppState,
   last_focused_tab: Focus,
   last_focused_component: Focus,
   popup: Option<Box<dyn PopUp>>,
+  catalog: SchemaCatalog,
+  last_sql_file: Option<PathBuf>,
+  history_file: HistoryFile,
+  slow_log: SlowQueryLog,
+  query_log: QueryLog,
+  // the query that's running, as it was sent to the database
+  running_query: Option<String>,
+  // whether the running query is the newest history entry, and its stats
+  // should be recorded when it finishes
+  history_stats_pending: bool,
+  layout_split: LayoutSplit,
+  status: Option<StatusMessage>,
+  // while a query with several statements runs, the statements after the
+  // running one. they run one at a time, and each result is kept.
+  batch: Option<VecDeque<String>>,
+  // whether the database is keeping a connection for the batch, to let go of
+  // once the batch is over
+  connection_pinned: bool,
+  // whether the results should be focused once the running query finishes
+  focus_data_on_finish: bool,
+  // a query sent again once asked about qualifying its tables, and whether it
+  // was picked to run as written
+  qualify_choice: Option<(String, bool)>,
+  // one entry per query tab, where the shown tab's is empty
+  query_tabs: Vec<Option<QueryTab>>,
+  query_tab: usize,
+  // the query tab the running query was started from, which its results go to
+  running_tab: usize,
+  // the panes the layout had room for when it was last drawn, ignoring zoom
+  visible_panes: Vec<Focus>,
+  // where the query tabs are saved as they're edited, unless autosave is off
+  scratch_file: Option<ScratchFile>,
+  // the tabs as they were last saved, and changes waiting to be, with when
+  // they last changed
+  scratch_saved: Vec<Vec<String>>,
+  scratch_pending: Option<(Vec<Vec<String>>, Instant)>,
+  // saved tabs that are put back if the user asks for them
+  restorable_scratch: Option<Vec<Vec<String>>>,
+  // where the session is saved when rainfrog closes, and the last query run
+  // from the editor to save with it
+  session_file: Option<SessionFile>,
+  last_query: Option<String>,
 }
 
 impl App {
@@ -80,6 +226,17 @@ impl App {
     let data = Data::new();
     let favorites = Favorites::new();
     let favorite_entries = FavoriteEntries::new(&config.config._favorites_dir)?;
+    let history_file =
+      HistoryFile::new(&config.config._data_dir, config.settings.history_max_entries.unwrap_or_default());
+    let slow_log = SlowQueryLog::new(&config.config._data_dir, config.settings.slow_query_ms.unwrap_or_default());
+    let query_log = QueryLog::new(&config.config._data_dir, config.settings.query_log.unwrap_or_default());
+    let layout_split = config.settings.layout_split.unwrap_or_default();
+    let confirm_destructive = config.settings.confirm_destructive.unwrap_or(true);
+    let chord_timeout_ms = config.settings.chord_timeout_ms.unwrap_or_default();
+    let history_entries = history_file.load().unwrap_or_else(|e| {
+      log::error!("failed to read query history: {e:?}");
+      vec![]
+    });
 
     Ok(Self {
       components: Components {
@@ -91,32 +248,192 @@ impl App {
       },
       should_quit: false,
       mouse_mode_override,
+      connection_name: None,
       config,
-      last_tick_key_events: Vec::new(),
+      pending_keys: PendingKeys::new(chord_timeout_ms),
       last_frame_mouse_event: None,
       state: AppState {
         focus,
-        history: vec![],
+        history: history_entries,
         last_query_start: None,
         last_query_end: None,
         favorites: favorite_entries,
         query_task_running: false,
+        page: None,
+        cursor: None,
+        zoomed: false,
+        confirm_destructive,
+        connection: ConnectionInfo::default(),
+        connection_tag: None,
       },
       last_focused_tab: Focus::Editor,
       last_focused_component: focus,
       popup: None,
+      catalog: SchemaCatalog::default(),
+      last_sql_file: None,
+      history_file,
+      slow_log,
+      query_log,
+      running_query: None,
+      history_stats_pending: false,
+      layout_split,
+      status: None,
+      batch: None,
+      connection_pinned: false,
+      focus_data_on_finish: false,
+      qualify_choice: None,
+      query_tabs: vec![None],
+      query_tab: 0,
+      running_tab: 0,
+      visible_panes: FOCUS_CYCLE.to_vec(),
+      scratch_file: None,
+      scratch_saved: vec![],
+      scratch_pending: None,
+      restorable_scratch: None,
+      session_file: None,
+      last_query: None,
+    })
+  }
+
+  fn add_to_history(&mut self, query_lines: Vec<String>, driver: Driver) {
+    self.history_stats_pending = false;
+    if let Some(latest) = self.state.history.first_mut()
+      && latest.query_lines == query_lines
+      && latest.driver == Some(driver)
+    {
+      let saved_at = latest.timestamp;
+      latest.run_count = latest.run_count.saturating_add(1);
+      latest.timestamp = chrono::Local::now();
+      latest.duration = None;
+      latest.row_count = None;
+      if let Err(e) = self.history_file.update(saved_at, latest) {
+        log::error!("failed to save query to history: {e:?}");
+      }
+      return;
+    }
+    let entry = HistoryEntry {
+      query_lines,
+      timestamp: chrono::Local::now(),
+      driver: Some(driver),
+      run_count: 1,
+      duration: None,
+      row_count: None,
+    };
+    if let Err(e) = self.history_file.append(&entry) {
+      log::error!("failed to save query to history: {e:?}");
+    }
+    self.state.history.insert(0, entry);
+    match self.config.settings.history_max_entries.unwrap_or_default() {
+      0 => {},
+      max_entries => self.state.history.truncate(max_entries),
+    }
+  }
+
+  // called once the database has been given a query to run
+  fn query_started(&mut self, query: String, record_stats: bool) {
+    self.state.last_query_start = Some(chrono::Utc::now());
+    self.state.last_query_end = None;
+    self.history_stats_pending = record_stats;
+    self.running_query = Some(query);
+    self.running_tab = self.query_tab;
+  }
+
+  // adds the query that just finished to the query log, if one is kept
+  fn log_query(
+    &self,
+    duration: Option<chrono::Duration>,
+    row_count: Option<u64>,
+    error: Option<String>,
+    statement: Option<&Statement>,
+  ) {
+    let Some(query) = &self.running_query else {
+      return;
+    };
+    self.query_log.record(&QueryLogEntry {
+      timestamp: chrono::Local::now().to_rfc3339(),
+      connection: self.connection_name.clone().or_else(|| Some(self.state.connection.to_string())),
+      query: query.clone(),
+      duration_ms: duration.and_then(|duration| duration.num_microseconds()).map(|micros| micros as f64 / 1000_f64),
+      row_count,
+      error,
+      // statements that weren't parsed, like ones that bypassed the parser, might write
+      write: !statement.is_some_and(database::is_read_only),
+    });
+  }
+
+  // adds the query that just finished to the slow query log if it took long enough
+  fn log_slow_query(&mut self, duration: Option<chrono::Duration>) {
+    let (Some(query), Some(duration)) = (self.running_query.take(), duration) else {
+      return;
+    };
+    match self.slow_log.record(&query, duration, chrono::Local::now()) {
+      Ok(true) => log::info!("slow query took {}", format_duration(duration)),
+      Ok(false) => {},
+      Err(e) => log::error!("failed to write to the slow query log: {e:?}"),
+    }
+  }
+
+  // adds a query to the history the first time it's dispatched. the popups that ask
+  // about a query dispatch it again once answered, which isn't another run of it.
+  // returns whether it was picked to run as written, if it's back from being asked
+  // about qualifying its tables.
+  fn query_dispatched(&mut self, query_lines: &[String], confirmed: bool, driver: Driver) -> Option<bool> {
+    let query_string = query_lines.join(" \n");
+    let qualify_choice =
+      self.qualify_choice.take().filter(|(query, _)| *query == query_string).map(|(_, as_written)| as_written);
+    if self.batch.is_none() && !confirmed && qualify_choice.is_none() {
+      self.add_to_history(query_lines.to_vec(), driver);
+    }
+    qualify_choice
+  }
+
+  // records how long the newest history entry took to run and how many rows it
+  // returned or affected
+  fn record_history_stats(&mut self, row_count: Option<u64>) {
+    if !std::mem::take(&mut self.history_stats_pending) {
+      return;
+    }
+    let Some(latest) = self.state.history.first_mut() else {
+      return;
+    };
+    latest.duration =
+      self.state.last_query_start.zip(self.state.last_query_end).map(|(start, end)| end.signed_duration_since(start));
+    latest.row_count = row_count;
+    if let Err(e) = self.history_file.update(latest.timestamp, latest) {
+      log::error!("failed to save query stats to history: {e:?}");
+    }
+  }
+
+  // the first statement of a script that has to be confirmed. its statements run one
+  // after another without asking about each, or a transaction for DELETE and UPDATE.
+  fn script_confirmation(&self, statements: &[String], driver: Driver) -> Option<Statement> {
+    statements.iter().find_map(|statement| match database::get_execution_type(statement.clone(), false, driver) {
+      Ok((ExecutionType::Normal, _)) | Err(_) => None,
+      Ok((_, statement)) => statement,
     })
   }
 
-  fn add_to_history(&mut self, query_lines: Vec<String>) {
-    self.state.history.insert(0, HistoryEntry { query_lines, timestamp: chrono::Local::now() });
-    if self.state.history.len() > 50 {
-      self.state.history.pop();
+  // keeps the results of a statement of a multi-statement query, and starts the
+  // next one unless this one failed
+  fn continue_batch(
+    &mut self,
+    results: QueryResultsWithMetadata,
+    action_tx: &mpsc::UnboundedSender<Action>,
+  ) -> Result<()> {
+    let failed = results.results.is_err();
+    self.components.data.push_result_set(results.results, results.statement_type);
+    match self.batch.as_mut().and_then(|remaining| remaining.pop_front()) {
+      Some(next) if !failed => action_tx.send(Action::Query(vec![next], true, false))?,
+      _ => self.batch = None,
     }
+    Ok(())
   }
 
   fn clear_history(&mut self) {
     self.state.history = vec![];
+    if let Err(e) = self.history_file.clear() {
+      log::error!("failed to clear saved query history: {e:?}");
+    }
   }
 
   fn set_focus(&mut self, focus: Focus) {
@@ -135,6 +452,184 @@ impl App {
     self.set_focus(Focus::PopUp);
   }
 
+  // swaps the shown query tab for another one, and returns the one that was shown
+  fn swap_query_tab(&mut self, mut tab: QueryTab) -> QueryTab {
+    std::mem::swap(&mut self.components.editor, &mut tab.editor);
+    std::mem::swap(&mut self.components.data, &mut tab.data);
+    std::mem::swap(&mut self.state.page, &mut tab.page);
+    std::mem::swap(&mut self.state.cursor, &mut tab.cursor);
+    std::mem::swap(&mut self.state.last_query_start, &mut tab.last_query_start);
+    std::mem::swap(&mut self.state.last_query_end, &mut tab.last_query_end);
+    std::mem::swap(&mut self.last_sql_file, &mut tab.last_sql_file);
+    tab
+  }
+
+  fn open_query_tab(&mut self, tab: QueryTab) {
+    let shown = self.swap_query_tab(tab);
+    self.query_tabs[self.query_tab] = Some(shown);
+    self.query_tabs.push(None);
+    self.query_tab = self.query_tabs.len() - 1;
+  }
+
+  fn select_query_tab(&mut self, index: usize) {
+    if index == self.query_tab {
+      return;
+    }
+    if let Some(tab) = self.query_tabs.get_mut(index).and_then(Option::take) {
+      let shown = self.swap_query_tab(tab);
+      self.query_tabs[self.query_tab] = Some(shown);
+      self.query_tab = index;
+    }
+  }
+
+  // shows the next tab, or the previous one if it was the last
+  fn close_query_tab(&mut self) {
+    if self.query_tabs.len() < 2 {
+      return;
+    }
+    let next = if self.query_tab + 1 < self.query_tabs.len() { self.query_tab + 1 } else { self.query_tab - 1 };
+    if let Some(tab) = self.query_tabs[next].take() {
+      self.swap_query_tab(tab);
+    }
+    self.query_tabs.remove(self.query_tab);
+    self.query_tab = next.min(self.query_tab);
+  }
+
+  fn new_query_tab(&self, action_tx: &mpsc::UnboundedSender<Action>, area: Rect) -> Result<QueryTab> {
+    let mut tab = QueryTab::new();
+    tab.editor.register_action_handler(action_tx.clone())?;
+    tab.data.register_action_handler(action_tx.clone())?;
+    tab.editor.register_config_handler(self.config.clone())?;
+    tab.data.register_config_handler(self.config.clone())?;
+    tab.editor.init(area)?;
+    tab.data.init(area)?;
+    Ok(tab)
+  }
+
+  // the lines in the editor of each query tab, in order
+  fn query_tab_lines(&self) -> Vec<Vec<String>> {
+    self
+      .query_tabs
+      .iter()
+      .map(|tab| match tab {
+        Some(tab) => tab.editor.lines().to_vec(),
+        None => self.components.editor.lines().to_vec(),
+      })
+      .collect()
+  }
+
+  // loads saved query tabs into the first tab and new ones after it
+  fn restore_scratch(
+    &mut self,
+    tabs: Vec<Vec<String>>,
+    action_tx: &mpsc::UnboundedSender<Action>,
+    area: Rect,
+  ) -> Result<()> {
+    let mut tabs = tabs.into_iter();
+    if let Some(lines) = tabs.next() {
+      self.components.editor.update(Action::QueryToEditor(lines), &self.state)?;
+    }
+    for lines in tabs {
+      let mut tab = self.new_query_tab(action_tx, area)?;
+      tab.editor.update(Action::QueryToEditor(lines), &self.state)?;
+      self.open_query_tab(tab);
+    }
+    self.select_query_tab(0);
+    Ok(())
+  }
+
+  // saves the query tabs once they've gone unchanged for a moment, or right
+  // away when rainfrog is closing
+  fn autosave_scratch(&mut self, now: bool) {
+    let Some(scratch) = &self.scratch_file else {
+      return;
+    };
+    // the saved tabs aren't saved over until it's been decided whether to restore them
+    if self.restorable_scratch.is_some() {
+      return;
+    }
+    let tabs = self.query_tab_lines();
+    if tabs == self.scratch_saved {
+      self.scratch_pending = None;
+      return;
+    }
+    let settled = match &self.scratch_pending {
+      Some((pending, changed_at)) if *pending == tabs => changed_at.elapsed() >= SCRATCH_DEBOUNCE,
+      _ => {
+        self.scratch_pending = Some((tabs.clone(), Instant::now()));
+        false
+      },
+    };
+    if settled || now {
+      if let Err(e) = scratch.save(&tabs) {
+        log::error!("failed to save the query tabs: {e:?}");
+      }
+      self.scratch_saved = tabs;
+      self.scratch_pending = None;
+    }
+  }
+
+  fn save_session(&self) {
+    let Some(file) = &self.session_file else {
+      return;
+    };
+    let session = Session {
+      focus: if self.state.focus == Focus::PopUp { self.last_focused_component } else { self.state.focus },
+      layout_split: Some(self.layout_split),
+      zoomed: self.state.zoomed,
+      query_tab: self.query_tab,
+      last_query: self.last_query.clone(),
+    };
+    if let Err(e) = file.save(&session) {
+      log::error!("failed to save the session: {e:?}");
+    }
+  }
+
+  fn query_running(&self) -> bool {
+    self.state.query_task_running || self.batch.is_some()
+  }
+
+  // shows the tab the running query was started from, if another one is shown,
+  // so its results go there. returns the tab to show again once they have.
+  fn show_running_tab(&mut self) -> Option<usize> {
+    let shown = self.query_tab;
+    if self.running_tab == shown || self.running_tab >= self.query_tabs.len() {
+      return None;
+    }
+    self.select_query_tab(self.running_tab);
+    Some(shown)
+  }
+
+  fn query_tab_labels(&self) -> Vec<String> {
+    self
+      .query_tabs
+      .iter()
+      .enumerate()
+      .map(|(i, tab)| match tab {
+        Some(tab) => query_tab_label(i, tab.last_sql_file.as_ref(), tab.editor.is_modified()),
+        None => query_tab_label(i, self.last_sql_file.as_ref(), self.components.editor.is_modified()),
+      })
+      .collect()
+  }
+
+  fn set_connection(&mut self, database: &dyn Database) {
+    self.state.connection = database.connection_info();
+    self.state.connection_tag =
+      self.connection_name.as_ref().and_then(|name| self.config.db.get(name)).and_then(|conn| conn.tag.clone());
+  }
+
+  fn request_sql_file(&mut self, mode: SqlFileMode) {
+    let popup = match mode {
+      SqlFileMode::Load | SqlFileMode::Save => {
+        let scripts_dir = self.config.settings.scripts_dir.clone().unwrap_or_else(get_scripts_dir);
+        SqlFile::new(mode, scripts_dir, self.last_sql_file.clone())
+      },
+      SqlFileMode::ExportFavorites => SqlFile::new(mode, get_export_dir(), Some(PathBuf::from("favorites.json"))),
+      SqlFileMode::ImportFavorites => SqlFile::new(mode, get_export_dir(), None),
+    };
+    self.set_popup(Box::new(popup));
+  }
+
   fn last_focused_tab(&mut self) {
     match self.last_focused_tab {
       Focus::Editor => self.set_focus(Focus::Editor),
@@ -155,26 +650,27 @@ impl App {
     }
   }
 
-  pub async fn run(&mut self, driver: Driver, args: Cli) -> Result<()> {
-    let mut database: Box<dyn Database> = match driver {
-      Driver::Postgres => Box::new(database::PostgresDriver::new()),
-      Driver::MySql => Box::new(database::MySqlDriver::new()),
-      Driver::Sqlite => Box::new(database::SqliteDriver::new()),
-      Driver::Oracle => Box::new(database::OracleDriver::new()),
-      #[cfg(feature = "duckdb")]
-      Driver::DuckDb => Box::new(database::DuckDbDriver::new()),
-    };
+  pub async fn run(&mut self, mut driver: Driver, args: Cli) -> Result<()> {
+    let mut database = new_database(driver, self.config.settings.max_rows_fetched.unwrap_or_default());
+    let fresh = args.fresh;
     database.init(args).await?;
+    self.set_connection(database.as_ref());
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
     log::info!("{driver:?}");
 
-    let mut tui = tui::Tui::new()?.mouse(self.mouse_mode_override.or(self.config.settings.mouse_mode));
+    let mut tui = tui::Tui::new()?
+      .mouse(self.mouse_mode_override.or(self.config.settings.mouse_mode))
+      .tick_rate(self.config.settings.tick_rate)
+      .frame_rate(self.config.settings.frame_rate);
     tui.enter()?;
 
     #[allow(unused_mut)]
     #[cfg(feature = "arboard")]
     let mut clipboard = Clipboard::new();
 
+    let mut export_task: Option<ExportTask> = None;
+    let mut table_stats_task: Option<TableStatsTask> = None;
+
     self.components.menu.register_action_handler(action_tx.clone())?;
     self.components.editor.register_action_handler(action_tx.clone())?;
     self.components.history.register_action_handler(action_tx.clone())?;
@@ -194,20 +690,153 @@ impl App {
     self.components.data.init(Rect { width: size.width, height: size.height, x: 0, y: 0 })?;
     self.components.favorites.init(Rect { width: size.width, height: size.height, x: 0, y: 0 })?;
 
+    let session_file = SessionFile::new(&self.config.config._data_dir, self.connection_name.as_deref());
+    let session = match session_file.load() {
+      _ if fresh => None,
+      Ok(session) => session,
+      Err(e) => {
+        log::error!("failed to read the saved session: {e:?}");
+        None
+      },
+    };
+    self.session_file = Some(session_file);
+    // the focus is set before the tabs are restored, so a prompt to restore
+    // them goes back to it once it's answered
+    if let Some(session) = &session {
+      self.layout_split = session.layout_split.unwrap_or(self.layout_split);
+      self.state.zoomed = session.zoomed;
+      self.last_query = session.last_query.clone();
+      if session.focus != Focus::PopUp {
+        self.set_focus(session.focus);
+      }
+    }
+
+    let autosave = self.config.settings.editor_autosave.unwrap_or_default();
+    if autosave != EditorAutosave::Off {
+      let scratch = ScratchFile::new(&self.config.config._data_dir, self.connection_name.as_deref());
+      self.scratch_saved = self.query_tab_lines();
+      match if fresh { Ok(None) } else { scratch.load() } {
+        Ok(Some(tabs)) if autosave == EditorAutosave::Ask => {
+          self.set_popup(Box::new(ConfirmRestore::new(tabs.len())));
+          self.scratch_saved = tabs.clone();
+          self.restorable_scratch = Some(tabs);
+        },
+        Ok(Some(tabs)) => {
+          self.scratch_saved = tabs.clone();
+          self.restore_scratch(tabs, &action_tx, Rect { width: size.width, height: size.height, x: 0, y: 0 })?;
+        },
+        Ok(None) => {},
+        Err(e) => log::error!("failed to read the saved query tabs: {e:?}"),
+      }
+      self.scratch_file = Some(scratch);
+    }
+    if let Some(session) = session {
+      if session.query_tab < self.query_tabs.len() {
+        self.select_query_tab(session.query_tab);
+      }
+      if self.restorable_scratch.is_none()
+        && self.query_tab_lines().iter().flatten().all(|line| line.trim().is_empty())
+        && let Some(query) = session.last_query
+      {
+        let lines = query.lines().map(str::to_owned).collect();
+        self.components.editor.update(Action::QueryToEditor(lines), &self.state)?;
+      }
+    }
+
     action_tx.send(Action::LoadMenu)?;
 
     loop {
       if self.popup.is_some() {
         self.set_focus(Focus::PopUp);
       }
-      match database.get_query_results().await? {
-        DbTaskResult::Finished(results) => {
-          self.components.data.set_data_state(Some(results.results), results.statement_type);
+      if self.batch.is_none()
+        && std::mem::take(&mut self.connection_pinned)
+        && let Err(e) = database.unpin_connection().await
+      {
+        log::error!("failed to let go of the script's connection: {e:?}");
+      }
+      // new connections of the pool run the startup sql too, so its failures can come at any time
+      if let Some(message) = startup_errors_message(&database.take_startup_errors()) {
+        action_tx.send(Action::StatusMessage(message, StatusLevel::Warning))?;
+      }
+      let task_result = database.get_query_results().await?;
+      let shown_tab = match &task_result {
+        DbTaskResult::Finished(_) | DbTaskResult::ConfirmTx(..) => self.show_running_tab(),
+        _ => None,
+      };
+      match task_result {
+        DbTaskResult::Finished(mut results) => {
+          // a page of a query is exported as the whole query
+          let source_query = match (&self.state.cursor, &self.state.page) {
+            (Some(cursor), _) => Some(cursor.query.clone()),
+            (None, Some(page)) => Some(page.query.clone()),
+            (None, None) => self.running_query.clone(),
+          };
+          let max_rows = self.config.settings.max_rows_fetched.unwrap_or_default();
+          let truncated = database::truncate_rows(&mut results.results, max_rows);
+          if results.results.is_ok() && results.statement_type.as_ref().is_some_and(database::changes_schema) {
+            action_tx.send(Action::LoadMenu)?;
+          }
           self.state.last_query_end = Some(chrono::Utc::now());
+          let duration = self
+            .state
+            .last_query_start
+            .zip(self.state.last_query_end)
+            .map(|(start, end)| end.signed_duration_since(start));
+          let (text, level) = query_status(&results.results, results.statement_type.as_ref(), duration);
+          action_tx.send(Action::StatusMessage(text, level))?;
+          match &results.results {
+            Ok(rows) if rows.headers.is_empty() => {
+              self.log_query(duration, rows.rows_affected, None, results.statement_type.as_ref())
+            },
+            Ok(rows) => self.log_query(duration, Some(rows.rows.len() as u64), None, results.statement_type.as_ref()),
+            Err(e) => self.log_query(duration, None, Some(e.to_string()), results.statement_type.as_ref()),
+          }
+          self.log_slow_query(duration);
+          match &results.results {
+            Ok(rows) if rows.headers.is_empty() => self.record_history_stats(rows.rows_affected),
+            Ok(rows) => self.record_history_stats(Some(rows.rows.len() as u64)),
+            Err(_) => self.record_history_stats(None),
+          }
+          if self.batch.is_some() {
+            self.continue_batch(results, &action_tx)?;
+            self.components.data.set_source_query(source_query);
+          } else if let Some(cursor) = self.state.cursor.as_mut().filter(|cursor| cursor.fetching) {
+            // the first page replaces the results shown, and the rest are added to it
+            let first_page = cursor.fetched == 0;
+            cursor.record(&results.results);
+            let more = cursor.has_more();
+            match results.results {
+              Ok(rows) if !first_page => self.components.data.append_rows(rows, more),
+              Err(_) if !first_page => self.components.data.set_more_rows(false),
+              data => {
+                self.components.data.set_data_state(Some(data), results.statement_type);
+                self.components.data.set_more_rows(more);
+                self.components.data.set_source_query(source_query);
+              },
+            }
+          } else {
+            self.components.data.set_data_state(Some(results.results), results.statement_type);
+            self.components.data.set_source_query(source_query);
+          }
+          if truncated && self.batch.is_none() {
+            self.components.data.set_truncated(max_rows);
+          }
+          if self.batch.is_none() && self.popup.is_none() && std::mem::take(&mut self.focus_data_on_finish) {
+            self.set_focus(Focus::Data);
+          }
           self.state.query_task_running = false;
         },
         DbTaskResult::ConfirmTx(rows_affected, statement) => {
           self.state.last_query_end = Some(chrono::Utc::now());
+          let duration = self
+            .state
+            .last_query_start
+            .zip(self.state.last_query_end)
+            .map(|(start, end)| end.signed_duration_since(start));
+          self.log_query(duration, rows_affected, None, statement.as_ref());
+          self.log_slow_query(duration);
+          self.record_history_stats(rows_affected);
           self.set_popup(Box::new(ConfirmTx::new(rows_affected, statement)));
           self.state.query_task_running = true;
         },
@@ -218,6 +847,30 @@ impl App {
           self.state.query_task_running = false;
         },
       }
+      if let Some(tab) = shown_tab {
+        self.select_query_tab(tab);
+      }
+      if let Some(task) = export_task.take_if(|task| task.is_finished()) {
+        match task.await? {
+          Ok(row_count) => {
+            log::info!("Exported {row_count} rows");
+            action_tx.send(Action::StatusMessage(format!("exported {row_count} rows"), StatusLevel::Info))?;
+          },
+          Err(e) => {
+            log::error!("{e:?}");
+            action_tx.send(Action::StatusMessage(format!("export failed: {}", first_line(&e)), StatusLevel::Error))?;
+            self.components.data.set_data_state(Some(Err(e.wrap_err("Export failed"))), None);
+          },
+        }
+        action_tx.send(Action::ExportDataFinished)?;
+      }
+      if let Some(task) = table_stats_task.take_if(|task| task.is_finished()) {
+        match task.await? {
+          Ok(rows) => self.components.menu.set_table_stats(rows),
+          // the menu is still usable without them, so this isn't shown
+          Err(e) => log::error!("{e:?}"),
+        }
+      }
       if let Some(e) = tui.next().await {
         let mut event_consumed = false;
         match e {
@@ -226,11 +879,14 @@ impl App {
           tui::Event::Render => action_tx.send(Action::Render)?,
           tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
           tui::Event::Mouse(event) => self.last_frame_mouse_event = Some(event),
+          // a paste doesn't finish a binding of several keys
+          tui::Event::Paste(_) => self.pending_keys.clear(),
           tui::Event::Key(key) => {
             if let Some(keymap) = self.config.keybindings.get(&self.state.focus) {
-              if let Some(action) = keymap.get(&vec![key]) {
+              if let Some(action) = keymap.get(&vec![normalize_key_event(key)]) {
                 log::info!("Got action: {action:?}");
                 action_tx.send(action.clone())?;
+                self.pending_keys.clear();
                 event_consumed = true;
               } else if let Some(popup) = &mut self.popup {
                 // popup captures all inputs. if it returns a payload, that means
@@ -238,6 +894,8 @@ impl App {
                 let payload = popup.handle_key_events(key, &mut self.state)?;
                 match payload {
                   Some(PopUpPayload::SetDataTable(result, statement)) => {
+                    self.batch = None;
+                    self.focus_data_on_finish = false;
                     self.components.data.set_data_state(result, statement);
                     self.set_focus(Focus::Editor);
                   },
@@ -245,18 +903,45 @@ impl App {
                     action_tx.send(Action::Query(vec![query], true, false))?;
                     self.set_focus(Focus::Editor);
                   },
+                  Some(PopUpPayload::ConfirmTxQuery(query)) => {
+                    self.components.data.set_loading();
+                    database.start_tx(query.clone()).await?;
+                    self.query_started(query, self.batch.is_none());
+                    self.set_focus(Focus::Editor);
+                  },
+                  Some(PopUpPayload::Query(query)) => {
+                    action_tx.send(Action::Query(vec![query], false, false))?;
+                    self.set_focus(Focus::Editor);
+                  },
+                  Some(PopUpPayload::ConfirmQualify(query)) => {
+                    self.qualify_choice = Some((query.clone(), false));
+                    action_tx.send(Action::Query(vec![query], false, false))?;
+                    self.set_focus(Focus::Editor);
+                  },
+                  Some(PopUpPayload::QueryAsWritten(query)) => {
+                    self.qualify_choice = Some((query.clone(), true));
+                    action_tx.send(Action::Query(vec![query], false, false))?;
+                    self.set_focus(Focus::Editor);
+                  },
                   Some(PopUpPayload::ConfirmBypass(query)) => {
                     action_tx.send(Action::Query(vec![query], true, true))?;
                     self.set_focus(Focus::Editor);
                   },
-                  Some(PopUpPayload::ConfirmExport(confirmed)) => {
-                    if confirmed {
-                      action_tx.send(Action::ExportData(ExportFormat::CSV))?;
+                  Some(PopUpPayload::ConfirmExport(choice)) => {
+                    if let Some((format, options)) = choice {
+                      action_tx.send(Action::ExportData(format, options))?;
                       self.set_popup(Box::new(Exporting::new()));
                     } else {
                       self.set_focus(Focus::Data);
                     }
                   },
+                  Some(PopUpPayload::AbortExport) => {
+                    if let Some(task) = export_task.take() {
+                      task.abort();
+                      log::info!("Export aborted");
+                    }
+                    self.set_focus(Focus::Data);
+                  },
                   Some(PopUpPayload::ConfirmYank(confirmed)) => {
                     if confirmed {
                       action_tx.send(Action::YankAll)?;
@@ -264,44 +949,120 @@ impl App {
                       self.set_focus(Focus::Data);
                     }
                   },
+                  Some(PopUpPayload::CopyAs(format)) => {
+                    action_tx.send(Action::CopyAs(format))?;
+                  },
+                  Some(PopUpPayload::Copy(text)) => {
+                    action_tx.send(Action::CopyData(text))?;
+                    self.last_focused_component();
+                  },
+                  Some(PopUpPayload::QueryToEditor(query)) => {
+                    action_tx.send(Action::QueryToEditor(query.lines().map(str::to_owned).collect()))?;
+                    self.set_focus(Focus::Editor);
+                  },
                   Some(PopUpPayload::Cancel) => {
                     self.last_focused_component();
                   },
+                  Some(PopUpPayload::RunAction(action)) => {
+                    self.last_focused_component();
+                    action_tx.send(action)?;
+                  },
+                  Some(PopUpPayload::SqlFile(mode, path)) => {
+                    action_tx.send(match mode {
+                      SqlFileMode::Load => Action::LoadSqlFile(path),
+                      SqlFileMode::Save => Action::SaveSqlFile(path),
+                      SqlFileMode::ExportFavorites => Action::ExportFavorites(path),
+                      SqlFileMode::ImportFavorites => Action::ImportFavorites(path),
+                    })?;
+                    match mode {
+                      SqlFileMode::Load | SqlFileMode::Save => self.set_focus(Focus::Editor),
+                      _ => self.set_focus(Focus::Favorites),
+                    }
+                  },
                   Some(PopUpPayload::NamedFavorite(name, query_lines)) => {
-                    self.state.favorites.add_entry(name, query_lines);
+                    let (folder, name) = match name.rsplit_once('/') {
+                      Some((folder, name)) => (Some(folder.to_string()), name.to_string()),
+                      None => (None, name),
+                    };
+                    action_tx.send(Action::SaveFavorite(folder, name, query_lines))?;
+                    self.set_focus(Focus::Editor);
+                  },
+                  Some(PopUpPayload::FilledPlaceholders(query_lines, values)) => {
+                    let filled =
+                      database::fill_placeholders(&query_lines.join("\n"), &values.into_iter().collect(), driver);
+                    action_tx.send(Action::Query(
+                      filled.lines().map(|line| line.to_string()).collect(),
+                      false,
+                      false,
+                    ))?;
+                    self.set_focus(Focus::Favorites);
+                  },
+                  Some(PopUpPayload::RestoreScratch) => {
+                    if let Some(tabs) = self.restorable_scratch.take() {
+                      let size = tui.size()?;
+                      let area = Rect { width: size.width, height: size.height, x: 0, y: 0 };
+                      self.restore_scratch(tabs, &action_tx, area)?;
+                    }
+                    self.set_focus(Focus::Editor);
+                  },
+                  Some(PopUpPayload::DiscardScratch) => {
+                    self.restorable_scratch = None;
+                    self.last_focused_component();
+                  },
+                  Some(PopUpPayload::CloseQueryTab) => {
+                    self.close_query_tab();
                     self.set_focus(Focus::Editor);
                   },
+                  Some(PopUpPayload::UseDatabase(name)) => {
+                    action_tx.send(Action::UseDatabase(name))?;
+                    self.set_focus(Focus::Menu);
+                  },
+                  Some(PopUpPayload::SwitchConnection(name)) => {
+                    action_tx.send(Action::SwitchConnection(name))?;
+                    self.set_focus(Focus::Menu);
+                  },
+                  Some(PopUpPayload::RenameFavorite(old_name, new_name)) => {
+                    action_tx.send(Action::RenameFavorite(old_name, new_name))?;
+                    self.set_focus(Focus::Favorites);
+                  },
                   Some(PopUpPayload::CommitTx) => {
+                    let shown_tab = self.show_running_tab();
                     let response = database.commit_tx().await?;
                     self.state.last_query_end = Some(chrono::Utc::now());
                     if let Some(results) = response {
-                      self.components.data.set_data_state(Some(results.results), results.statement_type);
+                      if self.batch.is_some() {
+                        self.continue_batch(results, &action_tx)?;
+                      } else {
+                        self.components.data.set_data_state(Some(results.results), results.statement_type);
+                      }
                       self.set_focus(Focus::Editor);
                     }
+                    if let Some(tab) = shown_tab {
+                      self.select_query_tab(tab);
+                    }
                   },
                   Some(PopUpPayload::RollbackTx) => {
+                    let shown_tab = self.show_running_tab();
+                    self.batch = None;
                     database.rollback_tx().await?;
                     self.state.last_query_end = Some(chrono::Utc::now());
                     self.components.data.set_data_state(
                       Some(Ok(Rows { headers: vec![], rows: vec![], rows_affected: None })),
                       Some(Statement::Rollback { chain: false, savepoint: None }),
                     );
+                    if let Some(tab) = shown_tab {
+                      self.select_query_tab(tab);
+                    }
                     self.set_focus(Focus::Editor);
                   },
                   None => {},
                 }
                 event_consumed = true;
-              } else {
-                // If the key was not handled as a single key action,
-                // then consider it for multi-key combinations.
-                self.last_tick_key_events.push(key);
-
-                // Check for multi-key combinations
-                if let Some(action) = keymap.get(&self.last_tick_key_events) {
-                  log::info!("Got action: {action:?}");
-                  action_tx.send(action.clone())?;
-                  event_consumed = true;
-                }
+              } else if let Some(action) = self.pending_keys.press(normalize_key_event(key), Instant::now(), keymap) {
+                // keys that aren't an action on their own may finish a binding of several keys
+                log::info!("Got action: {action:?}");
+                action_tx.send(action)?;
+                event_consumed = true;
               }
             }
           },
@@ -311,22 +1072,22 @@ impl App {
           for i in ComponentImpls::iter() {
             let action = match i {
               ComponentImpls::Menu => {
-                self.components.menu.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+                self.components.menu.handle_events(Some(e.clone()), self.pending_keys.keys().to_vec(), &self.state)?
               },
               ComponentImpls::Editor => {
-                self.components.editor.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+                self.components.editor.handle_events(Some(e.clone()), self.pending_keys.keys().to_vec(), &self.state)?
               },
               ComponentImpls::History => self.components.history.handle_events(
                 Some(e.clone()),
-                self.last_tick_key_events.clone(),
+                self.pending_keys.keys().to_vec(),
                 &self.state,
               )?,
               ComponentImpls::Data => {
-                self.components.data.handle_events(Some(e.clone()), self.last_tick_key_events.clone(), &self.state)?
+                self.components.data.handle_events(Some(e.clone()), self.pending_keys.keys().to_vec(), &self.state)?
               },
               ComponentImpls::Favorites => self.components.favorites.handle_events(
                 Some(e.clone()),
-                self.last_tick_key_events.clone(),
+                self.pending_keys.keys().to_vec(),
                 &self.state,
               )?,
             };
@@ -342,9 +1103,20 @@ impl App {
           log::debug!("{action:?}");
         }
         let action_consumed = false;
+        // the next statement of a script, and cancelling, are for the tab the query is running in
+        let shown_tab = match &action {
+          Action::Query(..) if self.batch.is_some() => self.show_running_tab(),
+          Action::AbortQuery => self.show_running_tab(),
+          _ => None,
+        };
         match &action {
           Action::Tick => {
-            self.last_tick_key_events.drain(..);
+            self.pending_keys.expire(Instant::now());
+            self.status.take_if(|status| status.shown_at.elapsed() >= STATUS_TIMEOUT);
+            self.autosave_scratch(false);
+          },
+          Action::StatusMessage(text, level) => {
+            self.status = Some(StatusMessage { text: text.clone(), level: *level, shown_at: Instant::now() });
           },
           Action::Quit => self.should_quit = true,
           Action::Resize(w, h) => {
@@ -362,67 +1134,234 @@ impl App {
           Action::FocusMenu => self.set_focus(Focus::Menu),
           Action::FocusEditor => self.set_focus(Focus::Editor),
           Action::FocusData => self.set_focus(Focus::Data),
+          Action::SubmitEditorQuery | Action::SubmitEditorQueryAndFocusData | Action::SubmitEditorQueryBypassParser => {
+            self.focus_data_on_finish = action == Action::SubmitEditorQueryAndFocusData;
+            // only queries from the editor are restored into it
+            let query = self.components.editor.lines().join("\n");
+            if !query.trim().is_empty() {
+              self.last_query = Some(query);
+            }
+          },
+          Action::ExplainQuery(query_lines, analyze) => {
+            match database::explain_query(&query_lines.join("\n"), driver, *analyze) {
+              Ok(query) => {
+                // the plan is looked through in the results
+                self.focus_data_on_finish = true;
+                action_tx.send(Action::Query(vec![query], false, false))?;
+              },
+              Err(e) => action_tx.send(Action::Error(e.to_string()))?,
+            }
+          },
           Action::FocusHistory => self.set_focus(Focus::History),
           Action::FocusFavorites => self.set_focus(Focus::Favorites),
-          Action::CycleFocusForwards => match self.state.focus {
-            Focus::Menu => self.set_focus(Focus::Editor),
-            Focus::Editor => self.set_focus(Focus::Data),
-            Focus::Data => self.set_focus(Focus::History),
-            Focus::History => self.set_focus(Focus::Favorites),
-            Focus::Favorites => self.set_focus(Focus::Menu),
-            Focus::PopUp => {},
-          },
-          Action::CycleFocusBackwards => match self.state.focus {
-            Focus::History => self.set_focus(Focus::Data),
-            Focus::Data => self.set_focus(Focus::Editor),
-            Focus::Editor => self.set_focus(Focus::Menu),
-            Focus::Menu => self.set_focus(Focus::Favorites),
-            Focus::Favorites => self.set_focus(Focus::History),
-            Focus::PopUp => {},
+          Action::ToggleZoom => self.state.zoomed = !self.state.zoomed,
+          Action::ToggleLayoutSplit => self.layout_split = self.layout_split.toggled(),
+          Action::CycleFocusForwards | Action::CycleFocusBackwards if self.state.focus != Focus::PopUp => {
+            let forwards = matches!(action, Action::CycleFocusForwards);
+            self.set_focus(cycle_focus(self.state.focus, &self.visible_panes, forwards));
           },
           Action::LoadMenu => {
-            let rows = database.load_menu().await;
+            let rows = database.load_menu(self.config.settings.menu_system_schemas.unwrap_or_default()).await;
+            if let Ok(menu) = &rows {
+              match database.search_path().await {
+                Ok(search_path) => {
+                  if self.config.settings.qualify_tables.unwrap_or_default() != QualifyTables::Off {
+                    self.catalog = SchemaCatalog::new(search_path.clone(), menu);
+                  }
+                  self.components.menu.set_search_path(search_path);
+                },
+                Err(e) => log::error!("{e:?}"),
+              }
+            }
             self.components.menu.set_table_list(Some(rows));
+            if self.config.settings.menu_table_stats.unwrap_or_default() {
+              action_tx.send(Action::LoadTableStats)?;
+            }
+          },
+          Action::LoadTableStats => {
+            if let Some(task) = table_stats_task.take() {
+              task.abort();
+            }
+            match database.start_table_stats() {
+              Ok(task) => table_stats_task = task,
+              Err(e) => log::error!("{e:?}"),
+            }
           },
           Action::Query(query_lines, confirmed, bypass) => 'query_action: {
             let query_string = query_lines.clone().join(" \n");
             if query_string.is_empty() {
               break 'query_action;
             }
-            self.add_to_history(query_lines.clone());
+            let qualify_choice = self.query_dispatched(query_lines, *confirmed, driver);
+            // statements of a query that has several run one after another, on one
+            // connection, once the script as a whole is confirmed
+            if self.batch.is_none()
+              && !bypass
+              && let Ok(statements) = database::split_statements(&query_string, driver)
+              && statements.len() > 1
+            {
+              if !confirmed && let Some(statement) = self.script_confirmation(&statements, driver) {
+                self.set_popup(Box::new(ConfirmQuery::script(query_string, statement, statements.len())));
+                break 'query_action;
+              }
+              self.components.data.clear_result_sets();
+              if let Err(e) = database.pin_connection().await {
+                self.components.data.set_data_state(Some(Err(e)), None);
+                break 'query_action;
+              }
+              self.connection_pinned = true;
+              let mut statements = VecDeque::from(statements);
+              let first = statements.pop_front().unwrap_or_default();
+              self.batch = Some(statements);
+              action_tx.send(Action::Query(vec![first], true, false))?;
+              break 'query_action;
+            }
             if *bypass && !confirmed {
               log::warn!("Bypassing parser");
               self.set_popup(Box::new(ConfirmBypass::new(query_string.clone())));
               break 'query_action;
             }
+            let qualify_tables = self.config.settings.qualify_tables.unwrap_or_default();
+            let as_written = qualify_choice == Some(true);
+            let qualified = match qualify_tables {
+              QualifyTables::Off => None,
+              _ if *bypass || as_written => None,
+              _ => self.catalog.qualify_query(&query_string, driver),
+            };
+            let query_string = match qualified {
+              // the statements of a script run as they were written, rather than asking about each
+              Some(_) if qualify_tables == QualifyTables::Warn && self.batch.is_some() => query_string,
+              Some((qualified_query, qualifications)) if qualify_tables == QualifyTables::Warn => {
+                self.set_popup(Box::new(ConfirmQualify::new(query_string, qualified_query, qualifications)));
+                break 'query_action;
+              },
+              Some((qualified_query, qualifications)) => {
+                log::info!("Qualified table names: {qualifications:?}");
+                qualified_query
+              },
+              None => query_string,
+            };
+            let page_size = self.config.settings.page_size.unwrap_or(0);
+            let paged = match page_size {
+              0 => None,
+              _ if *bypass || self.batch.is_some() => None,
+              _ => database::paginate(&query_string, driver, page_size, 0),
+            };
+            // a streamed query fetches its first page the same way, and more as it's scrolled
+            let stream = self.config.settings.stream_results.unwrap_or_default();
+            self.state.cursor = paged.as_ref().filter(|_| stream).map(|_| Cursor::new(query_string.clone(), page_size));
+            self.state.page = paged.as_ref().filter(|_| !stream).map(|_| Page {
+              query: query_string.clone(),
+              size: page_size,
+              offset: 0,
+            });
+            if let Err(e) = database.close_cursor().await {
+              log::error!("failed to close the cursor: {e:?}");
+            }
+            let query_string = paged.unwrap_or(query_string);
             let execution_info = match *bypass && *confirmed {
               true => Ok((ExecutionType::Normal, None)),
               false => database::get_execution_type(query_string.clone(), *confirmed, driver),
             };
             match execution_info {
+              // DELETE and UPDATE are asked about before they run, and still run in a
+              // transaction once confirmed, so the rows affected can be rolled back
+              Ok((ExecutionType::Transaction, Some(statement)))
+                if self.state.confirm_destructive && database::is_destructive(&statement) =>
+              {
+                self.set_popup(Box::new(ConfirmQuery::new(query_string.clone(), statement, true)));
+              },
               Ok((ExecutionType::Transaction, _)) => {
                 self.components.data.set_loading();
-                database.start_tx(query_string).await?;
-                self.state.last_query_start = Some(chrono::Utc::now());
-                self.state.last_query_end = None;
+                database.start_tx(query_string.clone()).await?;
+                self.query_started(query_string, self.batch.is_none());
               },
               Ok((ExecutionType::Confirm, Some(statement_type))) => {
-                self.set_popup(Box::new(ConfirmQuery::new(query_string.clone(), statement_type)));
+                self.set_popup(Box::new(ConfirmQuery::new(query_string.clone(), statement_type, false)));
               },
               Ok((ExecutionType::Normal, _)) => {
                 self.components.data.set_loading();
-                database.start_query(query_string, *bypass).await?;
-                self.state.last_query_start = Some(chrono::Utc::now());
-                self.state.last_query_end = None;
+                let server_side = match self.state.cursor.as_mut() {
+                  Some(cursor) => {
+                    cursor.fetching = true;
+                    cursor.server_side = database.start_cursor(cursor.query.clone(), cursor.page_size).await?;
+                    cursor.server_side
+                  },
+                  None => false,
+                };
+                if !server_side {
+                  database.start_query(query_string.clone(), *bypass).await?;
+                }
+                self.query_started(query_string, self.batch.is_none());
+              },
+              Err(e) if self.batch.is_some() => {
+                self.continue_batch(QueryResultsWithMetadata { results: Err(e), statement_type: None }, &action_tx)?;
               },
               Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
               _ => self.components.data.set_data_state(Some(Err(eyre!("Missing statement type but not bypass"))), None),
             }
           },
+          Action::NextPage | Action::PrevPage if !self.state.query_task_running => {
+            if let Some(page) = self.state.page.as_mut() {
+              page.offset = match action {
+                Action::NextPage => page.offset.saturating_add(page.size),
+                _ => page.offset.saturating_sub(page.size),
+              };
+              if let Some(query) = database::paginate(&page.query, driver, page.size, page.offset) {
+                self.components.data.set_loading();
+                database.start_query(query.clone(), false).await?;
+                self.query_started(query, false);
+              }
+            }
+          },
+          Action::LoadMoreRows => {
+            let more = self.state.cursor.as_ref().is_some_and(Cursor::has_more);
+            match self.state.cursor.as_mut() {
+              Some(cursor) if more && !self.state.query_task_running => {
+                let started = match cursor.server_side {
+                  true => database.fetch_cursor(cursor.page_size).await,
+                  false => match cursor.next_page_query(driver) {
+                    Some(query) => database.start_query(query, false).await,
+                    None => Err(eyre!("The query can't be fetched a page at a time")),
+                  },
+                };
+                match started {
+                  Ok(()) => {
+                    cursor.fetching = true;
+                    let query = cursor.query.clone();
+                    self.query_started(query, false);
+                  },
+                  Err(e) => {
+                    let text = format!("couldn't load more rows: {e}");
+                    cursor.record(&Err(e));
+                    self.components.data.set_more_rows(false);
+                    action_tx.send(Action::StatusMessage(text, StatusLevel::Error))?;
+                  },
+                }
+              },
+              // another query is running, so the rows are asked for again on the next scroll
+              _ => self.components.data.set_more_rows(more),
+            }
+          },
           Action::AbortQuery => match database.abort_query().await {
             Ok(true) => {
-              self.components.data.set_cancelled();
+              self.batch = None;
+              self.focus_data_on_finish = false;
+              // cancelling a streamed query keeps the rows it already fetched
+              let cursor = self.state.cursor.as_mut().filter(|cursor| cursor.fetching);
+              match cursor.as_ref().map(|cursor| cursor.fetched > 0) {
+                Some(true) => self.components.data.set_more_rows(false),
+                _ => self.components.data.set_cancelled(),
+              }
+              if let Some(cursor) = cursor {
+                cursor.close();
+                if let Err(e) = database.close_cursor().await {
+                  log::error!("failed to close the cursor: {e:?}");
+                }
+              }
+              action_tx.send(Action::StatusMessage("query cancelled".to_owned(), StatusLevel::Warning))?;
               self.state.last_query_end = Some(chrono::Utc::now());
+              self.history_stats_pending = false;
             },
             Ok(false) => {},
             Err(e) => {
@@ -438,11 +1377,26 @@ impl App {
               },
               MenuPreview::Indexes => database.preview_indexes_query(target.schema.as_str(), target.name.as_str()),
               MenuPreview::Policies => database.preview_policies_query(target.schema.as_str(), target.name.as_str()),
+              MenuPreview::ForeignKeys => {
+                database.preview_foreign_keys_query(target.schema.as_str(), target.name.as_str())
+              },
+              MenuPreview::Triggers => database.preview_triggers_query(target.schema.as_str(), target.name.as_str()),
+              MenuPreview::RoutineSource => match target.kind {
+                MenuItemKind::Routine { procedure } => {
+                  database.preview_routine_source_query(target.schema.as_str(), target.name.as_str(), procedure)
+                },
+                _ => "select 'Source preview is only available for functions and procedures' as message".to_owned(),
+              },
               MenuPreview::Definition => match target.kind {
                 MenuItemKind::View { materialized } => {
                   database.preview_view_definition_query(target.schema.as_str(), target.name.as_str(), materialized)
                 },
-                MenuItemKind::Table => "select 'Definition preview is only available for views' as message".to_owned(),
+                MenuItemKind::ForeignTable => {
+                  database.preview_foreign_table_query(target.schema.as_str(), target.name.as_str())
+                },
+                MenuItemKind::Table | MenuItemKind::Routine { .. } => {
+                  "select 'Definition preview is only available for views' as message".to_owned()
+                },
               },
             };
             action_tx.send(Action::QueryToEditor(vec![preview_query.clone()]))?;
@@ -450,6 +1404,38 @@ impl App {
             action_tx.send(Action::FocusMenu)?;
             action_tx.send(Action::Query(vec![preview_query.clone()], false, false))?;
           },
+          Action::FormatQueryLines(lines) => match database::format_query(&lines.join("\n"), driver) {
+            Ok(formatted) => action_tx.send(Action::FormattedQuery(formatted))?,
+            Err(e) => {
+              log::error!("{e:?}");
+              action_tx.send(Action::StatusMessage(format!("could not format query: {e}"), StatusLevel::Warning))?;
+            },
+          },
+          Action::CopyQualifiedName(target) => {
+            let name = match target.kind {
+              MenuItemKind::Routine { .. } => database::routine_name(&target.name),
+              _ => target.name.as_str(),
+            };
+            action_tx.send(Action::CopyData(database::qualified_name(&target.schema, name, driver)))?;
+          },
+          Action::EditRoutine(target) => {
+            if let MenuItemKind::Routine { procedure } = target.kind {
+              match database.load_routine_source(&target.schema, &target.name, procedure).await {
+                Ok(source) => {
+                  action_tx.send(Action::QueryToEditor(source.lines().map(str::to_owned).collect()))?;
+                  action_tx.send(Action::FocusEditor)?;
+                },
+                Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
+              }
+            }
+          },
+          Action::ShowCreateTable(target) => match database.load_create_statement(&target.schema, &target.name).await {
+            Ok(statement) => {
+              let name = database::qualified_name(&target.schema, &target.name, driver);
+              self.set_popup(Box::new(CellDetail::statement(&name, statement)));
+            },
+            Err(e) => action_tx.send(Action::StatusMessage(e.to_string(), StatusLevel::Warning))?,
+          },
 
           Action::RequestSaveFavorite(query_lines) => {
             self.set_popup(Box::new(NameFavorite::new(
@@ -457,30 +1443,172 @@ impl App {
               query_lines.clone(),
             )));
           },
+          Action::RequestLoadSqlFile => self.request_sql_file(SqlFileMode::Load),
+          Action::RequestSaveSqlFile => self.request_sql_file(SqlFileMode::Save),
+          Action::RunFavorite(query_lines) => {
+            let placeholders = database::find_placeholders(&query_lines.join("\n"));
+            if placeholders.is_empty() {
+              action_tx.send(Action::Query(query_lines.clone(), false, false))?;
+            } else {
+              self.set_popup(Box::new(FillPlaceholders::new(query_lines.clone(), placeholders)));
+            }
+          },
+          Action::RequestExportFavorites => self.request_sql_file(SqlFileMode::ExportFavorites),
+          Action::RequestImportFavorites => self.request_sql_file(SqlFileMode::ImportFavorites),
+          Action::ExportFavorites(path) => match self.state.favorites.export(path) {
+            Ok(count) => {
+              log::info!("Exported {count} favorites to {}", path.display());
+              let text = format!("exported {count} favorites to {}", path.display());
+              action_tx.send(Action::StatusMessage(text, StatusLevel::Info))?;
+            },
+            Err(e) => self.components.data.set_data_state(Some(Err(e.wrap_err("Exporting favorites failed"))), None),
+          },
+          Action::ImportFavorites(path) => match self.state.favorites.import(path) {
+            Ok(conflicts) if conflicts.is_empty() => {
+              let text = format!("imported favorites from {}", path.display());
+              action_tx.send(Action::StatusMessage(text, StatusLevel::Info))?;
+            },
+            Ok(conflicts) => self.set_popup(Box::new(ImportFavorites::new(conflicts))),
+            Err(e) => self.components.data.set_data_state(Some(Err(e.wrap_err("Importing favorites failed"))), None),
+          },
+          // the editor does the reading and writing, since it owns the buffer, and
+          // says which file it was once that worked
+          Action::SqlFileFinished(path) => {
+            self.last_sql_file = Some(path.clone());
+          },
+          Action::Error(e) => {
+            self.components.data.set_data_state(Some(Err(eyre!(e.clone()))), None);
+          },
+          Action::SaveFavorite(folder, name, query_lines) => {
+            let name = match folder {
+              Some(folder) => format!("{folder}/{name}"),
+              None => name.clone(),
+            };
+            action_tx.send(Action::StatusMessage(format!("saved favorite {name}"), StatusLevel::Info))?;
+            self.state.favorites.add_entry(name, query_lines.clone());
+          },
           Action::DeleteFavorite(name) => {
             self.state.favorites.delete_entry(name.clone());
+            action_tx.send(Action::StatusMessage(format!("deleted favorite {name}"), StatusLevel::Info))?;
+          },
+          Action::RequestRenameFavorite(name) => {
+            self.set_popup(Box::new(NameFavorite::rename(
+              self.state.favorites.iter().map(|f| f.get_name().to_string()).collect(),
+              name.clone(),
+            )));
+          },
+          Action::RenameFavorite(old_name, new_name) => match self.state.favorites.rename_entry(old_name, new_name) {
+            Ok(()) => {
+              let text = format!("renamed favorite {old_name} to {new_name}");
+              action_tx.send(Action::StatusMessage(text, StatusLevel::Info))?;
+            },
+            Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
           },
           Action::ClearHistory => {
             self.clear_history();
           },
           Action::CopyData(data) => {
             #[cfg(feature = "arboard")]
-            {
-              clipboard.as_mut().map_or_else(
-                |e| {
-                  log::error!("{e:?}");
-                },
-                |clipboard| {
-                  clipboard.set_text(data).unwrap_or_else(|e| {
-                    log::error!("{e:?}");
-                  })
+            action_tx.send(copy_to_clipboard(&mut clipboard, data, "copied to clipboard", StatusLevel::Info))?;
+            self.last_focused_component();
+          },
+          Action::CopyCell => {
+            // the raw value, not the truncated text drawn in the table
+            #[cfg(feature = "arboard")]
+            if let Some(value) = self.components.data.focused_cell() {
+              let (text, message) = if database::is_null(&value) {
+                (String::new(), "copied NULL (empty)")
+              } else {
+                (value, "copied value")
+              };
+              action_tx.send(copy_to_clipboard(&mut clipboard, &text, message, StatusLevel::Info))?;
+            }
+          },
+          Action::CopyRow => {
+            // NULLs are left empty, like a copied cell
+            #[cfg(feature = "arboard")]
+            if let Some(row) = self.components.data.focused_row() {
+              let delimiter = self.config.settings.copy_row_delimiter.unwrap_or_default();
+              let text = join_values(&row, delimiter.as_str());
+              action_tx.send(copy_to_clipboard(&mut clipboard, &text, "copied row", StatusLevel::Info))?;
+            }
+          },
+          Action::CopyInList => {
+            let column = self.components.data.focused_column();
+            match column.and_then(|(header, values)| database::in_list(&header, &values, IN_LIST_MAX_VALUES, driver)) {
+              Some((condition, count)) => {
+                // the editor keeps it to paste with `p`, even without a clipboard
+                self.components.editor.update(Action::CopyData(condition.clone()), &self.state)?;
+                let (message, level) = if count > IN_LIST_MAX_VALUES {
+                  (format!("copied IN list of the first {IN_LIST_MAX_VALUES} of {count} values"), StatusLevel::Warning)
+                } else {
+                  (format!("copied IN list of {count} values"), StatusLevel::Info)
+                };
+                #[cfg(feature = "arboard")]
+                action_tx.send(copy_to_clipboard(&mut clipboard, &condition, &message, level))?;
+                #[cfg(not(feature = "arboard"))]
+                action_tx.send(Action::StatusMessage(message, level))?;
+              },
+              None => {
+                let message = "no values to make an IN list from".to_owned();
+                action_tx.send(Action::StatusMessage(message, StatusLevel::Warning))?;
+              },
+            }
+          },
+          Action::CopyColumn => {
+            // one value per line, from the rows shown
+            #[cfg(feature = "arboard")]
+            if let Some((_, column)) = self.components.data.focused_column() {
+              let message = format!("copied {} values", column.len());
+              action_tx.send(copy_to_clipboard(
+                &mut clipboard,
+                &join_values(&column, "\n"),
+                &message,
+                StatusLevel::Info,
+              ))?;
+            }
+          },
+          Action::EditCell => {
+            let edit = match self.components.data.editable_cell() {
+              Some((statement, headers, column, row)) => match database::editable_table(&statement, &headers, driver) {
+                Ok(table) => match database.primary_key(table.schema.as_deref(), &table.table).await {
+                  Ok(key) => table.cell_edit(&key, column, &row, &headers),
+                  Err(e) => Err(e),
                 },
-              );
+                Err(e) => Err(e),
+              },
+              None => Err(eyre!("select a value in the results to edit it")),
+            };
+            match edit {
+              Ok(edit) => self.set_popup(Box::new(EditCell::new(edit))),
+              Err(e) => action_tx.send(Action::StatusMessage(e.to_string(), StatusLevel::Warning))?,
             }
-            self.last_focused_component();
           },
           Action::RequestExportData(row_count) => {
-            self.set_popup(Box::new(ConfirmExport::new(*row_count)));
+            self.set_popup(Box::new(ConfirmExport::new(*row_count, self.config.export.clone())));
+          },
+          Action::StreamExportData(format, options, query) => {
+            // the results are re-fetched from the query they came from rather than copied
+            // from memory, so exports aren't limited by the size of the result set
+            let name = format!("rainfrog_export_{}.{}", chrono::Utc::now().timestamp(), file_extension(format));
+            let task = database::get_execution_type(query.clone(), false, driver).and_then(|(_, statement)| {
+              if !matches!(statement, Some(Statement::Query(_))) {
+                return Err(eyre!("Only the results of a SELECT can be exported by re-running it"));
+              }
+              let sink = new_file_sink(format, options, &get_export_dir().join(name))?;
+              let sink = Box::new(ProgressSink::new(sink, action_tx.clone()));
+              database.start_export(query.clone(), sink)
+            });
+            match task {
+              Ok(task) => export_task = Some(task),
+              Err(e) => {
+                self.components.data.set_data_state(Some(Err(e.wrap_err("Export failed"))), None);
+                action_tx.send(Action::ExportDataFinished)?;
+              },
+            }
+          },
+          Action::ExportDataProgress(row_count) if export_task.is_some() => {
+            self.set_popup(Box::new(Exporting::with_progress(*row_count)));
           },
           Action::ExportDataFinished => {
             self.set_focus(Focus::Data);
@@ -488,6 +1616,160 @@ impl App {
           Action::RequestYankAll(row_count) => {
             self.set_popup(Box::new(ConfirmYank::new(*row_count)));
           },
+          Action::RequestCopyAs(row_count) => {
+            self.set_popup(Box::new(CopyAs::new(*row_count)));
+          },
+          Action::RequestCellDetail(column_name, type_name, composite_fields, value) => {
+            self.set_popup(Box::new(CellDetail::new(column_name, type_name, composite_fields.as_deref(), value)));
+          },
+          Action::RequestSwitchConnection => {
+            let names = self.config.db.keys().cloned().collect();
+            let popup = SwitchConnection::new(SwitchTarget::Connection, names, self.connection_name.clone());
+            self.set_popup(Box::new(popup));
+          },
+          Action::SwitchConnection(name) => 'switch: {
+            let Some(connection) = self.config.db.get(name) else {
+              break 'switch;
+            };
+            // the new connection is made before the old one is dropped, so a
+            // failed switch leaves things as they were
+            let connected = match connection.url(name) {
+              Ok(url) => {
+                let mut new =
+                  new_database(connection.driver, self.config.settings.max_rows_fetched.unwrap_or_default());
+                let args = Cli {
+                  mouse_mode: None,
+                  connection_url: Some(url),
+                  user: None,
+                  password: None,
+                  host: None,
+                  port: None,
+                  database: None,
+                  driver: Some(connection.driver),
+                  schema: connection.schema.clone(),
+                  startup_sql: connection.startup_sql.clone(),
+                  startup_sql_required: connection.startup_sql_required,
+                  fresh: false,
+                };
+                new.init(args).await.map(|_| new)
+              },
+              Err(e) => Err(e),
+            };
+            match connected {
+              Ok(new) => {
+                database.abort_query().await.ok();
+                if let Some(task) = table_stats_task.take() {
+                  task.abort();
+                }
+                database = new;
+                driver = connection.driver;
+                log::info!("Switched to {name} ({driver:?})");
+                // the session so far belongs to the connection being left, and the new one's
+                // last query is kept for its own session
+                self.save_session();
+                let session_file = SessionFile::new(&self.config.config._data_dir, Some(name));
+                self.last_query = match session_file.load() {
+                  Ok(session) => session.and_then(|session| session.last_query),
+                  Err(e) => {
+                    log::error!("failed to read the saved session: {e:?}");
+                    None
+                  },
+                };
+                self.session_file = Some(session_file);
+                // the query tabs are kept, and saved for the new connection from their next change
+                if self.scratch_file.is_some() {
+                  self.autosave_scratch(true);
+                  self.scratch_file = Some(ScratchFile::new(&self.config.config._data_dir, Some(name)));
+                }
+                self.connection_name = Some(name.clone());
+                self.set_connection(database.as_ref());
+                self.catalog = SchemaCatalog::default();
+                self.state.page = None;
+                self.state.cursor = None;
+                self.state.query_task_running = false;
+                self.batch = None;
+                self.components.data.set_data_state(None, None);
+                action_tx.send(Action::LoadMenu)?;
+                action_tx.send(Action::StatusMessage(format!("connected to {name}"), StatusLevel::Info))?;
+              },
+              Err(e) => {
+                let e = e.wrap_err(format!("Couldn't connect to {name}"));
+                self.components.data.set_data_state(Some(Err(e)), None);
+              },
+            }
+          },
+          Action::RequestUseDatabase | Action::UseDatabase(_) if self.query_running() => {
+            let text = "the database can't be changed while a query is running".to_owned();
+            action_tx.send(Action::StatusMessage(text, StatusLevel::Warning))?;
+          },
+          Action::RequestUseDatabase => match database.list_databases().await {
+            Ok((names, current)) => {
+              self.set_popup(Box::new(SwitchConnection::new(SwitchTarget::Database, names, current)))
+            },
+            Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
+          },
+          // the editors and their results are kept, since only the database changes
+          Action::UseDatabase(name) => match database.use_database(name).await {
+            Ok(()) => {
+              log::info!("Using database {name}");
+              self.set_connection(database.as_ref());
+              if let Some(task) = table_stats_task.take() {
+                task.abort();
+              }
+              self.catalog = SchemaCatalog::default();
+              self.state.page = None;
+              self.state.cursor = None;
+              self.components.data.set_more_rows(false);
+              for tab in self.query_tabs.iter_mut().flatten() {
+                tab.page = None;
+                tab.cursor = None;
+              }
+              action_tx.send(Action::LoadMenu)?;
+              action_tx.send(Action::StatusMessage(format!("using database {name}"), StatusLevel::Info))?;
+            },
+            Err(e) => {
+              let e = e.wrap_err(format!("Couldn't switch to database {name}"));
+              self.components.data.set_data_state(Some(Err(e)), None);
+            },
+          },
+          // other tabs can be used while a query runs, but closing one would move its results
+          Action::CloseQueryTab if self.query_running() => {
+            let text = "query tabs can't be closed while a query is running".to_owned();
+            action_tx.send(Action::StatusMessage(text, StatusLevel::Warning))?;
+          },
+          Action::NewQueryTab => {
+            let size = tui.size()?;
+            let tab = self.new_query_tab(&action_tx, Rect { width: size.width, height: size.height, x: 0, y: 0 })?;
+            self.open_query_tab(tab);
+            self.set_focus(Focus::Editor);
+          },
+          Action::CloseQueryTab if self.query_tabs.len() < 2 => {
+            let text = "the last query tab can't be closed".to_owned();
+            action_tx.send(Action::StatusMessage(text, StatusLevel::Warning))?;
+          },
+          Action::CloseQueryTab if self.components.editor.is_modified() => {
+            let name = query_tab_label(self.query_tab, self.last_sql_file.as_ref(), false);
+            self.set_popup(Box::new(ConfirmCloseTab::new(name)));
+          },
+          Action::CloseQueryTab => self.close_query_tab(),
+          Action::NextQueryTab | Action::PreviousQueryTab | Action::SelectQueryTab(_) => {
+            let count = self.query_tabs.len();
+            let index = match &action {
+              Action::NextQueryTab => (self.query_tab + 1) % count,
+              Action::PreviousQueryTab => (self.query_tab + count - 1) % count,
+              Action::SelectQueryTab(n) => n.saturating_sub(1).min(count - 1),
+              _ => self.query_tab,
+            };
+            self.select_query_tab(index);
+          },
+          Action::Help if self.state.focus != Focus::PopUp => {
+            let help = KeybindingHelp::new(self.state.focus, &self.config.keybindings, self.config.theme.clone());
+            self.set_popup(Box::new(help));
+          },
+          Action::CommandPalette if self.state.focus != Focus::PopUp => {
+            let palette = CommandPalette::new(self.state.focus, &self.config.keybindings, self.config.theme.clone());
+            self.set_popup(Box::new(palette));
+          },
           _ => {},
         }
         if !action_consumed {
@@ -505,6 +1787,9 @@ impl App {
             }
           }
         }
+        if let Some(tab) = shown_tab {
+          self.select_query_tab(tab);
+        }
       }
 
       if self.last_frame_mouse_event.is_some() {
@@ -513,6 +1798,8 @@ impl App {
         })?;
       }
       if self.should_quit {
+        self.autosave_scratch(true);
+        self.save_session();
         database.abort_query().await?;
         tui.stop()?;
         break;
@@ -526,22 +1813,51 @@ impl App {
     let hints_layout = Layout::default()
       .direction(Direction::Vertical)
       .constraints(match f.area().width {
-        x if x < 160 => [Constraint::Fill(1), Constraint::Length(2)],
-        _ => [Constraint::Fill(1), Constraint::Length(1)],
+        x if x < 160 => [Constraint::Fill(1), Constraint::Length(2), Constraint::Length(1)],
+        _ => [Constraint::Fill(1), Constraint::Length(1), Constraint::Length(1)],
       })
       .split(f.area());
+    let menu_width = self.config.settings.layout_menu_width.unwrap_or(25);
+    let editor_size = self.config.settings.layout_editor_size.unwrap_or(45);
     let root_layout = Layout::default()
       .direction(Direction::Horizontal)
-      .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+      .constraints([Constraint::Percentage(menu_width), Constraint::Percentage(100_u16.saturating_sub(menu_width))])
       .split(hints_layout[0]);
     let right_layout = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+      .direction(match self.layout_split {
+        LayoutSplit::Vertical => Direction::Vertical,
+        LayoutSplit::Horizontal => Direction::Horizontal,
+      })
+      .constraints([Constraint::Percentage(editor_size), Constraint::Percentage(100_u16.saturating_sub(editor_size))])
       .split(root_layout[1]);
+    // zooming follows the focus, so the cycle only skips panes the layout
+    // has no room for, and moves the zoom along otherwise
+    self.visible_panes = [
+      (Focus::Menu, root_layout[0]),
+      (Focus::Editor, right_layout[0]),
+      (Focus::Data, right_layout[1]),
+      (Focus::History, right_layout[0]),
+      (Focus::Favorites, right_layout[0]),
+    ]
+    .into_iter()
+    .filter(|(_, area)| !area.is_empty())
+    .map(|(focus, _)| focus)
+    .collect();
+    // a zoomed pane takes the whole screen, and the others aren't drawn
+    let zoomed = match self.state.focus {
+      Focus::PopUp => self.last_focused_component,
+      focus => focus,
+    };
+    let (menu_area, tabs_area, data_area) = match self.state.zoomed.then_some(zoomed) {
+      Some(Focus::Menu) => (hints_layout[0], Rect::default(), Rect::default()),
+      Some(Focus::Editor | Focus::History | Focus::Favorites) => (Rect::default(), hints_layout[0], Rect::default()),
+      Some(Focus::Data) => (Rect::default(), Rect::default(), hints_layout[0]),
+      _ => (root_layout[0], right_layout[0], right_layout[1]),
+    };
     let tabs_layout = Layout::default()
       .direction(Direction::Vertical)
       .constraints([Constraint::Length(1), Constraint::Fill(1)])
-      .split(right_layout[0]);
+      .split(tabs_area);
 
     if let Some(event) = &self.last_frame_mouse_event
       && self.popup.is_none()
@@ -549,10 +1865,10 @@ impl App {
       && !matches!(event.kind, MouseEventKind::Down(_))
     {
       let position = Position::new(event.column, event.row);
-      let menu_target = root_layout[0];
+      let menu_target = menu_area;
       let tabs_target = tabs_layout[0];
       let tab_content_target = tabs_layout[1];
-      let data_target = right_layout[1];
+      let data_target = data_area;
       if menu_target.contains(position) {
         self.set_focus(Focus::Menu);
       } else if data_target.contains(position) {
@@ -585,43 +1901,69 @@ impl App {
       }
     }
     let tabs = Tabs::new(vec![" 󰤏 query <alt+2>", "   history <alt+4>", "   favorites <alt+5>"])
-      .highlight_style(Style::new().fg(self.state.focus.tab_color()).reversed())
+      .highlight_style(Style::new().fg(self.state.focus.tab_color(&self.config.theme)).reversed())
       .select(self.last_focused_tab.tab_index())
       .padding(" ", "")
       .divider(" ");
 
     let state = &self.state;
 
-    f.render_widget(tabs, tabs_layout[0]);
-    f.render_widget(Clear, tabs_layout[1]);
+    if !tabs_area.is_empty() {
+      f.render_widget(tabs, tabs_layout[0]);
+      f.render_widget(Clear, tabs_layout[1]);
 
-    match self.last_focused_tab {
-      Focus::Editor => {
-        self.components.editor.draw(f, tabs_layout[1], state).unwrap();
-      },
-      Focus::History => {
-        self.components.history.draw(f, tabs_layout[1], state).unwrap();
-      },
-      Focus::Favorites => {
-        self.components.favorites.draw(f, tabs_layout[1], state).unwrap();
-      },
-      Focus::Menu | Focus::Data | Focus::PopUp => (),
-    };
+      match self.last_focused_tab {
+        Focus::Editor if self.query_tabs.len() > 1 => {
+          let [query_tabs_area, editor_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(tabs_layout[1]);
+          let query_tabs = Tabs::new(self.query_tab_labels())
+            .style(self.config.theme.muted)
+            .highlight_style(self.config.theme.highlight)
+            .select(self.query_tab)
+            .padding(" ", " ")
+            .divider("|");
+          f.render_widget(query_tabs, query_tabs_area);
+          self.components.editor.draw(f, editor_area, state).unwrap();
+        },
+        Focus::Editor => {
+          self.components.editor.draw(f, tabs_layout[1], state).unwrap();
+        },
+        Focus::History => {
+          self.components.history.draw(f, tabs_layout[1], state).unwrap();
+        },
+        Focus::Favorites => {
+          self.components.favorites.draw(f, tabs_layout[1], state).unwrap();
+        },
+        Focus::Menu | Focus::Data | Focus::PopUp => (),
+      };
+    }
 
-    self.components.menu.draw(f, root_layout[0], state).unwrap();
-    self.components.data.draw(f, right_layout[1], state).unwrap();
+    if !menu_area.is_empty() {
+      self.components.menu.draw(f, menu_area, state).unwrap();
+    }
+    if !data_area.is_empty() {
+      self.components.data.draw(f, data_area, state).unwrap();
+    }
     self.render_hints(f, hints_layout[1]);
+    self.render_status(f, hints_layout[2]);
 
     if let Some(popup) = &self.popup {
       self.render_popup(f, popup.as_ref());
     }
+    self.config.theme.strip_colors(f.buffer_mut());
     Ok(())
   }
 
   fn render_hints(&self, frame: &mut Frame, area: Rect) {
     let block = Block::default().style(Style::default().fg(Color::Blue));
     let help_text = format!(
-      "{}{}",
+      "{}{}{}{}",
+      match self.state.focus {
+        Focus::PopUp => "",
+        Focus::Editor => "[<f1>] keybindings ",
+        _ => "[?] keybindings ",
+      },
+      if self.state.zoomed && self.state.focus != Focus::PopUp { "[<alt + z>] unzoom " } else { "" },
       match self.state.query_task_running {
         false => "",
         _ if self.state.focus == Focus::Editor => "[<alt + q>] abort ",
@@ -630,14 +1972,15 @@ impl App {
       },
       match self.state.focus {
         Focus::Menu =>
-          "[R] refresh [j|↓] down [k|↑] up [l|<enter>] table list [h|󰁮 ] schema list [/] search [g] top [G] bottom",
+          "[R] refresh [j|↓] down [k|↑] up [l|<enter>] table list [h|󰁮 ] schema list [/] search [y] copy name [s] sizes [g] top [G] bottom",
         Focus::Editor if !self.state.query_task_running =>
-          "[<alt + enter>|<f5>] execute query [<ctrl + f>|<alt + f>] save query to favorites",
-        Focus::History => "[j|↓] down [k|↑] up [y] copy query [I] edit query [D] clear history",
+          "[<alt + enter>|<f5>] execute query [<f6>] format query [<ctrl + o>] open file [<ctrl + s>] save file [<ctrl + f>|<alt + f>] save query to favorites",
+        Focus::History =>
+          "[j|↓] down [k|↑] up [y] copy query [I|<enter>] edit query [/] search [n|N] next|prev match [D] clear history",
         Focus::Favorites =>
-          "[j|↓] down [k|↑] up [y] copy query [I] edit query [D] delete entry [/] search [<esc>] clear search",
+          "[j|↓] down [k|↑] up [y] copy query [I] edit query [<enter>] open folder|edit query [x] run [r] rename|move [D] delete entry [P] export all [O] import [/] search [<esc>] clear search",
         Focus::Data if !self.state.query_task_running =>
-          "[P] export [j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [V] select row [y] copy [Y] copy all [g] top [G] bottom [0] first col [$] last col",
+          "[P] export [j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [V] select row [<enter>] expand field [ctrl+v] select range [s] sort [/] filter [[|]] prev|next page or more rows [(|)] prev|next result [f|F] freeze|unfreeze col [<|>] narrower|wider col [z] utc|local time [y] copy [Y] copy all [C] copy as [g] top [G] bottom [:] go to row [0] first col [$] last col",
         Focus::PopUp => "[<esc>] cancel",
         _ => "",
       }
@@ -646,11 +1989,32 @@ impl App {
     frame.render_widget(paragraph, area);
   }
 
+  fn render_status(&self, frame: &mut Frame, area: Rect) {
+    // the start of a binding of several keys, while the rest of it is waited for
+    if let Some(keys) = self.pending_keys.describe() {
+      frame
+        .render_widget(Paragraph::new(Line::styled(format!("{keys} "), self.config.theme.title).right_aligned()), area);
+    }
+    let Some(status) = &self.status else {
+      return;
+    };
+    let style = match status.level {
+      _ if status.shown_at.elapsed() >= STATUS_FADE => self.config.theme.muted,
+      StatusLevel::Info => Style::default(),
+      StatusLevel::Warning => self.config.theme.title,
+      StatusLevel::Error => self.config.theme.error,
+    };
+    frame.render_widget(Paragraph::new(Line::styled(format!(" {}", status.text), style)), area);
+  }
+
   fn render_popup(&self, frame: &mut Frame, popup: &dyn PopUp) {
+    if popup.draw(frame, frame.area()) {
+      return;
+    }
     let area = center(frame.area(), Constraint::Percentage(50), Constraint::Percentage(50));
     let block = Block::default()
       .borders(Borders::ALL)
-      .border_style(Style::default().fg(Color::Yellow))
+      .border_style(self.config.theme.title)
       .title(Line::from(" Confirm Action ").centered())
       .padding(Padding::uniform(1));
     let layout = Layout::default()
@@ -666,3 +2030,292 @@ impl App {
     frame.render_widget(popup_actions, center(layout[1], Constraint::Fill(1), Constraint::Percentage(50)));
   }
 }
+
+// `max_rows` caps the rows kept of each query's results, with 0 keeping all of them
+fn new_database(driver: Driver, max_rows: u64) -> Box<dyn Database> {
+  match driver {
+    Driver::Postgres => Box::new(database::PostgresDriver::new(max_rows)),
+    Driver::MySql => Box::new(database::MySqlDriver::new(max_rows)),
+    Driver::Sqlite => Box::new(database::SqliteDriver::new(max_rows)),
+    Driver::Oracle => Box::new(database::OracleDriver::new(max_rows)),
+    #[cfg(feature = "duckdb")]
+    Driver::DuckDb => Box::new(database::DuckDbDriver::new(max_rows)),
+  }
+}
+
+// the order panes are focused in by `Tab`, wrapping at both ends
+const FOCUS_CYCLE: [Focus; 5] = [Focus::Menu, Focus::Editor, Focus::Data, Focus::History, Focus::Favorites];
+
+// the failures of the startup sql, as one status message
+fn startup_errors_message(errors: &[String]) -> Option<String> {
+  match errors {
+    [] => None,
+    [error] => Some(format!("startup sql failed: {error}")),
+    [error, rest @ ..] => Some(format!("startup sql failed: {error} (and {} more)", rest.len())),
+  }
+}
+
+// the next pane in the cycle that's visible, or the same one if none are
+fn cycle_focus(focus: Focus, visible: &[Focus], forwards: bool) -> Focus {
+  let Some(start) = FOCUS_CYCLE.iter().position(|f| *f == focus) else {
+    return focus;
+  };
+  (1..FOCUS_CYCLE.len())
+    .map(|step| match forwards {
+      true => FOCUS_CYCLE[(start + step) % FOCUS_CYCLE.len()],
+      false => FOCUS_CYCLE[(start + FOCUS_CYCLE.len() - step) % FOCUS_CYCLE.len()],
+    })
+    .find(|f| visible.contains(f))
+    .unwrap_or(focus)
+}
+
+// the file the tab's editor was last loaded from or saved to, or its number
+fn query_tab_label(index: usize, sql_file: Option<&PathBuf>, modified: bool) -> String {
+  let name = sql_file
+    .and_then(|path| path.file_name())
+    .map_or_else(|| format!("query {}", index + 1), |name| name.to_string_lossy().into_owned());
+  match modified {
+    true => format!("{name} *"),
+    false => name,
+  }
+}
+
+// a one line summary of a query's results for the status line
+fn query_status(
+  results: &Result<Rows>,
+  statement: Option<&Statement>,
+  duration: Option<chrono::Duration>,
+) -> (String, StatusLevel) {
+  let took = match duration {
+    Some(duration) => format!(" in {}", format_duration(duration)),
+    None => "".to_owned(),
+  };
+  let took = match (results, statement) {
+    (Ok(rows), Some(Statement::Explain { .. })) => match server_times(rows) {
+      Some(times) => format!("{took} ({times})"),
+      None => took,
+    },
+    _ => took,
+  };
+  match results {
+    Ok(rows) if rows.headers.is_empty() && statement.is_some_and(|s| !matches!(s, Statement::Query(_))) => {
+      (format!("{}{took}", database::command_tag(statement.unwrap(), rows.rows_affected)), StatusLevel::Info)
+    },
+    Ok(rows) if rows.headers.is_empty() => match rows.rows_affected {
+      Some(1) => (format!("query ok, 1 row affected{took}"), StatusLevel::Info),
+      Some(n) => (format!("query ok, {n} rows affected{took}"), StatusLevel::Info),
+      None => (format!("query ok{took}"), StatusLevel::Info),
+    },
+    Ok(rows) if rows.rows.len() == 1 => (format!("query ok, 1 row{took}"), StatusLevel::Info),
+    Ok(rows) => (format!("query ok, {} rows{took}", rows.rows.len()), StatusLevel::Info),
+    Err(e) => (format!("query failed: {}", first_line(e)), StatusLevel::Error),
+  }
+}
+
+// postgres ends the plan from `EXPLAIN ANALYZE` with how long the server spent
+// planning and running the query, as `Planning Time: 0.085 ms`, or as fields of
+// the plan in json
+fn server_times(rows: &Rows) -> Option<String> {
+  if let [row] = rows.rows.as_slice()
+    && let [json] = row.as_slice()
+    && let Some(plan) = parse_plan(json)
+  {
+    return match (plan.planning_time, plan.execution_time) {
+      (Some(planning), Some(execution)) => Some(format!("planning {planning}ms, execution {execution}ms")),
+      (None, Some(execution)) => Some(format!("execution {execution}ms")),
+      _ => None,
+    };
+  }
+  let time = |label: &str| {
+    rows.rows.iter().filter_map(|row| row.first()).find_map(|line| {
+      let time = line.trim().strip_prefix(label)?.trim();
+      Some(time.strip_suffix(" ms").map_or_else(|| time.to_owned(), |ms| format!("{ms}ms")))
+    })
+  };
+  match (time("Planning Time:"), time("Execution Time:")) {
+    (Some(planning), Some(execution)) => Some(format!("planning {planning}, execution {execution}")),
+    (None, Some(execution)) => Some(format!("execution {execution}")),
+    _ => None,
+  }
+}
+
+// raw values as copied to the clipboard, with NULL left empty
+#[cfg(feature = "arboard")]
+fn join_values(values: &[String], delimiter: &str) -> String {
+  values.iter().map(|value| database::display_value(value, "")).collect::<Vec<_>>().join(delimiter)
+}
+
+// the status to show for copying `text`, which is `message` if it worked
+#[cfg(feature = "arboard")]
+fn copy_to_clipboard(
+  clipboard: &mut Result<Clipboard, arboard::Error>,
+  text: &str,
+  message: &str,
+  level: StatusLevel,
+) -> Action {
+  let copied = match clipboard.as_mut() {
+    Ok(clipboard) => clipboard.set_text(text).map_err(|e| e.to_string()),
+    Err(e) => Err(e.to_string()),
+  };
+  match copied {
+    Ok(()) => Action::StatusMessage(message.to_owned(), level),
+    Err(e) => {
+      log::error!("{e:?}");
+      Action::StatusMessage(format!("couldn't copy to clipboard: {e}"), StatusLevel::Error)
+    },
+  }
+}
+
+fn first_line(e: &color_eyre::eyre::Report) -> String {
+  e.to_string().lines().next().unwrap_or_default().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_cycle_focus() {
+    let cycle = |visible: &[Focus], forwards: bool| {
+      std::iter::successors(Some(Focus::Menu), |focus| Some(cycle_focus(*focus, visible, forwards)))
+        .take(7)
+        .collect::<Vec<_>>()
+    };
+    use Focus::*;
+    assert_eq!(cycle(&FOCUS_CYCLE, true), vec![Menu, Editor, Data, History, Favorites, Menu, Editor]);
+    assert_eq!(cycle(&FOCUS_CYCLE, false), vec![Menu, Favorites, History, Data, Editor, Menu, Favorites]);
+    // with no room for the menu, it's skipped, and focus never lands on it after leaving
+    assert_eq!(
+      cycle(&[Editor, Data, History, Favorites], true),
+      vec![Menu, Editor, Data, History, Favorites, Editor, Data]
+    );
+    assert_eq!(cycle(&[Menu, Data], false), vec![Menu, Data, Menu, Data, Menu, Data, Menu]);
+    assert_eq!(cycle_focus(PopUp, &FOCUS_CYCLE, true), PopUp);
+    assert_eq!(cycle_focus(Data, &[], true), Data);
+  }
+
+  #[test]
+  fn test_confirmed_queries_are_recorded_once() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_app_history_{}", std::process::id()));
+    let mut config = Config::default();
+    config.config._data_dir = dir.clone();
+    config.config._favorites_dir = dir.join("favorites");
+    let mut app = App::new(None, config)?;
+    let query_lines = vec!["delete from users".to_owned(), "where id = 1".to_owned()];
+
+    // submitted from the editor, then sent again as one line once confirmed
+    assert_eq!(app.query_dispatched(&query_lines, false, Driver::Postgres), None);
+    app.query_dispatched(&[query_lines.join(" \n")], true, Driver::Postgres);
+    assert_eq!(app.state.history.len(), 1);
+    assert_eq!(app.state.history[0].query_lines, query_lines);
+    assert_eq!(app.state.history[0].run_count, 1);
+
+    // nor is the qualified query sent once that's picked
+    let qualified = "delete from \"sales\".users \nwhere id = 1".to_owned();
+    app.qualify_choice = Some((qualified.clone(), false));
+    assert_eq!(app.query_dispatched(&[qualified], false, Driver::Postgres), Some(false));
+    assert_eq!(app.state.history.len(), 1);
+    assert_eq!(app.state.history[0].run_count, 1);
+
+    // running it again is another run
+    app.query_dispatched(&query_lines, false, Driver::Postgres);
+    assert_eq!(app.state.history.len(), 1);
+    assert_eq!(app.state.history[0].run_count, 2);
+    std::fs::remove_dir_all(dir).ok();
+    Ok(())
+  }
+
+  #[test]
+  fn test_query_tab_label() {
+    assert_eq!(query_tab_label(0, None, false), "query 1");
+    assert_eq!(query_tab_label(2, None, true), "query 3 *");
+    let path = PathBuf::from("/scripts/report.sql");
+    assert_eq!(query_tab_label(1, Some(&path), false), "report.sql");
+    assert_eq!(query_tab_label(1, Some(&path), true), "report.sql *");
+  }
+
+  #[test]
+  #[cfg(feature = "arboard")]
+  fn test_join_values() {
+    let values = vec!["1".to_owned(), database::NULL.to_owned(), "a b".to_owned()];
+    assert_eq!(join_values(&values, crate::config::CopyDelimiter::Tab.as_str()), "1\t\ta b");
+    assert_eq!(join_values(&values, crate::config::CopyDelimiter::Comma.as_str()), "1, , a b");
+    assert_eq!(join_values(&values[..1], "\n"), "1");
+  }
+
+  #[test]
+  fn test_query_status() {
+    let rows = |count: usize, rows_affected: Option<u64>| Rows {
+      headers: if count == 0 {
+        vec![]
+      } else {
+        vec![database::Header { name: "id".to_owned(), type_name: "int4".to_owned(), composite_fields: None }]
+      },
+      rows: vec![vec![]; count],
+      rows_affected,
+    };
+    let ms = chrono::Duration::milliseconds;
+    let test_cases = vec![
+      (
+        Ok(rows(42, None)),
+        Some(chrono::Duration::microseconds(13_240)),
+        "query ok, 42 rows in 13.2ms",
+        StatusLevel::Info,
+      ),
+      (Ok(rows(1, None)), Some(ms(1500)), "query ok, 1 row in 1.500s", StatusLevel::Info),
+      (Ok(rows(0, Some(3))), None, "query ok, 3 rows affected", StatusLevel::Info),
+      (Ok(rows(0, None)), Some(ms(2)), "query ok in 2.0ms", StatusLevel::Info),
+      (
+        Err(eyre!("relation \"x\" does not exist\nLINE 1")),
+        None,
+        "query failed: relation \"x\" does not exist",
+        StatusLevel::Error,
+      ),
+    ];
+    for (results, duration, expected_text, expected_level) in test_cases {
+      assert_eq!(query_status(&results, None, duration), (expected_text.to_owned(), expected_level));
+    }
+
+    let (_, statement) =
+      database::get_execution_type("update users set a = 1".to_owned(), false, Driver::Postgres).unwrap();
+    assert_eq!(
+      query_status(&Ok(rows(0, Some(12))), statement.as_ref(), Some(ms(13))),
+      ("UPDATE 12 in 13.0ms".to_owned(), StatusLevel::Info)
+    );
+
+    let (_, statement) =
+      database::get_execution_type("explain analyze select 1".to_owned(), false, Driver::Postgres).unwrap();
+    let plan = Rows {
+      headers: vec![database::Header {
+        name: "QUERY PLAN".to_owned(),
+        type_name: "TEXT".to_owned(),
+        composite_fields: None,
+      }],
+      rows: vec![
+        vec!["Result  (cost=0.00..0.01 rows=1 width=4) (actual time=0.001..0.001 rows=1 loops=1)".to_owned()],
+        vec!["Planning Time: 0.030 ms".to_owned()],
+        vec!["Execution Time: 0.012 ms".to_owned()],
+      ],
+      rows_affected: None,
+    };
+    assert_eq!(
+      query_status(&Ok(plan), statement.as_ref(), Some(ms(4))),
+      ("query ok, 3 rows in 4.0ms (planning 0.030ms, execution 0.012ms)".to_owned(), StatusLevel::Info)
+    );
+    let json = r#"[{"Plan": {"Node Type": "Result", "Startup Cost": 0.0, "Total Cost": 0.01, "Plan Rows": 1,
+      "Actual Rows": 1, "Actual Loops": 1, "Actual Total Time": 0.001}, "Planning Time": 0.03, "Execution Time": 0.012}]"#;
+    let plan = Rows {
+      headers: vec![database::Header {
+        name: "QUERY PLAN".to_owned(),
+        type_name: "JSON".to_owned(),
+        composite_fields: None,
+      }],
+      rows: vec![vec![json.to_owned()]],
+      rows_affected: None,
+    };
+    assert_eq!(
+      query_status(&Ok(plan), statement.as_ref(), Some(ms(4))),
+      ("query ok, 1 row in 4.0ms (planning 0.03ms, execution 0.012ms)".to_owned(), StatusLevel::Info)
+    );
+  }
+}
@@ -0,0 +1,129 @@
+// keys typed towards a binding of several keys, like `<g><g>`. they're
+// forgotten once too long passes between them, so keys typed a while apart
+// don't run a binding by accident.
+use std::{
+  collections::HashMap,
+  time::{Duration, Instant},
+};
+
+use crossterm::event::KeyEvent;
+
+use crate::{action::Action, config::key_event_to_string};
+
+pub struct PendingKeys {
+  keys: Vec<KeyEvent>,
+  last_pressed: Option<Instant>,
+  timeout: Duration,
+}
+
+impl PendingKeys {
+  /// Keys that are forgotten `timeout_ms` after the last one was pressed.
+  pub fn new(timeout_ms: u64) -> Self {
+    Self { keys: vec![], last_pressed: None, timeout: Duration::from_millis(timeout_ms) }
+  }
+
+  /// Adds a key pressed at `now`, and returns the action of the binding the
+  /// keys make up, if they make one up. Keys that can't lead to a binding
+  /// are dropped, starting over from the new key if it can.
+  pub fn press(&mut self, key: KeyEvent, now: Instant, keymap: &HashMap<Vec<KeyEvent>, Action>) -> Option<Action> {
+    self.expire(now);
+    self.keys.push(key);
+    self.last_pressed = Some(now);
+    if let Some(action) = keymap.get(&self.keys) {
+      self.clear();
+      return Some(action.clone());
+    }
+    if !is_prefix(&self.keys, keymap) {
+      self.keys = match is_prefix(&[key], keymap) {
+        true => vec![key],
+        false => vec![],
+      };
+    }
+    None
+  }
+
+  /// Forgets the keys if the timeout has passed since the last one was
+  /// pressed. Returns whether they were forgotten.
+  pub fn expire(&mut self, now: Instant) -> bool {
+    match self.last_pressed {
+      Some(last) if !self.keys.is_empty() && now.saturating_duration_since(last) >= self.timeout => {
+        self.clear();
+        true
+      },
+      _ => false,
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.keys.clear();
+    self.last_pressed = None;
+  }
+
+  pub fn keys(&self) -> &[KeyEvent] {
+    &self.keys
+  }
+
+  /// The keys as they're written in the config, like `<g>`, to show while the
+  /// rest of the binding is waited for.
+  pub fn describe(&self) -> Option<String> {
+    match self.keys.is_empty() {
+      true => None,
+      false => Some(self.keys.iter().map(|key| format!("<{}>", key_event_to_string(key))).collect()),
+    }
+  }
+}
+
+// whether the keys are the start of a binding that needs more of them
+fn is_prefix(keys: &[KeyEvent], keymap: &HashMap<Vec<KeyEvent>, Action>) -> bool {
+  keymap.keys().any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::parse_key_sequence;
+
+  fn key(raw: &str) -> KeyEvent {
+    parse_key_sequence(raw).unwrap()[0]
+  }
+
+  fn keymap() -> HashMap<Vec<KeyEvent>, Action> {
+    HashMap::from([
+      (parse_key_sequence("<g><g>").unwrap(), Action::FocusMenu),
+      (parse_key_sequence("<d><d>").unwrap(), Action::FocusData),
+    ])
+  }
+
+  #[test]
+  fn test_pending_keys() {
+    let keymap = keymap();
+    let mut pending = PendingKeys::new(500);
+    let start = Instant::now();
+    assert_eq!(pending.press(key("<g>"), start, &keymap), None);
+    assert_eq!(pending.describe().as_deref(), Some("<g>"));
+    assert_eq!(pending.press(key("<g>"), start + Duration::from_millis(200), &keymap), Some(Action::FocusMenu));
+    assert!(pending.keys().is_empty());
+
+    // a key that can't lead anywhere is dropped, and one that can starts over
+    assert_eq!(pending.press(key("<x>"), start, &keymap), None);
+    assert!(pending.keys().is_empty());
+    pending.press(key("<g>"), start, &keymap);
+    assert_eq!(pending.press(key("<d>"), start, &keymap), None);
+    assert_eq!(pending.keys(), &[key("<d>")]);
+  }
+
+  #[test]
+  fn test_pending_keys_timeout() {
+    let keymap = keymap();
+    let mut pending = PendingKeys::new(500);
+    let start = Instant::now();
+    pending.press(key("<g>"), start, &keymap);
+    assert_eq!(pending.press(key("<g>"), start + Duration::from_millis(600), &keymap), None);
+    assert_eq!(pending.keys(), &[key("<g>")]);
+
+    assert!(!pending.expire(start + Duration::from_millis(1_000)));
+    assert!(pending.expire(start + Duration::from_millis(1_100)));
+    assert!(pending.keys().is_empty());
+    assert_eq!(pending.describe(), None);
+  }
+}
@@ -5,10 +5,11 @@ use std::{
 
 use clap::Parser;
 use color_eyre::eyre::{self, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
   config::{Config, DatabaseConnection},
+  database::StartupSql,
   utils::version,
 };
 
@@ -48,9 +49,32 @@ pub struct Cli {
 
   #[arg(long = "driver", value_name = "DRIVER", help = "Driver for database connection (ex. postgres)")]
   pub driver: Option<Driver>,
+
+  #[arg(
+    long = "schema",
+    value_name = "SCHEMA",
+    help = "Schemas to look up unqualified table names in, set as the search_path of each connection (ex. app,public)"
+  )]
+  pub schema: Option<String>,
+
+  #[arg(
+    long = "startup-sql",
+    value_name = "FILE",
+    help = "SQL file to run on each new connection, like SET statement_timeout or temporary views"
+  )]
+  pub startup_sql: Option<StartupSql>,
+
+  #[arg(
+    long = "startup-sql-required",
+    help = "Fail to connect if the startup SQL fails, instead of only reporting it"
+  )]
+  pub startup_sql_required: bool,
+
+  #[arg(long = "fresh", help = "Start without restoring the last session or the saved query tabs of the connection")]
+  pub fresh: bool,
 }
 
-#[derive(Parser, Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[derive(Parser, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Driver {
   #[serde(alias = "postgres", alias = "POSTGRES")]
   Postgres,
@@ -1,29 +1,101 @@
-use std::collections::VecDeque;
+use std::{
+  borrow::Cow,
+  collections::{HashMap, VecDeque},
+};
 
 use color_eyre::eyre::{self, Result};
-use crossterm::event::{KeyEvent, MouseEventKind};
-use csv::Writer;
+use crossterm::event::{KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
 use ratatui::{prelude::*, symbols::scrollbar, widgets::*};
+use serde::Serialize;
 use sqlparser::ast::Statement;
 use tokio::sync::mpsc::UnboundedSender;
 use tui_textarea::{Input, Key};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use super::{
   Frame,
-  scroll_table::{COLUMN_SPACING, ScrollDirection, ScrollTable, SelectionMode},
+  scroll_table::{ScrollDirection, ScrollTable, SelectionMode, TableCells},
 };
 use crate::{
-  action::Action,
+  action::{Action, StatusLevel},
   app::AppState,
   components::Component,
-  config::Config,
-  database::{Rows, header_to_vec, statement_type_string},
+  config::{Config, TypeDisplay},
+  database::{
+    DbError, Header, Headers, Rows, command_tag, display_value, format_array_inline, format_composite_inline,
+    format_range, is_array_type, is_binary_type, is_boolean_type, is_json_type, is_null, is_numeric_type,
+    is_range_type, parse_array, parse_composite,
+  },
+  diff::{RowChange, diff_results},
+  export::{file_extension, format_rows, new_file_sink},
+  filter::{CellSearch, RowFilter, next_match},
   focus::Focus,
+  plan::{PlanTree, VisibleNode, parse_plan},
+  sort::{SortOrder, sort_rows, unsort_rows},
+  theme::Theme,
+  timezone::{DisplayZone, format_in_zone, is_timestamp_type},
   utils::get_export_dir,
 };
 
 const MAX_COLUMN_WIDTH: u16 = 36;
+const DEFAULT_WRAP_MAX_LINES: u16 = 5;
+const COLUMN_RESIZE_STEP: i32 = 2;
+// `\x` and 16 bytes of hex
+const BINARY_PREVIEW_CHARS: usize = 34;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+  #[default]
+  Plain,
+  Json,
+  Binary,
+  Timestamp(DisplayZone),
+  // right-aligned
+  Number,
+  Boolean {
+    symbols: bool,
+  },
+  // styled by a `[display.types]` rule
+  Styled(Style),
+  // each distinct value in its own color
+  Values,
+  // postgres arrays, shown as a list with how many elements it has
+  Array,
+  // postgres ranges and composites, shown without their quoting
+  Range,
+  Composite,
+}
+// the colors values are given by a `values` display rule
+const VALUE_COLORS: [Color; 12] = [
+  Color::Cyan,
+  Color::Green,
+  Color::Yellow,
+  Color::Magenta,
+  Color::Blue,
+  Color::Red,
+  Color::LightCyan,
+  Color::LightGreen,
+  Color::LightYellow,
+  Color::LightMagenta,
+  Color::LightBlue,
+  Color::LightRed,
+];
 const TITLE_CELL_PREVIEW_MAX_CHARS: usize = 96;
+// queries run in the background, so the loading message spins while they do
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_FRAME_MS: i64 = 100;
+// how close to the last fetched row the cursor gets before a streamed query
+// fetches more
+const LOAD_MORE_MARGIN: usize = 50;
+
+// the results of one statement of a query that had several. errors are kept as
+// a `DbError`, since reports can't be cloned to be shown again.
+#[derive(Clone)]
+struct ResultSet {
+  results: std::result::Result<Rows, DbError>,
+  statement_type: Option<Statement>,
+  source_query: Option<String>,
+}
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Default)]
@@ -34,10 +106,13 @@ pub enum DataState<'a> {
   NoResults,
   HasResults(Rows),
   Explain(Text<'a>),
+  // a plan from `EXPLAIN (FORMAT JSON)`, shown as a tree
+  Plan(PlanTree),
   Error(eyre::Report),
   Cancelled,
   RowsAffected(u64),
-  StatementCompleted(Statement),
+  // a statement that returned no rows, with how many it affected
+  StatementCompleted(Statement, Option<u64>),
 }
 
 #[derive(Clone, Debug)]
@@ -48,24 +123,167 @@ pub struct ExplainOffsets {
 
 pub trait SettableDataTable<'a> {
   fn set_data_state(&mut self, data: Option<Result<Rows>>, statement_type: Option<Statement>);
+  // result sets keep the results of each statement of a query that had several,
+  // and the newest is shown
+  fn clear_result_sets(&mut self);
+  fn push_result_set(&mut self, data: Result<Rows>, statement_type: Option<Statement>);
+  // the query the results shown came from, as it was written, so exporting
+  // them can re-run it. showing other results forgets it.
+  fn set_source_query(&mut self, query: Option<String>);
   fn set_loading(&mut self);
   fn set_cancelled(&mut self);
+  // adds the next rows of a streamed query to the results shown, and whether
+  // there are more to fetch after them
+  fn append_rows(&mut self, rows: Rows, more: bool);
+  fn set_more_rows(&mut self, more: bool);
+  // notes that the results were cut short at the `max_rows_fetched` setting
+  fn set_truncated(&mut self, max_rows: u64);
+  // the raw value under the cursor, when results are shown
+  fn focused_cell(&self) -> Option<String>;
+  // the raw values of the row or column under the cursor, from the rows shown
+  fn focused_row(&self) -> Option<Vec<String>>;
+  fn focused_column(&self) -> Option<(Header, Vec<String>)>;
+  // the statement the results came from, their columns, and the column and row
+  // under the cursor, to edit the value there
+  fn editable_cell(&self) -> Option<(Statement, Headers, usize, Vec<String>)>;
 }
 
 pub trait DataComponent<'a>: Component + SettableDataTable<'a> {}
 impl<'a, T> DataComponent<'a> for T where T: Component + SettableDataTable<'a> {}
 
+// builds the cells of the results as they come into view, so results with a
+// million rows don't need a million rows of cells
+struct ResultCells<'d> {
+  rows: &'d Rows,
+  cell_kinds: Vec<CellKind>,
+  null: &'d str,
+  max_lines: usize,
+  sort: Option<(usize, SortOrder)>,
+  // in the order they were found, row by row
+  search_matches: &'d [(usize, usize)],
+  // how each row differs from the pinned results, while they're compared
+  row_changes: &'d [RowChange],
+  theme: &'d Theme,
+}
+
+// a comparison with the pinned results, shown in place of the results it was made from
+struct ShownDiff {
+  changes: Vec<RowChange>,
+  summary: String,
+  // the column rows were matched by, when it wasn't all of them
+  key: Option<String>,
+  results: Rows,
+}
+
+impl TableCells for ResultCells<'_> {
+  fn header(&self, column: usize, width: u16) -> Cell<'_> {
+    let Some(h) = self.rows.headers.get(column) else {
+      return Cell::default();
+    };
+    let header_name = match self.sort {
+      Some((sorted, SortOrder::Ascending)) if sorted == column => format!("▲ {}", h.name),
+      Some((sorted, SortOrder::Descending)) if sorted == column => format!("▼ {}", h.name),
+      _ => h.name.clone(),
+    };
+    let header_name = Data::clamp_render_text(&header_name, width as usize);
+    let header_type = Data::clamp_render_text(&h.type_name, width as usize);
+    let header = Text::from(vec![Line::from(header_name), Line::from(header_type)]);
+    match self.cell_kinds.get(column) {
+      Some(CellKind::Number) => Cell::from(header.right_aligned()),
+      _ => Cell::from(header),
+    }
+  }
+
+  fn cell(&self, row: usize, column: usize, width: u16) -> Cell<'_> {
+    let Some(value) = self.rows.rows.get(row).and_then(|r| r.get(column)) else {
+      return Cell::default();
+    };
+    let kind = self.cell_kinds.get(column).copied().unwrap_or_default();
+    let text = Text::from_iter(Data::cell_lines(value, self.null, kind, width, self.max_lines));
+    let cell = match is_null(value) {
+      true if kind == CellKind::Number => Cell::from(text.style(self.theme.null_value).right_aligned()),
+      true => Cell::from(text.style(self.theme.null_value)),
+      false if kind == CellKind::Number => Cell::from(text.right_aligned()),
+      false if kind == CellKind::Json => Cell::from(text.cyan()),
+      false if kind == CellKind::Binary => Cell::from(text.magenta()),
+      false if matches!(kind, CellKind::Boolean { .. }) => match value.as_str() {
+        "true" => Cell::from(text.style(self.theme.true_value)),
+        "false" => Cell::from(text.style(self.theme.false_value)),
+        _ => Cell::from(text),
+      },
+      false => match kind {
+        // timestamps that weren't shown in the display zone don't have a zone of their own
+        CellKind::Timestamp(zone) if format_in_zone(value, zone).is_none() => Cell::from(text.italic()),
+        CellKind::Styled(style) => Cell::from(text.style(style)),
+        CellKind::Values => Cell::from(text.fg(value_color(value))),
+        _ => Cell::from(text),
+      },
+    };
+    let cell = match self.row_changes.get(row) {
+      Some(RowChange::Added) => cell.style(self.theme.added_row),
+      Some(RowChange::Removed) => cell.style(self.theme.removed_row),
+      Some(RowChange::Changed(columns)) if columns.contains(&column) => cell.style(self.theme.changed_value),
+      _ => cell,
+    };
+    match self.search_matches.binary_search_by_key(&(row, column), |(x, y)| (*y, *x)) {
+      Ok(_) => cell.style(self.theme.highlight.reversed()),
+      Err(_) => cell,
+    }
+  }
+}
+
 #[derive(Default)]
 pub struct Data<'a> {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
   scrollable: ScrollTable<'a>,
   data_state: DataState<'a>,
+  // what was shown before the running query started, to go back to if it's cancelled
+  before_loading: Option<DataState<'a>>,
+  // the query the results came from, which can be re-run to stream them
+  // straight to an export file when it's a plain query
+  source_query: Option<String>,
+  // the statement the results came from
+  statement: Option<Statement>,
+  // the column the rows are sorted by, and the position each row was fetched in
+  sort: Option<(usize, SortOrder)>,
+  row_positions: Vec<usize>,
+  // while a filter is active, the table shows the matching rows and the full
+  // results are kept here
+  filter: Option<RowFilter>,
+  filter_focused: bool,
+  filter_error: Option<String>,
+  unfiltered: Option<Rows>,
+  // finds cells without hiding rows. matches are the (column, row) of each
+  // matching cell, and the origin is where the cursor was when the search started
+  search: Option<CellSearch>,
+  search_focused: bool,
+  search_matches: Vec<(usize, usize)>,
+  search_origin: (usize, usize),
+  display_zone: DisplayZone,
+  // whether long text wraps onto more lines instead of being cut off
+  wrap_text: bool,
+  // columns whose width was changed by hand, which stick until the next query
+  resized_columns: HashMap<usize, u16>,
+  // the row number being typed after `:`
+  row_jump: Option<String>,
+  // results kept to compare the results of later queries with, and the
+  // comparison while it's shown
+  pinned: Option<Rows>,
+  diff: Option<ShownDiff>,
   explain_scroll: Option<ExplainOffsets>,
   explain_width: u16,
   explain_height: u16,
   explain_max_x_offset: u16,
   explain_max_y_offset: u16,
+  // the results of each statement of the last query, when it had several
+  result_sets: Vec<ResultSet>,
+  result_set_index: usize,
+  // whether the results are from a streamed query with rows left to fetch,
+  // and whether they're being fetched
+  more_rows: bool,
+  loading_more: bool,
+  truncated_at: Option<u64>,
 }
 
 impl Data<'_> {
@@ -75,14 +293,136 @@ impl Data<'_> {
       config: Config::default(),
       scrollable: ScrollTable::default(),
       data_state: DataState::Blank,
+      before_loading: None,
+      source_query: None,
+      statement: None,
+      sort: None,
+      row_positions: vec![],
+      filter: None,
+      filter_focused: false,
+      filter_error: None,
+      unfiltered: None,
+      search: None,
+      search_focused: false,
+      search_matches: vec![],
+      search_origin: (0, 0),
+      display_zone: DisplayZone::default(),
+      wrap_text: false,
+      resized_columns: HashMap::new(),
+      row_jump: None,
+      pinned: None,
+      diff: None,
       explain_scroll: None,
       explain_width: 0,
       explain_height: 0,
       explain_max_x_offset: 0,
       explain_max_y_offset: 0,
+      result_sets: vec![],
+      result_set_index: 0,
+      more_rows: false,
+      loading_more: false,
+      truncated_at: None,
     }
   }
 
+  fn show_results(&mut self, data: Option<Result<Rows>>, statement_type: Option<Statement>) {
+    self.before_loading = None;
+    self.explain_width = 0;
+    self.explain_height = 0;
+    self.explain_max_x_offset = 0;
+    self.explain_max_y_offset = 0;
+    self.explain_scroll = None;
+    self.scrollable = ScrollTable::default();
+    self.source_query = None;
+    self.statement = statement_type.clone();
+    self.sort = None;
+    self.row_positions = vec![];
+    self.filter = None;
+    self.filter_focused = false;
+    self.filter_error = None;
+    self.unfiltered = None;
+    self.search = None;
+    self.search_focused = false;
+    self.search_matches = vec![];
+    self.row_jump = None;
+    self.diff = None;
+    self.resized_columns.clear();
+    self.more_rows = false;
+    self.loading_more = false;
+    self.truncated_at = None;
+    match data {
+      Some(Ok(rows)) => {
+        if rows.rows.is_empty()
+          && let Some(statement) = statement_type.as_ref().filter(|s| !matches!(s, Statement::Query(_)))
+        {
+          self.data_state = DataState::StatementCompleted(statement.clone(), rows.rows_affected);
+        } else if rows.rows.is_empty() && rows.rows_affected.is_some_and(|n| n > 0) {
+          self.data_state = DataState::RowsAffected(rows.rows_affected.unwrap());
+        } else if rows.rows.is_empty() && rows.headers.is_empty() {
+          // a result with columns but no rows falls through to an empty table
+          // instead, so the columns and their types can still be seen
+          self.data_state = DataState::NoResults;
+        } else if matches!(statement_type, Some(Statement::Explain { .. }))
+          && let [row] = rows.rows.as_slice()
+          && let [json] = row.as_slice()
+          && let Some(plan) = parse_plan(json)
+        {
+          self.data_state = DataState::Plan(PlanTree::new(plan, json.clone()));
+        } else if matches!(statement_type, Some(Statement::Explain { .. })) {
+          let null = self.null_placeholder();
+          let lines = rows
+            .rows
+            .iter()
+            .map(|r| r.iter().map(|v| display_value(v, null)).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>();
+          self.explain_width = lines.iter().fold(0_u16, |acc, l| acc.max(l.len() as u16));
+          self.explain_height = lines.len() as u16;
+          self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset: 0 });
+          self.data_state = DataState::Explain(Text::from_iter(lines));
+        } else {
+          self.set_table(&rows);
+          self.data_state = DataState::HasResults(rows);
+        }
+      },
+      Some(Err(e)) => {
+        self.data_state = DataState::Error(e);
+      },
+      _ => {
+        self.data_state = DataState::Blank;
+      },
+    }
+  }
+
+  fn show_result_set(&mut self, index: usize) {
+    let Some(set) = self.result_sets.get(index).cloned() else {
+      return;
+    };
+    self.result_set_index = index;
+    self.show_results(Some(set.results.map_err(eyre::Report::new)), set.statement_type);
+    self.source_query = set.source_query;
+  }
+
+  // asks for the next rows of a streamed query once the cursor gets near the
+  // last row fetched
+  fn load_more_near_end(&mut self) -> Result<()> {
+    let DataState::HasResults(rows) = &self.data_state else {
+      return Ok(());
+    };
+    let (_, y) = self.scrollable.get_cell_offsets();
+    if y.saturating_add(LOAD_MORE_MARGIN) >= rows.rows.len() {
+      self.load_more()?;
+    }
+    Ok(())
+  }
+
+  fn load_more(&mut self) -> Result<()> {
+    if self.more_rows && !self.loading_more {
+      self.loading_more = true;
+      self.command_tx.clone().unwrap().send(Action::LoadMoreRows)?;
+    }
+    Ok(())
+  }
+
   pub fn scroll(&mut self, direction: ScrollDirection) {
     if let DataState::Explain(_) = self.data_state {
       if let Some(offsets) = self.explain_scroll.clone() {
@@ -105,6 +445,13 @@ impl Data<'_> {
           },
         };
       }
+    } else if let DataState::Plan(tree) = &mut self.data_state {
+      match direction {
+        ScrollDirection::Up => tree.select_by(-1),
+        ScrollDirection::Down => tree.select_by(1),
+        ScrollDirection::Left => tree.collapse(),
+        ScrollDirection::Right => tree.expand(),
+      }
     } else if let DataState::HasResults(_) = self.data_state {
       self.scrollable.scroll(direction);
     }
@@ -120,6 +467,8 @@ impl Data<'_> {
           self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset: 0 });
         },
       }
+    } else if let DataState::Plan(tree) = &mut self.data_state {
+      tree.select_first();
     } else if let DataState::HasResults(_) = self.data_state {
       self.scrollable.top_row();
     }
@@ -135,6 +484,8 @@ impl Data<'_> {
           self.explain_scroll = Some(ExplainOffsets { y_offset: self.explain_max_y_offset, x_offset: 0 });
         },
       }
+    } else if let DataState::Plan(tree) = &mut self.data_state {
+      tree.select_last();
     } else if let DataState::HasResults(_) = self.data_state {
       self.scrollable.bottom_row();
     }
@@ -171,14 +522,118 @@ impl Data<'_> {
   }
 
   fn column_widths(&self, rows: &Rows) -> Vec<u16> {
-    if self.config.settings.data_compact_columns.unwrap_or(false) {
-      Self::compact_column_widths(rows)
+    let max_width = self.max_column_width();
+    let mut widths = if self.config.settings.data_compact_columns.unwrap_or(false) {
+      Self::compact_column_widths(rows, self.null_placeholder(), &self.cell_kinds(&rows.headers), max_width)
     } else {
-      vec![MAX_COLUMN_WIDTH; rows.headers.len()]
+      vec![max_width; rows.headers.len()]
+    };
+    for (column, width) in &self.resized_columns {
+      if let Some(w) = widths.get_mut(*column) {
+        *w = *width;
+      }
+    }
+    widths
+  }
+
+  fn max_column_width(&self) -> u16 {
+    self.config.settings.data_max_column_width.unwrap_or(MAX_COLUMN_WIDTH).max(1)
+  }
+
+  fn toggle_display_zone(&mut self) {
+    self.display_zone = self.display_zone.toggled();
+    self.rebuild_table();
+  }
+
+  fn toggle_wrap_text(&mut self) {
+    self.wrap_text = !self.wrap_text;
+    self.rebuild_table();
+  }
+
+  // rebuilds the table after a change to how it's shown
+  fn rebuild_table(&mut self) {
+    if !matches!(self.data_state, DataState::HasResults(_)) {
+      return;
+    }
+    let DataState::HasResults(rows) = std::mem::take(&mut self.data_state) else {
+      return;
+    };
+    self.set_table(&rows);
+    self.data_state = DataState::HasResults(rows);
+  }
+
+  // grows or shrinks the current column, starting from the width it has now
+  fn resize_column(&mut self, by: i32) {
+    let (column, _) = self.scrollable.get_cell_offsets();
+    let DataState::HasResults(rows) = &self.data_state else {
+      return;
+    };
+    if let Some(width) = self.column_widths(rows).get(column) {
+      let width = (i32::from(*width) + by).clamp(1, i32::from(u16::MAX)) as u16;
+      self.resized_columns.insert(column, width);
+      self.rebuild_table();
     }
   }
 
-  fn compact_column_widths(rows: &Rows) -> Vec<u16> {
+  fn null_placeholder(&self) -> &str {
+    self.config.settings.null_placeholder.as_deref().unwrap_or_default()
+  }
+
+  // how each column's values are shown. json is only collapsed onto one line, and numbers
+  // right-aligned, when that's enabled.
+  fn cell_kinds(&self, headers: &Headers) -> Vec<CellKind> {
+    let inline_json = self.config.settings.data_inline_json.unwrap_or(false);
+    let align_numbers = self.config.settings.data_align_numbers.unwrap_or(false);
+    let boolean_symbols = self.config.display.boolean_symbols;
+    headers
+      .iter()
+      .map(|h| match self.config.display.type_display(&h.type_name) {
+        Some(TypeDisplay::Style(style)) => CellKind::Styled(style),
+        Some(TypeDisplay::Values) => CellKind::Values,
+        None if h.composite_fields.is_some() => CellKind::Composite,
+        None => match &h.type_name {
+          t if is_array_type(t) => CellKind::Array,
+          t if is_range_type(t) => CellKind::Range,
+          t if is_boolean_type(t) => CellKind::Boolean { symbols: boolean_symbols },
+          t if inline_json && is_json_type(t) => CellKind::Json,
+          t if is_binary_type(t) => CellKind::Binary,
+          t if is_timestamp_type(t) => CellKind::Timestamp(self.display_zone),
+          t if align_numbers && is_numeric_type(t) => CellKind::Number,
+          _ => CellKind::Plain,
+        },
+      })
+      .collect()
+  }
+
+  // the text a value is shown as in the table
+  fn cell_text<'v>(value: &'v str, null: &'v str, kind: CellKind) -> Cow<'v, str> {
+    match (is_null(value), kind) {
+      (true, _) => Cow::Borrowed(null),
+      (false, CellKind::Json) => inline_json(value).map_or(Cow::Borrowed(value), Cow::Owned),
+      // the full value is in the cell detail popup
+      (false, CellKind::Binary) if value.len() > BINARY_PREVIEW_CHARS => {
+        Cow::Owned(format!("{}…", value.get(..BINARY_PREVIEW_CHARS).unwrap_or(value)))
+      },
+      (false, CellKind::Timestamp(zone)) => format_in_zone(value, zone).map_or(Cow::Borrowed(value), Cow::Owned),
+      (false, CellKind::Array) => match parse_array(value) {
+        Some(elements) => Cow::Owned(format!("{} ({})", format_array_inline(&elements, null), elements.len())),
+        None => Cow::Borrowed(value),
+      },
+      (false, CellKind::Range) => format_range(value).map_or(Cow::Borrowed(value), Cow::Owned),
+      (false, CellKind::Composite) => match parse_composite(value) {
+        Some(fields) => Cow::Owned(format_composite_inline(&fields, null)),
+        None => Cow::Borrowed(value),
+      },
+      (false, CellKind::Boolean { symbols: true }) => match value {
+        "true" => Cow::Borrowed("✓"),
+        "false" => Cow::Borrowed("✗"),
+        _ => Cow::Borrowed(value),
+      },
+      (false, _) => Cow::Borrowed(value),
+    }
+  }
+
+  fn compact_column_widths(rows: &Rows, null: &str, cell_kinds: &[CellKind], max_width: u16) -> Vec<u16> {
     let column_count = rows.headers.len();
     if column_count == 0 {
       return Vec::new();
@@ -189,37 +644,401 @@ impl Data<'_> {
     }
     for row in &rows.rows {
       for (index, value) in row.iter().enumerate().take(column_count) {
-        widths[index] = widths[index].max(Self::cell_display_width(value));
+        let kind = cell_kinds.get(index).copied().unwrap_or_default();
+        widths[index] = widths[index].max(Self::cell_display_width(&Self::cell_text(value, null, kind)));
       }
     }
     widths
       .into_iter()
       .map(|len| {
         let len_with_padding = len.saturating_add(1);
-        let clamped = std::cmp::min(len_with_padding, MAX_COLUMN_WIDTH as usize);
+        let clamped = std::cmp::min(len_with_padding, max_width as usize);
         std::cmp::max(1, clamped) as u16
       })
       .collect()
   }
 
+  // sorts by the current column ascending, then descending, then back to the fetched order
+  fn cycle_sort(&mut self) {
+    // the comparison's changes are kept in the order of its rows
+    if !matches!(self.data_state, DataState::HasResults(_)) || self.diff.is_some() {
+      return;
+    }
+    // while filtering, the full results are sorted and then filtered again
+    if let Some(mut rows) = self.unfiltered.take() {
+      self.sort_rows(&mut rows);
+      self.unfiltered = Some(rows);
+      self.apply_filter();
+      return;
+    }
+    let DataState::HasResults(mut rows) = std::mem::take(&mut self.data_state) else {
+      return;
+    };
+    self.sort_rows(&mut rows);
+    self.set_table(&rows);
+    self.data_state = DataState::HasResults(rows);
+  }
+
+  fn sort_rows(&mut self, rows: &mut Rows) {
+    let (column, _) = self.scrollable.get_cell_offsets();
+    self.sort = match self.sort {
+      Some((sorted, SortOrder::Ascending)) if sorted == column => Some((column, SortOrder::Descending)),
+      Some((sorted, SortOrder::Descending)) if sorted == column => None,
+      _ => Some((column, SortOrder::Ascending)),
+    };
+    match (self.sort, rows.headers.get(column)) {
+      (Some((column, order)), Some(header)) => {
+        sort_rows(&mut rows.rows, &mut self.row_positions, column, &header.type_name, order)
+      },
+      _ => unsort_rows(&mut rows.rows, &mut self.row_positions),
+    }
+  }
+
+  // keeps the full results, as they were fetched, to compare later results with
+  fn pin_results(&mut self) -> Result<()> {
+    let rows = match (&self.diff, &self.unfiltered, &self.data_state) {
+      (Some(diff), _, _) => &diff.results,
+      (None, Some(rows), _) | (None, None, DataState::HasResults(rows)) => rows,
+      _ => return Ok(()),
+    };
+    let mut rows = rows.clone();
+    if self.sort.is_some() {
+      unsort_rows(&mut rows.rows, &mut self.row_positions.clone());
+    }
+    let message = format!("pinned {} rows to compare with", rows.rows.len());
+    self.pinned = Some(rows);
+    self.command_tx.clone().unwrap().send(Action::StatusMessage(message, StatusLevel::Info))?;
+    Ok(())
+  }
+
+  // shows how the results differ from the pinned ones, or stops showing it.
+  // rows are matched by the column under the cursor when a cell is selected,
+  // and otherwise by all their values.
+  fn toggle_diff(&mut self) -> Result<()> {
+    if let Some(diff) = self.diff.take() {
+      self.set_table(&diff.results);
+      self.scrollable.top_row();
+      self.data_state = DataState::HasResults(diff.results);
+      return Ok(());
+    }
+    if self.pinned.is_none() {
+      let message = "pin results to compare with first, with m".to_owned();
+      self.command_tx.clone().unwrap().send(Action::StatusMessage(message, StatusLevel::Error))?;
+      return Ok(());
+    }
+    if !matches!(self.data_state, DataState::HasResults(_)) {
+      return Ok(());
+    }
+    // the full results are compared, in the order they were fetched
+    self.clear_filter();
+    let DataState::HasResults(mut results) = std::mem::take(&mut self.data_state) else {
+      return Ok(());
+    };
+    if self.sort.take().is_some() {
+      unsort_rows(&mut results.rows, &mut self.row_positions);
+    }
+    let key = match self.scrollable.get_selection_mode() {
+      Some(SelectionMode::Cell) => Some(self.scrollable.get_cell_offsets().0),
+      _ => None,
+    };
+    match diff_results(self.pinned.as_ref().unwrap(), &results, key) {
+      Ok(diff) => {
+        self.set_table(&diff.rows);
+        self.scrollable.top_row();
+        self.diff = Some(ShownDiff {
+          summary: diff.summary(),
+          key: key.and_then(|column| results.headers.get(column)).map(|header| header.name.clone()),
+          changes: diff.changes,
+          results,
+        });
+        self.data_state = DataState::HasResults(diff.rows);
+      },
+      Err(e) => {
+        self.data_state = DataState::HasResults(results);
+        self.command_tx.clone().unwrap().send(Action::StatusMessage(e.to_string(), StatusLevel::Error))?;
+      },
+    }
+    Ok(())
+  }
+
+  // shows only the rows matching the filter. an empty filter shows everything.
+  fn apply_filter(&mut self) {
+    let Some(filter) = self.filter.as_ref().filter(|f| !f.text.is_empty()) else {
+      self.restore_unfiltered();
+      return;
+    };
+    let all_rows = match (&self.unfiltered, &self.data_state) {
+      (Some(rows), _) | (None, DataState::HasResults(rows)) => rows,
+      _ => return,
+    };
+    let matcher = match filter.matcher(&all_rows.headers) {
+      Ok(matcher) => matcher,
+      Err(e) => {
+        // an incomplete regex is expected while it's being typed, so the last results stay up
+        self.filter_error = Some(e.to_string());
+        return;
+      },
+    };
+    self.filter_error = None;
+    let rows = Rows {
+      headers: all_rows.headers.clone(),
+      rows: all_rows.rows.iter().filter(|row| matcher.matches(row)).cloned().collect(),
+      rows_affected: None,
+    };
+    if self.unfiltered.is_none()
+      && let DataState::HasResults(all_rows) = std::mem::take(&mut self.data_state)
+    {
+      self.unfiltered = Some(all_rows);
+    }
+    self.set_table(&rows);
+    self.scrollable.top_row();
+    self.data_state = DataState::HasResults(rows);
+  }
+
+  fn restore_unfiltered(&mut self) {
+    self.filter_error = None;
+    if let Some(rows) = self.unfiltered.take() {
+      self.set_table(&rows);
+      self.scrollable.top_row();
+      self.data_state = DataState::HasResults(rows);
+    }
+  }
+
+  fn clear_filter(&mut self) {
+    self.filter = None;
+    self.filter_focused = false;
+    self.restore_unfiltered();
+  }
+
+  fn handle_filter_input(&mut self, input: Input) {
+    let Some(filter) = self.filter.as_mut() else {
+      return;
+    };
+    match input {
+      Input { key: Key::Esc, .. } => self.clear_filter(),
+      Input { key: Key::Enter, .. } => self.filter_focused = false,
+      Input { key: Key::Backspace, .. } if filter.text.is_empty() => self.clear_filter(),
+      Input { key: Key::Backspace, .. } => {
+        filter.text.pop();
+        self.apply_filter();
+      },
+      Input { key: Key::Char('r'), alt: true, .. } => {
+        filter.regex = !filter.regex;
+        self.apply_filter();
+      },
+      Input { key: Key::Char(c), ctrl: false, alt: false, .. } => {
+        filter.text.push(c);
+        self.apply_filter();
+      },
+      _ => {},
+    }
+  }
+
+  fn clear_search(&mut self) {
+    self.search = None;
+    self.search_focused = false;
+    self.rebuild_table();
+  }
+
+  // highlights the matches for the search as it's typed, and moves to the first
+  // one from where the search started
+  fn update_search(&mut self) {
+    self.rebuild_table();
+    if let Some(found) = next_match(&self.search_matches, self.search_origin, true, true) {
+      self.scrollable.select_cell(found.0, found.1);
+    }
+  }
+
+  fn handle_search_input(&mut self, input: Input) {
+    let Some(search) = self.search.as_mut() else {
+      return;
+    };
+    match input {
+      Input { key: Key::Esc, .. } => self.clear_search(),
+      Input { key: Key::Enter, .. } => self.search_focused = false,
+      Input { key: Key::Backspace, .. } if search.text.is_empty() => self.clear_search(),
+      Input { key: Key::Backspace, .. } => {
+        search.text.pop();
+        self.update_search();
+      },
+      Input { key: Key::Char('s'), alt: true, .. } => {
+        search.case_sensitive = !search.case_sensitive;
+        self.update_search();
+      },
+      Input { key: Key::Char(c), ctrl: false, alt: false, .. } => {
+        search.text.push(c);
+        self.update_search();
+      },
+      _ => {},
+    }
+  }
+
+  // moves to the next or previous match from the cursor
+  fn jump_to_match(&mut self, forwards: bool) {
+    let cursor = self.scrollable.get_cell_offsets();
+    if let Some(found) = next_match(&self.search_matches, cursor, forwards, false) {
+      self.scrollable.select_cell(found.0, found.1);
+    }
+  }
+
+  fn handle_row_jump_input(&mut self, input: Input) {
+    let Some(text) = self.row_jump.as_mut() else {
+      return;
+    };
+    match input {
+      Input { key: Key::Esc, .. } => self.row_jump = None,
+      Input { key: Key::Enter, .. } => {
+        // rows are numbered from 1, as in the title, and anything past the end goes to the last row
+        if let Ok(row) = text.parse::<usize>() {
+          self.scrollable.go_to_row(row.saturating_sub(1));
+        }
+        self.row_jump = None;
+      },
+      Input { key: Key::Backspace, .. } => {
+        text.pop();
+      },
+      Input { key: Key::Char(c), ctrl: false, alt: false, .. } if c.is_ascii_digit() => text.push(c),
+      _ => {},
+    }
+  }
+
+  // (re)builds the scroll table for the rows. the scroll position is kept, so
+  // this can be called after sorting.
+  fn set_table(&mut self, rows: &Rows) {
+    self.search_matches = self.search.as_ref().map(|search| search.matches(&rows.rows)).unwrap_or_default();
+    let row_spacing_enabled = self.config.settings.data_row_spacer.unwrap_or(false);
+    let row_bottom_margin: u16 = if row_spacing_enabled { 1 } else { 0 };
+    let header_height: u16 = 2;
+    let data_row_offset = header_height.saturating_add(row_bottom_margin);
+    let column_widths = self.column_widths(rows);
+    // the cells are built as they're drawn. only how tall wrapped rows are is
+    // worked out here, since scrolling needs every row's height.
+    let max_lines = self.max_lines();
+    let row_heights = match max_lines {
+      1 => vec![],
+      _ => {
+        let cell_kinds = self.cell_kinds(&rows.headers);
+        let null = self.null_placeholder();
+        rows
+          .rows
+          .iter()
+          .map(|r| {
+            r.iter()
+              .zip(&column_widths)
+              .zip(&cell_kinds)
+              .map(|((value, width), kind)| Self::cell_lines(value, null, *kind, *width, max_lines).len() as u16)
+              .fold(1, u16::max)
+          })
+          .collect()
+      },
+    };
+    self
+      .scrollable
+      .set_table(column_widths, rows.rows.len(), data_row_offset, row_bottom_margin)
+      .header_style(self.config.theme.header)
+      .row_heights(row_heights);
+  }
+
+  fn max_lines(&self) -> usize {
+    match self.wrap_text {
+      true => self.config.settings.data_wrap_max_lines.unwrap_or(DEFAULT_WRAP_MAX_LINES).max(1) as usize,
+      false => 1,
+    }
+  }
+
+  // the lines a value is drawn on in a column `width` wide. only text is
+  // wrapped: numbers, timestamps and binary previews are short.
+  fn cell_lines(value: &str, null: &str, kind: CellKind, width: u16, max_lines: usize) -> Vec<String> {
+    let text = Self::cell_text(value, null, kind);
+    match kind {
+      CellKind::Plain | CellKind::Json | CellKind::Styled(_) | CellKind::Values if max_lines > 1 && !is_null(value) => {
+        Self::wrap_render_text(&text, width as usize, max_lines)
+      },
+      _ => vec![Self::clamp_render_text(&text, width as usize)],
+    }
+  }
+
+  // widths are in terminal columns, which cjk characters and emoji take two of
   fn cell_display_width(value: &str) -> usize {
-    value.chars().take(MAX_COLUMN_WIDTH as usize).count()
+    value.width()
   }
 
-  fn clamp_render_text(value: &str, max_chars: usize) -> String {
-    if max_chars == 0 || value.is_empty() {
+  // cuts the text down to fit in `max_width` columns, without splitting a wide character
+  fn clamp_render_text(value: &str, max_width: usize) -> String {
+    if max_width == 0 || value.is_empty() {
       return String::new();
     }
-    let mut chars_seen = 0_usize;
-    for (idx, _) in value.char_indices() {
-      if chars_seen == max_chars {
+    let mut width_seen = 0_usize;
+    for (idx, c) in value.char_indices() {
+      width_seen = width_seen.saturating_add(c.width().unwrap_or(0));
+      if width_seen > max_width {
         return value[..idx].to_owned();
       }
-      chars_seen = chars_seen.saturating_add(1);
     }
     value.to_owned()
   }
 
+  // splits the text into lines of at most `max_width` columns, breaking at spaces
+  // where it can, and at line breaks in the text. text that doesn't fit in
+  // `max_lines` ends with an ellipsis.
+  fn wrap_render_text(value: &str, max_width: usize, max_lines: usize) -> Vec<String> {
+    if max_width == 0 {
+      return vec![String::new()];
+    }
+    let mut lines = vec![];
+    for paragraph in value.lines() {
+      let mut line = String::new();
+      for word in paragraph.split_inclusive(' ') {
+        if !line.is_empty() && line.width() + word.trim_end().width() > max_width {
+          lines.push(line.trim_end().to_owned());
+          line = String::new();
+        }
+        line.push_str(word);
+        // a word longer than the whole line is broken wherever it has to be
+        while line.trim_end().width() > max_width {
+          let head = Self::clamp_render_text(&line, max_width);
+          if head.is_empty() {
+            break;
+          }
+          line = line[head.len()..].to_owned();
+          lines.push(head);
+        }
+      }
+      lines.push(line.trim_end().to_owned());
+    }
+    if lines.is_empty() {
+      lines.push(String::new());
+    }
+    if lines.len() > max_lines {
+      lines.truncate(max_lines);
+      if let Some(last) = lines.last_mut() {
+        *last = format!("{}…", Self::clamp_render_text(last, max_width.saturating_sub(1)));
+      }
+    }
+    lines
+  }
+
+  // the rows to export: the range selection if there is one, otherwise everything
+  fn export_rows<'b>(&self, rows: &'b Rows) -> Cow<'b, Rows> {
+    match self.scrollable.get_selected_range() {
+      Some(range) => Cow::Owned(Self::rows_in_range(rows, range)),
+      None => Cow::Borrowed(rows),
+    }
+  }
+
+  fn rows_in_range(rows: &Rows, range: ((usize, usize), (usize, usize))) -> Rows {
+    let ((first_col, last_col), (first_row, last_row)) = range;
+    let headers = rows.headers.iter().skip(first_col).take(last_col.saturating_sub(first_col) + 1).cloned().collect();
+    let selected_rows = rows
+      .rows
+      .iter()
+      .skip(first_row)
+      .take(last_row.saturating_sub(first_row) + 1)
+      .map(|row| row.iter().skip(first_col).take(last_col.saturating_sub(first_col) + 1).cloned().collect())
+      .collect();
+    Rows { headers, rows: selected_rows, rows_affected: None }
+  }
+
   fn preview_text(value: &str, max_chars: usize) -> String {
     if max_chars == 0 || value.is_empty() {
       return String::new();
@@ -239,85 +1058,124 @@ impl Data<'_> {
 
 impl<'a> SettableDataTable<'a> for Data<'a> {
   fn set_data_state(&mut self, data: Option<Result<Rows>>, statement_type: Option<Statement>) {
-    self.explain_width = 0;
-    self.explain_height = 0;
-    self.explain_max_x_offset = 0;
-    self.explain_max_y_offset = 0;
-    self.explain_scroll = None;
-    self.scrollable = ScrollTable::default();
-    match data {
-      Some(Ok(rows)) => {
-        if rows.rows.is_empty() && rows.rows_affected.is_some_and(|n| n > 0) {
-          self.data_state = DataState::RowsAffected(rows.rows_affected.unwrap());
-        } else if rows.rows.is_empty()
-          && statement_type.is_some()
-          && !matches!(statement_type, Some(Statement::Query(_)))
-        {
-          self.data_state = DataState::StatementCompleted(statement_type.unwrap());
-        } else if rows.rows.is_empty() {
-          self.data_state = DataState::NoResults;
-        } else if matches!(statement_type, Some(Statement::Explain { .. })) {
-          self.explain_width = rows.rows.iter().fold(0_u16, |acc, r| acc.max(r.join(" ").len() as u16));
-          self.explain_height = rows.rows.len() as u16;
-          self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset: 0 });
-          self.data_state = DataState::Explain(Text::from_iter(rows.rows.iter().map(|r| r.join(" "))));
-        } else {
-          let row_spacing_enabled = self.config.settings.data_row_spacer.unwrap_or(false);
-          let row_bottom_margin: u16 = if row_spacing_enabled { 1 } else { 0 };
-          let header_height: u16 = 2;
-          let data_row_offset = header_height.saturating_add(row_bottom_margin);
-          let column_widths = self.column_widths(&rows);
-          let header_row = Row::new(
-            rows
-              .headers
-              .iter()
-              .enumerate()
-              .map(|(index, h)| {
-                let col_width = column_widths.get(index).copied().unwrap_or(MAX_COLUMN_WIDTH) as usize;
-                let header_name = Self::clamp_render_text(&h.name, col_width);
-                let header_type = Self::clamp_render_text(&h.type_name, col_width);
-                Cell::from(format!("{header_name}\n{header_type}"))
-              })
-              .collect::<Vec<Cell>>(),
-          )
-          .height(header_height)
-          .bottom_margin(row_bottom_margin);
-          let value_rows = rows.rows.iter().map(|r| {
-            Row::new(
-              r.iter()
-                .enumerate()
-                .map(|(index, value)| {
-                  let col_width = column_widths.get(index).copied().unwrap_or(MAX_COLUMN_WIDTH) as usize;
-                  Self::clamp_render_text(value, col_width)
-                })
-                .collect::<Vec<String>>(),
-            )
-            .bottom_margin(row_bottom_margin)
-          });
-          let buf_table = Table::new(value_rows, column_widths.clone())
-            .header(header_row)
-            .style(Style::default())
-            .column_spacing(COLUMN_SPACING)
-            .row_highlight_style(Style::default().fg(Color::LightBlue).reversed().bold());
-          self.scrollable.set_table(buf_table, column_widths, rows.rows.len(), data_row_offset);
-          self.data_state = DataState::HasResults(rows);
-        }
-      },
-      Some(Err(e)) => {
-        self.data_state = DataState::Error(e);
-      },
-      _ => {
-        self.data_state = DataState::Blank;
-      },
+    self.result_sets.clear();
+    self.show_results(data, statement_type);
+  }
+
+  fn clear_result_sets(&mut self) {
+    self.result_sets.clear();
+    self.result_set_index = 0;
+  }
+
+  fn push_result_set(&mut self, data: Result<Rows>, statement_type: Option<Statement>) {
+    let results = match &data {
+      Ok(rows) => Ok(rows.clone()),
+      Err(e) => Err(match e.downcast_ref::<DbError>() {
+        Some(e) => e.clone(),
+        None => DbError { message: e.to_string(), ..Default::default() },
+      }),
+    };
+    self.result_sets.push(ResultSet { results, statement_type: statement_type.clone(), source_query: None });
+    self.result_set_index = self.result_sets.len() - 1;
+    self.show_results(Some(data), statement_type);
+  }
+
+  fn set_source_query(&mut self, query: Option<String>) {
+    if let Some(set) = self.result_sets.get_mut(self.result_set_index) {
+      set.source_query = query.clone();
     }
+    self.source_query = query;
   }
 
   fn set_loading(&mut self) {
-    self.data_state = DataState::Loading;
+    let previous = std::mem::replace(&mut self.data_state, DataState::Loading);
+    if !matches!(previous, DataState::Loading) {
+      self.before_loading = Some(previous);
+    }
   }
 
+  // the table, scroll position and selection weren't touched while loading, so
+  // earlier results come back as they were
   fn set_cancelled(&mut self) {
-    self.data_state = DataState::Cancelled;
+    self.data_state = match self.before_loading.take() {
+      Some(DataState::Blank | DataState::Loading) | None => DataState::Cancelled,
+      Some(previous) => previous,
+    };
+  }
+
+  // rows are added to the full results, sorted and filtered with them, and the
+  // cursor stays where it was
+  fn append_rows(&mut self, rows: Rows, more: bool) {
+    self.more_rows = more;
+    self.loading_more = false;
+    // the comparison was of the rows fetched before
+    if let Some(diff) = self.diff.take() {
+      self.data_state = DataState::HasResults(diff.results);
+    }
+    let all_rows = match (&mut self.unfiltered, &mut self.data_state) {
+      (Some(all_rows), _) | (None, DataState::HasResults(all_rows)) => all_rows,
+      _ => return,
+    };
+    if !self.row_positions.is_empty() {
+      let fetched = all_rows.rows.len();
+      self.row_positions.extend(fetched..fetched.saturating_add(rows.rows.len()));
+    }
+    all_rows.rows.extend(rows.rows);
+    if let Some((column, order)) = self.sort
+      && let Some(header) = all_rows.headers.get(column)
+    {
+      sort_rows(&mut all_rows.rows, &mut self.row_positions, column, &header.type_name, order);
+    }
+    match self.unfiltered.is_some() {
+      true => {
+        let (_, y) = self.scrollable.get_cell_offsets();
+        self.apply_filter();
+        self.scrollable.go_to_row(y);
+      },
+      false => self.rebuild_table(),
+    }
+  }
+
+  fn set_more_rows(&mut self, more: bool) {
+    self.more_rows = more;
+    self.loading_more = false;
+  }
+
+  fn set_truncated(&mut self, max_rows: u64) {
+    self.truncated_at = Some(max_rows);
+  }
+
+  fn focused_cell(&self) -> Option<String> {
+    match &self.data_state {
+      DataState::HasResults(rows) => self.scrollable.get_focused_cell(&rows.rows).cloned(),
+      _ => None,
+    }
+  }
+
+  fn focused_row(&self) -> Option<Vec<String>> {
+    let DataState::HasResults(rows) = &self.data_state else {
+      return None;
+    };
+    let (_, y) = self.scrollable.get_cell_offsets();
+    rows.rows.get(y).cloned()
+  }
+
+  fn focused_column(&self) -> Option<(Header, Vec<String>)> {
+    let DataState::HasResults(rows) = &self.data_state else {
+      return None;
+    };
+    let (x, _) = self.scrollable.get_cell_offsets();
+    let header = rows.headers.get(x)?.clone();
+    Some((header, rows.rows.iter().map(|row| row.get(x).cloned().unwrap_or_default()).collect()))
+  }
+
+  // rows removed since the pinned results aren't there to edit
+  fn editable_cell(&self) -> Option<(Statement, Headers, usize, Vec<String>)> {
+    let (DataState::HasResults(rows), None) = (&self.data_state, &self.diff) else {
+      return None;
+    };
+    let (x, y) = self.scrollable.get_cell_offsets();
+    Some((self.statement.clone()?, rows.headers.clone(), x, rows.rows.get(y)?.clone()))
   }
 }
 
@@ -328,6 +1186,11 @@ impl Component for Data<'_> {
   }
 
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    // the zone was checked when the config was loaded
+    self.display_zone =
+      config.settings.display_timezone.as_deref().and_then(|zone| zone.parse().ok()).unwrap_or_default();
+    self.scrollable.selection_style(config.theme.selection);
+    self.wrap_text = config.settings.data_wrap_text.unwrap_or(false);
     self.config = config;
     Ok(())
   }
@@ -337,10 +1200,28 @@ impl Component for Data<'_> {
     mouse: crossterm::event::MouseEvent,
     app_state: &AppState,
   ) -> Result<Option<Action>> {
+    // a click focuses the data table as well as the cell, so it's handled before
+    // the table has focus
+    if let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+      && app_state.focus != Focus::PopUp
+      && let DataState::HasResults(rows) = &self.data_state
+      && let Some((column, row)) = self.scrollable.cell_at(Position::new(mouse.column, mouse.row))
+      && row < rows.rows.len()
+    {
+      self.scrollable.select_cell(column, row).transition_selection_mode(Some(SelectionMode::Cell));
+      return Ok(None);
+    }
     if app_state.focus != Focus::Data {
       return Ok(None);
     }
+    let shift = mouse.modifiers.contains(KeyModifiers::SHIFT);
     match mouse.kind {
+      MouseEventKind::ScrollDown if shift => {
+        self.scroll(ScrollDirection::Right);
+      },
+      MouseEventKind::ScrollUp if shift => {
+        self.scroll(ScrollDirection::Left);
+      },
       MouseEventKind::ScrollDown => {
         self.scroll(ScrollDirection::Down);
       },
@@ -355,6 +1236,7 @@ impl Component for Data<'_> {
       },
       _ => {},
     };
+    self.load_more_near_end()?;
     Ok(None)
   }
 
@@ -363,12 +1245,65 @@ impl Component for Data<'_> {
       return Ok(None);
     }
     let input = Input::from(key);
+    if self.filter_focused {
+      self.handle_filter_input(input);
+      return Ok(None);
+    }
+    if self.search_focused {
+      self.handle_search_input(input);
+      return Ok(None);
+    }
+    if self.row_jump.is_some() {
+      self.handle_row_jump_input(input);
+      return Ok(None);
+    }
     match input {
+      Input { key: Key::Enter | Key::Char(' '), .. } if matches!(self.data_state, DataState::Plan(_)) => {
+        if let DataState::Plan(tree) = &mut self.data_state {
+          tree.toggle();
+        }
+      },
+      Input { key: Key::Char(':'), .. } => {
+        if let DataState::HasResults(_) = self.data_state {
+          self.row_jump = Some(String::new());
+        }
+      },
+      Input { key: Key::Char('/'), alt: true, .. } => {
+        if let DataState::HasResults(_) = self.data_state {
+          self.search.get_or_insert_default();
+          self.search_focused = true;
+          self.search_origin = self.scrollable.get_cell_offsets();
+        }
+      },
+      Input { key: Key::Char('n'), .. } if self.search.is_some() => self.jump_to_match(true),
+      Input { key: Key::Char('N'), .. } if self.search.is_some() => self.jump_to_match(false),
+      Input { key: Key::Char('/'), .. } if self.diff.is_none() => {
+        if let DataState::HasResults(_) = self.data_state {
+          self.filter.get_or_insert_default();
+          self.filter_focused = true;
+        }
+      },
       Input { key: Key::Char('P'), .. } => {
         if let DataState::HasResults(rows) = &self.data_state {
-          self.command_tx.clone().unwrap().send(Action::RequestExportData(rows.rows.len() as i64))?;
+          let row_count = match self.scrollable.get_selected_range() {
+            Some((_, (first_row, last_row))) => last_row.saturating_sub(first_row).saturating_add(1),
+            None => rows.rows.len(),
+          };
+          self.command_tx.clone().unwrap().send(Action::RequestExportData(row_count as i64))?;
         }
       },
+      Input { key: Key::Char('u'), ctrl: true, .. } => {
+        self.scrollable.half_pg_up();
+      },
+      Input { key: Key::Char('d'), ctrl: true, .. } => {
+        self.scrollable.half_pg_down();
+      },
+      Input { key: Key::Char('h'), ctrl: true, .. } => {
+        self.scrollable.half_pg_left();
+      },
+      Input { key: Key::Char('l'), ctrl: true, .. } => {
+        self.scrollable.half_pg_right();
+      },
       Input { key: Key::Right, .. } | Input { key: Key::Char('l'), .. } => {
         self.scroll(ScrollDirection::Right);
       },
@@ -409,6 +1344,35 @@ impl Component for Data<'_> {
       | Input { key: Key::PageDown, .. } => {
         self.scrollable.pg_down();
       },
+      Input { key: Key::Char('f'), .. } => {
+        // the last column is never frozen, so there's always something to scroll
+        if let DataState::HasResults(rows) = &self.data_state {
+          let frozen = self.scrollable.frozen_columns();
+          if frozen + 1 < rows.headers.len() {
+            self.scrollable.freeze_columns(frozen + 1);
+          }
+        }
+      },
+      Input { key: Key::Char('F'), .. } => {
+        self.scrollable.freeze_columns(0);
+      },
+      Input { key: Key::Char('z'), .. } => {
+        self.toggle_display_zone();
+      },
+      Input { key: Key::Char('W'), .. } => {
+        self.toggle_wrap_text();
+      },
+      Input { key: Key::Char('>'), .. } => {
+        self.resize_column(COLUMN_RESIZE_STEP);
+      },
+      Input { key: Key::Char('<'), .. } => {
+        self.resize_column(-COLUMN_RESIZE_STEP);
+      },
+      Input { key: Key::Char('v'), ctrl: true, .. } => {
+        if let DataState::HasResults(_) = self.data_state {
+          self.scrollable.start_range_selection();
+        }
+      },
       Input { key: Key::Char('v'), .. } => {
         self.scrollable.transition_selection_mode(Some(SelectionMode::Cell));
       },
@@ -423,6 +1387,20 @@ impl Component for Data<'_> {
           None | Some(SelectionMode::Copied) => {
             self.scrollable.transition_selection_mode(Some(SelectionMode::Row));
           },
+          Some(SelectionMode::Cell) => {
+            if let DataState::HasResults(rows) = &self.data_state
+              && let Some(value) = self.scrollable.get_focused_cell(&rows.rows)
+              && let Some(header) = rows.headers.get(self.scrollable.get_cell_offsets().0)
+            {
+              let value = display_value(value, self.null_placeholder()).to_string();
+              self.command_tx.clone().unwrap().send(Action::RequestCellDetail(
+                header.name.clone(),
+                header.type_name.clone(),
+                header.composite_fields.clone(),
+                value,
+              ))?;
+            }
+          },
           _ => {},
         };
       },
@@ -434,22 +1412,29 @@ impl Component for Data<'_> {
           Some(SelectionMode::Cell) => {
             self.scrollable.transition_selection_mode(Some(SelectionMode::Row));
           },
+          Some(SelectionMode::Range(..)) => {
+            self.scrollable.transition_selection_mode(Some(SelectionMode::Cell));
+          },
           _ => {},
         };
       },
       Input { key: Key::Char('y'), .. } => {
         if let DataState::HasResults(Rows { rows, .. }) = &self.data_state {
           let (x, y) = self.scrollable.get_cell_offsets();
-          let row = &rows[y];
+          let Some(row) = rows.get(y) else {
+            return Ok(None);
+          };
           match self.scrollable.get_selection_mode() {
             Some(SelectionMode::Row) => {
-              let row_string = row.join(", ");
+              let null = &self.config.export.null;
+              let row_string = row.iter().map(|v| display_value(v, null)).collect::<Vec<_>>().join(", ");
               self.command_tx.clone().unwrap().send(Action::CopyData(row_string))?;
               self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
             },
             Some(SelectionMode::Cell) => {
               if let Some(cell) = row.get(x) {
-                self.command_tx.clone().unwrap().send(Action::CopyData(cell.clone()))?;
+                let cell = display_value(cell, &self.config.export.null).to_string();
+                self.command_tx.clone().unwrap().send(Action::CopyData(cell))?;
                 self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
               }
             },
@@ -458,6 +1443,9 @@ impl Component for Data<'_> {
         } else if let DataState::Explain(text) = &self.data_state {
           self.command_tx.clone().unwrap().send(Action::CopyData(text.to_string()))?;
           self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
+        } else if let DataState::Plan(tree) = &self.data_state {
+          self.command_tx.clone().unwrap().send(Action::CopyData(tree.json.clone()))?;
+          self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
         } else if let DataState::Error(err) = &self.data_state {
           self.command_tx.clone().unwrap().send(Action::CopyData(err.to_string()))?;
           self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
@@ -468,37 +1456,109 @@ impl Component for Data<'_> {
           self.command_tx.clone().unwrap().send(Action::RequestYankAll(rows.rows.len() as i64))?;
         }
       },
+      Input { key: Key::Char('s'), .. } => {
+        self.cycle_sort();
+      },
+      Input { key: Key::Char('m'), .. } => {
+        self.pin_results()?;
+      },
+      Input { key: Key::Char('M'), .. } => {
+        self.toggle_diff()?;
+      },
+      Input { key: Key::Char(']'), .. } if self.more_rows => {
+        self.load_more()?;
+      },
+      Input { key: Key::Char(']'), .. } => {
+        // a short page is the last one
+        let row_count = match (&self.unfiltered, &self.data_state) {
+          (Some(rows), _) | (None, DataState::HasResults(rows)) => rows.rows.len() as u64,
+          _ => 0,
+        };
+        if let Some(page) = &app_state.page
+          && row_count == page.size
+        {
+          self.command_tx.clone().unwrap().send(Action::NextPage)?;
+        }
+      },
+      Input { key: Key::Char('['), .. } if app_state.page.as_ref().is_some_and(|page| page.offset > 0) => {
+        self.command_tx.clone().unwrap().send(Action::PrevPage)?;
+      },
+      Input { key: Key::Char(')'), .. } if self.result_set_index + 1 < self.result_sets.len() => {
+        self.show_result_set(self.result_set_index + 1);
+      },
+      Input { key: Key::Char('('), .. } if self.result_set_index > 0 => {
+        self.show_result_set(self.result_set_index - 1);
+      },
+      Input { key: Key::Char('C'), .. } => {
+        if let DataState::HasResults(rows) = &self.data_state {
+          let row_count = match self.scrollable.get_selected_range() {
+            Some((_, (first_row, last_row))) => last_row.saturating_sub(first_row).saturating_add(1),
+            None => rows.rows.len(),
+          };
+          self.command_tx.clone().unwrap().send(Action::RequestCopyAs(row_count as i64))?;
+        }
+      },
+      Input { key: Key::Esc, .. } if self.scrollable.get_selection_mode().is_none() && self.search.is_some() => {
+        self.clear_search();
+      },
+      Input { key: Key::Esc, .. } if self.scrollable.get_selection_mode().is_none() && self.filter.is_some() => {
+        self.clear_filter();
+      },
       Input { key: Key::Esc, .. } => {
         self.scrollable.transition_selection_mode(None);
       },
       _ => {},
     };
+    self.load_more_near_end()?;
     Ok(None)
   }
 
   fn update(&mut self, action: Action, app_state: &AppState) -> Result<Option<Action>> {
     if let Action::Query(query, confirmed, bypass) = action {
       self.scrollable.reset_scroll();
-    } else if let Action::ExportData(format) = action {
+    } else if let Action::ExportData(format, options) = action {
       let DataState::HasResults(rows) = &self.data_state else {
         self.command_tx.clone().unwrap().send(Action::ExportDataFinished)?;
         return Ok(None);
       };
-      let name = format!("rainfrog_export_{}_rows_{}.csv", rows.rows.len(), chrono::Utc::now().timestamp());
-      let mut writer = Writer::from_path(get_export_dir().join(name))?;
-      writer.write_record(header_to_vec(&rows.headers))?;
+      // a range selection is already in memory, so there's nothing to gain from re-running
+      // the query. sorted and filtered rows are too, and re-running would lose that.
+      if let Some(query) = self.source_query.clone().filter(|_| matches!(self.statement, Some(Statement::Query(_))))
+        && self.scrollable.get_selected_range().is_none()
+        && self.sort.is_none()
+        && self.unfiltered.is_none()
+      {
+        self.command_tx.clone().unwrap().send(Action::StreamExportData(format, options, query))?;
+        return Ok(None);
+      }
+      let rows = self.export_rows(rows);
+      let name = format!(
+        "rainfrog_export_{}_rows_{}.{}",
+        rows.rows.len(),
+        chrono::Utc::now().timestamp(),
+        file_extension(&format)
+      );
+      let mut sink = new_file_sink(&format, &options, &get_export_dir().join(name))?;
+      sink.write_headers(&rows.headers)?;
       for row in &rows.rows {
-        writer.write_record(row)?;
+        sink.write_row(row)?;
       }
-      writer.flush()?;
+      sink.finish()?;
       self.command_tx.clone().unwrap().send(Action::ExportDataFinished)?;
     } else if let Action::YankAll = action {
       let DataState::HasResults(rows) = &self.data_state else {
         return Ok(None);
       };
-      let table_for_yank = TableForYank::new(rows, app_state).yank();
+      let table_for_yank = TableForYank::new(rows, &self.config.export.null, app_state).yank();
       self.command_tx.clone().unwrap().send(Action::CopyData(table_for_yank))?;
       self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
+    } else if let Action::CopyAs(format) = action {
+      let DataState::HasResults(rows) = &self.data_state else {
+        return Ok(None);
+      };
+      let text = format_rows(&self.export_rows(rows), &format, &self.config.export)?;
+      self.command_tx.clone().unwrap().send(Action::CopyData(text))?;
+      self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
     }
     Ok(None)
   }
@@ -506,11 +1566,7 @@ impl Component for Data<'_> {
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState) -> Result<()> {
     let focused = app_state.focus == Focus::Data;
 
-    let mut block = Block::default().borders(Borders::ALL).border_style(if focused {
-      Style::new().green()
-    } else {
-      Style::new().dim()
-    });
+    let mut block = Block::default().borders(Borders::ALL).border_style(self.config.theme.border(focused));
 
     let inner_area = block.inner(area);
 
@@ -523,23 +1579,108 @@ impl Component for Data<'_> {
       });
     }
 
-    if let DataState::HasResults(Rows { rows, .. }) = &self.data_state {
+    if let DataState::HasResults(Rows { rows, headers, .. }) = &self.data_state {
+      let rows_have_timestamps = headers.iter().any(|h| is_timestamp_type(&h.type_name));
       let (x, y) = self.scrollable.get_cell_offsets();
-      let row = &rows[y];
+      let row = rows.get(y);
       let title_string = match self.scrollable.get_selection_mode() {
         Some(SelectionMode::Row) => {
           format!(" 󰆼 results <alt+3> (row {} of {})", y.saturating_add(1), rows.len())
         },
         Some(SelectionMode::Cell) => {
-          let cell = row.get(x).map(|c| Self::preview_text(c, TITLE_CELL_PREVIEW_MAX_CHARS)).unwrap_or_default();
+          let cell = row
+            .and_then(|row| row.get(x))
+            .map(|c| Self::preview_text(display_value(c, self.null_placeholder()), TITLE_CELL_PREVIEW_MAX_CHARS))
+            .unwrap_or_default();
           format!(" 󰆼 results <alt+3> (row {} of {}) - {} ", y.saturating_add(1), rows.len(), cell)
         },
+        Some(SelectionMode::Range(..)) => {
+          let ((first_col, last_col), (first_row, last_row)) = self.scrollable.get_selected_range().unwrap_or_default();
+          format!(
+            " 󰆼 results <alt+3> (row {} of {}) - selected {} rows, {} columns ",
+            y.saturating_add(1),
+            rows.len(),
+            last_row.saturating_sub(first_row).saturating_add(1),
+            last_col.saturating_sub(first_col).saturating_add(1),
+          )
+        },
         Some(SelectionMode::Copied) => {
           format!(" 󰆼 results <alt+3> ({} rows) - copied! ", rows.len())
         },
         _ => format!(" 󰆼 results <alt+3> ({} rows)", rows.len()),
       };
       block = block.title(title_string);
+      if rows_have_timestamps {
+        block = block.title(Line::from(format!(" times in {} ", self.display_zone)).right_aligned());
+      }
+      if self.scrollable.frozen_columns() > 0 {
+        block = block.title(Line::from(format!(" {} frozen ", self.scrollable.frozen_columns())).right_aligned());
+      }
+      if let Some(page) = &app_state.page {
+        block = block.title(
+          Line::from(format!(
+            " page {} (rows {}-{}) ",
+            page.offset / page.size.max(1) + 1,
+            page.offset.saturating_add(1),
+            page.offset.saturating_add(rows.len() as u64)
+          ))
+          .right_aligned(),
+        );
+      }
+      match (self.loading_more, self.more_rows) {
+        (true, _) => block = block.title(Line::from(" loading more rows... ").right_aligned()),
+        (false, true) => block = block.title(Line::from(" more rows <]> ").right_aligned()),
+        _ => {},
+      }
+      if let Some(max_rows) = self.truncated_at {
+        block = block.title(
+          Line::styled(format!(" truncated to {max_rows} rows (max_rows_fetched) "), self.config.theme.error)
+            .right_aligned(),
+        );
+      }
+      if let Some(filter) = &self.filter {
+        let cursor = if self.filter_focused { "▏" } else { "" };
+        let status = match (&self.filter_error, &self.unfiltered) {
+          (Some(e), _) => format!("invalid: {}", e.lines().last().unwrap_or_default()),
+          (None, Some(all_rows)) => format!("showing {} of {}", rows.len(), all_rows.rows.len()),
+          (None, None) => format!("showing {} of {}", rows.len(), rows.len()),
+        };
+        block = block.title_bottom(format!(" / {}{cursor} {} - {status} ", filter.text, filter.indicator()));
+      }
+      if let Some(search) = &self.search {
+        let cursor = if self.search_focused { "▏" } else { "" };
+        let current = self.search_matches.iter().position(|m| *m == self.scrollable.get_cell_offsets());
+        let status = match (self.search_matches.len(), current) {
+          (0, _) => "no matches".to_string(),
+          (count, Some(index)) => format!("match {} of {count}", index + 1),
+          (count, None) => format!("{count} matches"),
+        };
+        block = block.title_bottom(format!(" find {}{cursor} {} - {status} ", search.text, search.indicator()));
+      }
+      if let Some(text) = &self.row_jump {
+        block = block.title_bottom(format!(" :{text}▏ go to row (1-{}) ", rows.len()));
+      }
+      if let Some(diff) = &self.diff {
+        let key = diff.key.as_ref().map(|key| format!(" by {key}")).unwrap_or_default();
+        block = block.title_bottom(format!(" compared with pinned results{key}: {} <M> ", diff.summary));
+      }
+    } else if let DataState::Plan(tree) = &self.data_state {
+      let title_string = match self.scrollable.get_selection_mode() {
+        Some(SelectionMode::Copied) => " 󰆼 results <alt+3> (query plan) - copied! ",
+        _ => " 󰆼 results <alt+3> (query plan)",
+      };
+      block = block.title(title_string);
+      match (tree.plan.planning_time, tree.plan.execution_time) {
+        (Some(planning), Some(execution)) => {
+          block =
+            block.title(Line::from(format!(" planning {planning:.3}ms, execution {execution:.3}ms ")).right_aligned());
+        },
+        (None, Some(execution)) => {
+          block = block.title(Line::from(format!(" execution {execution:.3}ms ")).right_aligned());
+        },
+        _ => {},
+      }
+      block = block.title_bottom(" [j|k] move [<enter>] fold [h|l] fold/unfold [y] copy json ");
     } else {
       let title_string = match self.scrollable.get_selection_mode() {
         Some(SelectionMode::Copied) => " 󰆼 results <alt+3> - copied! ",
@@ -547,16 +1688,19 @@ impl Component for Data<'_> {
       };
       block = block.title(title_string);
     }
+    if self.result_sets.len() > 1 {
+      block = block.title(
+        Line::from(format!(" result {} of {} ", self.result_set_index + 1, self.result_sets.len())).right_aligned(),
+      );
+    }
 
     match &self.data_state {
       DataState::NoResults => {
         f.render_widget(Paragraph::new("no results").wrap(Wrap { trim: false }).block(block), area);
       },
-      DataState::StatementCompleted(statement) => {
+      DataState::StatementCompleted(statement, rows_affected) => {
         f.render_widget(
-          Paragraph::new(format!("{} statement completed", statement_type_string(Some(statement.clone()))))
-            .wrap(Wrap { trim: false })
-            .block(block),
+          Paragraph::new(command_tag(statement, *rows_affected)).wrap(Wrap { trim: false }).block(block),
           area,
         );
       },
@@ -614,19 +1758,62 @@ impl Component for Data<'_> {
           };
         }
       },
-      DataState::HasResults(_) => {
-        self.scrollable.block(block);
-        self.scrollable.draw(f, area, app_state)?;
+      DataState::Plan(tree) => {
+        let visible = tree.visible();
+        let lines = visible
+          .iter()
+          .enumerate()
+          .map(|(i, node)| plan_line(node, i == tree.selected(), &self.config.theme))
+          .collect::<Vec<_>>();
+        // keeps the selected node in view
+        let height = block.inner(area).height;
+        let scroll = u16::try_from(tree.selected()).unwrap_or(u16::MAX).saturating_sub(height.saturating_sub(1));
+        f.render_widget(Paragraph::new(lines).scroll((scroll, 0)).block(block), area);
       },
-      DataState::Error(e) => {
-        f.render_widget(
-          Paragraph::new(e.to_string()).style(Style::default().fg(Color::Red)).wrap(Wrap { trim: true }).block(block),
-          area,
-        );
+      DataState::HasResults(rows) => {
+        let first_row_number = self.config.settings.data_row_numbers.unwrap_or(false).then(|| {
+          let page_offset = app_state.page.as_ref().map_or(0, |page| page.offset as usize);
+          page_offset.saturating_add(1)
+        });
+        let cells = ResultCells {
+          rows,
+          cell_kinds: self.cell_kinds(&rows.headers),
+          null: self.config.settings.null_placeholder.as_deref().unwrap_or_default(),
+          max_lines: self.max_lines(),
+          sort: self.sort,
+          search_matches: &self.search_matches,
+          row_changes: self.diff.as_ref().map_or(&[], |diff| &diff.changes),
+          theme: &self.config.theme,
+        };
+        self.scrollable.number_rows(first_row_number).block(block);
+        self.scrollable.draw(f, area, &cells)?;
+      },
+      DataState::Error(e) => match e.downcast_ref::<DbError>() {
+        Some(e) => {
+          f.render_widget(
+            Paragraph::new(db_error_text(e, self.config.theme.error, self.config.theme.muted))
+              .wrap(Wrap { trim: false })
+              .block(block),
+            area,
+          );
+        },
+        None => {
+          f.render_widget(
+            Paragraph::new(e.to_string()).style(self.config.theme.error).wrap(Wrap { trim: true }).block(block),
+            area,
+          );
+        },
       },
       DataState::Loading => {
+        let elapsed = app_state
+          .last_query_start
+          .map(|start| chrono::Utc::now().signed_duration_since(start).num_milliseconds())
+          .unwrap_or_default();
+        let frame = SPINNER_FRAMES[(elapsed / SPINNER_FRAME_MS).rem_euclid(SPINNER_FRAMES.len() as i64) as usize];
         f.render_widget(
-          Paragraph::new(Text::from("loading...").fg(Color::Green)).wrap(Wrap { trim: false }).block(block),
+          Paragraph::new(Text::from(format!("{frame} loading...")).fg(Color::Green))
+            .wrap(Wrap { trim: false })
+            .block(block),
           area,
         );
       },
@@ -642,19 +1829,108 @@ impl Component for Data<'_> {
   }
 }
 
+// a node of a query plan on one line, indented under its parent, with its costs,
+// its rows as estimated and (with ANALYZE) as they were, and its own share of
+// the plan. the most expensive nodes stand out.
+fn plan_line<'a>(node: &VisibleNode, selected: bool, theme: &Theme) -> Line<'a> {
+  let plan = node.node;
+  let marker = match (plan.children.is_empty(), node.collapsed) {
+    (true, _) => " ",
+    (false, true) => "▸",
+    (false, false) => "▾",
+  };
+  let name = match &plan.detail {
+    Some(detail) => format!("{} {detail}", plan.node_type),
+    None => plan.node_type.clone(),
+  };
+  let mut stats = format!("cost={:.2}..{:.2} rows={}", plan.startup_cost, plan.total_cost, plan.plan_rows);
+  if let (Some(rows), Some(time)) = (plan.actual_rows, plan.actual_total_time) {
+    stats.push_str(&format!(" actual rows={rows} loops={} time={time:.3}ms", plan.actual_loops.unwrap_or(1.0)));
+  }
+  let mut spans = vec![
+    Span::raw(format!("{}{marker} ", "  ".repeat(node.path.len()))),
+    Span::styled(name, if node.expensive { theme.error } else { Style::default() }),
+    Span::styled(format!("  {stats}"), theme.muted),
+  ];
+  // an estimate that's off by 10 times or more is likely why a plan is slow
+  if let Some(misestimate) = plan.misestimate().filter(|m| !(0.1..10.0).contains(m)) {
+    let text = match misestimate >= 1.0 {
+      true => format!(" ({misestimate:.0}x more rows than estimated)"),
+      false => format!(" ({:.0}x fewer rows than estimated)", 1.0 / misestimate),
+    };
+    spans.push(Span::styled(text, theme.error));
+  }
+  spans.push(Span::styled(format!("  {:.0}%", node.share * 100.0), theme.muted));
+  let line = Line::from(spans);
+  if selected { line.style(theme.selection) } else { line }
+}
+
+// the error the way psql shows it: the message, then the line of the query it
+// points at with a caret under the offending token, then any detail and hint.
+fn db_error_text(e: &DbError, style: Style, muted: Style) -> Text<'static> {
+  let mut lines = vec![Line::styled(format!("ERROR: {}", e.message), style)];
+  if let Some((line_number, line, before)) = e.location() {
+    let label = format!("LINE {line_number}: ");
+    // tabs have no width once drawn, so they're spaced out in both
+    let line = line.replace('\t', "    ");
+    let before = before.replace('\t', "    ");
+    lines.push(Line::from(vec![Span::styled(label.clone(), muted), Span::raw(line)]));
+    lines.push(Line::styled(format!("{}^", " ".repeat(label.width() + before.width())), style));
+  }
+  if let Some(detail) = &e.detail {
+    lines.push(Line::from(vec![Span::styled("DETAIL: ", muted), Span::raw(detail.clone())]));
+  }
+  if let Some(hint) = &e.hint {
+    lines.push(Line::from(vec![Span::styled("HINT: ", muted), Span::raw(hint.clone())]));
+  }
+  Text::from(lines)
+}
+
+// the same value always gets the same color, so equal values can be spotted
+// at a glance
+fn value_color(value: &str) -> Color {
+  let hash = value.bytes().fold(0_usize, |hash, b| hash.wrapping_mul(31).wrapping_add(b as usize));
+  VALUE_COLORS[hash % VALUE_COLORS.len()]
+}
+
+// json on one line with a space after each `:` and `,`. keys come out sorted,
+// so equal values look the same however they were written.
+fn inline_json(value: &str) -> Option<String> {
+  let json = serde_json::from_str::<serde_json::Value>(value).ok()?;
+  let mut buf = Vec::new();
+  json.serialize(&mut serde_json::Serializer::with_formatter(&mut buf, InlineJsonFormatter)).ok()?;
+  String::from_utf8(buf).ok()
+}
+
+struct InlineJsonFormatter;
+
+impl serde_json::ser::Formatter for InlineJsonFormatter {
+  fn begin_array_value<W: ?Sized + std::io::Write>(&mut self, writer: &mut W, first: bool) -> std::io::Result<()> {
+    if first { Ok(()) } else { writer.write_all(b", ") }
+  }
+
+  fn begin_object_key<W: ?Sized + std::io::Write>(&mut self, writer: &mut W, first: bool) -> std::io::Result<()> {
+    if first { Ok(()) } else { writer.write_all(b", ") }
+  }
+
+  fn begin_object_value<W: ?Sized + std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+    writer.write_all(b": ")
+  }
+}
+
 struct TableForYank {
   sql: Vec<String>,
   table: Vec<VecDeque<String>>,
 }
 
 impl TableForYank {
-  fn new(rows: &Rows, app_state: &AppState) -> Self {
+  fn new(rows: &Rows, null: &str, app_state: &AppState) -> Self {
     let sql = app_state.history.first().expect("expected the last SQL query in history").query_lines.clone();
 
     let headers: &Vec<String> = &rows.headers.iter().map(|h| h.name.clone()).collect();
     let rows = &rows.rows;
 
-    let table = Self::to_columns(headers, rows);
+    let table = Self::to_columns(headers, rows, null);
 
     Self { sql, table }
   }
@@ -706,19 +1982,151 @@ impl TableForYank {
     }
   }
 
-  fn to_columns(headers: &[String], rows: &[Vec<String>]) -> Vec<VecDeque<String>> {
+  fn to_columns(headers: &[String], rows: &[Vec<String>], null: &str) -> Vec<VecDeque<String>> {
     headers
       .iter()
       .enumerate()
       .map(|(i, h)| {
         let mut col: VecDeque<String> = VecDeque::from([h.clone()]);
-        rows.iter().filter_map(|row| row.get(i)).cloned().for_each(|v| col.push_back(v));
+        rows.iter().filter_map(|row| row.get(i)).for_each(|v| col.push_back(display_value(v, null).to_string()));
         col
       })
       .collect()
   }
 }
 
+#[cfg(test)]
+mod export {
+  use crate::{
+    components::data::Data,
+    database::{Header, Rows},
+  };
+
+  #[test]
+  fn rows_in_range_selects_rectangle() {
+    let headers = ["id", "name", "age"]
+      .iter()
+      .map(|name| Header { name: name.to_string(), type_name: "TEXT".to_string(), composite_fields: None })
+      .collect();
+    let rows = Rows {
+      headers,
+      rows: vec![
+        vec!["id1".to_string(), "name1".to_string(), "age1".to_string()],
+        vec!["id2".to_string(), "name2".to_string(), "age2".to_string()],
+        vec!["id3".to_string(), "name3".to_string(), "age3".to_string()],
+      ],
+      rows_affected: None,
+    };
+
+    let result = Data::rows_in_range(&rows, ((1, 2), (1, 2)));
+
+    assert_eq!(result.headers.iter().map(|h| h.name.as_str()).collect::<Vec<_>>(), vec!["name", "age"]);
+    assert_eq!(
+      result.rows,
+      vec![vec!["name2".to_string(), "age2".to_string()], vec!["name3".to_string(), "age3".to_string()]]
+    );
+  }
+}
+
+#[cfg(test)]
+mod cell_text {
+  use crate::components::data::{CellKind, Data, inline_json, value_color};
+
+  #[test]
+  fn inline_json_collapses_whitespace() {
+    assert_eq!(inline_json("{\n  \"b\": [1,2],\n  \"a\": null\n}").unwrap(), r#"{"a": null, "b": [1, 2]}"#);
+    assert_eq!(inline_json(r#"{"a":null,"b":[1, 2]}"#), inline_json(r#"{ "b": [1,2], "a": null }"#));
+    assert_eq!(inline_json("not json"), None);
+  }
+
+  #[test]
+  fn booleans_can_be_shown_as_symbols() {
+    let null = crate::database::NULL;
+    assert_eq!(Data::cell_text("true", "∅", CellKind::Boolean { symbols: true }), "✓");
+    assert_eq!(Data::cell_text("false", "∅", CellKind::Boolean { symbols: true }), "✗");
+    assert_eq!(Data::cell_text("true", "∅", CellKind::Boolean { symbols: false }), "true");
+    assert_eq!(Data::cell_text(null, "∅", CellKind::Boolean { symbols: true }), "∅");
+  }
+
+  #[test]
+  fn equal_values_get_the_same_color() {
+    assert_eq!(value_color("active"), value_color("active"));
+    assert_ne!(value_color("active"), value_color("pending"));
+  }
+
+  #[test]
+  fn cell_text_shows_nulls_and_json() {
+    let null = crate::database::NULL;
+    assert_eq!(Data::cell_text(null, "∅", CellKind::Plain), "∅");
+    assert_eq!(Data::cell_text("", "∅", CellKind::Plain), "");
+    assert_eq!(Data::cell_text(r#"{"a":1}"#, "∅", CellKind::Json), r#"{"a": 1}"#);
+    assert_eq!(Data::cell_text(r#"{"a":1}"#, "∅", CellKind::Plain), r#"{"a":1}"#);
+    assert_eq!(Data::cell_text(r"\x48656c6c6f", "∅", CellKind::Binary), r"\x48656c6c6f");
+    let long = format!(r"\x{}", "ab".repeat(20));
+    assert_eq!(Data::cell_text(&long, "∅", CellKind::Binary), format!(r"\x{}…", "ab".repeat(16)));
+    assert_eq!(Data::cell_text(r#"{1,NULL,"a b"}"#, "∅", CellKind::Array), "{1, ∅, a b} (3)");
+    assert_eq!(Data::cell_text("{}", "∅", CellKind::Array), "{} (0)");
+    assert_eq!(Data::cell_text(r#"["2024-01-01","2024-02-01")"#, "∅", CellKind::Range), "[2024-01-01, 2024-02-01)");
+    assert_eq!(Data::cell_text(r#"(1,"a b",)"#, "∅", CellKind::Composite), "(1, a b, ∅)");
+    assert_eq!(Data::cell_text("active", "∅", CellKind::Composite), "active");
+    // quoted separators, nesting, and NULL next to the string "NULL"
+    assert_eq!(
+      Data::cell_text(r#"{"a,b",NULL,"NULL","c \"d\""}"#, "∅", CellKind::Array),
+      r#"{a,b, ∅, NULL, c "d"} (4)"#
+    );
+    assert_eq!(Data::cell_text("{{1,NULL},{3,4}}", "∅", CellKind::Array), "{{1, ∅}, {3, 4}} (2)");
+    assert_eq!(Data::cell_text(r#"("a, b","(1,2)",,"")"#, "∅", CellKind::Composite), "(a, b, (1,2), ∅, )");
+    assert_eq!(Data::cell_text(r#"(,"NULL","say ""hi""")"#, "∅", CellKind::Composite), r#"(∅, NULL, say "hi")"#);
+    assert_eq!(Data::cell_text("(1,2", "∅", CellKind::Composite), "(1,2");
+    // numerics are shown exactly as fetched, however precise
+    let numeric = "123456789012345678901234567890.123456789012345678901234567890";
+    assert_eq!(Data::cell_text(numeric, "∅", CellKind::Number), numeric);
+    let uuid = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+    assert_eq!(Data::cell_text(uuid, "∅", CellKind::Plain), uuid);
+  }
+}
+
+#[cfg(test)]
+mod cell_width {
+  use crate::{
+    components::data::{CellKind, Data},
+    database::{Header, Rows},
+  };
+
+  #[test]
+  fn wide_characters_take_two_columns() {
+    assert_eq!(Data::cell_display_width("日本語"), 6);
+    assert_eq!(Data::clamp_render_text("日本語", 6), "日本語");
+    assert_eq!(Data::clamp_render_text("日本語", 5), "日本");
+    assert_eq!(Data::clamp_render_text("a日本", 2), "a");
+  }
+
+  #[test]
+  fn compact_widths_fit_contents_up_to_the_max() {
+    let headers = ["id", "description"]
+      .iter()
+      .map(|name| Header { name: name.to_string(), type_name: "TEXT".to_string(), composite_fields: None })
+      .collect();
+    let rows = Rows {
+      headers,
+      rows: vec![vec!["1".to_string(), "a description that is quite long".to_string()]],
+      rows_affected: None,
+    };
+    assert_eq!(Data::compact_column_widths(&rows, "", &[CellKind::Plain, CellKind::Plain], 20), vec![5, 20]);
+  }
+
+  #[test]
+  fn text_wraps_at_spaces_up_to_the_max_lines() {
+    assert_eq!(Data::wrap_render_text("the quick brown fox", 10, 5), vec!["the quick", "brown fox"]);
+    assert_eq!(Data::wrap_render_text("short", 10, 5), vec!["short"]);
+    assert_eq!(Data::wrap_render_text("", 10, 5), vec![""]);
+    assert_eq!(Data::wrap_render_text("one\ntwo", 10, 5), vec!["one", "two"]);
+    assert_eq!(Data::wrap_render_text("abcdefghijkl", 5, 5), vec!["abcde", "fghij", "kl"]);
+    assert_eq!(Data::wrap_render_text("日本語です", 5, 5), vec!["日本", "語で", "す"]);
+    assert_eq!(Data::wrap_render_text("a b c d e f", 3, 2), vec!["a b", "c …"]);
+  }
+}
+
 #[cfg(test)]
 mod yank {
 
@@ -735,7 +2143,7 @@ mod yank {
       vec!["id3".to_string(), "name3".to_string(), "age3".to_string()],
     ];
 
-    let result = TableForYank::to_columns(&headers, &rows);
+    let result = TableForYank::to_columns(&headers, &rows, "NULL");
 
     let expected = vec![
       VecDeque::from(["id".to_string(), "id1".to_string(), "id2".to_string(), "id3".to_string()]),
@@ -757,7 +2165,7 @@ mod yank {
 
     let mut data_to_yank = TableForYank {
       sql: vec!["select".to_string(), "*".to_string(), "from".to_string(), "something".to_string()],
-      table: TableForYank::to_columns(&headers, &rows),
+      table: TableForYank::to_columns(&headers, &rows, "NULL"),
     };
 
     let result = data_to_yank.yank();
@@ -776,3 +2184,276 @@ something
 ";
   }
 }
+
+#[cfg(test)]
+mod result_sets {
+  use super::*;
+  use crate::database::Header;
+
+  #[test]
+  fn result_sets_are_kept_for_each_statement() {
+    let rows = Rows {
+      headers: vec![Header { name: "id".to_string(), type_name: "INT4".to_string(), composite_fields: None }],
+      rows: vec![vec!["1".to_string()]],
+      rows_affected: None,
+    };
+    let mut data = Data::new();
+    data.clear_result_sets();
+    data.push_result_set(Ok(rows), None);
+    data.push_result_set(Err(eyre::eyre!("relation does not exist")), None);
+    assert_eq!(data.result_set_index, 1);
+    assert!(matches!(&data.data_state, DataState::Error(e) if e.to_string() == "relation does not exist"));
+
+    data.show_result_set(0);
+    assert!(matches!(&data.data_state, DataState::HasResults(rows) if rows.rows.len() == 1));
+    data.show_result_set(2);
+    assert_eq!(data.result_set_index, 0);
+
+    data.set_data_state(None, None);
+    assert!(data.result_sets.is_empty());
+  }
+
+  #[test]
+  fn result_sets_keep_the_query_they_came_from() {
+    let rows = Rows {
+      headers: vec![Header { name: "id".to_string(), type_name: "INT4".to_string(), composite_fields: None }],
+      rows: vec![vec!["1".to_string()]],
+      rows_affected: None,
+    };
+    let mut data = Data::new();
+    data.clear_result_sets();
+    data.push_result_set(Ok(rows.clone()), None);
+    data.set_source_query(Some("select 1 as id".to_string()));
+    data.push_result_set(Ok(rows.clone()), None);
+    data.set_source_query(Some("select 2 as id".to_string()));
+    data.show_result_set(0);
+    assert_eq!(data.source_query.as_deref(), Some("select 1 as id"));
+    data.show_result_set(1);
+    assert_eq!(data.source_query.as_deref(), Some("select 2 as id"));
+
+    data.set_data_state(Some(Ok(rows)), None);
+    assert_eq!(data.source_query, None);
+  }
+
+  #[test]
+  fn database_errors_keep_their_location() {
+    let error = DbError {
+      message: "syntax error at or near \"form\"".to_string(),
+      hint: Some("check the spelling".to_string()),
+      position: Some(11),
+      query: "select *\n\tform users;".to_string(),
+      ..Default::default()
+    };
+    let mut data = Data::new();
+    data.clear_result_sets();
+    data.push_result_set(Err(eyre::Report::new(error.clone())), None);
+    data.push_result_set(Err(eyre::eyre!("connection reset")), None);
+    data.show_result_set(0);
+    let DataState::Error(e) = &data.data_state else { panic!("expected an error") };
+    let kept = e.downcast_ref::<DbError>().unwrap();
+    assert_eq!(kept, &error);
+
+    let text = db_error_text(kept, Style::default(), Style::default());
+    let lines: Vec<String> = text.lines.iter().map(|line| line.to_string()).collect();
+    assert_eq!(
+      lines,
+      vec![
+        "ERROR: syntax error at or near \"form\"",
+        "LINE 2:     form users;",
+        "            ^",
+        "HINT: check the spelling",
+      ]
+    );
+  }
+}
+
+#[cfg(test)]
+mod cancelling {
+  use super::*;
+
+  #[test]
+  fn cancelling_restores_the_previous_results() {
+    let mut data = Data::new();
+    data.set_loading();
+    data.set_cancelled();
+    assert!(matches!(data.data_state, DataState::Cancelled));
+
+    data.set_data_state(Some(Ok(Rows { headers: vec![], rows: vec![], rows_affected: Some(3) })), None);
+    data.set_loading();
+    data.set_loading();
+    data.set_cancelled();
+    assert!(matches!(data.data_state, DataState::RowsAffected(3)));
+  }
+}
+
+#[cfg(test)]
+mod empty_results {
+  use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+  use super::*;
+  use crate::database::Header;
+
+  #[test]
+  fn empty_results_keep_their_columns() {
+    let statement = |sql: &str| Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap().remove(0);
+    let mut data = Data::new();
+    let headers = vec![Header { name: "id".to_string(), type_name: "INT4".to_string(), composite_fields: None }];
+    let rows = Rows { headers, rows: vec![], rows_affected: Some(0) };
+    data.set_data_state(Some(Ok(rows)), Some(statement("select id from t where false")));
+    assert!(matches!(&data.data_state, DataState::HasResults(rows) if rows.rows.is_empty() && rows.headers.len() == 1));
+
+    let rows = Rows { headers: vec![], rows: vec![], rows_affected: Some(0) };
+    data.set_data_state(Some(Ok(rows.clone())), Some(statement("select * from t where false")));
+    assert!(matches!(data.data_state, DataState::NoResults));
+    data.set_data_state(Some(Ok(rows)), Some(statement("set search_path = public")));
+    assert!(matches!(data.data_state, DataState::StatementCompleted(..)));
+  }
+}
+
+#[cfg(test)]
+mod focused_cell {
+  use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+  use super::*;
+  use crate::database::{Header, NULL};
+
+  #[test]
+  fn focused_cell_is_the_raw_value() {
+    let statement = Parser::parse_sql(&PostgreSqlDialect {}, "select * from t").unwrap().remove(0);
+    let mut data = Data::new();
+    assert_eq!(data.focused_cell(), None);
+
+    let long = "x".repeat(500);
+    let headers = vec![
+      Header { name: "body".to_string(), type_name: "TEXT".to_string(), composite_fields: None },
+      Header { name: "note".to_string(), type_name: "TEXT".to_string(), composite_fields: None },
+    ];
+    let rows = Rows { headers, rows: vec![vec![long.clone(), NULL.to_string()]], rows_affected: Some(1) };
+    data.set_data_state(Some(Ok(rows)), Some(statement));
+    assert_eq!(data.focused_cell(), Some(long.clone()));
+    data.scrollable.next_column();
+    assert_eq!(data.focused_cell(), Some(NULL.to_string()));
+    assert_eq!(data.focused_row(), Some(vec![long, NULL.to_string()]));
+    assert_eq!(
+      data.focused_column().map(|(header, values)| (header.name, values)),
+      Some(("note".to_string(), vec![NULL.to_string()]))
+    );
+    let (statement, headers, column, row) = data.editable_cell().unwrap();
+    assert_eq!(statement.to_string(), "SELECT * FROM t");
+    assert_eq!((headers.len(), column, row[1].as_str()), (2, 1, NULL));
+  }
+}
+
+#[cfg(test)]
+mod cell_search {
+  use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+  use super::*;
+  use crate::database::Header;
+
+  #[test]
+  fn search_moves_between_matches_without_hiding_rows() {
+    let statement = Parser::parse_sql(&PostgreSqlDialect {}, "select * from t").unwrap().remove(0);
+    let headers = vec![Header { name: "name".to_string(), type_name: "TEXT".to_string(), composite_fields: None }];
+    let rows = ["alice", "bob", "Alicia", "carol"].iter().map(|name| vec![name.to_string()]).collect();
+    let mut data = Data::new();
+    data.set_data_state(Some(Ok(Rows { headers, rows, rows_affected: None })), Some(statement));
+    data.scrollable.go_to_row(1);
+
+    data.search = Some(CellSearch::default());
+    data.search_focused = true;
+    data.search_origin = data.scrollable.get_cell_offsets();
+    for c in "ALI".chars() {
+      data.handle_search_input(Input { key: Key::Char(c), ..Default::default() });
+    }
+    assert_eq!(data.search_matches, vec![(0, 0), (0, 2)]);
+    assert_eq!(data.scrollable.get_cell_offsets(), (0, 2));
+    data.handle_search_input(Input { key: Key::Enter, ..Default::default() });
+    assert!(!data.search_focused);
+    assert!(matches!(&data.data_state, DataState::HasResults(rows) if rows.rows.len() == 4));
+
+    data.jump_to_match(true);
+    assert_eq!(data.scrollable.get_cell_offsets(), (0, 0));
+    data.jump_to_match(false);
+    assert_eq!(data.scrollable.get_cell_offsets(), (0, 2));
+
+    data.search.as_mut().unwrap().case_sensitive = true;
+    data.rebuild_table();
+    assert!(data.search_matches.is_empty());
+    data.clear_search();
+    assert!(data.search.is_none());
+  }
+}
+
+#[cfg(test)]
+mod query_plan {
+  use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+  use super::*;
+  use crate::database::Header;
+
+  #[test]
+  fn json_plans_are_shown_as_a_tree() {
+    let statement = |sql: &str| Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap().remove(0);
+    let headers =
+      vec![Header { name: "QUERY PLAN".to_string(), type_name: "JSON".to_string(), composite_fields: None }];
+    let json = r#"[{"Plan": {"Node Type": "Limit", "Startup Cost": 0.0, "Total Cost": 1.5, "Plan Rows": 10,
+      "Plans": [{"Node Type": "Seq Scan", "Relation Name": "t", "Alias": "t", "Startup Cost": 0.0,
+      "Total Cost": 15.0, "Plan Rows": 100}]}}]"#;
+    let rows = Rows { headers: headers.clone(), rows: vec![vec![json.to_string()]], rows_affected: None };
+    let mut data = Data::new();
+    data.set_data_state(Some(Ok(rows)), Some(statement("explain (format json) select * from t limit 10")));
+    let DataState::Plan(tree) = &data.data_state else {
+      panic!("the plan wasn't read");
+    };
+    assert_eq!(tree.visible().len(), 2);
+    data.scroll(ScrollDirection::Down);
+    data.scroll(ScrollDirection::Left);
+    data.scroll(ScrollDirection::Left);
+    let DataState::Plan(tree) = &data.data_state else { unreachable!() };
+    assert_eq!(tree.visible().len(), 1);
+
+    let text = Rows { headers, rows: vec![vec!["Seq Scan on t".to_string()]], rows_affected: None };
+    data.set_data_state(Some(Ok(text)), Some(statement("explain select * from t")));
+    assert!(matches!(data.data_state, DataState::Explain(_)));
+  }
+}
+
+#[cfg(test)]
+mod comparing {
+  use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+  use super::*;
+  use crate::database::Header;
+
+  #[test]
+  fn results_are_compared_with_the_pinned_ones() {
+    let statement = Parser::parse_sql(&PostgreSqlDialect {}, "select * from t").unwrap().remove(0);
+    let results = |rows: &[[&str; 2]]| Rows {
+      headers: ["id", "name"]
+        .map(|name| Header { name: name.to_string(), type_name: "TEXT".to_string(), composite_fields: None })
+        .to_vec(),
+      rows: rows.iter().map(|row| row.map(String::from).to_vec()).collect(),
+      rows_affected: None,
+    };
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut data = Data::new();
+    data.register_action_handler(tx).unwrap();
+    data.set_data_state(Some(Ok(results(&[["1", "a"], ["2", "b"]]))), Some(statement.clone()));
+    data.toggle_diff().unwrap();
+    assert!(matches!(rx.try_recv(), Ok(Action::StatusMessage(_, StatusLevel::Error))));
+    data.pin_results().unwrap();
+
+    data.set_data_state(Some(Ok(results(&[["1", "x"], ["3", "c"]]))), Some(statement));
+    data.scrollable.transition_selection_mode(Some(SelectionMode::Cell));
+    data.toggle_diff().unwrap();
+    let diff = data.diff.as_ref().unwrap();
+    assert_eq!(diff.changes, vec![RowChange::Changed(vec![1]), RowChange::Added, RowChange::Removed]);
+    assert_eq!(diff.key.as_deref(), Some("id"));
+    assert!(matches!(&data.data_state, DataState::HasResults(rows) if rows.rows.len() == 3));
+
+    data.toggle_diff().unwrap();
+    assert!(data.diff.is_none());
+    assert!(matches!(&data.data_state, DataState::HasResults(rows) if rows.rows.len() == 2));
+  }
+}
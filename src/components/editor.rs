@@ -1,8 +1,12 @@
+use std::path::{Path, PathBuf};
+
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyEvent, MouseEvent, MouseEventKind};
 use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::UnboundedSender;
-use tui_textarea::{Input, Key, TextArea};
+use tui_textarea::{CursorMove, Input, Key, TextArea};
 
 use super::{Component, Frame};
 use crate::{
@@ -11,14 +15,90 @@ use crate::{
   config::Config,
   database::get_keywords,
   focus::Focus,
+  search::{SearchOptions, Substitute},
   tui::Event,
+  undo::{Snapshot, UndoHistory},
+  utils::connection_file_name,
   vim::{Mode, Transition, Vim},
 };
 
+const SCRATCH_DIR: &str = "scratch";
+
+// the brackets and quotes that are typed in pairs
+const PAIRS: [(char, char); 6] = [('(', ')'), ('[', ']'), ('{', '}'), ('\'', '\''), ('"', '"'), ('`', '`')];
+
 fn keyword_regex() -> String {
   format!("(?i)(^|[^a-zA-Z0-9\'\"`._]+)({})($|[^a-zA-Z0-9\'\"`._]+)", get_keywords().join("|"))
 }
 
+// the `/pattern` or `:s/old/new/` line being typed at the bottom of the editor
+#[derive(Default)]
+struct CommandLine {
+  prefix: char,
+  text: String,
+}
+
+pub trait EditorBuffer {
+  // whether the contents changed since they were last loaded from or saved to a file
+  fn is_modified(&self) -> bool;
+  fn lines(&self) -> &[String];
+}
+
+pub trait EditorComponent: Component + EditorBuffer {}
+
+impl<T> EditorComponent for T where T: Component + EditorBuffer {}
+
+// the scratch file's contents: the lines of each query tab, in order
+#[derive(Serialize, Deserialize)]
+struct SavedScratch {
+  tabs: Vec<Vec<String>>,
+}
+
+/// The contents of the query tabs, saved as they're edited so they aren't lost
+/// if rainfrog is closed or crashes. Each connection from the config has its
+/// own, so scratch queries for different projects are kept apart.
+pub struct ScratchFile {
+  path: PathBuf,
+}
+
+impl ScratchFile {
+  pub fn new(data_dir: &Path, connection_name: Option<&str>) -> Self {
+    Self { path: data_dir.join(SCRATCH_DIR).join(format!("{}.json", connection_file_name(connection_name))) }
+  }
+
+  /// Reads the saved tabs, if there are any with something in them.
+  pub fn load(&self) -> Result<Option<Vec<Vec<String>>>> {
+    if !self.path.exists() {
+      return Ok(None);
+    }
+    let saved: SavedScratch = serde_json::from_str(&std::fs::read_to_string(&self.path)?)?;
+    Ok(Some(saved.tabs).filter(|tabs| !is_blank(tabs)))
+  }
+
+  /// Saves the tabs, or removes the file when they're all empty. The file is
+  /// written next to the old one and moved over it, so a crash part way
+  /// through doesn't leave it cut short.
+  pub fn save(&self, tabs: &[Vec<String>]) -> Result<()> {
+    if is_blank(tabs) {
+      return match std::fs::remove_file(&self.path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+      };
+    }
+    if let Some(dir) = self.path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    let partial = self.path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_string(&SavedScratch { tabs: tabs.to_vec() })?)?;
+    std::fs::rename(&partial, &self.path)?;
+    Ok(())
+  }
+}
+
+fn is_blank(tabs: &[Vec<String>]) -> bool {
+  tabs.iter().flatten().all(|line| line.trim().is_empty())
+}
+
 #[derive(Default)]
 pub struct Editor<'a> {
   command_tx: Option<UnboundedSender<Action>>,
@@ -27,12 +107,23 @@ pub struct Editor<'a> {
   vim_state: Vim,
   cursor_style: Style,
   last_query_duration: Option<chrono::Duration>,
+  command_line: Option<CommandLine>,
+  // while searching, matches are highlighted instead of keywords
+  search: Option<String>,
+  search_options: SearchOptions,
+  message: Option<String>,
+  history: UndoHistory,
+  // whether the last input undid or redid, so its change isn't recorded as an edit
+  moved_in_history: bool,
+  // the contents as they were last loaded or saved
+  saved_lines: Vec<String>,
 }
 
 impl Editor<'_> {
   pub fn new() -> Self {
     let mut textarea = TextArea::default();
     textarea.set_search_pattern(keyword_regex()).unwrap();
+    textarea.set_max_histories(0);
     Editor {
       command_tx: None,
       config: Config::default(),
@@ -40,7 +131,309 @@ impl Editor<'_> {
       vim_state: Vim::new(Mode::Normal),
       cursor_style: Mode::Normal.cursor_style(),
       last_query_duration: None,
+      command_line: None,
+      search: None,
+      search_options: SearchOptions::default(),
+      message: None,
+      history: UndoHistory::default(),
+      moved_in_history: false,
+      saved_lines: vec![String::new()],
+    }
+  }
+
+  // replaces the editor contents with a fresh buffer and history
+  fn load_lines(&mut self, lines: Vec<String>) {
+    self.textarea = TextArea::from(lines);
+    self.textarea.set_max_histories(0);
+    self.history.clear();
+    self.clear_search();
+  }
+
+  fn snapshot(&self) -> Snapshot {
+    Snapshot { lines: self.textarea.lines().to_vec(), cursor: self.textarea.cursor() }
+  }
+
+  fn restore(&mut self, snapshot: Snapshot) {
+    self.textarea.cancel_selection();
+    self.replace_text(&snapshot.lines.join("\n"));
+    let (row, col) = snapshot.cursor;
+    self.textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+  }
+
+  fn undo(&mut self) {
+    self.moved_in_history = true;
+    match self.history.undo(self.snapshot()) {
+      Some(snapshot) => self.restore(snapshot),
+      None => self.message = Some("Already at oldest change".to_string()),
+    }
+  }
+
+  fn redo(&mut self) {
+    self.moved_in_history = true;
+    match self.history.redo(self.snapshot()) {
+      Some(snapshot) => self.restore(snapshot),
+      None => self.message = Some("Already at newest change".to_string()),
+    }
+  }
+
+  // whether an input that changed the buffer is part of a run of typing, which
+  // is undone as one chunk. in vim mode that's everything done in insert mode.
+  fn is_typing(&self, input: &Input) -> bool {
+    if self.vim_enabled() {
+      return self.vim_state.mode == Mode::Insert;
+    }
+    match input {
+      Input { ctrl: true, .. } | Input { alt: true, .. } => false,
+      Input { key, .. } => matches!(key, Key::Char(_) | Key::Backspace | Key::Delete | Key::Enter | Key::Tab),
+    }
+  }
+
+  fn open_command_line(&mut self, prefix: char, text: &str) {
+    self.command_line = Some(CommandLine { prefix, text: text.to_string() });
+  }
+
+  // a bracketed paste comes as one event, so it's inserted as a whole without
+  // its newlines running keybindings. terminals send those newlines as `\r`
+  // or `\r\n`, and the textarea would keep a lone `\r` as it is.
+  fn paste(&mut self, text: &str) {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    match self.command_line.as_mut() {
+      // the command line only holds one line
+      Some(command_line) => command_line.text.push_str(text.lines().next().unwrap_or_default()),
+      None => {
+        self.textarea.insert_str(text);
+      },
+    }
+  }
+
+  fn handle_command_line_input(&mut self, input: Input) {
+    let Some(command_line) = self.command_line.as_mut() else {
+      return;
+    };
+    match input {
+      Input { key: Key::Esc, .. } => self.command_line = None,
+      Input { key: Key::Backspace, .. } if command_line.text.is_empty() => self.command_line = None,
+      Input { key: Key::Backspace, .. } => {
+        command_line.text.pop();
+      },
+      Input { key: Key::Enter, .. } => {
+        let CommandLine { prefix, text } = self.command_line.take().unwrap_or_default();
+        match prefix {
+          '/' => self.search(text),
+          _ => self.substitute(&text),
+        }
+      },
+      Input { key: Key::Char('c'), alt: true, .. } => {
+        self.search_options.ignore_case = !self.search_options.ignore_case
+      },
+      Input { key: Key::Char('w'), alt: true, .. } => self.search_options.whole_word = !self.search_options.whole_word,
+      Input { key: Key::Char(c), ctrl: false, alt: false, .. } => command_line.text.push(c),
+      _ => {},
+    }
+  }
+
+  // an empty pattern repeats the last search, like vim
+  fn search(&mut self, pattern: String) {
+    let Some(pattern) = Some(pattern).filter(|p| !p.is_empty()).or_else(|| self.search.clone()) else {
+      return;
+    };
+    if let Err(e) = self.textarea.set_search_pattern(self.search_options.pattern(&pattern)) {
+      self.message = Some(format!("Invalid pattern: {e}"));
+      self.clear_search();
+      return;
+    }
+    if !self.textarea.search_forward(false) {
+      self.message = Some(format!("Pattern not found: {pattern}"));
+    }
+    self.search = Some(pattern);
+  }
+
+  fn search_next(&mut self, forward: bool) {
+    let found = if forward { self.textarea.search_forward(false) } else { self.textarea.search_back(false) };
+    if !found && let Some(pattern) = &self.search {
+      self.message = Some(format!("Pattern not found: {pattern}"));
+    }
+  }
+
+  fn clear_search(&mut self) {
+    self.search = None;
+    self.textarea.set_search_pattern(keyword_regex()).unwrap();
+  }
+
+  fn substitute(&mut self, command: &str) {
+    let result = Substitute::parse(command).and_then(|substitute| {
+      let (row, col) = self.textarea.cursor();
+      let text = match substitute.whole_buffer {
+        true => self.textarea.lines().join("\n"),
+        false => self.textarea.lines()[row].clone(),
+      };
+      let (replaced, count) = substitute.apply(&text, self.search_options)?;
+      if count > 0 {
+        if !substitute.whole_buffer {
+          self.textarea.move_cursor(CursorMove::Head);
+          self.textarea.start_selection();
+          self.textarea.move_cursor(CursorMove::End);
+        }
+        self.replace_text(&replaced);
+        self.textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+      }
+      Ok(count)
+    });
+    self.message = Some(match result {
+      Ok(0) => "Pattern not found".to_string(),
+      Ok(count) => format!("{count} replaced"),
+      Err(e) => e.to_string(),
+    });
+  }
+
+  // replaces the selection, or the whole buffer if nothing is selected. this goes
+  // through the textarea rather than recreating it, so it can be undone.
+  fn replace_text(&mut self, text: &str) {
+    if !self.textarea.is_selecting() {
+      self.textarea.select_all();
+    }
+    self.textarea.delete_str(usize::MAX);
+    self.textarea.insert_str(text);
+  }
+
+  // toggles `--` comments on the selected lines, or the cursor's line,
+  // keeping the cursor and selection on the same text
+  fn toggle_comment(&mut self) {
+    let selection = self.textarea.selection_range();
+    let cursor = self.textarea.cursor();
+    let rows = match selection {
+      // a selection that ends at the start of a line doesn't include it
+      Some(((start, _), (end, 0))) if end > start => start..=end - 1,
+      Some(((start, _), (end, _))) => start..=end,
+      None => cursor.0..=cursor.0,
+    };
+    let toggled = toggle_comments(&self.textarea.lines()[rows.clone()]);
+    if toggled.iter().all(|(_, edit)| edit.is_none()) {
+      return;
+    }
+    self.history.record(self.snapshot(), false);
+    let mut lines = self.textarea.lines().to_vec();
+    for (row, (line, _)) in rows.clone().zip(&toggled) {
+      lines[row] = line.clone();
+    }
+    // positions keep their place in the text around the `--`
+    let shift = |(row, col): (usize, usize)| match rows.contains(&row).then(|| toggled[row - rows.start()].1) {
+      Some(Some((at, by))) if col >= at => (row, col.saturating_add_signed(by).max(at)),
+      _ => (row, col),
+    };
+    self.textarea.cancel_selection();
+    self.replace_text(&lines.join("\n"));
+    if let Some((start, end)) = selection {
+      let anchor = if start == cursor { end } else { start };
+      let (row, col) = shift(anchor);
+      self.textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+      self.textarea.start_selection();
     }
+    let (row, col) = shift(cursor);
+    self.textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+  }
+
+  fn vim_enabled(&self) -> bool {
+    self.config.settings.vim_mode.unwrap_or(true)
+  }
+
+  // pairs are only typed while inserting, since vim's other modes use these
+  // keys as commands
+  fn auto_pairs_enabled(&self) -> bool {
+    self.config.settings.editor_auto_pairs.unwrap_or(true)
+      && (!self.vim_enabled() || self.vim_state.mode == Mode::Insert)
+  }
+
+  // closes brackets and quotes as they're typed, types over the closing ones,
+  // and removes an empty pair with backspace. with a selection, the selected
+  // text is wrapped in the pair instead. returns whether the input was
+  // handled here.
+  fn auto_pair(&mut self, input: &Input) -> bool {
+    let (row, col) = self.textarea.cursor();
+    let line = &self.textarea.lines()[row];
+    let prev = col.checked_sub(1).and_then(|col| line.chars().nth(col));
+    let next = line.chars().nth(col);
+    // a pair is only added before the end of a line, a space, or a closer, so
+    // typing an opener in front of a word doesn't close it
+    let before_gap = next.is_none_or(|c| c.is_whitespace() || PAIRS.iter().any(|(_, close)| *close == c));
+    match *input {
+      Input { key: Key::Backspace, ctrl: false, alt: false, .. } if !self.textarea.is_selecting() => {
+        match PAIRS.iter().find(|(open, _)| Some(*open) == prev) {
+          Some((_, close)) if next == Some(*close) => {
+            self.textarea.delete_next_char();
+            self.textarea.delete_char();
+            true
+          },
+          _ => false,
+        }
+      },
+      Input { key: Key::Char(c), ctrl: false, alt: false, .. } => {
+        let Some(&(open, close)) = PAIRS.iter().find(|(open, close)| *open == c || *close == c) else {
+          return false;
+        };
+        if let Some(((start_row, start_col), (end_row, end_col))) =
+          self.textarea.selection_range().filter(|_| c == open)
+        {
+          self.textarea.cancel_selection();
+          self.textarea.move_cursor(CursorMove::Jump(end_row as u16, end_col as u16));
+          self.textarea.insert_char(close);
+          self.textarea.move_cursor(CursorMove::Jump(start_row as u16, start_col as u16));
+          self.textarea.insert_char(open);
+          // the wrapped text stays selected
+          self.textarea.start_selection();
+          let end_col = if end_row == start_row { end_col + 1 } else { end_col };
+          self.textarea.move_cursor(CursorMove::Jump(end_row as u16, end_col as u16));
+          return true;
+        }
+        let quote = open == close;
+        if c == close && next == Some(close) {
+          self.textarea.move_cursor(CursorMove::Forward);
+        } else if c == open && before_gap && !(quote && prev.is_some_and(char::is_alphanumeric)) {
+          self.textarea.insert_char(open);
+          self.textarea.insert_char(close);
+          self.textarea.move_cursor(CursorMove::Back);
+        } else {
+          return false;
+        }
+        true
+      },
+      _ => false,
+    }
+  }
+
+  // keys for when vim emulation is turned off. anything not handled here
+  // gets tui-textarea's default (emacs-like) key mappings.
+  pub fn handle_plain_input(&mut self, input: Input, app_state: &AppState) -> Result<()> {
+    match input {
+      Input { key: Key::Enter, alt: true, .. } | Input { key: Key::Enter, ctrl: true, .. } => {
+        if !app_state.query_task_running
+          && let Some(sender) = &self.command_tx
+        {
+          sender.send(Action::SubmitEditorQuery)?;
+        }
+      },
+      Input { key: Key::Char('f'), alt: true, .. } => {
+        if let Some(sender) = &self.command_tx {
+          sender.send(Action::RequestSaveFavorite(self.textarea.lines().to_vec()))?;
+        }
+      },
+      Input { key: Key::Char('c'), ctrl: true, .. } => {
+        if let Some(sender) = &self.command_tx {
+          sender.send(Action::Quit)?;
+        }
+      },
+      Input { key: Key::Char('z' | 'u'), ctrl: true, .. } => self.undo(),
+      Input { key: Key::Char('r'), ctrl: true, .. } => self.redo(),
+      Input { key: Key::Char('/'), alt: true, .. } => self.open_command_line('/', ""),
+      Input { key: Key::Char('r'), alt: true, .. } => self.open_command_line(':', "%s/"),
+      Input { key: Key::Char('n'), alt: true, .. } if self.search.is_some() => self.search_next(true),
+      Input { key: Key::Char('p'), alt: true, .. } if self.search.is_some() => self.search_next(false),
+      Input { key: Key::Esc, .. } if self.search.is_some() => self.clear_search(),
+      input => {
+        self.textarea.input(input);
+      },
+    };
+    Ok(())
   }
 
   pub fn transition_vim_state(&mut self, input: Input, app_state: &AppState) -> Result<()> {
@@ -49,12 +442,21 @@ impl Editor<'_> {
         if !app_state.query_task_running
           && let Some(sender) = &self.command_tx
         {
-          sender.send(Action::Query(self.textarea.lines().to_vec(), false, false))?;
+          sender.send(Action::SubmitEditorQuery)?;
           self.vim_state = Vim::new(Mode::Normal);
           self.vim_state.register_action_handler(self.command_tx.clone())?;
           self.cursor_style = Mode::Normal.cursor_style();
         }
       },
+      Input { key: Key::Enter, ctrl: false, alt: false, shift: false }
+        if matches!(self.vim_state.mode, Mode::Normal) =>
+      {
+        if !app_state.query_task_running
+          && let Some(sender) = &self.command_tx
+        {
+          sender.send(Action::SubmitEditorQueryAndFocusData)?;
+        }
+      },
       Input { key: Key::Tab, shift: false, .. } if self.vim_state.mode != Mode::Insert => {
         if let Some(sender) = &self.command_tx {
           sender.send(Action::CycleFocusForwards)?;
@@ -80,15 +482,45 @@ impl Editor<'_> {
           sender.send(Action::AbortQuery)?;
         }
       },
+      Input { key: Key::Char(prefix @ ('/' | ':')), ctrl: false, alt: false, .. }
+        if matches!(self.vim_state.mode, Mode::Normal) =>
+      {
+        self.open_command_line(prefix, "");
+      },
+      Input { key: Key::Char('n'), ctrl: false, .. }
+        if matches!(self.vim_state.mode, Mode::Normal) && self.search.is_some() =>
+      {
+        self.search_next(true);
+      },
+      Input { key: Key::Char('N'), ctrl: false, .. }
+        if matches!(self.vim_state.mode, Mode::Normal) && self.search.is_some() =>
+      {
+        self.search_next(false);
+      },
+      Input { key: Key::Esc, .. } if matches!(self.vim_state.mode, Mode::Normal) && self.search.is_some() => {
+        self.clear_search();
+      },
       _ => {
         let new_vim_state = self.vim_state.clone();
-        self.vim_state = match new_vim_state.transition(input, &mut self.textarea) {
-          Transition::Mode(mode) if new_vim_state.mode != mode => {
+        let transition = new_vim_state.transition(input, &mut self.textarea);
+        self.vim_state = match &transition {
+          Transition::Mode(mode) if new_vim_state.mode != *mode => {
+            self.history.break_chunk();
             self.cursor_style = mode.cursor_style();
-            Vim::new(mode)
+            Vim::new(*mode)
           },
           Transition::Nop | Transition::Mode(_) => new_vim_state,
-          Transition::Pending(input) => new_vim_state.with_pending(input),
+          Transition::Pending(input) => new_vim_state.with_pending(input.clone()),
+          Transition::Undo | Transition::Redo if new_vim_state.mode != Mode::Normal => {
+            self.cursor_style = Mode::Normal.cursor_style();
+            Vim::new(Mode::Normal)
+          },
+          Transition::Undo | Transition::Redo => new_vim_state,
+        };
+        match transition {
+          Transition::Undo => self.undo(),
+          Transition::Redo => self.redo(),
+          _ => {},
         };
         self.vim_state.register_action_handler(self.command_tx.clone())?;
       },
@@ -97,6 +529,16 @@ impl Editor<'_> {
   }
 }
 
+impl EditorBuffer for Editor<'_> {
+  fn is_modified(&self) -> bool {
+    self.textarea.lines() != self.saved_lines.as_slice()
+  }
+
+  fn lines(&self) -> &[String] {
+    self.textarea.lines()
+  }
+}
+
 impl Component for Editor<'_> {
   fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
     self.vim_state.register_action_handler(self.command_tx.clone())?;
@@ -106,6 +548,9 @@ impl Component for Editor<'_> {
 
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
     self.config = config;
+    if !self.vim_enabled() {
+      self.cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+    }
     Ok(())
   }
 
@@ -120,6 +565,12 @@ impl Component for Editor<'_> {
       MouseEventKind::ScrollUp => {
         self.textarea.scroll((-1, 0));
       },
+      MouseEventKind::ScrollLeft if !self.vim_enabled() => {
+        self.textarea.move_cursor(CursorMove::Back);
+      },
+      MouseEventKind::ScrollRight if !self.vim_enabled() => {
+        self.textarea.move_cursor(CursorMove::Forward);
+      },
       MouseEventKind::ScrollLeft => {
         self.transition_vim_state(Input { key: Key::Char('h'), ctrl: false, alt: false, shift: false }, app_state)?;
       },
@@ -140,14 +591,35 @@ impl Component for Editor<'_> {
     if app_state.focus != Focus::Editor {
       return Ok(None);
     }
+    let before = self.snapshot();
+    let mut typing = false;
+    self.moved_in_history = false;
     if let Some(Event::Paste(text)) = event {
-      self.textarea.insert_str(text);
+      self.paste(&text);
     } else if let Some(Event::Mouse(event)) = event {
       self.handle_mouse_events(event, app_state).unwrap();
     } else if let Some(Event::Key(key)) = event {
       let input = Input::from(key);
-      self.transition_vim_state(input, app_state)?;
+      self.message = None;
+      if self.command_line.is_some() {
+        self.handle_command_line_input(input);
+      } else if self.auto_pairs_enabled() && self.auto_pair(&input) {
+        typing = self.is_typing(&input);
+      } else if self.vim_enabled() {
+        self.transition_vim_state(input.clone(), app_state)?;
+        typing = self.is_typing(&input);
+      } else {
+        typing = self.is_typing(&input);
+        self.handle_plain_input(input, app_state)?;
+      }
     };
+    if self.moved_in_history {
+      // undo and redo keep their own history, and recording would clear the redos
+    } else if self.textarea.lines() != before.lines.as_slice() {
+      self.history.record(before, typing);
+    } else if self.textarea.cursor() != before.cursor {
+      self.history.break_chunk();
+    }
     Ok(None)
   }
 
@@ -158,14 +630,61 @@ impl Component for Editor<'_> {
           sender.send(Action::Query(self.textarea.lines().to_vec(), false, true))?;
         }
       },
-      Action::SubmitEditorQuery => {
+      Action::SubmitEditorQuery | Action::SubmitEditorQueryAndFocusData => {
         if let Some(sender) = &self.command_tx {
           sender.send(Action::Query(self.textarea.lines().to_vec(), false, false))?;
         }
       },
+      Action::ExplainEditorQuery | Action::ExplainAnalyzeEditorQuery => {
+        if let Some(sender) = &self.command_tx {
+          let analyze = action == Action::ExplainAnalyzeEditorQuery;
+          sender.send(Action::ExplainQuery(self.textarea.lines().to_vec(), analyze))?;
+        }
+      },
+      Action::FormatQuery => {
+        if let Some(sender) = &self.command_tx {
+          sender.send(Action::FormatQueryLines(self.textarea.lines().to_vec()))?;
+        }
+      },
+      Action::ToggleComment => self.toggle_comment(),
+      Action::FormattedQuery(query) => {
+        self.history.record(self.snapshot(), false);
+        self.textarea.cancel_selection();
+        self.replace_text(&query);
+        self.textarea.move_cursor(CursorMove::Top);
+      },
+      Action::LoadSqlFile(path) => match std::fs::read_to_string(&path) {
+        Ok(contents) => {
+          self.load_lines(contents.lines().map(String::from).collect());
+          self.saved_lines = self.textarea.lines().to_vec();
+          if let Some(sender) = &self.command_tx {
+            sender.send(Action::SqlFileFinished(path))?;
+          }
+        },
+        Err(e) => {
+          if let Some(sender) = &self.command_tx {
+            sender.send(Action::Error(format!("Could not load {}: {e}", path.display())))?;
+          }
+        },
+      },
+      Action::SaveSqlFile(path) => {
+        let contents = format!("{}\n", self.textarea.lines().join("\n"));
+        match std::fs::write(&path, contents) {
+          Ok(()) => {
+            self.saved_lines = self.textarea.lines().to_vec();
+            if let Some(sender) = &self.command_tx {
+              sender.send(Action::SqlFileFinished(path))?;
+            }
+          },
+          Err(e) => {
+            if let Some(sender) = &self.command_tx {
+              sender.send(Action::Error(format!("Could not save {}: {e}", path.display())))?;
+            }
+          },
+        }
+      },
       Action::QueryToEditor(lines) => {
-        self.textarea = TextArea::from(lines.clone());
-        self.textarea.set_search_pattern(keyword_regex()).unwrap();
+        self.load_lines(lines);
       },
       Action::CopyData(data) => {
         self.textarea.set_yank_text(data);
@@ -197,21 +716,231 @@ impl Component for Editor<'_> {
         seconds
       )
     });
-    let block = self
-      .vim_state
-      .mode
-      .block()
-      .border_style(if focused { Style::new().green() } else { Style::new().dim() })
+    let block = if self.vim_enabled() { self.vim_state.mode.block() } else { Block::default().borders(Borders::ALL) };
+    let status = match (&self.command_line, &self.message) {
+      (Some(command_line), _) => {
+        format!(" {}{} {} ", command_line.prefix, command_line.text, self.search_options.indicator())
+      },
+      (None, Some(message)) => format!(" {message} "),
+      (None, None) => "".to_string(),
+    };
+    let block = block
+      .title_bottom(Line::from(status).left_aligned())
+      .border_style(self.config.theme.border(focused))
       .title(Line::from(duration_string).right_aligned());
 
     self.textarea.set_cursor_style(self.cursor_style);
     self.textarea.set_block(block);
-    self.textarea.set_line_number_style(if focused { Style::default().fg(Color::Yellow) } else { Style::new().dim() });
+    self.textarea.set_line_number_style(if focused {
+      self.config.theme.title
+    } else {
+      self.config.theme.unfocused_border
+    });
     self.textarea.set_cursor_line_style(Style::default().not_underlined());
     self.textarea.set_hard_tab_indent(false);
     self.textarea.set_tab_length(2);
-    self.textarea.set_search_style(Style::default().fg(Color::Magenta).bold());
+    self.textarea.set_search_style(match self.search {
+      Some(_) => self.config.theme.highlight.reversed(),
+      None => Style::default().fg(Color::Magenta).bold(),
+    });
     f.render_widget(&self.textarea, area);
     Ok(())
   }
 }
+
+/// Comments each line with `--`, at the indentation of the least indented
+/// one so they stay lined up, or uncomments them if they all are. Blank lines
+/// are left as they are. Each line comes with where it changed, in chars, and
+/// by how many chars, if it did.
+fn toggle_comments(lines: &[String]) -> Vec<(String, Option<(usize, isize)>)> {
+  let indent = |line: &str| line.chars().take_while(|c| c.is_whitespace()).count();
+  let code = lines.iter().filter(|line| !line.trim().is_empty());
+  let commented = code.clone().next().is_some() && code.clone().all(|line| line.trim_start().starts_with("--"));
+  let code_indent = code.map(|line| indent(line)).min().unwrap_or_default();
+  lines
+    .iter()
+    .map(|line| {
+      let chars = line.chars().collect::<Vec<_>>();
+      match (line.trim().is_empty(), commented) {
+        (true, _) => (line.clone(), None),
+        (false, true) => {
+          // the space after the `--` goes with it
+          let at = indent(line);
+          let len = if chars.get(at + 2) == Some(&' ') { 3 } else { 2 };
+          (chars[..at].iter().chain(&chars[at + len..]).collect(), Some((at, -(len as isize))))
+        },
+        (false, false) => {
+          let line = chars[..code_indent].iter().chain(&['-', '-', ' ']).chain(&chars[code_indent..]).collect();
+          (line, Some((code_indent, 3)))
+        },
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{components::favorites::FavoriteEntries, database::ConnectionInfo};
+
+  fn app_state() -> AppState {
+    let favorites_dir = std::env::temp_dir().join(format!("rainfrog_test_editor_{}", std::process::id()));
+    AppState {
+      focus: Focus::Editor,
+      history: vec![],
+      favorites: FavoriteEntries::new(&favorites_dir).unwrap(),
+      last_query_start: None,
+      last_query_end: None,
+      query_task_running: false,
+      page: None,
+      cursor: None,
+      zoomed: false,
+      confirm_destructive: true,
+      connection: ConnectionInfo::default(),
+      connection_tag: None,
+    }
+  }
+
+  fn press(editor: &mut Editor, app_state: &AppState, keys: &[KeyEvent]) {
+    for key in keys {
+      editor.handle_events(Some(Event::Key(*key)), vec![], app_state).unwrap();
+    }
+  }
+
+  #[test]
+  fn test_scratch_file() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_scratch_{}", std::process::id()));
+    let scratch = ScratchFile::new(&dir, Some("prod/orders db"));
+    assert_eq!(scratch.path, dir.join("scratch").join("prod_orders_db.json"));
+    assert!(scratch.load().unwrap().is_none());
+
+    let tabs = vec![vec!["select *".to_string(), "from orders;".to_string()], vec![String::new()]];
+    scratch.save(&tabs).unwrap();
+    assert_eq!(scratch.load().unwrap(), Some(tabs));
+    assert!(ScratchFile::new(&dir, None).load().unwrap().is_none());
+
+    // nothing is kept for empty tabs
+    scratch.save(&[vec![String::new()], vec!["  ".to_string()]]).unwrap();
+    assert!(!scratch.path.exists());
+    scratch.save(&[vec![String::new()]]).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_toggle_comments() {
+    let lines = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+    let toggle = |lines: &[String]| toggle_comments(lines).into_iter().map(|(line, _)| line).collect::<Vec<_>>();
+
+    let query = lines(&["  select *", "", "    from users", "  where id = 1"]);
+    let commented = toggle(&query);
+    assert_eq!(commented, lines(&["  -- select *", "", "  --   from users", "  -- where id = 1"]));
+    assert_eq!(toggle(&commented), query);
+    assert_eq!(toggle_comments(&query)[0].1, Some((2, 3)));
+
+    // any line that isn't commented yet makes them all commented
+    let mixed = lines(&["--select 1", "select 2"]);
+    assert_eq!(toggle(&mixed), lines(&["-- --select 1", "-- select 2"]));
+    assert_eq!(toggle(&lines(&["--select 1", "\t-- select 2"])), lines(&["select 1", "\tselect 2"]));
+    assert_eq!(toggle_comments(&lines(&["", "  "])), vec![(String::new(), None), ("  ".to_owned(), None)]);
+  }
+
+  #[test]
+  fn test_toggle_comment_keeps_cursor() {
+    let mut editor = Editor::new();
+    editor.paste("select *\nfrom users\nwhere id = 1");
+    editor.textarea.move_cursor(CursorMove::Jump(0, 2));
+    editor.textarea.start_selection();
+    editor.textarea.move_cursor(CursorMove::Jump(2, 0));
+    editor.toggle_comment();
+    assert_eq!(editor.textarea.lines(), ["-- select *", "-- from users", "where id = 1"]);
+    assert_eq!(editor.textarea.selection_range(), Some(((0, 5), (2, 0))));
+
+    editor.textarea.cancel_selection();
+    editor.textarea.move_cursor(CursorMove::Jump(1, 1));
+    editor.toggle_comment();
+    assert_eq!(editor.textarea.lines(), ["-- select *", "from users", "where id = 1"]);
+    assert_eq!(editor.textarea.cursor(), (1, 0));
+    editor.undo();
+    assert_eq!(editor.textarea.lines(), ["-- select *", "-- from users", "where id = 1"]);
+  }
+
+  #[test]
+  fn test_auto_pairs() {
+    let mut editor = Editor::new();
+    let typing = |editor: &mut Editor, keys: &[Key]| {
+      for key in keys {
+        let input = Input { key: *key, ..Input::default() };
+        if !editor.auto_pair(&input) {
+          editor.textarea.input(input);
+        }
+      }
+    };
+    let chars = |text: &str| text.chars().map(Key::Char).collect::<Vec<_>>();
+
+    typing(&mut editor, &chars("count(*"));
+    assert_eq!(editor.textarea.lines(), ["count(*)"]);
+    // the closing bracket is typed over instead of doubled
+    typing(&mut editor, &chars(") = '"));
+    assert_eq!(editor.textarea.lines(), ["count(*) = ''"]);
+    typing(&mut editor, &[Key::Backspace]);
+    assert_eq!(editor.textarea.lines(), ["count(*) = "]);
+
+    // nothing is closed in front of a word, or for a quote after one
+    editor.load_lines(vec!["name".to_owned()]);
+    typing(&mut editor, &chars("("));
+    editor.textarea.move_cursor(CursorMove::End);
+    typing(&mut editor, &chars("'"));
+    assert_eq!(editor.textarea.lines(), ["(name'"]);
+
+    // a selection is wrapped, and stays selected
+    editor.load_lines(vec!["select a, b".to_owned()]);
+    editor.textarea.move_cursor(CursorMove::Jump(0, 7));
+    editor.textarea.start_selection();
+    editor.textarea.move_cursor(CursorMove::End);
+    typing(&mut editor, &chars("("));
+    assert_eq!(editor.textarea.lines(), ["select (a, b)"]);
+    assert_eq!(editor.textarea.selection_range(), Some(((0, 8), (0, 12))));
+  }
+
+  #[test]
+  fn test_paste() {
+    let mut editor = Editor::new();
+    editor.paste("select *\rfrom users\r\nwhere id = 1");
+    assert_eq!(editor.textarea.lines(), ["select *", "from users", "where id = 1"]);
+
+    editor.open_command_line('/', "");
+    editor.paste("users\rwhere");
+    assert_eq!(editor.command_line.as_ref().map(|command_line| command_line.text.as_str()), Some("users"));
+    assert_eq!(editor.textarea.lines().len(), 3);
+  }
+
+  #[test]
+  fn test_undo_redo_keys() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    let app_state = app_state();
+    let key = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+    let ctrl = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+    let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+
+    // in vim mode, each run of insert mode is a change
+    let mut editor = Editor::new();
+    press(&mut editor, &app_state, &[key('i'), key('a'), esc, key('a'), key('b'), esc]);
+    assert_eq!(editor.textarea.lines(), ["ab"]);
+    for (keys, lines) in [([key('u')], ["a"]), ([key('u')], [""]), ([ctrl('r')], ["a"]), ([ctrl('r')], ["ab"])] {
+      press(&mut editor, &app_state, &keys);
+      assert_eq!(editor.textarea.lines(), lines);
+    }
+
+    let mut editor = Editor::new();
+    editor.config.settings.vim_mode = Some(false);
+    // moving the cursor ends a run of typing
+    let arrow = |code: KeyCode| KeyEvent::new(code, KeyModifiers::NONE);
+    press(&mut editor, &app_state, &[key('a'), arrow(KeyCode::Left), arrow(KeyCode::Right), key('b')]);
+    assert_eq!(editor.textarea.lines(), ["ab"]);
+    for (keys, lines) in [([ctrl('z')], ["a"]), ([ctrl('z')], [""]), ([ctrl('r')], ["a"]), ([ctrl('r')], ["ab"])] {
+      press(&mut editor, &app_state, &keys);
+      assert_eq!(editor.textarea.lines(), lines);
+    }
+  }
+}
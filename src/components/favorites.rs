@@ -1,12 +1,16 @@
-use std::path::{Path, PathBuf};
+use std::{
+  collections::{BTreeSet, HashSet},
+  path::{Path, PathBuf},
+};
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, eyre};
 use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
 use ratatui::{prelude::*, symbols::scrollbar, widgets::*};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::UnboundedSender;
 
 use super::{Component, Frame};
-use crate::{action::Action, app::AppState, config::Config, focus::Focus};
+use crate::{action::Action, app::AppState, config::Config, database, focus::Focus};
 
 #[derive(Default)]
 pub struct Favorites {
@@ -16,6 +20,13 @@ pub struct Favorites {
   copied: bool,
   search: Option<String>,
   search_focused: bool,
+  collapsed_folders: HashSet<String>,
+}
+
+// a line in the favorites tree
+enum FavoriteRow<'a> {
+  Folder { path: String, count: usize, collapsed: bool },
+  Entry(&'a FavoriteEntry),
 }
 
 pub struct FavoriteEntries {
@@ -24,11 +35,32 @@ pub struct FavoriteEntries {
 }
 
 pub struct FavoriteEntry {
+  // the path from the favorites dir, without `.sql`. folders are separated by `/`
   name: String,
   query_lines: Vec<String>,
 }
 
+/// A collection of favorites exported to a single json file, so they can be
+/// moved to another machine or backed up.
+#[derive(Serialize, Deserialize)]
+struct FavoritesBundle {
+  favorites: Vec<BundledFavorite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledFavorite {
+  // includes the folder, like the names of favorite entries
+  pub name: String,
+  pub query: String,
+}
+
 impl FavoriteEntry {
+  // folders are separated by `/`, and none of them can be empty or leave the
+  // favorites dir
+  pub fn is_valid_name(name: &str) -> bool {
+    name.split('/').all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains('\\'))
+  }
+
   pub fn path(&self, base: PathBuf) -> PathBuf {
     Self::path_impl(base, self.name.clone())
   }
@@ -41,6 +73,15 @@ impl FavoriteEntry {
   pub fn get_name(&self) -> &str {
     &self.name
   }
+
+  pub fn folder(&self) -> Option<&str> {
+    self.name.rsplit_once('/').map(|(folder, _)| folder)
+  }
+
+  // the name without its folder
+  pub fn short_name(&self) -> &str {
+    self.name.rsplit_once('/').map_or(self.name.as_str(), |(_, name)| name)
+  }
 }
 
 impl FavoriteEntries {
@@ -80,17 +121,49 @@ impl FavoriteEntries {
   pub fn delete_entry(&mut self, name: String) {
     if let Some((i, e)) = self.iter().enumerate().find(|(_, entry)| entry.name == name) {
       let entry = self.entries.remove(i);
-      if let Err(e) = std::fs::remove_file(entry.path(self.dir.clone())) {
+      let path = entry.path(self.dir.clone());
+      if let Err(e) = std::fs::remove_file(&path) {
         log::error!("failed to delete favorite query from disk: {e}");
       }
+      self.remove_empty_folders(&path);
+    }
+  }
+
+  pub fn rename_entry(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+    let old_path = FavoriteEntry::path_impl(self.dir.clone(), old_name.to_string());
+    let new_path = FavoriteEntry::path_impl(self.dir.clone(), new_name.to_string());
+    if self.iter().any(|entry| entry.name == new_name) || new_path.exists() {
+      return Err(eyre!("A favorite named {new_name} already exists"));
+    }
+    if let Some(parent) = new_path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(&old_path, &new_path)?;
+    self.remove_empty_folders(&old_path);
+    self.entries = Self::read_queries(&self.dir)?;
+    Ok(())
+  }
+
+  // removes the folders a favorite was in, if it was the last one there
+  fn remove_empty_folders(&self, path: &Path) {
+    let mut folder = path.parent();
+    while let Some(dir) = folder.filter(|dir| dir.starts_with(&self.dir) && *dir != self.dir) {
+      if std::fs::remove_dir(dir).is_err() {
+        break;
+      }
+      folder = dir.parent();
     }
   }
 
   pub fn add_entry(&mut self, name: String, query_lines: Vec<String>) {
     if query_lines.iter().map(|l| l.len()).sum::<usize>() > 0 {
       let content = query_lines.join("\n");
+      let path = FavoriteEntry::path_impl(self.dir.clone(), name.clone());
+      if let Some(Err(e)) = path.parent().map(std::fs::create_dir_all) {
+        log::error!("failed to create favorite query folder: {e}");
+      }
 
-      match std::fs::write(FavoriteEntry::path_impl(self.dir.clone(), name.clone()), content) {
+      match std::fs::write(path, content) {
         Ok(_) => {
           self.entries = Self::read_queries(&self.dir).unwrap_or_else(|e| {
             log::error!("failed to read favorite queries after writing new entry: {e}");
@@ -104,15 +177,77 @@ impl FavoriteEntries {
     }
   }
 
-  fn read_queries(favorites_dir: &Path) -> Result<Vec<FavoriteEntry>> {
-    let paths = std::fs::read_dir(favorites_dir)?;
+  pub fn contains(&self, name: &str) -> bool {
+    self.iter().any(|entry| entry.name == name)
+  }
+
+  // the name with a number added to it, if it's already taken
+  pub fn unused_name(&self, name: &str) -> String {
+    (1..).map(|i| if i == 1 { name.to_string() } else { format!("{name}-{i}") }).find(|n| !self.contains(n)).unwrap()
+  }
+
+  /// Writes every favorite to a json bundle, returning how many were written.
+  pub fn export(&self, path: &Path) -> Result<usize> {
+    let bundle = FavoritesBundle {
+      favorites: self
+        .iter()
+        .map(|entry| BundledFavorite { name: entry.name.clone(), query: entry.query_lines.join("\n") })
+        .collect(),
+    };
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok(bundle.favorites.len())
+  }
 
+  /// Adds the favorites from a bundle that don't clash with existing ones,
+  /// and returns the ones that do so the user can choose what to do with
+  /// them. Favorites that are already saved with the same query are skipped.
+  pub fn import(&mut self, path: &Path) -> Result<Vec<BundledFavorite>> {
+    let bundle: FavoritesBundle = serde_json::from_str(&std::fs::read_to_string(path)?)
+      .map_err(|e| eyre!("{} is not a favorites bundle: {e}", path.display()))?;
+    let mut conflicts = vec![];
+    for favorite in bundle.favorites {
+      if !FavoriteEntry::is_valid_name(&favorite.name) {
+        log::warn!("skipping imported favorite with an invalid name: {}", favorite.name);
+        continue;
+      }
+      match self.iter().find(|entry| entry.name == favorite.name) {
+        Some(entry) if entry.query_lines.join("\n") == favorite.query => {},
+        Some(_) => conflicts.push(favorite),
+        None => self.add_entry(favorite.name, favorite.query.split('\n').map(|s| s.to_string()).collect()),
+      }
+    }
+    Ok(conflicts)
+  }
+
+  fn read_queries(favorites_dir: &Path) -> Result<Vec<FavoriteEntry>> {
     let mut out = Vec::new();
+    Self::read_folder(favorites_dir, None, &mut out)?;
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(out)
+  }
+
+  // reads the queries in a folder and its subfolders. `folder` is the path
+  // from the favorites dir, which is prepended to the names
+  fn read_folder(dir: &Path, folder: Option<&str>, out: &mut Vec<FavoriteEntry>) -> Result<()> {
+    let paths = std::fs::read_dir(dir)?;
 
     for path in paths {
       match path {
         Ok(p) => {
           if let Some(file_name) = p.path().file_name().and_then(|p| p.to_str()) {
+            let prefixed = |name: &str| match folder {
+              Some(folder) => format!("{folder}/{name}"),
+              None => name.to_string(),
+            };
+            if p.path().is_dir() {
+              if let Err(e) = Self::read_folder(&p.path(), Some(&prefixed(file_name)), out) {
+                log::error!("failed to read favorite query folder: '{file_name}' error: {e}");
+              }
+              continue;
+            }
             if !file_name.ends_with(".sql") {
               continue;
             }
@@ -122,7 +257,7 @@ impl FavoriteEntries {
             match std::fs::read_to_string(p.path()) {
               Ok(query_text) => {
                 out.push(FavoriteEntry {
-                  name: name.to_string(),
+                  name: prefixed(name),
                   query_lines: query_text.split('\n').map(|s| s.to_string()).collect(),
                 });
               },
@@ -138,7 +273,7 @@ impl FavoriteEntries {
       };
     }
 
-    Ok(out)
+    Ok(())
   }
 }
 
@@ -151,7 +286,46 @@ impl Favorites {
       copied: false,
       search: None,
       search_focused: false,
+      collapsed_folders: HashSet::new(),
+    }
+  }
+
+  // favorites outside of folders first, then each folder followed by its
+  // contents. folders are always open while searching, so matches aren't hidden
+  fn rows<'a>(&self, favorites: &'a FavoriteEntries) -> Vec<FavoriteRow<'a>> {
+    let filtered = favorites.filter(self.search.clone());
+    let searching = self.search.as_ref().is_some_and(|search| !search.trim().is_empty());
+    let is_collapsed = |path: &str| !searching && self.collapsed_folders.contains(path);
+    let mut folders = BTreeSet::new();
+    for folder in filtered.iter().filter_map(|entry| entry.folder()) {
+      let parts = folder.split('/').collect::<Vec<_>>();
+      for i in 1..=parts.len() {
+        folders.insert(parts[..i].to_vec());
+      }
     }
+    let mut rows = filtered
+      .iter()
+      .filter(|entry| entry.folder().is_none())
+      .map(|entry| FavoriteRow::Entry(entry))
+      .collect::<Vec<_>>();
+    for parts in folders {
+      let hidden = (1..parts.len()).any(|i| is_collapsed(&parts[..i].join("/")));
+      if hidden {
+        continue;
+      }
+      let path = parts.join("/");
+      let collapsed = is_collapsed(&path);
+      let count = filtered
+        .iter()
+        .filter(|entry| entry.folder().is_some_and(|folder| folder == path || folder.starts_with(&format!("{path}/"))))
+        .count();
+      let entries = filtered.iter().filter(|entry| entry.folder() == Some(path.as_str()));
+      rows.push(FavoriteRow::Folder { path: path.clone(), count, collapsed });
+      if !collapsed {
+        rows.extend(entries.map(|entry| FavoriteRow::Entry(entry)));
+      }
+    }
+    rows
   }
 
   pub fn scroll_up(&mut self) {
@@ -203,7 +377,11 @@ impl Component for Favorites {
     }
     self.copied = false;
     let current_selected = self.list_state.selected();
-    let filtered = app_state.favorites.filter(self.search.clone());
+    let rows = self.rows(&app_state.favorites);
+    let selected_entry = match current_selected.and_then(|i| rows.get(i)) {
+      Some(FavoriteRow::Entry(entry)) => Some(*entry),
+      _ => None,
+    };
     match key.code {
       KeyCode::Enter if self.search_focused => {
         self.search_focused = false;
@@ -248,20 +426,50 @@ impl Component for Favorites {
         self.list_state.select(Some(0));
       },
       KeyCode::Char('D') => {
-        if let Some(i) = current_selected {
-          self.command_tx.as_ref().unwrap().send(Action::DeleteFavorite(filtered[i].name.clone()))?;
+        if let Some(entry) = selected_entry {
+          self.command_tx.as_ref().unwrap().send(Action::DeleteFavorite(entry.name.clone()))?;
+        }
+      },
+      KeyCode::Char('r') => {
+        if let Some(entry) = selected_entry {
+          self.command_tx.as_ref().unwrap().send(Action::RequestRenameFavorite(entry.name.clone()))?;
         }
       },
+      KeyCode::Char('x') => {
+        if let Some(entry) = selected_entry {
+          self.command_tx.as_ref().unwrap().send(Action::RunFavorite(entry.query_lines.clone()))?;
+        }
+      },
+      KeyCode::Char('P') => {
+        self.command_tx.as_ref().unwrap().send(Action::RequestExportFavorites)?;
+      },
+      KeyCode::Char('O') => {
+        self.command_tx.as_ref().unwrap().send(Action::RequestImportFavorites)?;
+      },
       KeyCode::Char('y') => {
-        if let Some(i) = current_selected {
-          self.command_tx.as_ref().unwrap().send(Action::CopyData(filtered[i].query_lines.join("\n")))?;
+        if let Some(entry) = selected_entry {
+          self.command_tx.as_ref().unwrap().send(Action::CopyData(entry.query_lines.join("\n")))?;
           self.copied = true;
         }
       },
-      KeyCode::Char('G') => self.list_state.select(Some(filtered.len().saturating_sub(1))),
+      KeyCode::Char('G') => self.list_state.select(Some(rows.len().saturating_sub(1))),
+      KeyCode::Enter => match current_selected.and_then(|i| rows.get(i)) {
+        Some(FavoriteRow::Folder { path, collapsed, .. }) => {
+          if *collapsed {
+            self.collapsed_folders.remove(path);
+          } else {
+            self.collapsed_folders.insert(path.clone());
+          }
+        },
+        Some(FavoriteRow::Entry(entry)) => {
+          self.command_tx.as_ref().unwrap().send(Action::QueryToEditor(entry.query_lines.clone()))?;
+          self.command_tx.as_ref().unwrap().send(Action::FocusEditor)?;
+        },
+        None => {},
+      },
       KeyCode::Char('I') => {
-        if let Some(i) = current_selected {
-          self.command_tx.as_ref().unwrap().send(Action::QueryToEditor(filtered[i].query_lines.clone()))?;
+        if let Some(entry) = selected_entry {
+          self.command_tx.as_ref().unwrap().send(Action::QueryToEditor(entry.query_lines.clone()))?;
           self.command_tx.as_ref().unwrap().send(Action::FocusEditor)?;
         }
       },
@@ -276,20 +484,16 @@ impl Component for Favorites {
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState) -> Result<()> {
     let focused = app_state.focus == Focus::Favorites;
-    let block = Block::default().borders(Borders::ALL).border_style(if focused {
-      Style::new().green()
-    } else {
-      Style::new().dim()
-    });
+    let block = Block::default().borders(Borders::ALL).border_style(self.config.theme.border(focused));
 
     let scrollbar_margin = area.inner(Margin { vertical: 1, horizontal: 0 });
 
-    let filtered_items = app_state.favorites.filter(self.search.clone());
-    let filtered_count = filtered_items.len();
+    let rows = self.rows(&app_state.favorites);
+    let filtered_count = rows.len();
 
     match self.list_state.selected() {
-      Some(x) if x > filtered_items.len().saturating_sub(1) => {
-        self.list_state.select(Some(filtered_items.len().saturating_sub(1)));
+      Some(x) if x > rows.len().saturating_sub(1) => {
+        self.list_state.select(Some(rows.len().saturating_sub(1)));
       },
       None if !self.search_focused => {
         self.list_state.select(Some(0));
@@ -297,26 +501,45 @@ impl Component for Favorites {
       _ => {},
     };
 
-    let item_lines = filtered_items.into_iter().enumerate().map(|(i, h)| {
+    let item_lines = rows.into_iter().enumerate().map(|(i, row)| {
         let selected = self.list_state.selected() == Some(i);
-        let color = if selected && focused { Color::Blue } else { Color::default() };
+        let h = match row {
+          FavoriteRow::Folder { path, count, collapsed } => {
+            let depth = path.matches('/').count();
+            let name = path.rsplit_once('/').map_or(path.as_str(), |(_, name)| name);
+            return ListItem::new(Line::from(format!(
+              "{}{} {name}/ ({count})",
+              "  ".repeat(depth),
+              if collapsed { "▸" } else { "▾" }
+            )))
+            .style(if focused { self.config.theme.focused_border } else { Style::default() });
+          },
+          FavoriteRow::Entry(entry) => entry,
+        };
+        let style = if selected && focused { self.config.theme.selected_item } else { Style::default() };
         let max_lines = 1_usize.max(area.height.saturating_sub(6) as usize);
         let mut lines = h
           .query_lines[0..max_lines.min(h.query_lines.len())]
           .iter()
-          .map(|s| Line::from(s.clone()).style(Style::default().fg(color)))
+          .map(|s| Line::from(s.clone()).style(style))
           .collect::<Vec<Line>>();
         if h.query_lines.len() > max_lines {
-          lines.push(Line::from(format!("... and {} more lines", h.query_lines.len().saturating_sub(max_lines))).style(Style::default().fg(color)));
+          lines.push(Line::from(format!("... and {} more lines", h.query_lines.len().saturating_sub(max_lines))).style(style));
         }
         lines.insert(
           0,
-          Line::from(format!("{}{}", if self.copied && selected { " copied! - " } else { "" }, h.name))
-            .style(if focused { Color::Yellow } else { Color::default() }),
+          Line::from(format!(
+            "{}{}{}{}",
+            if self.copied && selected { " copied! - " } else { "" },
+            "  ".repeat(h.folder().map_or(0, |folder| folder.matches('/').count() + 1)),
+            h.short_name(),
+            placeholders_label(h)
+          ))
+            .style(if focused { self.config.theme.title } else { Style::default() }),
         );
         lines.push(
           Line::from("----------------------------------------------------------------------------------------------------------------------------------------------------------------")
-            .style(Style::default().fg(color)),
+            .style(style),
         );
         ListItem::new(Text::from_iter(lines))
       })
@@ -340,9 +563,9 @@ impl Component for Favorites {
         Text::styled(
           "/ ".to_owned() + search.to_owned().as_str(),
           if !focused {
-            Style::new().dim()
+            self.config.theme.unfocused_border
           } else if self.search_focused {
-            Style::default().fg(Color::Yellow)
+            self.config.theme.title
           } else {
             Style::default()
           },
@@ -354,7 +577,7 @@ impl Component for Favorites {
     f.render_stateful_widget(list, layout[layout.len().saturating_sub(1)], &mut self.list_state);
     let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
       .symbols(scrollbar::VERTICAL)
-      .style(if focused { Style::default().fg(Color::Green) } else { Style::default() });
+      .style(if focused { self.config.theme.focused_border } else { Style::default() });
     let mut vertical_scrollbar_state =
       ScrollbarState::new(filtered_count.saturating_sub(1)).position(self.list_state.selected().map_or(0, |x| x));
     f.render_stateful_widget(vertical_scrollbar, scrollbar_margin, &mut vertical_scrollbar_state);
@@ -362,3 +585,78 @@ impl Component for Favorites {
     Ok(())
   }
 }
+
+// lists the placeholders a favorite will ask for when it runs
+fn placeholders_label(entry: &FavoriteEntry) -> String {
+  let placeholders = database::find_placeholders(&entry.query_lines.join("\n"));
+  match placeholders.is_empty() {
+    true => String::new(),
+    false => format!(" ({})", placeholders.join(", ")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_favorite_folders() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_favorites_{}", std::process::id()));
+    let mut favorites = FavoriteEntries::new(&dir).unwrap();
+    favorites.add_entry("users".to_owned(), vec!["select * from users".to_owned()]);
+    favorites.add_entry("reports/daily/signups".to_owned(), vec!["select 1".to_owned()]);
+    favorites.add_entry("reports/churn".to_owned(), vec!["select 2".to_owned()]);
+    assert_eq!(
+      favorites.iter().map(|entry| entry.get_name()).collect::<Vec<_>>(),
+      vec!["reports/churn", "reports/daily/signups", "users"]
+    );
+
+    let describe = |rows: Vec<FavoriteRow>| {
+      rows
+        .into_iter()
+        .map(|row| match row {
+          FavoriteRow::Folder { path, count, .. } => format!("{path}/ ({count})"),
+          FavoriteRow::Entry(entry) => entry.get_name().to_owned(),
+        })
+        .collect::<Vec<_>>()
+    };
+    let mut component = Favorites::new();
+    assert_eq!(
+      describe(component.rows(&favorites)),
+      vec!["users", "reports/ (2)", "reports/churn", "reports/daily/ (1)", "reports/daily/signups"]
+    );
+    component.collapsed_folders.insert("reports".to_owned());
+    assert_eq!(describe(component.rows(&favorites)), vec!["users", "reports/ (2)"]);
+    // searching opens folders with matches
+    component.search = Some("signups".to_owned());
+    assert_eq!(
+      describe(component.rows(&favorites)),
+      vec!["reports/ (1)", "reports/daily/ (1)", "reports/daily/signups"]
+    );
+
+    // bundles keep folders, and only report favorites that clash
+    let bundle = dir.with_extension("json");
+    assert_eq!(favorites.export(&bundle).unwrap(), 3);
+    favorites.add_entry("reports/churn".to_owned(), vec!["select 3".to_owned()]);
+    favorites.delete_entry("users".to_owned());
+    let conflicts = favorites.import(&bundle).unwrap();
+    assert_eq!(conflicts, vec![BundledFavorite { name: "reports/churn".to_owned(), query: "select 2".to_owned() }]);
+    assert!(favorites.contains("users"));
+    assert_eq!(favorites.unused_name("reports/churn"), "reports/churn-2");
+    assert_eq!(favorites.unused_name("reports/new"), "reports/new");
+    std::fs::remove_file(bundle).ok();
+
+    // renaming can't overwrite another favorite
+    assert!(favorites.rename_entry("reports/churn", "users").is_err());
+    assert_eq!(std::fs::read_to_string(dir.join("users.sql")).unwrap(), "select * from users");
+
+    // moving the last favorite out of a folder removes the folder
+    favorites.rename_entry("reports/daily/signups", "signups").unwrap();
+    assert!(!dir.join("reports").join("daily").exists());
+    assert!(dir.join("signups.sql").exists());
+    favorites.delete_entry("reports/churn".to_owned());
+    assert!(!dir.join("reports").exists());
+    assert_eq!(favorites.iter().map(|entry| entry.get_name()).collect::<Vec<_>>(), vec!["signups", "users"]);
+    std::fs::remove_dir_all(dir).ok();
+  }
+}
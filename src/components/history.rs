@@ -1,10 +1,25 @@
+use std::{
+  fs::OpenOptions,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
 use ratatui::{prelude::*, symbols::scrollbar, widgets::*};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::UnboundedSender;
 
-use super::{Component, Frame};
-use crate::{action::Action, app::AppState, config::Config, focus::Focus};
+use super::{Component, Frame, menu::fuzzy_match};
+use crate::{
+  action::Action,
+  app::{AppState, HistoryEntry},
+  cli::Driver,
+  config::Config,
+  focus::Focus,
+};
+
+const HISTORY_FILE: &str = "history.jsonl";
 
 #[derive(Default)]
 pub struct History {
@@ -13,6 +28,143 @@ pub struct History {
   list_state: ListState,
   copied: bool,
   last_query_duration: Option<chrono::Duration>,
+  search: Option<String>,
+  search_focused: bool,
+}
+
+// one line of the history file
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+  // set when the line updates the entry saved with this timestamp, for a rerun
+  // or the stats of a run, instead of adding an entry
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  updates: Option<String>,
+  timestamp: String,
+  #[serde(default)]
+  driver: Option<Driver>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  query_lines: Vec<String>,
+  #[serde(default)]
+  run_count: usize,
+  #[serde(default)]
+  duration_ms: Option<i64>,
+  #[serde(default)]
+  row_count: Option<u64>,
+}
+
+impl From<&HistoryEntry> for SavedEntry {
+  fn from(entry: &HistoryEntry) -> Self {
+    SavedEntry {
+      updates: None,
+      timestamp: entry.timestamp.to_rfc3339(),
+      driver: entry.driver,
+      query_lines: entry.query_lines.clone(),
+      run_count: entry.run_count,
+      duration_ms: entry.duration.map(|duration| duration.num_milliseconds()),
+      row_count: entry.row_count,
+    }
+  }
+}
+
+/// Query history saved as json lines, oldest first, so that saving a query
+/// or updating one is a single append. Other rainfrog instances can share the
+/// file without losing each other's entries.
+pub struct HistoryFile {
+  path: PathBuf,
+  // 0 keeps every entry
+  max_entries: usize,
+}
+
+impl HistoryFile {
+  /// History that keeps the newest `max_entries`, or every entry when it's 0.
+  pub fn new(data_dir: &Path, max_entries: usize) -> Self {
+    Self { path: data_dir.join(HISTORY_FILE), max_entries }
+  }
+
+  /// Reads the saved history, newest first, with the updates to each entry
+  /// applied. Once the file has more lines than `max_entries`, it's rewritten
+  /// with just the newest entries, so it doesn't grow without bound.
+  pub fn load(&self) -> Result<Vec<HistoryEntry>> {
+    if !self.path.exists() {
+      return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&self.path)?;
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+    let mut entries: Vec<HistoryEntry> = vec![];
+    for line in &lines {
+      let Some(saved) = serde_json::from_str::<SavedEntry>(line)
+        .inspect_err(|e| log::warn!("skipping unreadable history entry: {e}"))
+        .ok()
+      else {
+        continue;
+      };
+      let Some(timestamp) = parse_timestamp(&saved.timestamp) else {
+        continue;
+      };
+      let duration = saved.duration_ms.map(chrono::Duration::milliseconds);
+      match saved.updates.as_deref() {
+        Some(updates) => {
+          let updated = parse_timestamp(updates);
+          // the newest entry with the timestamp, in case two were saved at the same time
+          if let Some(entry) = entries.iter_mut().rev().find(|entry| Some(entry.timestamp) == updated) {
+            entry.timestamp = timestamp;
+            entry.run_count = saved.run_count.max(1);
+            entry.duration = duration;
+            entry.row_count = saved.row_count;
+          }
+        },
+        None => entries.push(HistoryEntry {
+          query_lines: saved.query_lines,
+          timestamp,
+          driver: saved.driver,
+          run_count: saved.run_count.max(1),
+          duration,
+          row_count: saved.row_count,
+        }),
+      }
+    }
+    if self.max_entries == 0 {
+      return Ok(entries.into_iter().rev().collect());
+    }
+    let entries = entries.split_off(entries.len().saturating_sub(self.max_entries));
+    if lines.len() > self.max_entries {
+      let mut kept = String::new();
+      for entry in &entries {
+        kept.push_str(&serde_json::to_string(&SavedEntry::from(entry))?);
+        kept.push('\n');
+      }
+      std::fs::write(&self.path, kept)?;
+    }
+    Ok(entries.into_iter().rev().collect())
+  }
+
+  pub fn append(&self, entry: &HistoryEntry) -> Result<()> {
+    if let Some(dir) = self.path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    let line = serde_json::to_string(&SavedEntry::from(entry))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+    writeln!(file, "{line}")?;
+    Ok(())
+  }
+
+  /// Saves the run count, timestamp and stats of an entry that was saved with
+  /// the timestamp `saved_at`, for when a query is run again or its run stats
+  /// come in.
+  pub fn update(&self, saved_at: chrono::DateTime<chrono::Local>, entry: &HistoryEntry) -> Result<()> {
+    let update =
+      SavedEntry { updates: Some(saved_at.to_rfc3339()), driver: None, query_lines: vec![], ..SavedEntry::from(entry) };
+    let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+    writeln!(file, "{}", serde_json::to_string(&update)?)?;
+    Ok(())
+  }
+
+  pub fn clear(&self) -> Result<()> {
+    match std::fs::remove_file(&self.path) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+      _ => Ok(()),
+    }
+  }
 }
 
 impl History {
@@ -23,6 +175,35 @@ impl History {
       list_state: ListState::default(),
       copied: false,
       last_query_duration: None,
+      search: None,
+      search_focused: false,
+    }
+  }
+
+  // indexes of the entries whose query matches the search
+  fn matches(&self, history: &[HistoryEntry]) -> Vec<usize> {
+    match self.search.as_deref() {
+      Some(search) if !search.trim().is_empty() => history
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| fuzzy_match(search, &h.query_lines.join("\n")).is_some())
+        .map(|(i, _)| i)
+        .collect(),
+      _ => vec![],
+    }
+  }
+
+  // selects the next (or previous) match after the selection, wrapping around
+  fn select_match(&mut self, history: &[HistoryEntry], forward: bool, include_current: bool) {
+    let matches = self.matches(history);
+    let current = self.list_state.selected().unwrap_or(0);
+    let next = if forward {
+      matches.iter().find(|i| if include_current { **i >= current } else { **i > current }).or(matches.first())
+    } else {
+      matches.iter().rev().find(|i| **i < current).or(matches.last())
+    };
+    if let Some(i) = next {
+      self.list_state.select(Some(*i));
     }
   }
 
@@ -74,7 +255,49 @@ impl Component for History {
       return Ok(None);
     }
     self.copied = false;
-    let current_selected = self.list_state.selected();
+    match key.code {
+      KeyCode::Enter if self.search_focused => {
+        self.search_focused = false;
+        if self.search.as_ref().is_some_and(|search| search.is_empty()) {
+          self.search = None;
+        }
+        return Ok(None);
+      },
+      KeyCode::Char(c) if self.search_focused => {
+        if let Some(search) = self.search.as_mut() {
+          search.push(c);
+        }
+        self.select_match(&app_state.history, true, true);
+        return Ok(None);
+      },
+      KeyCode::Backspace if self.search_focused => {
+        if let Some(search) = self.search.as_mut() {
+          search.pop();
+        }
+        self.select_match(&app_state.history, true, true);
+        return Ok(None);
+      },
+      KeyCode::Char('/') => {
+        self.search_focused = true;
+        self.search = Some(String::new());
+        return Ok(None);
+      },
+      KeyCode::Esc => {
+        self.search = None;
+        self.search_focused = false;
+        return Ok(None);
+      },
+      KeyCode::Char('n') => {
+        self.select_match(&app_state.history, true, false);
+        return Ok(None);
+      },
+      KeyCode::Char('N') => {
+        self.select_match(&app_state.history, false, false);
+        return Ok(None);
+      },
+      _ => {},
+    }
+    let current_selected = self.list_state.selected().filter(|i| *i < app_state.history.len());
     if let Some(i) = current_selected {
       match key.code {
         KeyCode::Down | KeyCode::Char('j') => {
@@ -87,7 +310,7 @@ impl Component for History {
           self.list_state.select(Some(0));
         },
         KeyCode::Char('G') => self.list_state.select(Some(app_state.history.len().saturating_sub(1))),
-        KeyCode::Char('I') => {
+        KeyCode::Char('I') | KeyCode::Enter => {
           self.command_tx.as_ref().unwrap().send(Action::QueryToEditor(app_state.history[i].query_lines.clone()))?;
           self.command_tx.as_ref().unwrap().send(Action::FocusEditor)?;
         },
@@ -131,9 +354,10 @@ impl Component for History {
     });
     let block = Block::default()
       .borders(Borders::ALL)
-      .border_style(if focused { Style::new().green() } else { Style::new().dim() })
+      .border_style(self.config.theme.border(focused))
       .title(Line::from(duration_string).right_aligned());
     let scrollbar_margin = area.inner(Margin { vertical: 1, horizontal: 0 });
+    let search = self.search.as_deref().filter(|search| !search.trim().is_empty());
 
     let items = app_state
       .history
@@ -141,24 +365,23 @@ impl Component for History {
       .enumerate()
       .map(|(i, h)| {
         let selected = self.list_state.selected() == Some(i);
-        let color = if selected && focused { Color::Blue } else { Color::default() };
+        let style = if selected && focused { self.config.theme.selected_item } else { Style::default() };
         let max_lines = 1_usize.max(area.height.saturating_sub(6) as usize);
-        let mut lines = h
-          .query_lines[0..max_lines.min(h.query_lines.len())]
-          .iter()
-          .map(|s| Line::from(s.clone()).style(Style::default().fg(color)))
+        let mut lines = highlight_query(&h.query_lines[0..max_lines.min(h.query_lines.len())], search, self.config.theme.highlight)
+          .into_iter()
+          .map(|line| line.style(style))
           .collect::<Vec<Line>>();
         if h.query_lines.len() > max_lines {
-          lines.push(Line::from(format!("... and {} more lines", h.query_lines.len().saturating_sub(max_lines))).style(Style::default().fg(color)));
+          lines.push(Line::from(format!("... and {} more lines", h.query_lines.len().saturating_sub(max_lines))).style(style));
         }
         lines.insert(
           0,
-          Line::from(format!("{}{}", if self.copied && selected { " copied! - " } else { "" }, h.timestamp))
-            .style(if focused { Color::Yellow } else { Color::default() }),
+          Line::from(format!("{}{}", if self.copied && selected { " copied! - " } else { "" }, entry_stats(h)))
+            .style(if focused { self.config.theme.title } else { Style::default() }),
         );
         lines.push(
           Line::from("----------------------------------------------------------------------------------------------------------------------------------------------------------------")
-            .style(Style::default().fg(color)),
+            .style(style),
         );
         ListItem::new(Text::from_iter(lines))
       })
@@ -181,13 +404,205 @@ impl Component for History {
       .highlight_symbol(if self.copied { "  " } else { " > " })
       .highlight_spacing(HighlightSpacing::Always);
 
-    f.render_stateful_widget(list, area, &mut self.list_state);
+    // create a space for the search if present
+    let mut constraints = vec![Constraint::Percentage(100)];
+    if self.search.is_some() {
+      constraints.insert(0, Constraint::Length(1));
+    }
+    let layout = Layout::default().constraints(constraints).direction(Direction::Vertical).split(area);
+    if let Some(search) = self.search.as_ref() {
+      let matches = self.matches(&app_state.history);
+      let position = self.list_state.selected().and_then(|selected| matches.iter().position(|i| *i == selected));
+      let count = match (search.trim().is_empty(), position) {
+        (true, _) => String::new(),
+        (false, Some(position)) => format!("  [{}/{}]", position + 1, matches.len()),
+        (false, None) => format!("  [{} matches]", matches.len()),
+      };
+      f.render_widget(
+        Text::styled(
+          format!("/ {search}{count}"),
+          if !focused {
+            self.config.theme.unfocused_border
+          } else if self.search_focused {
+            self.config.theme.title
+          } else {
+            Style::default()
+          },
+        ),
+        layout[0],
+      )
+    }
+
+    f.render_stateful_widget(list, layout[layout.len().saturating_sub(1)], &mut self.list_state);
     let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
       .symbols(scrollbar::VERTICAL)
-      .style(if focused { Style::default().fg(Color::Green) } else { Style::default() });
+      .style(if focused { self.config.theme.focused_border } else { Style::default() });
     let mut vertical_scrollbar_state = ScrollbarState::new(app_state.history.len().saturating_sub(1))
       .position(self.list_state.selected().map_or(0, |x| x));
     f.render_stateful_widget(vertical_scrollbar, scrollbar_margin, &mut vertical_scrollbar_state);
     Ok(())
   }
 }
+
+fn parse_timestamp(timestamp: &str) -> Option<chrono::DateTime<chrono::Local>> {
+  chrono::DateTime::parse_from_rfc3339(timestamp)
+    .inspect_err(|e| log::warn!("skipping history entry with a bad timestamp: {e}"))
+    .ok()
+    .map(|timestamp| timestamp.with_timezone(&chrono::Local))
+}
+
+// the header line of an entry: when it last ran, where, how many times, and
+// how long it took, laid out in columns
+fn entry_stats(entry: &HistoryEntry) -> String {
+  let driver = entry.driver.map(|driver| format!("{driver:?}")).unwrap_or_default();
+  let runs = format!("{}x", entry.run_count);
+  let duration = entry
+    .duration
+    .map(|duration| format!("{:.3}s", duration.num_milliseconds() as f64 / 1000_f64))
+    .unwrap_or_else(|| "-".to_owned());
+  let rows = entry.row_count.map(|rows| format!("{rows} rows")).unwrap_or_else(|| "-".to_owned());
+  format!("{}  {driver:<8}  {runs:>5}  {duration:>10}  {rows:>12}", entry.timestamp.format("%Y-%m-%d %H:%M:%S"))
+}
+
+// the query lines with the characters matching the search highlighted. the
+// search is matched against the whole query, so a match can span lines.
+fn highlight_query(query_lines: &[String], search: Option<&str>, highlight: Style) -> Vec<Line<'static>> {
+  let positions = search.and_then(|search| fuzzy_match(search, &query_lines.join("\n"))).unwrap_or_default();
+  let mut offset = 0;
+  query_lines
+    .iter()
+    .map(|line| {
+      let spans = line
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+          if positions.contains(&(offset + i)) {
+            Span::styled(c.to_string(), highlight)
+          } else {
+            Span::raw(c.to_string())
+          }
+        })
+        .collect::<Vec<_>>();
+      offset += line.chars().count() + 1;
+      Line::from(spans)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_history_file() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_history_{}", std::process::id()));
+    let history_file = HistoryFile::new(&dir, 2);
+    assert!(history_file.load().unwrap().is_empty());
+
+    for query in ["select 1", "select 2", "select 3"] {
+      let entry = HistoryEntry {
+        query_lines: vec![query.to_owned(), "from users".to_owned()],
+        timestamp: chrono::Local::now(),
+        driver: Some(Driver::Postgres),
+        run_count: 1,
+        duration: None,
+        row_count: None,
+      };
+      history_file.append(&entry).unwrap();
+    }
+    std::fs::write(dir.join(HISTORY_FILE), std::fs::read_to_string(dir.join(HISTORY_FILE)).unwrap() + "not json\n")
+      .unwrap();
+
+    // the newest entries are kept, and anything unreadable is skipped
+    let entries = history_file.load().unwrap();
+    assert_eq!(
+      entries.iter().map(|entry| entry.query_lines[0].as_str()).collect::<Vec<_>>(),
+      vec!["select 3", "select 2"]
+    );
+    assert_eq!(entries[0].query_lines[1], "from users");
+    assert_eq!(entries[0].driver, Some(Driver::Postgres));
+    assert_eq!(std::fs::read_to_string(dir.join(HISTORY_FILE)).unwrap().lines().count(), 2);
+
+    // reruns and stats are appended, and applied to the entry they're for
+    let mut earlier = entries.into_iter().next().unwrap();
+    let saved_at = earlier.timestamp;
+    earlier.run_count = 3;
+    earlier.timestamp = chrono::Local::now();
+    history_file.update(saved_at, &earlier).unwrap();
+    earlier.duration = Some(chrono::Duration::milliseconds(1500));
+    earlier.row_count = Some(42);
+    history_file.update(earlier.timestamp, &earlier).unwrap();
+    // an entry saved in between, like by another rainfrog running at the same time
+    let other = HistoryEntry {
+      query_lines: vec!["select 4".to_owned()],
+      timestamp: chrono::Local::now(),
+      driver: Some(Driver::Postgres),
+      run_count: 1,
+      duration: None,
+      row_count: None,
+    };
+    history_file.append(&other).unwrap();
+    let entries = history_file.load().unwrap();
+    assert_eq!(
+      entries.iter().map(|entry| entry.query_lines[0].as_str()).collect::<Vec<_>>(),
+      vec!["select 4", "select 3"]
+    );
+    assert_eq!(entries[1].run_count, 3);
+    assert_eq!(entries[1].query_lines[1], "from users");
+    assert_eq!(entries[1].driver, Some(Driver::Postgres));
+    assert_eq!(entries[1].duration, Some(chrono::Duration::milliseconds(1500)));
+    assert_eq!(entries[1].row_count, Some(42));
+    assert_eq!(entries[0].run_count, 1);
+    assert!(entry_stats(&entries[1]).ends_with("Postgres     3x      1.500s       42 rows"));
+    // the updates are folded into their entries once the file is trimmed
+    assert_eq!(std::fs::read_to_string(dir.join(HISTORY_FILE)).unwrap().lines().count(), 2);
+    assert_eq!(history_file.load().unwrap().len(), 2);
+
+    // 0 keeps every entry
+    let unlimited = HistoryFile::new(&dir, 0);
+    assert_eq!(unlimited.load().unwrap().len(), 2);
+    unlimited.append(&other).unwrap();
+    unlimited.append(&other).unwrap();
+    assert_eq!(unlimited.load().unwrap().len(), 4);
+    assert_eq!(std::fs::read_to_string(dir.join(HISTORY_FILE)).unwrap().lines().count(), 4);
+
+    history_file.clear().unwrap();
+    assert!(history_file.load().unwrap().is_empty());
+    history_file.clear().unwrap();
+    std::fs::remove_dir_all(dir).ok();
+  }
+
+  #[test]
+  fn test_search_history() {
+    let entry = |lines: &[&str]| HistoryEntry {
+      query_lines: lines.iter().map(|line| line.to_string()).collect(),
+      timestamp: chrono::Local::now(),
+      driver: None,
+      run_count: 1,
+      duration: None,
+      row_count: None,
+    };
+    let history =
+      vec![entry(&["select *", "from users"]), entry(&["select 1"]), entry(&["delete from users", "where id = 1"])];
+    let mut component = History::new();
+    component.search = Some("users".to_owned());
+    assert_eq!(component.matches(&history), vec![0, 2]);
+
+    component.list_state.select(Some(0));
+    component.select_match(&history, true, false);
+    assert_eq!(component.list_state.selected(), Some(2));
+    component.select_match(&history, true, false);
+    assert_eq!(component.list_state.selected(), Some(0));
+    component.select_match(&history, false, false);
+    assert_eq!(component.list_state.selected(), Some(2));
+
+    // matches can span lines
+    let highlight = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let lines = highlight_query(&history[0].query_lines, Some("*fr"), highlight);
+    let highlighted = |line: &Line| {
+      line.spans.iter().filter(|span| span.style == highlight).map(|span| span.content.clone()).collect::<String>()
+    };
+    assert_eq!(highlighted(&lines[0]), "*");
+    assert_eq!(highlighted(&lines[1]), "fr");
+  }
+}
@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, MouseEventKind};
 use indexmap::IndexMap;
@@ -10,31 +12,94 @@ use crate::{
   action::{Action, MenuItemKind, MenuPreview, MenuTarget},
   app::AppState,
   config::Config,
-  database::Rows,
+  database::{Rows, is_null},
   focus::Focus,
+  theme::Theme,
 };
 
+// tags that mark a connection as production, which is shown in the error color
+fn is_production(tag: &str) -> bool {
+  matches!(tag.to_lowercase().as_str(), "prod" | "production")
+}
+
+// what the app is connected to, so windows connected to different places
+// can be told apart
+fn connection_title<'a>(app_state: &AppState, theme: &Theme) -> Option<Line<'a>> {
+  let info = app_state.connection.to_string();
+  let tag = app_state.connection_tag.as_deref().filter(|tag| !tag.is_empty());
+  if info.is_empty() && tag.is_none() {
+    return None;
+  }
+  let production = tag.is_some_and(is_production);
+  let mut spans = vec![Span::styled(
+    format!(" 󰆼 {info} "),
+    match production {
+      true => theme.error.add_modifier(Modifier::BOLD),
+      false => theme.muted,
+    },
+  )];
+  if let Some(tag) = tag {
+    spans.push(Span::styled(
+      format!(" {tag} "),
+      match production {
+        true => theme.error.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        false => theme.highlight,
+      },
+    ));
+  }
+  Some(Line::from(spans))
+}
+
 #[derive(Debug, Clone)]
-struct MenuViewItem {
+struct MenuObject {
   name: String,
-  materialized: bool,
+  // shown after the name, e.g. a routine's return type
+  detail: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
 struct MenuSchemaItems {
-  tables: Vec<String>,
-  views: Vec<MenuViewItem>,
+  tables: Vec<MenuObject>,
+  views: Vec<MenuObject>,
+  materialized_views: Vec<MenuObject>,
+  foreign_tables: Vec<MenuObject>,
+  functions: Vec<MenuObject>,
+  procedures: Vec<MenuObject>,
+}
+
+impl MenuSchemaItems {
+  // the groups shown in the menu, in order
+  fn groups(&self) -> [(&'static str, &Vec<MenuObject>, MenuItemKind); 6] {
+    [
+      ("Tables", &self.tables, MenuItemKind::Table),
+      ("Views", &self.views, MenuItemKind::View { materialized: false }),
+      ("Materialized views", &self.materialized_views, MenuItemKind::View { materialized: true }),
+      ("Foreign tables", &self.foreign_tables, MenuItemKind::ForeignTable),
+      ("Functions", &self.functions, MenuItemKind::Routine { procedure: false }),
+      ("Procedures", &self.procedures, MenuItemKind::Routine { procedure: true }),
+    ]
+  }
 }
 
 #[derive(Debug, Clone)]
 struct MenuItem {
+  schema: String,
   name: String,
+  detail: Option<String>,
   kind: MenuItemKind,
 }
 
+impl MenuItem {
+  fn target(&self) -> MenuTarget {
+    MenuTarget { schema: self.schema.clone(), name: self.name.clone(), kind: self.kind.clone() }
+  }
+}
+
 #[derive(Debug, Clone)]
 enum MenuEntry {
-  Header(String),
+  // only listed when a search spans several schemas
+  Schema(String),
+  Header { title: &'static str, count: usize, collapsed: bool },
   Item(MenuItem),
 }
 
@@ -47,6 +112,11 @@ pub enum MenuFocus {
 
 pub trait SettableTableList<'a> {
   fn set_table_list(&mut self, data: Option<Result<Rows>>);
+  /// Takes the rows returned by `Database::start_table_stats()`.
+  fn set_table_stats(&mut self, rows: Rows);
+  /// Takes the schemas returned by `Database::search_path()`, to open the
+  /// first of them when the menu is first loaded.
+  fn set_search_path(&mut self, search_path: Vec<String>);
 }
 
 pub trait MenuComponent<'a>: Component + SettableTableList<'a> {}
@@ -63,6 +133,12 @@ pub struct Menu {
   menu_focus: MenuFocus,
   search: Option<String>,
   search_focused: bool,
+  collapsed_groups: HashSet<&'static str>,
+  // labels like `≈1.2M, 340 MB`, by schema and then table
+  table_stats: HashMap<String, HashMap<String, String>>,
+  show_table_stats: bool,
+  // the schemas unqualified names are looked up in, shown under the menu
+  search_path: Vec<String>,
 }
 
 impl Menu {
@@ -76,10 +152,19 @@ impl Menu {
       menu_focus: MenuFocus::default(),
       search: None,
       search_focused: false,
+      collapsed_groups: HashSet::new(),
+      table_stats: HashMap::new(),
+      show_table_stats: false,
+      search_path: vec![],
     }
   }
 
   pub fn change_focus(&mut self, new_focus: MenuFocus) {
+    // search results from every schema are listed together, so there's no
+    // schema to pick
+    if new_focus == MenuFocus::Schema && self.searching_all_schemas() {
+      return;
+    }
     if self.menu_focus != new_focus && self.table_map.keys().len() > 1 {
       match new_focus {
         MenuFocus::Schema => {
@@ -123,7 +208,7 @@ impl Menu {
           return;
         }
         let prev = match self.list_state.selected() {
-          Some(i) => Self::previous_selectable_index(&entries, i).or_else(|| Self::last_selectable_index(&entries)),
+          Some(i) => Self::previous_selectable_index(i).or_else(|| Self::last_selectable_index(&entries)),
           None => Self::last_selectable_index(&entries),
         };
         self.list_state = ListState::default().with_selected(prev);
@@ -157,72 +242,105 @@ impl Menu {
   }
 
   pub fn reset_search(&mut self) {
+    // a result picked from another schema stays selected, in its own schema
+    let selected = self.selected_item();
     self.search = None;
     self.search_focused = false;
+    if let Some(item) = &selected
+      && let Some(index) = self.table_map.get_index_of(&item.schema)
+    {
+      self.schema_index = index;
+    }
     let entries = self.filtered_entries();
-    self.list_state = ListState::default().with_selected(Self::first_selectable_index(&entries));
+    let index = selected
+      .and_then(|selected| {
+        entries.iter().position(
+          |entry| matches!(entry, MenuEntry::Item(item) if item.name == selected.name && item.kind == selected.kind),
+        )
+      })
+      .or_else(|| Self::first_selectable_index(&entries));
+    self.list_state = ListState::default().with_selected(index);
   }
 
-  fn filtered_entries(&self) -> Vec<MenuEntry> {
-    let Some((_, items)) = self.table_map.get_index(self.schema_index) else {
-      return vec![];
-    };
-    let matches_search = |name: &str, search: &Option<String>| {
-      if let Some(search) = search.as_ref() { name.to_lowercase().contains(search.to_lowercase().trim()) } else { true }
-    };
+  fn searching_all_schemas(&self) -> bool {
+    self.search.is_some() && self.table_map.len() > 1
+  }
 
-    let tables: Vec<MenuEntry> = items
-      .tables
-      .iter()
-      .filter(|t| matches_search(t.as_str(), &self.search))
-      .cloned()
-      .map(|name| MenuEntry::Item(MenuItem { name, kind: MenuItemKind::Table }))
-      .collect();
+  fn filtered_entries(&self) -> Vec<MenuEntry> {
+    if self.searching_all_schemas() {
+      let mut entries = Vec::new();
+      for (schema, items) in self.table_map.iter() {
+        let schema_entries = self.schema_entries(schema, items);
+        if !schema_entries.is_empty() {
+          entries.push(MenuEntry::Schema(schema.clone()));
+          entries.extend(schema_entries);
+        }
+      }
+      return entries;
+    }
+    match self.table_map.get_index(self.schema_index) {
+      Some((schema, items)) => self.schema_entries(schema, items),
+      None => vec![],
+    }
+  }
 
-    let views: Vec<MenuEntry> = items
-      .views
-      .iter()
-      .filter(|v| matches_search(v.name.as_str(), &self.search))
-      .map(|v| {
-        MenuEntry::Item(MenuItem { name: v.name.clone(), kind: MenuItemKind::View { materialized: v.materialized } })
-      })
-      .collect();
+  fn schema_entries(&self, schema: &str, items: &MenuSchemaItems) -> Vec<MenuEntry> {
+    let matches_search =
+      |name: &str, search: &Option<String>| search.as_ref().is_none_or(|search| fuzzy_match(search, name).is_some());
 
     let mut entries = Vec::new();
-    if !tables.is_empty() {
-      entries.push(MenuEntry::Header("Tables".to_owned()));
-      entries.extend(tables);
-    }
-    if !views.is_empty() {
-      entries.push(MenuEntry::Header("Views".to_owned()));
-      entries.extend(views);
+    for (title, names, kind) in items.groups() {
+      let matching: Vec<&MenuObject> =
+        names.iter().filter(|object| matches_search(object.name.as_str(), &self.search)).collect();
+      if matching.is_empty() {
+        continue;
+      }
+      // a filter shows every match, even in collapsed groups
+      let collapsed = self.search.is_none() && self.collapsed_groups.contains(title);
+      entries.push(MenuEntry::Header { title, count: matching.len(), collapsed });
+      if !collapsed {
+        entries.extend(matching.into_iter().map(|object| {
+          MenuEntry::Item(MenuItem {
+            schema: schema.to_owned(),
+            name: object.name.clone(),
+            detail: object.detail.clone(),
+            kind: kind.clone(),
+          })
+        }));
+      }
     }
     entries
   }
 
+  fn toggle_group(&mut self, title: &'static str) {
+    if !self.collapsed_groups.remove(title) {
+      self.collapsed_groups.insert(title);
+    }
+    let entries = self.filtered_entries();
+    let index = entries.iter().position(|entry| matches!(entry, MenuEntry::Header { title: t, .. } if *t == title));
+    self.list_state = ListState::default().with_selected(index);
+  }
+
+  // group headers can be selected so they can be collapsed, but a fresh
+  // list starts on its first item
   fn first_selectable_index(entries: &[MenuEntry]) -> Option<usize> {
-    entries.iter().position(|entry| matches!(entry, MenuEntry::Item(_)))
+    entries.iter().position(|entry| matches!(entry, MenuEntry::Item(_))).or(if entries.is_empty() {
+      None
+    } else {
+      Some(0)
+    })
   }
 
   fn last_selectable_index(entries: &[MenuEntry]) -> Option<usize> {
-    entries.iter().rposition(|entry| matches!(entry, MenuEntry::Item(_)))
+    entries.len().checked_sub(1)
   }
 
   fn next_selectable_index(entries: &[MenuEntry], current: usize) -> Option<usize> {
-    entries
-      .iter()
-      .enumerate()
-      .skip(current + 1)
-      .find_map(|(index, entry)| if matches!(entry, MenuEntry::Item(_)) { Some(index) } else { None })
+    if current + 1 < entries.len() { Some(current + 1) } else { None }
   }
 
-  fn previous_selectable_index(entries: &[MenuEntry], current: usize) -> Option<usize> {
-    entries
-      .iter()
-      .enumerate()
-      .take(current)
-      .rfind(|(_, entry)| matches!(entry, MenuEntry::Item(_)))
-      .map(|(index, _)| index)
+  fn previous_selectable_index(current: usize) -> Option<usize> {
+    current.checked_sub(1)
   }
 
   fn selected_item(&self) -> Option<MenuItem> {
@@ -233,11 +351,26 @@ impl Menu {
       _ => None,
     }
   }
+
+  fn selected_group(&self) -> Option<&'static str> {
+    let entries = self.filtered_entries();
+    let selected = self.list_state.selected()?;
+    match entries.get(selected) {
+      Some(MenuEntry::Header { title, .. }) => Some(title),
+      _ => None,
+    }
+  }
 }
 
 impl SettableTableList<'_> for Menu {
   fn set_table_list(&mut self, data: Option<Result<Rows>>) {
     log::info!("setting menu table list");
+    // a reload keeps the schema, focus, and selection, as far as they still exist
+    let reloading = !self.table_map.is_empty();
+    let previous_schema = self.table_map.get_index(self.schema_index).map(|(schema, _)| schema.clone());
+    let previous_item = self.selected_item();
+    let previous_group = self.selected_group();
+    let previous_index = self.list_state.selected();
     self.table_map = IndexMap::new();
     match data {
       Some(Ok(rows)) => {
@@ -245,16 +378,53 @@ impl SettableTableList<'_> for Menu {
           let schema = row.first().cloned().unwrap_or_default();
           let name = row.get(1).cloned().unwrap_or_default();
           let kind = row.get(2).map(|value| value.to_lowercase()).unwrap_or_else(|| "table".to_owned());
+          let detail = row.get(3).filter(|value| !is_null(value) && !value.is_empty()).cloned();
           let entry = self.table_map.entry(schema.clone()).or_default();
+          let object = MenuObject { name, detail };
           match kind.as_str() {
-            "view" => entry.views.push(MenuViewItem { name, materialized: false }),
-            "materialized_view" | "materialized view" | "mview" => {
-              entry.views.push(MenuViewItem { name, materialized: true })
-            },
-            _ => entry.tables.push(name),
+            "view" => entry.views.push(object),
+            "materialized_view" | "materialized view" | "mview" => entry.materialized_views.push(object),
+            "foreign_table" | "foreign table" => entry.foreign_tables.push(object),
+            "function" => entry.functions.push(object),
+            "procedure" => entry.procedures.push(object),
+            _ => entry.tables.push(object),
           }
         });
-        if self.table_map.keys().len() == 1 {
+        if reloading {
+          self.schema_index = previous_schema
+            .and_then(|schema| self.table_map.get_index_of(&schema))
+            .unwrap_or(0)
+            .min(self.table_map.len().saturating_sub(1));
+          if self.table_map.len() == 1 {
+            self.menu_focus = MenuFocus::Tables;
+          }
+          let entries = self.filtered_entries();
+          let index = match self.menu_focus {
+            MenuFocus::Schema => None,
+            MenuFocus::Tables => previous_item
+              .and_then(|previous| {
+                entries.iter().position(|entry| {
+                  matches!(entry, MenuEntry::Item(item)
+                    if item.schema == previous.schema && item.name == previous.name && item.kind == previous.kind)
+                })
+              })
+              .or_else(|| {
+                previous_group.and_then(|previous| {
+                  entries.iter().position(|entry| matches!(entry, MenuEntry::Header { title, .. } if *title == previous))
+                })
+              })
+              // the selection was dropped, so the one that took its place is selected
+              .or_else(|| previous_index.filter(|_| !entries.is_empty()).map(|index| index.min(entries.len() - 1)))
+              .or_else(|| Self::first_selectable_index(&entries)),
+          };
+          self.list_state = ListState::default().with_selected(index);
+        } else if let Some(index) = self.search_path.iter().find_map(|schema| self.table_map.get_index_of(schema)) {
+          // unqualified names are looked up in this schema, so it's the one to start in
+          self.schema_index = index;
+          self.menu_focus = MenuFocus::Tables;
+          let entries = self.filtered_entries();
+          self.list_state = ListState::default().with_selected(Self::first_selectable_index(&entries));
+        } else if self.table_map.keys().len() == 1 {
           self.menu_focus = MenuFocus::Tables;
           let entries = self.filtered_entries();
           self.list_state = ListState::default().with_selected(Self::first_selectable_index(&entries));
@@ -269,6 +439,22 @@ impl SettableTableList<'_> for Menu {
       None => {},
     }
   }
+
+  fn set_table_stats(&mut self, rows: Rows) {
+    self.table_stats = HashMap::new();
+    for row in rows.rows {
+      let [schema, table, row_count, size] = &row[..] else {
+        continue;
+      };
+      if let Some(label) = table_stats_label(row_count, size) {
+        self.table_stats.entry(schema.clone()).or_default().insert(table.clone(), label);
+      }
+    }
+  }
+
+  fn set_search_path(&mut self, search_path: Vec<String>) {
+    self.search_path = search_path;
+  }
 }
 
 impl Component for Menu {
@@ -278,6 +464,7 @@ impl Component for Menu {
   }
 
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.show_table_stats = config.settings.menu_table_stats.unwrap_or_default();
     self.config = config;
     Ok(())
   }
@@ -319,7 +506,12 @@ impl Component for Menu {
             KeyCode::Char('/') => {
               self.search_focused = true;
               if self.search.is_none() {
-                self.search = Some("".to_owned())
+                self.search = Some("".to_owned());
+                if self.searching_all_schemas() {
+                  self.menu_focus = MenuFocus::Tables;
+                  let entries = self.filtered_entries();
+                  self.list_state = ListState::default().with_selected(Self::first_selectable_index(&entries));
+                }
               }
             },
             KeyCode::Char('l') => self.change_focus(MenuFocus::Tables),
@@ -329,23 +521,47 @@ impl Component for Menu {
             KeyCode::Char('g') => self.scroll_top(),
             KeyCode::Char('G') => self.scroll_bottom(),
             KeyCode::Char('R') => self.command_tx.as_ref().unwrap().send(Action::LoadMenu)?,
-            KeyCode::Char('1') | KeyCode::Char('2') | KeyCode::Char('3') | KeyCode::Char('4') => {
-              if let Some(item) = self.selected_item()
-                && let Some((schema, _)) = self.table_map.get_index(self.schema_index)
-              {
+            KeyCode::Char('s') => {
+              self.show_table_stats = !self.show_table_stats;
+              if self.show_table_stats && self.table_stats.is_empty() {
+                self.command_tx.as_ref().unwrap().send(Action::LoadTableStats)?;
+              }
+            },
+            KeyCode::Char('y') => {
+              if let Some(item) = self.selected_item() {
+                self.command_tx.as_ref().unwrap().send(Action::CopyQualifiedName(item.target()))?;
+              }
+            },
+            KeyCode::Char('1')
+            | KeyCode::Char('2')
+            | KeyCode::Char('3')
+            | KeyCode::Char('4')
+            | KeyCode::Char('5')
+            | KeyCode::Char('6')
+            | KeyCode::Char('7') => {
+              if let Some(item) = self.selected_item() {
+                if let (KeyCode::Char('1'), MenuItemKind::Routine { .. }) = (key.code, &item.kind) {
+                  self.command_tx.as_ref().unwrap().send(Action::EditRoutine(item.target()))?;
+                  return Ok(None);
+                }
+                if let (KeyCode::Char('7'), MenuItemKind::Table) = (key.code, &item.kind) {
+                  self.command_tx.as_ref().unwrap().send(Action::ShowCreateTable(item.target()))?;
+                  return Ok(None);
+                }
                 let preview = match (key.code, item.kind.clone()) {
                   (KeyCode::Char('1'), _) => Some(MenuPreview::Columns),
-                  (KeyCode::Char('2'), MenuItemKind::View { .. }) => Some(MenuPreview::Definition),
+                  (KeyCode::Char('2'), MenuItemKind::View { .. } | MenuItemKind::ForeignTable) => {
+                    Some(MenuPreview::Definition)
+                  },
                   (KeyCode::Char('2'), MenuItemKind::Table) => Some(MenuPreview::Constraints),
                   (KeyCode::Char('3'), MenuItemKind::Table) => Some(MenuPreview::Indexes),
                   (KeyCode::Char('4'), MenuItemKind::Table) => Some(MenuPreview::Policies),
+                  (KeyCode::Char('5'), MenuItemKind::Table) => Some(MenuPreview::ForeignKeys),
+                  (KeyCode::Char('6'), MenuItemKind::Table) => Some(MenuPreview::Triggers),
                   _ => None,
                 };
                 if let Some(preview) = preview {
-                  self.command_tx.as_ref().unwrap().send(Action::MenuPreview(
-                    preview,
-                    MenuTarget { schema: schema.clone(), name: item.name.clone(), kind: item.kind.clone() },
-                  ))?;
+                  self.command_tx.as_ref().unwrap().send(Action::MenuPreview(preview, item.target()))?;
                 }
               }
             },
@@ -358,13 +574,14 @@ impl Component for Menu {
           self.search_focused = false;
         } else if self.menu_focus == MenuFocus::Schema {
           self.change_focus(MenuFocus::Tables);
-        } else if let Some(item) = self.selected_item()
-          && let Some((schema, _)) = self.table_map.get_index(self.schema_index)
-        {
-          self.command_tx.as_ref().unwrap().send(Action::MenuPreview(
-            MenuPreview::Rows,
-            MenuTarget { schema: schema.clone(), name: item.name.clone(), kind: item.kind.clone() },
-          ))?;
+        } else if let Some(group) = self.selected_group() {
+          self.toggle_group(group);
+        } else if let Some(item) = self.selected_item() {
+          let preview = match item.kind {
+            MenuItemKind::Routine { .. } => MenuPreview::RoutineSource,
+            _ => MenuPreview::Rows,
+          };
+          self.command_tx.as_ref().unwrap().send(Action::MenuPreview(preview, item.target()))?;
         }
       },
       KeyCode::Esc => self.reset_search(),
@@ -390,13 +607,26 @@ impl Component for Menu {
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState) -> Result<()> {
     let focused = app_state.focus == Focus::Menu;
-    let parent_block = Block::default();
-    let schema_keys: Vec<String> = self.table_map.keys().cloned().collect();
+    let mut parent_block = match connection_title(app_state, &self.config.theme) {
+      Some(title) => Block::default().title(title),
+      None => Block::default(),
+    };
+    if !self.search_path.is_empty() {
+      let search_path = format!(" search path: {} ", self.search_path.join(", "));
+      parent_block = parent_block.title_bottom(Line::styled(search_path, self.config.theme.muted));
+    }
+    // a search across schemas is shown as one list, in place of the schemas
+    let searching_all_schemas = self.searching_all_schemas();
+    let (schema_keys, active_index) = if searching_all_schemas {
+      (vec!["all schemas".to_owned()], 0)
+    } else {
+      (self.table_map.keys().cloned().collect::<Vec<String>>(), self.schema_index)
+    };
     let mut constraints: Vec<Constraint> = schema_keys
       .iter()
       .enumerate()
       .map(|(i, _)| match i {
-        x if x == self.schema_index => Constraint::Min(5),
+        x if x == active_index => Constraint::Min(5),
         _ => Constraint::Length(1),
       })
       .collect();
@@ -410,9 +640,9 @@ impl Component for Menu {
         Text::styled(
           "/ ".to_owned() + search.to_owned().as_str(),
           if !focused {
-            Style::new().dim()
+            self.config.theme.unfocused_border
           } else if self.search_focused {
-            Style::default().fg(Color::Yellow)
+            self.config.theme.title
           } else {
             Style::default()
           },
@@ -423,16 +653,20 @@ impl Component for Menu {
     schema_keys.iter().enumerate().for_each(|(i, k)| {
       let layout_index = if self.search.is_some() { i + 1 } else { i };
       match i {
-        x if x == self.schema_index => {
+        x if x == active_index => {
           let block = Block::default()
-            .title(format!(" 󰦄  {k} <alt+1> (schema) "))
+            .title(if searching_all_schemas {
+              format!(" 󰦄  {k} <alt+1> (search) ")
+            } else {
+              format!(" 󰦄  {k} <alt+1> (schema) ")
+            })
             .borders(Borders::ALL)
             .border_style(if focused && self.menu_focus == MenuFocus::Schema {
-              Style::default().fg(Color::Green)
+              self.config.theme.focused_border
             } else if focused {
               Style::default()
             } else {
-              Style::new().dim()
+              self.config.theme.unfocused_border
             })
             .padding(Padding { left: 0, right: 1, top: 0, bottom: 0 });
           let block_margin = layout[layout_index].inner(Margin { vertical: 1, horizontal: 0 });
@@ -444,19 +678,29 @@ impl Component for Menu {
             .iter()
             .enumerate()
             .map(|(i, entry)| match entry {
-              MenuEntry::Header(title) => {
-                ListItem::new(Text::styled(format!("─ {title}"), Style::default().fg(Color::DarkGray)))
+              MenuEntry::Schema(schema) => {
+                ListItem::new(Text::styled(format!("󰦄 {schema}"), Style::default().add_modifier(Modifier::BOLD)))
               },
+              MenuEntry::Header { title, count, collapsed } => ListItem::new(Text::styled(
+                format!("{} {title} ({count})", if *collapsed { "▸" } else { "▾" }),
+                self.config.theme.muted,
+              )),
               MenuEntry::Item(item) => {
-                let display_name = match item.kind {
-                  MenuItemKind::View { materialized: true } => format!(" {} (materialized)", item.name),
-                  _ => " ".to_owned() + &item.name.clone(),
-                };
+                let mut display_name =
+                  highlight_matches(&item.name, self.search.as_deref(), self.config.theme.highlight);
+                if let Some(detail) = &item.detail {
+                  display_name.push_span(Span::styled(format!(" → {detail}"), self.config.theme.muted));
+                }
+                if self.show_table_stats
+                  && let Some(label) = self.table_stats.get(&item.schema).and_then(|tables| tables.get(&item.name))
+                {
+                  display_name.push_span(Span::styled(format!(" ({label})"), self.config.theme.muted));
+                }
                 let is_selected = selected_index == Some(i);
                 if is_selected && focused && !self.search_focused {
                   match item.kind {
                     MenuItemKind::Table => ListItem::new(Text::from(vec![
-                      Line::from(display_name),
+                      display_name,
                       Line::from(if app_state.query_task_running { " ├[...] rows" } else { " ├[<enter>] rows" }),
                       Line::from(if app_state.query_task_running { " ├[...] columns" } else { " ├[1] columns" }),
                       Line::from(if app_state.query_task_running {
@@ -466,13 +710,24 @@ impl Component for Menu {
                       }),
                       Line::from(if app_state.query_task_running { " ├[...] indexes" } else { " ├[3] indexes" }),
                       Line::from(if app_state.query_task_running {
-                        " └[...] rls policies"
+                        " ├[...] rls policies"
                       } else {
-                        " └[4] rls policies"
+                        " ├[4] rls policies"
+                      }),
+                      Line::from(if app_state.query_task_running {
+                        " ├[...] foreign keys"
+                      } else {
+                        " ├[5] foreign keys"
+                      }),
+                      Line::from(if app_state.query_task_running { " ├[...] triggers" } else { " ├[6] triggers" }),
+                      Line::from(if app_state.query_task_running {
+                        " └[...] create statement"
+                      } else {
+                        " └[7] create statement"
                       }),
                     ])),
                     MenuItemKind::View { .. } => ListItem::new(Text::from(vec![
-                      Line::from(display_name),
+                      display_name,
                       Line::from(if app_state.query_task_running { " ├[...] rows" } else { " ├[<enter>] rows" }),
                       Line::from(if app_state.query_task_running { " ├[...] columns" } else { " ├[1] columns" }),
                       Line::from(if app_state.query_task_running {
@@ -481,6 +736,29 @@ impl Component for Menu {
                         " └[2] schema definition"
                       }),
                     ])),
+                    MenuItemKind::Routine { .. } => ListItem::new(Text::from(vec![
+                      display_name,
+                      Line::from(if app_state.query_task_running {
+                        " ├[...] source"
+                      } else {
+                        " ├[<enter>] source"
+                      }),
+                      Line::from(if app_state.query_task_running {
+                        " └[...] edit in editor"
+                      } else {
+                        " └[1] edit in editor"
+                      }),
+                    ])),
+                    MenuItemKind::ForeignTable => ListItem::new(Text::from(vec![
+                      display_name,
+                      Line::from(if app_state.query_task_running { " ├[...] rows" } else { " ├[<enter>] rows" }),
+                      Line::from(if app_state.query_task_running { " ├[...] columns" } else { " ├[1] columns" }),
+                      Line::from(if app_state.query_task_running {
+                        " └[...] foreign server"
+                      } else {
+                        " └[2] foreign server"
+                      }),
+                    ])),
                   }
                 } else {
                   ListItem::new(display_name)
@@ -489,19 +767,18 @@ impl Component for Menu {
             })
             .collect();
           let list = List::default().items(entries_items).block(block).highlight_style(
-            Style::default()
-              .fg(if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
-                Color::Green
-              } else {
-                Color::Gray
-              })
-              .add_modifier(if focused { Modifier::BOLD } else { Modifier::REVERSED }),
+            (if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
+              self.config.theme.focused_border
+            } else {
+              Style::default().fg(Color::Gray)
+            })
+            .add_modifier(if focused { Modifier::BOLD } else { Modifier::REVERSED }),
           );
           f.render_stateful_widget(list, layout[layout_index], &mut self.list_state);
           let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .symbols(scrollbar::VERTICAL)
             .style(if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
-              Style::default().fg(Color::Green)
+              self.config.theme.focused_border
             } else {
               Style::default()
             });
@@ -509,11 +786,11 @@ impl Component for Menu {
             ScrollbarState::new(entry_length.saturating_sub(available_height)).position(self.list_state.offset());
           f.render_stateful_widget(vertical_scrollbar, block_margin, &mut vertical_scrollbar_state);
         },
-        x if x == self.table_map.keys().len().saturating_sub(1) => {
+        x if x == schema_keys.len().saturating_sub(1) => {
           f.render_widget(
             Text::styled(
               "└ ".to_owned() + k.to_owned().as_str(),
-              if focused { Style::default() } else { Style::new().dim() },
+              if focused { Style::default() } else { self.config.theme.unfocused_border },
             ),
             layout[layout_index],
           );
@@ -522,7 +799,7 @@ impl Component for Menu {
           f.render_widget(
             Text::styled(
               "┌ ".to_owned() + k.to_owned().as_str(),
-              if focused { Style::default() } else { Style::new().dim() },
+              if focused { Style::default() } else { self.config.theme.unfocused_border },
             ),
             layout[layout_index],
           );
@@ -530,7 +807,7 @@ impl Component for Menu {
         _ => f.render_widget(
           Text::styled(
             "├ ".to_owned() + k.to_owned().as_str(),
-            if focused { Style::default() } else { Style::new().dim() },
+            if focused { Style::default() } else { self.config.theme.unfocused_border },
           ),
           layout[layout_index],
         ),
@@ -541,3 +818,253 @@ impl Component for Menu {
     Ok(())
   }
 }
+
+// e.g. `≈1.2M, 340 MB`. values that aren't numbers, like NULL, are left out
+fn table_stats_label(row_count: &str, size: &str) -> Option<String> {
+  let row_count =
+    row_count.parse::<f64>().ok().map(|count| format!("≈{}", abbreviate(count, 1000.0, &["", "K", "M", "B", "T"], "")));
+  let size = size.parse::<f64>().ok().map(|bytes| abbreviate(bytes, 1024.0, &["bytes", "kB", "MB", "GB", "TB"], " "));
+  match (row_count, size) {
+    (Some(row_count), Some(size)) => Some(format!("{row_count}, {size}")),
+    (row_count, size) => row_count.or(size),
+  }
+}
+
+// scales `value` to the largest unit it's at least one of, with a decimal
+// place below 10 so small values aren't rounded too far
+fn abbreviate(value: f64, step: f64, units: &[&str], separator: &str) -> String {
+  let mut value = value.max(0.0);
+  let mut unit = 0;
+  while value >= step && unit < units.len() - 1 {
+    value /= step;
+    unit += 1;
+  }
+  if unit > 0 && value < 10.0 {
+    format!("{value:.1}{separator}{}", units[unit])
+  } else {
+    format!("{value:.0}{separator}{}", units[unit])
+  }
+}
+
+/// Matches the characters of `search` in order, ignoring case, anywhere in
+/// `name`. Returns the positions of the matched characters, preferring them
+/// to be next to each other.
+pub fn fuzzy_match(search: &str, name: &str) -> Option<Vec<usize>> {
+  let search = search.trim().to_lowercase().chars().collect::<Vec<_>>();
+  let name = name.to_lowercase().chars().collect::<Vec<_>>();
+  if let Some(start) = name.windows(search.len().max(1)).position(|window| window == search.as_slice()) {
+    return Some((start..start + search.len()).collect());
+  }
+  let mut positions = Vec::with_capacity(search.len());
+  let mut next = 0;
+  for c in search {
+    let position = next + name.get(next..)?.iter().position(|n| *n == c)?;
+    positions.push(position);
+    next = position + 1;
+  }
+  Some(positions)
+}
+
+// the name with the characters matching the search highlighted
+fn highlight_matches(name: &str, search: Option<&str>, highlight: Style) -> Line<'static> {
+  let positions = search.and_then(|search| fuzzy_match(search, name)).unwrap_or_default();
+  let mut spans = vec![Span::raw(" ")];
+  for (i, c) in name.chars().enumerate() {
+    if positions.contains(&i) {
+      spans.push(Span::styled(c.to_string(), highlight));
+    } else {
+      spans.push(Span::raw(c.to_string()));
+    }
+  }
+  Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fuzzy_match() {
+    let test_cases = vec![
+      ("", "users", Some(vec![])),
+      ("ser", "users", Some(vec![1, 2, 3])),
+      ("USR", "users", Some(vec![0, 1, 3])),
+      ("ord", "product_orders", Some(vec![8, 9, 10])),
+      ("po", "product_orders", Some(vec![0, 2])),
+      (" us ", "users", Some(vec![0, 1])),
+      ("sru", "users", None),
+      ("usersx", "users", None),
+    ];
+
+    for (search, name, expected) in test_cases {
+      assert_eq!(fuzzy_match(search, name), expected, "Failed for search: {search} in {name}");
+    }
+  }
+
+  #[test]
+  fn test_menu_groups() {
+    let mut menu = Menu::new();
+    let rows = [
+      ("public", "users", "table"),
+      ("public", "active_users", "view"),
+      ("public", "user_stats", "materialized_view"),
+      ("public", "remote_users", "foreign_table"),
+    ];
+    let mut rows: Vec<Vec<String>> =
+      rows.iter().map(|(schema, name, kind)| vec![schema.to_string(), name.to_string(), kind.to_string()]).collect();
+    rows.push(vec!["public".to_owned(), "add(a integer)".to_owned(), "function".to_owned(), "integer".to_owned()]);
+    menu.set_table_list(Some(Ok(Rows { headers: vec![], rows, rows_affected: None })));
+
+    let describe = |menu: &Menu| -> Vec<String> {
+      menu
+        .filtered_entries()
+        .iter()
+        .map(|entry| match entry {
+          MenuEntry::Schema(schema) => schema.clone(),
+          MenuEntry::Header { title, count, collapsed } => format!("{title} {count} {collapsed}"),
+          MenuEntry::Item(item) => format!("{} {:?} {:?}", item.name, item.kind, item.detail),
+        })
+        .collect()
+    };
+    assert_eq!(
+      describe(&menu),
+      vec![
+        "Tables 1 false",
+        "users Table None",
+        "Views 1 false",
+        "active_users View { materialized: false } None",
+        "Materialized views 1 false",
+        "user_stats View { materialized: true } None",
+        "Foreign tables 1 false",
+        "remote_users ForeignTable None",
+        "Functions 1 false",
+        "add(a integer) Routine { procedure: false } Some(\"integer\")",
+      ]
+    );
+    assert_eq!(menu.list_state.selected(), Some(1));
+
+    menu.toggle_group("Views");
+    assert_eq!(menu.selected_group(), Some("Views"));
+    assert_eq!(describe(&menu)[2..4], ["Views 1 true", "Materialized views 1 false"]);
+
+    // a filter shows matches inside collapsed groups
+    menu.search = Some("active".to_owned());
+    assert_eq!(describe(&menu), vec!["Views 1 false", "active_users View { materialized: false } None"]);
+  }
+
+  #[test]
+  fn test_search_all_schemas() {
+    let mut menu = Menu::new();
+    let rows = [("public", "users"), ("public", "orders"), ("sales", "invoices"), ("sales", "user_accounts")];
+    menu.set_table_list(Some(Ok(Rows {
+      headers: vec![],
+      rows: rows.iter().map(|(schema, name)| vec![schema.to_string(), name.to_string(), "table".to_string()]).collect(),
+      rows_affected: None,
+    })));
+    assert_eq!(menu.menu_focus, MenuFocus::Schema);
+
+    let describe = |menu: &Menu| -> Vec<String> {
+      menu
+        .filtered_entries()
+        .iter()
+        .map(|entry| match entry {
+          MenuEntry::Schema(schema) => format!("[{schema}]"),
+          MenuEntry::Header { title, .. } => title.to_string(),
+          MenuEntry::Item(item) => format!("{}.{}", item.schema, item.name),
+        })
+        .collect()
+    };
+
+    menu.search = Some("user".to_owned());
+    assert_eq!(describe(&menu), vec!["[public]", "Tables", "public.users", "[sales]", "Tables", "sales.user_accounts"]);
+
+    // picking a result from another schema switches to it once the search is cleared
+    menu.menu_focus = MenuFocus::Tables;
+    menu.list_state = ListState::default().with_selected(Some(5));
+    menu.change_focus(MenuFocus::Schema);
+    assert_eq!(menu.menu_focus, MenuFocus::Tables);
+    menu.reset_search();
+    assert_eq!(menu.schema_index, 1);
+    assert_eq!(menu.selected_item().map(|item| item.name), Some("user_accounts".to_owned()));
+  }
+
+  #[test]
+  fn test_reload_keeps_selection() {
+    let rows = |tables: &[(&str, &str)]| {
+      Some(Ok(Rows {
+        headers: vec![],
+        rows: tables
+          .iter()
+          .map(|(schema, name)| vec![schema.to_string(), name.to_string(), "table".to_string()])
+          .collect(),
+        rows_affected: None,
+      }))
+    };
+    let mut menu = Menu::new();
+    menu.set_table_list(rows(&[("public", "users"), ("sales", "accounts"), ("sales", "orders")]));
+    menu.change_focus(MenuFocus::Tables);
+    menu.scroll_down();
+    menu.scroll_down();
+    menu.change_focus(MenuFocus::Schema);
+    menu.scroll_down();
+    menu.change_focus(MenuFocus::Tables);
+    menu.scroll_down();
+    assert_eq!(menu.selected_item().map(|item| item.name), Some("orders".to_owned()));
+
+    // a table created before the selection moves it down, but it stays selected
+    menu.set_table_list(rows(&[
+      ("audit", "log"),
+      ("public", "users"),
+      ("sales", "accounts"),
+      ("sales", "orders"),
+      ("sales", "a"),
+    ]));
+    assert_eq!(menu.schema_index, 2);
+    assert_eq!(menu.menu_focus, MenuFocus::Tables);
+    assert_eq!(menu.selected_item().map(|item| item.name), Some("orders".to_owned()));
+
+    // once it's dropped, the entry in its place is selected
+    menu.set_table_list(rows(&[("audit", "log"), ("public", "users"), ("sales", "a"), ("sales", "accounts")]));
+    assert_eq!(menu.selected_item().map(|item| item.name), Some("accounts".to_owned()));
+  }
+
+  #[test]
+  fn test_opens_on_search_path() {
+    let rows = Rows {
+      headers: vec![],
+      rows: [("audit", "log"), ("public", "users"), ("sales", "orders")]
+        .iter()
+        .map(|(schema, name)| vec![schema.to_string(), name.to_string(), "table".to_string()])
+        .collect(),
+      rows_affected: None,
+    };
+    let mut menu = Menu::new();
+    menu.set_table_list(Some(Ok(rows.clone())));
+    assert_eq!(menu.menu_focus, MenuFocus::Schema);
+
+    // schemas missing from the menu are skipped
+    let mut menu = Menu::new();
+    menu.set_search_path(vec!["app".to_owned(), "sales".to_owned(), "public".to_owned()]);
+    menu.set_table_list(Some(Ok(rows)));
+    assert_eq!(menu.menu_focus, MenuFocus::Tables);
+    assert_eq!(menu.schema_index, 2);
+    assert_eq!(menu.selected_item().map(|item| item.name), Some("orders".to_owned()));
+  }
+
+  #[test]
+  fn test_table_stats_label() {
+    let test_cases = vec![
+      ("1234567", "356515840", Some("≈1.2M, 340 MB")),
+      ("850", "8192", Some("≈850, 8.0 kB")),
+      ("12000", "512", Some("≈12K, 512 bytes")),
+      ("0", "16384", Some("≈0, 16 kB")),
+      (crate::database::NULL, "16384", Some("16 kB")),
+      ("42", crate::database::NULL, Some("≈42")),
+      (crate::database::NULL, crate::database::NULL, None),
+    ];
+
+    for (row_count, size, expected) in test_cases {
+      assert_eq!(table_stats_label(row_count, size).as_deref(), expected, "Failed for {row_count:?}, {size:?}");
+    }
+  }
+}
@@ -1,16 +1,21 @@
 use color_eyre::eyre::Result;
 use ratatui::{
-  buffer::Cell,
+  buffer,
   prelude::*,
-  widgets::{Block, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState, WidgetRef},
+  widgets::{Block, Cell, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState, WidgetRef},
 };
 use symbols::scrollbar;
-
-use super::Component;
-use crate::app::AppState;
+use unicode_width::UnicodeWidthStr;
 
 pub const COLUMN_SPACING: u16 = 1;
 
+/// Where the cells of a table come from. They're only asked for once they're
+/// in view, so tall results don't have to be turned into cells up front.
+pub trait TableCells {
+  fn header(&self, column: usize, width: u16) -> Cell<'_>;
+  fn cell(&self, row: usize, column: usize, width: u16) -> Cell<'_>;
+}
+
 pub enum ScrollDirection {
   Left,
   Right,
@@ -22,63 +27,98 @@ pub enum ScrollDirection {
 pub enum SelectionMode {
   Row,
   Cell,
+  // anchored at the (column, row) where the selection was started
+  Range(usize, usize),
   Copied,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct ScrollTable<'a> {
-  table: Table<'a>,
+  header_style: Style,
   parent_area: Rect,
   block: Option<Block<'a>>,
   pg_height: u16,
   requested_width: u16,
   column_widths: Vec<u16>,
   column_offsets: Vec<u16>,
-  max_height: u16,
   x_offset: u16,
   y_offset: usize,
   max_x_offset: u16,
   max_y_offset: usize,
   selection_mode: Option<SelectionMode>,
   data_row_offset: u16,
+  row_spacing: u16,
+  // how many lines each row takes up, when some are taller than one line
+  row_heights: Vec<u16>,
+  // how many of the leftmost columns stay in place when scrolling horizontally
+  frozen_columns: usize,
+  // the number shown next to the first row, when rows are numbered
+  first_row_number: Option<usize>,
+  row_count: usize,
+  // the selected cells. the selected row is styled by the table itself
+  selection_style: Style,
 }
 
 impl<'a> ScrollTable<'a> {
   pub fn new() -> Self {
     Self {
-      table: Table::default(),
+      header_style: Style::default(),
       parent_area: Rect::new(0, 0, 0, 0),
       block: None,
       pg_height: 0,
       requested_width: 0,
       column_widths: Vec::new(),
       column_offsets: Vec::new(),
-      max_height: 0,
       x_offset: 0,
       y_offset: 0,
       max_x_offset: 0,
       max_y_offset: 0,
       selection_mode: None,
       data_row_offset: 0,
+      row_spacing: 0,
+      row_heights: Vec::new(),
+      frozen_columns: 0,
+      first_row_number: None,
+      row_count: 0,
+      selection_style: Style::default(),
     }
   }
 
+  /// Sets the width of each column and how many rows there are. The cells
+  /// themselves are given when drawing. `data_row_offset` is the number of
+  /// lines before the first row: the header and the spacer after it.
   pub fn set_table(
     &mut self,
-    table: Table<'a>,
     column_widths: Vec<u16>,
     row_count: usize,
     data_row_offset: u16,
+    row_spacing: u16,
   ) -> &mut Self {
-    let requested_width = Self::requested_width(&column_widths);
-    let max_height = u16::MAX.saturating_div(std::cmp::max(1, requested_width));
-    self.table = table;
+    self.requested_width = Self::requested_width(&column_widths);
     self.column_widths = column_widths;
     self.column_offsets = Self::build_offsets(&self.column_widths);
-    self.requested_width = requested_width;
-    self.max_height = max_height;
     self.max_y_offset = row_count.saturating_sub(1);
+    self.row_count = row_count;
     self.data_row_offset = data_row_offset;
+    self.row_spacing = row_spacing;
+    self.row_heights = Vec::new();
+    self
+  }
+
+  /// Sets how many lines each row of the table takes up. Rows are one line
+  /// tall unless given a height here.
+  pub fn row_heights(&mut self, heights: Vec<u16>) -> &mut Self {
+    self.row_heights = heights;
+    self
+  }
+
+  pub fn header_style(&mut self, style: Style) -> &mut Self {
+    self.header_style = style;
+    self
+  }
+
+  pub fn selection_style(&mut self, style: Style) -> &mut Self {
+    self.selection_style = style;
     self
   }
 
@@ -127,31 +167,82 @@ impl<'a> ScrollTable<'a> {
   }
 
   pub fn pg_up(&mut self) -> &mut Self {
-    self.y_offset = self.y_offset.saturating_sub(std::cmp::max(
-      1,
-      self.pg_height.saturating_div(2).saturating_sub(
-        u16::from(self.pg_height.is_multiple_of(2)), // always round down
-      ) as usize,
-    ));
+    let rows = self.rows_in_lines(self.pg_height, false);
+    self.y_offset = self.y_offset.saturating_sub(rows);
     self
   }
 
   pub fn pg_down(&mut self) -> &mut Self {
-    let new_y_offset = self.y_offset.saturating_add(std::cmp::max(
-      1,
-      self.pg_height.saturating_div(2).saturating_sub(
-        u16::from(self.pg_height.is_multiple_of(2)), // always rounds down
-      ) as usize,
-    ));
-    self.y_offset = std::cmp::min(self.max_y_offset, new_y_offset);
+    let rows = self.rows_in_lines(self.pg_height, true);
+    self.y_offset = std::cmp::min(self.max_y_offset, self.y_offset.saturating_add(rows));
+    self
+  }
+
+  pub fn half_pg_up(&mut self) -> &mut Self {
+    let rows = self.rows_in_lines(self.half_pg_height(), false);
+    self.y_offset = self.y_offset.saturating_sub(rows);
+    self
+  }
+
+  pub fn half_pg_down(&mut self) -> &mut Self {
+    let rows = self.rows_in_lines(self.half_pg_height(), true);
+    self.y_offset = std::cmp::min(self.max_y_offset, self.y_offset.saturating_add(rows));
+    self
+  }
+
+  pub fn half_pg_left(&mut self) -> &mut Self {
+    self.x_offset = self.x_offset.saturating_sub(self.half_pg_width());
+    self
+  }
+
+  pub fn half_pg_right(&mut self) -> &mut Self {
+    self.x_offset = std::cmp::min(self.max_x_offset, self.x_offset.saturating_add(self.half_pg_width()));
     self
   }
 
+  fn half_pg_height(&self) -> u16 {
+    self.pg_height.saturating_div(2).saturating_sub(
+      u16::from(self.pg_height.is_multiple_of(2)), // always rounds down
+    )
+  }
+
+  // half the width of the columns that scroll sideways, so at least one cell
+  fn half_pg_width(&self) -> u16 {
+    let width = self.table_area(self.block.inner_if_some(self.parent_area)).width;
+    std::cmp::max(1, width.saturating_sub(self.frozen_width(width)) / 2)
+  }
+
+  // how many rows fit in `lines` going up or down from the cursor, which is
+  // fewer than the lines when rows are wrapped. always at least one.
+  fn rows_in_lines(&self, mut lines: u16, forwards: bool) -> usize {
+    let mut rows = 0_usize;
+    loop {
+      let row = match forwards {
+        true => self.y_offset.saturating_add(rows).saturating_add(1),
+        false => match self.y_offset.checked_sub(rows.saturating_add(1)) {
+          Some(row) => row,
+          None => break,
+        },
+      };
+      match lines.checked_sub(self.row_height(row)) {
+        Some(rest) if row < self.row_count => lines = rest,
+        _ => break,
+      }
+      rows += 1;
+    }
+    std::cmp::max(1, rows)
+  }
+
   pub fn bottom_row(&mut self) -> &mut Self {
     self.y_offset = self.max_y_offset;
     self
   }
 
+  pub fn go_to_row(&mut self, row: usize) -> &mut Self {
+    self.y_offset = std::cmp::min(row, self.max_y_offset);
+    self
+  }
+
   pub fn top_row(&mut self) -> &mut Self {
     self.y_offset = 0;
     self
@@ -167,6 +258,22 @@ impl<'a> ScrollTable<'a> {
     self
   }
 
+  pub fn freeze_columns(&mut self, count: usize) -> &mut Self {
+    self.frozen_columns = count;
+    self
+  }
+
+  pub fn frozen_columns(&self) -> usize {
+    self.frozen_columns
+  }
+
+  /// Numbers rows in a gutter left of the table, counting from `first`, or
+  /// hides the numbers.
+  pub fn number_rows(&mut self, first: Option<usize>) -> &mut Self {
+    self.first_row_number = first;
+    self
+  }
+
   pub fn reset_scroll(&mut self) -> &mut Self {
     self.x_offset = 0;
     self.y_offset = 0;
@@ -178,6 +285,41 @@ impl<'a> ScrollTable<'a> {
     (col_index, self.y_offset)
   }
 
+  // the value under the cursor, from the rows this table was built from
+  pub fn get_focused_cell<'r>(&self, rows: &'r [Vec<String>]) -> Option<&'r String> {
+    let (x, y) = self.get_cell_offsets();
+    rows.get(y).and_then(|row| row.get(x))
+  }
+
+  /// Returns the (column, row) of the cell drawn at `position` on screen, if
+  /// there is one. Headers, gaps between columns and row spacers aren't cells.
+  pub fn cell_at(&self, position: Position) -> Option<(usize, usize)> {
+    let render_area = self.table_area(self.block.inner_if_some(self.parent_area));
+    if !render_area.contains(position) {
+      return None;
+    }
+    let row = self.row_at_line(position.y - render_area.y)?;
+    let frozen_width = self.frozen_width(render_area.width);
+    let content_x = match position.x - render_area.x {
+      frozen_x if frozen_x < frozen_width => frozen_x,
+      scrolled_x => scrolled_x.saturating_add(self.scrolled_by(frozen_width)),
+    };
+    let column = self
+      .column_offsets
+      .iter()
+      .zip(&self.column_widths)
+      .position(|(start, width)| content_x >= *start && content_x < start.saturating_add(*width))?;
+    Some((column, row))
+  }
+
+  /// Moves the cursor to the given cell, the same as navigating to it with the keyboard.
+  pub fn select_cell(&mut self, column: usize, row: usize) -> &mut Self {
+    if let Some(start) = self.column_offsets.get(column) {
+      self.x_offset = std::cmp::min(*start, self.max_x_offset);
+    }
+    self.go_to_row(row)
+  }
+
   pub fn get_selection_mode(&self) -> Option<SelectionMode> {
     self.selection_mode.clone()
   }
@@ -187,6 +329,25 @@ impl<'a> ScrollTable<'a> {
     self
   }
 
+  pub fn start_range_selection(&mut self) -> &mut Self {
+    let (col_index, row_index) = self.get_cell_offsets();
+    self.selection_mode = Some(SelectionMode::Range(col_index, row_index));
+    self
+  }
+
+  /// Returns the inclusive column and row bounds of the range selection,
+  /// as `((first_col, last_col), (first_row, last_row))`.
+  pub fn get_selected_range(&self) -> Option<((usize, usize), (usize, usize))> {
+    let Some(SelectionMode::Range(anchor_col, anchor_row)) = self.selection_mode else {
+      return None;
+    };
+    let (col_index, row_index) = self.get_cell_offsets();
+    Some((
+      (anchor_col.min(col_index), anchor_col.max(col_index)),
+      (anchor_row.min(row_index), anchor_row.max(row_index)),
+    ))
+  }
+
   fn get_max_x_offset(&self, parent_area: &Rect, parent_block: &Option<Block>) -> u16 {
     let render_area = parent_block.inner_if_some(*parent_area);
     if render_area.is_empty() {
@@ -195,6 +356,63 @@ impl<'a> ScrollTable<'a> {
     self.column_offsets.last().copied().unwrap_or(0)
   }
 
+  fn row_height(&self, row: usize) -> u16 {
+    self.row_heights.get(row).copied().unwrap_or(1).max(1)
+  }
+
+  // the row drawn on a line of the table, counting from the top of the header
+  fn row_at_line(&self, content_y: u16) -> Option<usize> {
+    self.row_line_at(content_y).map(|(row, _)| row)
+  }
+
+  // the row drawn on a line of the table and which of its lines it is. the
+  // spacers between rows aren't part of any row.
+  fn row_line_at(&self, content_y: u16) -> Option<(usize, u16)> {
+    let mut line = content_y.checked_sub(self.data_row_offset)?;
+    let mut row = self.y_offset;
+    loop {
+      let height = self.row_height(row);
+      if line < height {
+        return Some((row, line));
+      }
+      line = line.checked_sub(height.saturating_add(self.row_spacing))?;
+      row = row.saturating_add(1);
+    }
+  }
+
+  // the width of the row number gutter and the gap after it. there's no gutter
+  // when it wouldn't leave any room for the table.
+  fn gutter_width(&self, render_width: u16) -> u16 {
+    let Some(first) = self.first_row_number else {
+      return 0;
+    };
+    let last = first.saturating_add(self.row_count.saturating_sub(1));
+    let width = (last.to_string().len() as u16).saturating_add(COLUMN_SPACING);
+    if width < render_width { width } else { 0 }
+  }
+
+  // the part of the render area right of the gutter, where the table itself goes
+  fn table_area(&self, render_area: Rect) -> Rect {
+    let gutter_width = self.gutter_width(render_area.width);
+    Rect { x: render_area.x + gutter_width, width: render_area.width - gutter_width, ..render_area }
+  }
+
+  // the width taken up by the frozen columns and the gap after them. nothing is
+  // frozen when that wouldn't leave any room for the rest of the table.
+  fn frozen_width(&self, render_width: u16) -> u16 {
+    if self.frozen_columns == 0 || self.frozen_columns >= self.column_widths.len() {
+      return 0;
+    }
+    let width = self.column_offsets[self.frozen_columns];
+    if width < render_width { width } else { 0 }
+  }
+
+  // how far the unfrozen columns are scrolled. while the cursor is on a frozen
+  // column the table isn't scrolled at all.
+  fn scrolled_by(&self, frozen_width: u16) -> u16 {
+    self.x_offset.saturating_sub(frozen_width)
+  }
+
   fn requested_width(column_widths: &[u16]) -> u16 {
     if column_widths.is_empty() {
       return 0;
@@ -247,26 +465,99 @@ impl<'a> ScrollTable<'a> {
     false
   }
 
-  fn widget(&'a self) -> Renderer<'a> {
-    Renderer::new(self, self.y_offset)
+  fn is_within_selected_range(&self, content_x: u16, content_y: u16) -> bool {
+    let Some(((first_col, last_col), (first_row, last_row))) = self.get_selected_range() else {
+      return false;
+    };
+    let Some(row_index) = self.row_at_line(content_y) else {
+      return false;
+    };
+    if row_index < first_row || row_index > last_row {
+      return false;
+    }
+    match (self.column_offsets.get(first_col), self.column_offsets.get(last_col), self.column_widths.get(last_col)) {
+      (Some(start), Some(last_start), Some(last_width)) => {
+        content_x >= *start && content_x < last_start.saturating_add(*last_width)
+      },
+      _ => false,
+    }
+  }
+
+  // the columns with any part in view: the frozen ones, then the others from
+  // `scrolled_by` on. also how far into the table the first of those others
+  // starts, past the frozen columns, which isn't drawn.
+  fn columns_in_view(&self, width: u16, frozen_width: u16, scrolled_by: u16) -> (Vec<usize>, u16) {
+    let frozen = if frozen_width > 0 { self.frozen_columns } else { 0 };
+    let start = frozen_width.saturating_add(scrolled_by);
+    let end = scrolled_by.saturating_add(width);
+    let scrolled = (frozen..self.column_widths.len())
+      // a column counts from the gap before the next one, so the first column
+      // in view never starts after the part that's drawn
+      .skip_while(|&column| {
+        self.column_offsets[column].saturating_add(self.column_widths[column]).saturating_add(COLUMN_SPACING) <= start
+      })
+      .take_while(|&column| self.column_offsets[column] < end);
+    let columns = (0..frozen).chain(scrolled).collect::<Vec<_>>();
+    let skipped = match columns.get(frozen) {
+      Some(first) => self.column_offsets[*first].saturating_sub(frozen_width),
+      None => 0,
+    };
+    (columns, skipped)
+  }
+
+  // a table of the cells in view, from the row under the cursor down to as
+  // many as fit in `height` lines. only these cells are built.
+  fn table_in_view<'c>(
+    &self,
+    cells: &'c dyn TableCells,
+    columns: &[usize],
+    column_widths: Vec<u16>,
+    height: u16,
+  ) -> Table<'c> {
+    let header = columns.iter().zip(&column_widths).map(|(column, width)| cells.header(*column, *width));
+    let header = Row::new(header.collect::<Vec<_>>())
+      .height(self.data_row_offset.saturating_sub(self.row_spacing))
+      .bottom_margin(self.row_spacing)
+      .style(self.header_style);
+    let mut lines = self.data_row_offset;
+    let rows = (self.y_offset..self.row_count)
+      .take_while(|row| {
+        let in_view = lines < height;
+        lines = lines.saturating_add(self.row_height(*row)).saturating_add(self.row_spacing);
+        in_view
+      })
+      .map(|row| {
+        let row_cells = columns.iter().zip(&column_widths).map(|(column, width)| cells.cell(row, *column, *width));
+        Row::new(row_cells.collect::<Vec<_>>()).height(self.row_height(row)).bottom_margin(self.row_spacing)
+      })
+      .collect::<Vec<_>>();
+    Table::new(rows, column_widths)
+      .header(header)
+      .column_spacing(COLUMN_SPACING)
+      .row_highlight_style(self.selection_style)
+  }
+
+  fn widget(&'a self, cells: &'a dyn TableCells) -> Renderer<'a> {
+    Renderer::new(self, cells)
   }
-}
 
-impl Component for ScrollTable<'_> {
-  fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState) -> Result<()> {
+  /// Draws the table, building the cells in view from `cells`. The scrollbars
+  /// are sized by every row, not just the ones that were built.
+  pub fn draw(&mut self, f: &mut Frame<'_>, area: Rect, cells: &dyn TableCells) -> Result<()> {
     self.parent_area = area;
     let render_area = self.block.inner_if_some(area);
-    self.pg_height = std::cmp::min(self.max_height, render_area.height).saturating_sub(3);
+    self.pg_height = render_area.height.saturating_sub(3);
     self.max_x_offset = self.get_max_x_offset(&self.parent_area, &self.block);
-    let max_x_offset = self.max_x_offset;
-    let x_offset = self.x_offset;
-    f.render_widget(self.widget(), area);
+    let frozen_width = self.frozen_width(self.table_area(render_area).width);
+    let max_x_offset = self.max_x_offset.saturating_sub(frozen_width);
+    let x_offset = self.scrolled_by(frozen_width);
+    f.render_widget(self.widget(cells), area);
     let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight).symbols(scrollbar::VERTICAL);
-    let mut vertical_scrollbar_state = ScrollbarState::new(self.max_y_offset).position(self.y_offset);
+    let mut vertical_scrollbar_state = scrollbar_state(self.max_y_offset, self.y_offset);
     let horizontal_scrollbar =
       Scrollbar::new(ScrollbarOrientation::HorizontalBottom).symbols(scrollbar::HORIZONTAL).thumb_symbol("▀");
-    let mut horizontal_scrollbar_state = ScrollbarState::new(max_x_offset as usize).position(x_offset as usize);
-    match (self.max_x_offset, self.max_y_offset) {
+    let mut horizontal_scrollbar_state = scrollbar_state(max_x_offset as usize, x_offset as usize);
+    match (max_x_offset, self.max_y_offset) {
       (0, 0) => {},
       (0, y) => {
         f.render_stateful_widget(
@@ -299,64 +590,96 @@ impl Component for ScrollTable<'_> {
   }
 }
 
+// offsets go from 0 to `max_offset` inclusive, so there's one more position than
+// the max offset. otherwise the thumb hits the end of the track a step early.
+fn scrollbar_state(max_offset: usize, offset: usize) -> ScrollbarState {
+  ScrollbarState::new(max_offset.saturating_add(1)).position(offset)
+}
+
 // based on scrolling approach from tui-textarea:
+// the table's header is always drawn at the top, and the y offset only skips
+// body rows, so the header stays pinned while scrolling down. the x offset
+// applies to every line, which keeps the header aligned with its columns.
+// only the rows and columns in view are drawn, into a buffer no wider than
+// them, so drawing doesn't get slower with the size of the results.
 // https://github.com/rhysd/tui-textarea/blob/main/src/widget.rs
-pub struct Renderer<'a>(&'a ScrollTable<'a>, TableState);
+pub struct Renderer<'a>(&'a ScrollTable<'a>, &'a dyn TableCells);
 
 impl<'a> Renderer<'a> {
-  pub fn new(scrollable: &'a ScrollTable<'a>, y_offset: usize) -> Self {
-    Self(scrollable, TableState::default().with_offset(y_offset))
-  }
-
-  pub fn offset(&self) -> usize {
-    self.1.offset()
+  pub fn new(scrollable: &'a ScrollTable<'a>, cells: &'a dyn TableCells) -> Self {
+    Self(scrollable, cells)
   }
 }
 
 impl Widget for Renderer<'_> {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let scrollable = self.0;
-    let table = &scrollable.table;
-    let mut table_state = self.1;
-    let current_offset = table_state.offset();
-    if let Some(SelectionMode::Row) = scrollable.selection_mode {
-      table_state = table_state.with_selected(current_offset);
-    }
+    let Self(scrollable, cells) = self;
     scrollable.block.render_ref(area, buf);
     let render_area = scrollable.block.inner_if_some(area);
     if render_area.is_empty() {
       return;
     }
-    let area = render_area.intersection(buf.area);
-    let mut content_buf = Buffer::empty(Rect::new(
-      0,
-      0,
-      scrollable.requested_width,
-      std::cmp::min(scrollable.max_height, render_area.height),
-    ));
-    ratatui::widgets::StatefulWidgetRef::render_ref(table, content_buf.area, &mut content_buf, &mut table_state);
-    let content_width = content_buf.area.width;
-    let content_height = content_buf.area.height;
-    let max_x = std::cmp::min(area.x.saturating_add(area.width), area.x.saturating_add(content_width));
-    let max_y = std::cmp::min(area.y.saturating_add(area.height), area.y.saturating_add(content_height));
+    let render_area = render_area.intersection(buf.area);
+    let area = scrollable.table_area(render_area);
+    if let Some(first) = scrollable.first_row_number
+      && area.x > render_area.x
+    {
+      let number_width = (area.x - render_area.x - COLUMN_SPACING) as usize;
+      for y in render_area.top()..render_area.bottom() {
+        // wrapped rows are numbered on their first line
+        if let Some((row, 0)) = scrollable.row_line_at(y - render_area.y).filter(|(row, _)| *row < scrollable.row_count)
+        {
+          let number = format!("{:>number_width$}", first.saturating_add(row));
+          buf.set_string(render_area.x, y, number, Style::default().dim());
+        }
+      }
+    }
+    let frozen_width = scrollable.frozen_width(area.width);
+    let scrolled_by = scrollable.scrolled_by(frozen_width);
+    let (columns, skipped) = scrollable.columns_in_view(area.width, frozen_width, scrolled_by);
+    let column_widths = columns.iter().map(|column| scrollable.column_widths[*column]).collect::<Vec<_>>();
+    let mut content_buf =
+      Buffer::empty(Rect::new(0, 0, ScrollTable::requested_width(&column_widths), render_area.height));
+    // the first row drawn is the one under the cursor
+    let mut table_state = TableState::default();
+    if let Some(SelectionMode::Row) = scrollable.selection_mode {
+      table_state = table_state.with_selected(0);
+    }
+    StatefulWidget::render(
+      scrollable.table_in_view(cells, &columns, column_widths, render_area.height),
+      content_buf.area,
+      &mut content_buf,
+      &mut table_state,
+    );
+    let max_x = std::cmp::min(area.x.saturating_add(area.width), area.x.saturating_add(scrollable.requested_width));
+    let max_y = area.y.saturating_add(area.height);
+    let default_cell = buffer::Cell::default();
     for y in area.y..max_y {
       let content_y = y - area.y;
-      let row = get_row(&content_buf.content, content_y, content_width);
       for x in area.x..max_x {
-        let content_x = x + scrollable.x_offset - area.x;
-        let default_cell = Cell::default();
-        let cell = match &row.len().saturating_sub(1).saturating_sub(content_x as usize) {
-          0 => &default_cell,
-          _ => &row[content_x as usize],
+        // frozen columns are drawn where they are, and everything after them is scrolled
+        let (content_x, drawn_x) = match x - area.x {
+          frozen_x if frozen_x < frozen_width => (frozen_x, frozen_x),
+          scrolled_x => {
+            let content_x = scrolled_x.saturating_add(scrolled_by);
+            (content_x, content_x.saturating_sub(skipped))
+          },
+        };
+        let cell = match content_buf.cell(Position::new(drawn_x, content_y)) {
+          // a wide character that would stick out past the edge isn't drawn at all
+          Some(cell) if x.saturating_add(cell.symbol().width() as u16) > max_x => &default_cell,
+          Some(cell) => cell,
+          None => &default_cell,
         };
-        let should_highlight = matches!(scrollable.selection_mode.as_ref(), Some(SelectionMode::Cell))
-          && content_y == scrollable.data_row_offset
-          && scrollable.is_within_selected_column(content_x);
-        let style = if should_highlight {
-          Style::default().fg(Color::LightBlue).reversed().bold().italic()
-        } else {
-          cell.style()
+        let should_highlight = match scrollable.selection_mode.as_ref() {
+          Some(SelectionMode::Cell) => {
+            scrollable.row_at_line(content_y) == Some(scrollable.y_offset)
+              && scrollable.is_within_selected_column(content_x)
+          },
+          Some(SelectionMode::Range(..)) => scrollable.is_within_selected_range(content_x, content_y),
+          _ => false,
         };
+        let style = if should_highlight { scrollable.selection_style.italic() } else { cell.style() };
         buf
           .cell_mut(Position::from((x, y)))
           .unwrap()
@@ -370,6 +693,348 @@ impl Widget for Renderer<'_> {
   }
 }
 
-fn get_row(content: &[Cell], row: u16, width: u16) -> Vec<Cell> {
-  content[((row * width) as usize)..(((row + 1) * width) as usize)].to_vec()
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cells<'a, T: Into<Text<'a>>>(values: impl IntoIterator<Item = T>) -> Vec<Cell<'a>> {
+    values.into_iter().map(Cell::from).collect()
+  }
+
+  // cells built up front, for tests of everything but building them
+  struct Prebuilt<'a>(Vec<Cell<'a>>, Vec<Vec<Cell<'a>>>);
+
+  impl TableCells for Prebuilt<'_> {
+    fn header(&self, column: usize, _width: u16) -> Cell<'_> {
+      self.0.get(column).cloned().unwrap_or_default()
+    }
+
+    fn cell(&self, row: usize, column: usize, _width: u16) -> Cell<'_> {
+      self.1.get(row).and_then(|cells| cells.get(column)).cloned().unwrap_or_default()
+    }
+  }
+
+  fn render(scrollable: &ScrollTable, cells: &dyn TableCells, buf: &mut Buffer) {
+    scrollable.widget(cells).render(buf.area, buf);
+  }
+
+  fn line(buf: &Buffer, y: u16) -> String {
+    (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect()
+  }
+
+  #[test]
+  fn test_header_stays_pinned() {
+    let column_widths = vec![4, 5];
+    let rows = (0..20).map(|i| cells([format!("r{i}"), format!("v{i}")])).collect::<Vec<_>>();
+    let table = Prebuilt(cells(["id", "name"]), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0);
+    for _ in 0..5 {
+      scrollable.scroll(ScrollDirection::Down);
+    }
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 10, 3));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(line(&buf, 0), "id   name ");
+    assert_eq!(line(&buf, 1), "r5   v5   ");
+    assert_eq!(line(&buf, 2), "r6   v6   ");
+
+    // the header scrolls sideways with the rows below it
+    scrollable.max_x_offset = 5;
+    scrollable.next_column();
+    let mut buf = Buffer::empty(Rect::new(0, 0, 10, 3));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(line(&buf, 0), "name      ");
+    assert_eq!(line(&buf, 1), "v5        ");
+  }
+
+  #[test]
+  fn test_wide_tables_draw_every_line() {
+    // far wider than a buffer of every column could be tall enough for
+    let column_widths = vec![20_u16; 500];
+    let rows = (0..40).map(|i| (0..500).map(|c| Cell::from(format!("r{i}c{c}"))).collect()).collect::<Vec<_>>();
+    let table = Prebuilt(cells((0..500).map(|c| format!("col{c}"))), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0);
+    scrollable.max_x_offset = u16::MAX;
+    scrollable.select_cell(450, 0).scroll(ScrollDirection::Right);
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 30, 30));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(line(&buf, 0), format!("l450{}col451{}", " ".repeat(15), " ".repeat(5)));
+    assert_eq!(line(&buf, 29), format!("8c450{}r28c451{}", " ".repeat(14), " ".repeat(4)));
+  }
+
+  // a synthetic wide result whose cells are only made when asked for, counting how many were
+  struct Synthetic {
+    built: std::sync::atomic::AtomicUsize,
+  }
+
+  impl TableCells for Synthetic {
+    fn header(&self, column: usize, _width: u16) -> Cell<'_> {
+      Cell::from(format!("col{column}"))
+    }
+
+    fn cell(&self, row: usize, column: usize, _width: u16) -> Cell<'_> {
+      self.built.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+      Cell::from(format!("r{row}c{column}"))
+    }
+  }
+
+  fn wide_results(columns: usize, rows: usize) -> (ScrollTable<'static>, Synthetic) {
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(vec![20_u16; columns], rows, 1, 0);
+    scrollable.max_x_offset = u16::MAX;
+    (scrollable, Synthetic { built: Default::default() })
+  }
+
+  #[test]
+  fn test_wide_results_only_build_cells_in_view() {
+    let (mut scrollable, table) = wide_results(500, 10_000);
+    scrollable.select_cell(250, 5_000);
+    let mut buf = Buffer::empty(Rect::new(0, 0, 120, 40));
+    render(&scrollable, &table, &mut buf);
+    // at most one partly shown column on each side of the ones that fit
+    let visible = (120 / 20 + 2) * 39;
+    let built = table.built.load(std::sync::atomic::Ordering::Relaxed);
+    assert!(built > 0 && built <= visible, "built {built} cells for {visible} in view");
+  }
+
+  // how long a frame of a 500 column result takes while scrolling across it. run with
+  // `cargo test --release bench_wide_results -- --ignored --nocapture`
+  #[test]
+  #[ignore]
+  fn bench_wide_results() {
+    let (mut scrollable, table) = wide_results(500, 100_000);
+    let mut buf = Buffer::empty(Rect::new(0, 0, 200, 60));
+    let frames = 1_000;
+    let start = std::time::Instant::now();
+    for frame in 0..frames {
+      if frame % 2 == 0 {
+        scrollable.next_column();
+      } else {
+        scrollable.scroll(ScrollDirection::Down);
+      }
+      render(&scrollable, &table, &mut buf);
+    }
+    let elapsed = start.elapsed();
+    eprintln!("{frames} frames in {elapsed:?}, {:?} a frame", elapsed / frames);
+  }
+
+  #[test]
+  fn test_frozen_columns() {
+    let column_widths = vec![2, 3, 3, 3];
+    let rows = vec![cells(["1", "aaa", "bbb", "ccc"])];
+    let table = Prebuilt(cells(["id", "a", "b", "c"]), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0);
+    scrollable.freeze_columns(1);
+    scrollable.max_x_offset = 11;
+
+    let render = |scrollable: &ScrollTable| {
+      let mut buf = Buffer::empty(Rect::new(0, 0, 8, 2));
+      render(scrollable, &table, &mut buf);
+      line(&buf, 1)
+    };
+    assert_eq!(render(&scrollable), "1  aaa b");
+    // the cursor moving onto the first unfrozen column doesn't scroll anything yet
+    scrollable.next_column();
+    assert_eq!(render(&scrollable), "1  aaa b");
+    scrollable.next_column();
+    assert_eq!(render(&scrollable), "1  bbb c");
+    scrollable.next_column();
+    assert_eq!(render(&scrollable), "1  ccc  ");
+
+    // nothing is frozen when the frozen columns would fill the whole width
+    scrollable.freeze_columns(3);
+    assert_eq!(render(&scrollable), "ccc     ");
+
+    // half a page sideways is half the width left for the unfrozen columns
+    scrollable.freeze_columns(1);
+    scrollable.parent_area = Rect::new(0, 0, 8, 2);
+    scrollable.first_column().half_pg_right();
+    assert_eq!(scrollable.x_offset, 2);
+    scrollable.half_pg_right().half_pg_left();
+    assert_eq!(scrollable.x_offset, 2);
+  }
+
+  #[test]
+  fn test_column_navigation_snaps_to_column_starts() {
+    let column_widths = vec![2, 5, 3];
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, 1, 1, 0);
+    scrollable.max_x_offset = 9;
+
+    let mut offsets = vec![];
+    for _ in 0..3 {
+      scrollable.next_column();
+      offsets.push(scrollable.x_offset);
+    }
+    assert_eq!(offsets, vec![3, 9, 9]);
+
+    // part way into a column, going back lands on its start before the previous column
+    scrollable.first_column().next_column().scroll(ScrollDirection::Right);
+    assert_eq!(scrollable.x_offset, 5);
+    scrollable.prev_column();
+    assert_eq!(scrollable.x_offset, 3);
+    scrollable.prev_column();
+    assert_eq!(scrollable.x_offset, 0);
+    scrollable.scroll(ScrollDirection::Right).next_column();
+    assert_eq!(scrollable.x_offset, 3);
+  }
+
+  #[test]
+  fn test_cell_at() {
+    let column_widths = vec![2, 5, 3];
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, 10, 1, 0);
+    scrollable.parent_area = Rect::new(10, 5, 20, 6);
+    scrollable.block(Block::bordered());
+    scrollable.max_x_offset = 9;
+    scrollable.y_offset = 4;
+
+    let test_cases = vec![
+      ((11, 6), None),
+      ((11, 7), Some((0, 4))),
+      ((13, 7), None),
+      ((14, 9), Some((1, 6))),
+      ((20, 7), Some((2, 4))),
+      ((9, 7), None),
+    ];
+    for ((x, y), expected) in test_cases {
+      assert_eq!(scrollable.cell_at(Position::new(x, y)), expected, "Failed for position: ({x}, {y})");
+    }
+
+    scrollable.select_cell(2, 6);
+    assert_eq!(scrollable.get_cell_offsets(), (2, 6));
+    scrollable.go_to_row(50000);
+    assert_eq!(scrollable.get_cell_offsets(), (2, 9));
+  }
+
+  #[test]
+  fn test_max_scroll_reaches_the_end() {
+    let column_widths = vec![4, 6];
+    let rows = vec![cells(["1", "abcdef"])];
+    let table = Prebuilt(cells(["id", "name"]), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0);
+    scrollable.max_x_offset = 5;
+    scrollable.last_column();
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 8, 2));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(line(&buf, 1), "abcdef  ");
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 10, 1));
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::HorizontalBottom).symbols(scrollbar::HORIZONTAL);
+    scrollbar.clone().render(buf.area, &mut buf, &mut scrollbar_state(5, 5));
+    assert_eq!(buf[(8, 0)].symbol(), scrollbar::HORIZONTAL.thumb);
+    // one short of the end, the thumb doesn't reach the end yet
+    scrollbar.render(buf.area, &mut buf, &mut scrollbar_state(5, 4));
+    assert_ne!(buf[(8, 0)].symbol(), scrollbar::HORIZONTAL.thumb);
+  }
+
+  #[test]
+  fn test_wide_characters() {
+    let column_widths = vec![6, 1];
+    let rows = vec![cells(["日本語", "x"])];
+    let table = Prebuilt(cells(["name", "c"]), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0);
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 8, 2));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(buf[(0, 1)].symbol(), "日");
+    assert_eq!(buf[(2, 1)].symbol(), "本");
+    assert_eq!(buf[(4, 1)].symbol(), "語");
+    assert_eq!(buf[(7, 1)].symbol(), "x");
+
+    // half of a character doesn't fit at the edge
+    let mut buf = Buffer::empty(Rect::new(0, 0, 5, 2));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(buf[(2, 1)].symbol(), "本");
+    assert_eq!(buf[(4, 1)].symbol(), " ");
+  }
+
+  #[test]
+  fn test_row_heights() {
+    let column_widths = vec![3];
+    let rows = (0..6).map(|i| cells([format!("r{i}\n..")])).collect::<Vec<_>>();
+    let table = Prebuilt(cells(["id"]), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0).row_heights(vec![1, 2, 1, 2, 1, 2]);
+    scrollable.number_rows(Some(1)).go_to_row(1);
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 6, 6));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(line(&buf, 1), "2 r1  ");
+    assert_eq!(line(&buf, 2), "  ..  ");
+    assert_eq!(line(&buf, 3), "3 r2  ");
+    assert_eq!(line(&buf, 4), "4 r3  ");
+
+    scrollable.parent_area = buf.area;
+    assert_eq!(scrollable.cell_at(Position::new(2, 2)), Some((0, 1)));
+    assert_eq!(scrollable.cell_at(Position::new(2, 5)), Some((0, 3)));
+
+    // paging moves by as many rows as fit in half a page of 4 lines
+    scrollable.pg_height = 9;
+    scrollable.half_pg_down();
+    assert_eq!(scrollable.y_offset, 4);
+    scrollable.half_pg_up();
+    assert_eq!(scrollable.y_offset, 2);
+    // and full pages by as many as fit in all 9
+    scrollable.pg_down();
+    assert_eq!(scrollable.y_offset, 5);
+    scrollable.pg_up();
+    assert_eq!(scrollable.y_offset, 0);
+  }
+
+  #[test]
+  fn test_row_numbers() {
+    let column_widths = vec![3];
+    let rows = (0..12).map(|i| cells([format!("r{i}")])).collect::<Vec<_>>();
+    let table = Prebuilt(cells(["id"]), rows);
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(column_widths, table.1.len(), 1, 0);
+    scrollable.number_rows(Some(101)).go_to_row(8);
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 8, 5));
+    render(&scrollable, &table, &mut buf);
+    assert_eq!(line(&buf, 0), "    id  ");
+    assert_eq!(line(&buf, 1), "109 r8  ");
+    assert_eq!(line(&buf, 4), "112 r11 ");
+
+    scrollable.parent_area = buf.area;
+    assert_eq!(scrollable.cell_at(Position::new(1, 2)), None);
+    assert_eq!(scrollable.cell_at(Position::new(4, 2)), Some((0, 9)));
+  }
+
+  #[test]
+  fn test_only_cells_in_view_are_built() {
+    struct Counted(std::cell::Cell<usize>);
+
+    impl TableCells for Counted {
+      fn header(&self, column: usize, _width: u16) -> Cell<'_> {
+        Cell::from(format!("c{column}"))
+      }
+
+      fn cell(&self, row: usize, column: usize, _width: u16) -> Cell<'_> {
+        self.0.set(self.0.get() + 1);
+        Cell::from(format!("{row}.{column}"))
+      }
+    }
+
+    let cells = Counted(std::cell::Cell::new(0));
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(vec![8; 100], 1_000_000, 1, 0);
+    scrollable.go_to_row(500_000);
+
+    let mut buf = Buffer::empty(Rect::new(0, 0, 17, 4));
+    render(&scrollable, &cells, &mut buf);
+    assert_eq!(line(&buf, 1), "500000.0 500000.1");
+    assert_eq!(line(&buf, 3), "500002.0 500002.1");
+    // three rows of the two columns in view
+    assert_eq!(cells.0.get(), 6);
+    assert_eq!(scrollable.max_y_offset, 999_999);
+  }
 }
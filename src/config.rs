@@ -5,9 +5,17 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
 use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 use ratatui::style::{Color, Modifier, Style};
-use serde::{Deserialize, de::Deserializer};
-
-use crate::{action::Action, cli::Driver, focus::Focus, keyring::Password};
+use serde::{Deserialize, Serialize, de::Deserializer};
+
+use crate::{
+  action::Action,
+  cli::Driver,
+  database::StartupSql,
+  focus::Focus,
+  keyring::{Password, find_password},
+  theme::{Theme, ThemeConfig},
+  timezone::DisplayZone,
+};
 
 // percent encoding for passwords in connection strings
 const FRAGMENT: &AsciiSet = &CONTROLS
@@ -80,6 +88,18 @@ pub struct DatabaseConnection {
   pub connection: ConnectionString,
   #[serde(default)]
   pub default: bool,
+  // shown next to the connection, like `production` or `staging`
+  #[serde(default)]
+  pub tag: Option<String>,
+  // set as the search_path of each connection, like `app` or `app, public`
+  #[serde(default)]
+  pub schema: Option<String>,
+  // run on each new connection, from a file or as a list of statements
+  #[serde(default)]
+  pub startup_sql: Option<StartupSql>,
+  // fails the connection when the startup sql fails, instead of reporting it
+  #[serde(default)]
+  pub startup_sql_required: bool,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -93,7 +113,16 @@ pub struct Config {
   #[serde(default)]
   pub settings: Settings,
   #[serde(default)]
+  pub export: ExportOptions,
+  #[serde(default)]
+  pub display: DisplayOptions,
+  #[serde(default)]
   pub db: HashMap<String, DatabaseConnection>,
+  #[serde(default, rename = "theme")]
+  pub theme_config: ThemeConfig,
+  // built from `theme_config` once the config is loaded
+  #[serde(skip)]
+  pub theme: Theme,
 }
 
 impl StructuredConnection {
@@ -117,8 +146,28 @@ impl StructuredConnection {
   }
 }
 
+impl DatabaseConnection {
+  /// The url to connect with, using the password saved in the keyring if there
+  /// is one. Otherwise the driver falls back to its own sources, like
+  /// `PGPASSWORD` or `.pgpass` for postgres, since there's no way to prompt.
+  pub fn url(&self, name: &str) -> Result<String> {
+    match &self.connection {
+      ConnectionString::Raw { connection_string } => Ok(connection_string.clone()),
+      ConnectionString::Structured { details } => {
+        let password = find_password(name, &details.username)?.unwrap_or_default();
+        details.connection_string(self.driver, password)
+      },
+    }
+  }
+}
+
 impl Config {
   pub fn new() -> Result<Self, config::ConfigError> {
+    Self::with_default_theme(crate::theme::detected_name())
+  }
+
+  // the theme named `default_theme` is used when the config doesn't name one
+  fn with_default_theme(default_theme: &str) -> Result<Self, config::ConfigError> {
     let default_config: Config = toml::from_str(CONFIG).unwrap();
     let data_dir = crate::utils::get_data_dir();
     let config_dir = crate::utils::get_config_dir();
@@ -147,6 +196,8 @@ impl Config {
     }
 
     let mut cfg: Self = builder.build()?.try_deserialize()?;
+    cfg.theme = Theme::load(&cfg.theme_config, &config_dir, default_theme)
+      .map_err(|e| config::ConfigError::Message(e.to_string()))?;
 
     for (focus, default_bindings) in default_config.keybindings.iter() {
       let user_bindings = cfg.keybindings.entry(*focus).or_default();
@@ -160,6 +211,13 @@ impl Config {
         user_styles.entry(style_key.clone()).or_insert_with(|| *style);
       }
     }
+    // type names are matched without regard to case, so a user's rule for
+    // a type replaces the default one however it's written
+    for (type_name, rule) in default_config.display.types.iter() {
+      if cfg.display.types.keys().all(|name| !name.eq_ignore_ascii_case(type_name)) {
+        cfg.display.types.insert(type_name.clone(), rule.clone());
+      }
+    }
     match cfg.settings.mouse_mode {
       Some(_) => {},
       None => {
@@ -172,17 +230,211 @@ impl Config {
         cfg.settings.data_compact_columns = default_config.settings.data_compact_columns;
       },
     };
+    match cfg.settings.data_max_column_width {
+      Some(_) => {},
+      None => {
+        cfg.settings.data_max_column_width = default_config.settings.data_max_column_width;
+      },
+    };
+    match cfg.settings.data_align_numbers {
+      Some(_) => {},
+      None => {
+        cfg.settings.data_align_numbers = default_config.settings.data_align_numbers;
+      },
+    };
+    match cfg.settings.data_row_numbers {
+      Some(_) => {},
+      None => {
+        cfg.settings.data_row_numbers = default_config.settings.data_row_numbers;
+      },
+    };
+    match cfg.settings.data_inline_json {
+      Some(_) => {},
+      None => {
+        cfg.settings.data_inline_json = default_config.settings.data_inline_json;
+      },
+    };
+    match cfg.settings.page_size {
+      Some(_) => {},
+      None => {
+        cfg.settings.page_size = default_config.settings.page_size;
+      },
+    };
+    match cfg.settings.stream_results {
+      Some(_) => {},
+      None => {
+        cfg.settings.stream_results = default_config.settings.stream_results;
+      },
+    };
+    match cfg.settings.chord_timeout_ms {
+      Some(_) => {},
+      None => {
+        cfg.settings.chord_timeout_ms = default_config.settings.chord_timeout_ms;
+      },
+    };
+    match cfg.settings.display_timezone {
+      Some(_) => {},
+      None => {
+        cfg.settings.display_timezone = default_config.settings.display_timezone.clone();
+      },
+    };
+    if let Some(zone) = &cfg.settings.display_timezone {
+      zone.parse::<DisplayZone>().map_err(|e| config::ConfigError::Message(e.to_string()))?;
+    }
+    match cfg.settings.null_placeholder {
+      Some(_) => {},
+      None => {
+        cfg.settings.null_placeholder = default_config.settings.null_placeholder.clone();
+      },
+    };
     match cfg.settings.data_row_spacer {
       Some(_) => {},
       None => {
         cfg.settings.data_row_spacer = default_config.settings.data_row_spacer;
       },
     };
+    match cfg.settings.data_wrap_text {
+      Some(_) => {},
+      None => {
+        cfg.settings.data_wrap_text = default_config.settings.data_wrap_text;
+      },
+    };
+    match cfg.settings.data_wrap_max_lines {
+      Some(_) => {},
+      None => {
+        cfg.settings.data_wrap_max_lines = default_config.settings.data_wrap_max_lines;
+      },
+    };
+    match cfg.settings.qualify_tables {
+      Some(_) => {},
+      None => {
+        cfg.settings.qualify_tables = default_config.settings.qualify_tables;
+      },
+    };
+    match cfg.settings.history_max_entries {
+      Some(_) => {},
+      None => {
+        cfg.settings.history_max_entries = default_config.settings.history_max_entries;
+      },
+    };
+    match cfg.settings.confirm_destructive {
+      Some(_) => {},
+      None => {
+        cfg.settings.confirm_destructive = default_config.settings.confirm_destructive;
+      },
+    };
+    match cfg.settings.menu_table_stats {
+      Some(_) => {},
+      None => {
+        cfg.settings.menu_table_stats = default_config.settings.menu_table_stats;
+      },
+    };
+    match cfg.settings.menu_system_schemas {
+      Some(_) => {},
+      None => {
+        cfg.settings.menu_system_schemas = default_config.settings.menu_system_schemas;
+      },
+    };
+    match cfg.settings.vim_mode {
+      Some(_) => {},
+      None => {
+        cfg.settings.vim_mode = default_config.settings.vim_mode;
+      },
+    };
+    match cfg.settings.editor_auto_pairs {
+      Some(_) => {},
+      None => {
+        cfg.settings.editor_auto_pairs = default_config.settings.editor_auto_pairs;
+      },
+    };
+    match cfg.settings.slow_query_ms {
+      Some(_) => {},
+      None => {
+        cfg.settings.slow_query_ms = default_config.settings.slow_query_ms;
+      },
+    };
+    match cfg.settings.max_rows_fetched {
+      Some(_) => {},
+      None => {
+        cfg.settings.max_rows_fetched = default_config.settings.max_rows_fetched;
+      },
+    };
+    match cfg.settings.query_log {
+      Some(_) => {},
+      None => {
+        cfg.settings.query_log = default_config.settings.query_log;
+      },
+    };
+    match cfg.settings.copy_row_delimiter {
+      Some(_) => {},
+      None => {
+        cfg.settings.copy_row_delimiter = default_config.settings.copy_row_delimiter;
+      },
+    };
+    match cfg.settings.editor_autosave {
+      Some(_) => {},
+      None => {
+        cfg.settings.editor_autosave = default_config.settings.editor_autosave;
+      },
+    };
+    match cfg.settings.layout_split {
+      Some(_) => {},
+      None => {
+        cfg.settings.layout_split = default_config.settings.layout_split;
+      },
+    };
+    // a pane squeezed to nothing can't be used or even seen, so percentages
+    // outside of this range fall back to the default
+    const LAYOUT_PERCENT_RANGE: std::ops::RangeInclusive<u16> = 10..=90;
+    match cfg.settings.layout_menu_width {
+      Some(width) if LAYOUT_PERCENT_RANGE.contains(&width) => {},
+      width => {
+        if let Some(width) = width {
+          log::warn!("layout_menu_width of {width}% is not between 10 and 90, using the default");
+        }
+        cfg.settings.layout_menu_width = default_config.settings.layout_menu_width;
+      },
+    };
+    match cfg.settings.layout_editor_size {
+      Some(height) if LAYOUT_PERCENT_RANGE.contains(&height) => {},
+      height => {
+        if let Some(height) = height {
+          log::warn!("layout_editor_size of {height}% is not between 10 and 90, using the default");
+        }
+        cfg.settings.layout_editor_size = default_config.settings.layout_editor_size;
+      },
+    };
+    // a rate of zero or less can't be waited on, and a high one keeps the cpu
+    // busy redrawing, so rates are kept to these ranges
+    cfg.settings.tick_rate =
+      checked_rate("tick_rate", cfg.settings.tick_rate, TICK_RATE_RANGE).or(default_config.settings.tick_rate);
+    cfg.settings.frame_rate =
+      checked_rate("frame_rate", cfg.settings.frame_rate, FRAME_RATE_RANGE).or(default_config.settings.frame_rate);
 
     Ok(cfg)
   }
 }
 
+const TICK_RATE_RANGE: std::ops::RangeInclusive<f64> = 1.0..=30.0;
+const FRAME_RATE_RANGE: std::ops::RangeInclusive<f64> = 1.0..=60.0;
+
+// a rate per second kept within `range`, or `None` for one that isn't a
+// positive number, so the default is used
+fn checked_rate(name: &str, rate: Option<f64>, range: std::ops::RangeInclusive<f64>) -> Option<f64> {
+  match rate? {
+    rate if range.contains(&rate) => Some(rate),
+    rate if rate.is_finite() && rate > 0.0 => {
+      let clamped = rate.clamp(*range.start(), *range.end());
+      log::warn!("{name} of {rate} is not between {} and {}, using {clamped}", range.start(), range.end());
+      Some(clamped)
+    },
+    rate => {
+      log::warn!("{name} of {rate} is not a positive number, using the default");
+      None
+    },
+  }
+}
+
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
 pub struct KeyBindings(pub HashMap<Focus, HashMap<Vec<KeyEvent>, Action>>);
 
@@ -207,26 +459,43 @@ impl<'de> Deserialize<'de> for KeyBindings {
 }
 
 fn parse_key_event(raw: &str) -> Result<KeyEvent, String> {
-  let raw_lower = raw.to_ascii_lowercase();
-  let (remaining, modifiers) = extract_modifiers(&raw_lower);
+  let (remaining, modifiers) = extract_modifiers(raw);
   parse_key_code_with_modifiers(remaining, modifiers)
 }
 
+/// The key as bindings are looked up by. Terminals report the same key with
+/// different lock-key state, press kinds, and with or without shift on
+/// capital letters, so those are made to match how the config's keys parse.
+pub fn normalize_key_event(key: KeyEvent) -> KeyEvent {
+  let mut modifiers = key.modifiers;
+  let code = match key.code {
+    KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+    code => code,
+  };
+  match code {
+    KeyCode::Char(c) if c.is_ascii_uppercase() => modifiers.insert(KeyModifiers::SHIFT),
+    KeyCode::BackTab => modifiers.insert(KeyModifiers::SHIFT),
+    _ => {},
+  }
+  KeyEvent::new(code, modifiers)
+}
+
 fn extract_modifiers(raw: &str) -> (&str, KeyModifiers) {
   let mut modifiers = KeyModifiers::empty();
   let mut current = raw;
 
   loop {
+    let lower = current.to_ascii_lowercase();
     match current {
-      rest if rest.starts_with("ctrl-") => {
+      rest if lower.starts_with("ctrl-") => {
         modifiers.insert(KeyModifiers::CONTROL);
         current = &rest[5..];
       },
-      rest if rest.starts_with("alt-") => {
+      rest if lower.starts_with("alt-") => {
         modifiers.insert(KeyModifiers::ALT);
         current = &rest[4..];
       },
-      rest if rest.starts_with("shift-") => {
+      rest if lower.starts_with("shift-") => {
         modifiers.insert(KeyModifiers::SHIFT);
         current = &rest[6..];
       },
@@ -238,7 +507,7 @@ fn extract_modifiers(raw: &str) -> (&str, KeyModifiers) {
 }
 
 fn parse_key_code_with_modifiers(raw: &str, mut modifiers: KeyModifiers) -> Result<KeyEvent, String> {
-  let c = match raw {
+  let c = match raw.to_ascii_lowercase().as_str() {
     "esc" => KeyCode::Esc,
     "enter" => KeyCode::Enter,
     "left" => KeyCode::Left,
@@ -272,11 +541,15 @@ fn parse_key_code_with_modifiers(raw: &str, mut modifiers: KeyModifiers) -> Resu
     "hyphen" => KeyCode::Char('-'),
     "minus" => KeyCode::Char('-'),
     "tab" => KeyCode::Tab,
-    c if c.len() == 1 => {
-      let mut c = c.chars().next().unwrap();
+    // single characters keep their case, so `<G>` is shift-g rather than g
+    _ if raw.chars().count() == 1 => {
+      let mut c = raw.chars().next().unwrap();
       if modifiers.contains(KeyModifiers::SHIFT) {
         c = c.to_ascii_uppercase();
       }
+      if c.is_ascii_uppercase() {
+        modifiers.insert(KeyModifiers::SHIFT);
+      }
       KeyCode::Char(c)
     },
     _ => return Err(format!("Unable to parse {raw}")),
@@ -302,7 +575,7 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
     KeyCode::Delete => "delete",
     KeyCode::Insert => "insert",
     KeyCode::F(c) => {
-      char = format!("f({c})");
+      char = format!("f{c}");
       &char
     },
     KeyCode::Char(' ') => "space",
@@ -378,7 +651,163 @@ pub fn parse_key_sequence(raw: &str) -> Result<Vec<KeyEvent>, String> {
 pub struct Settings {
   pub mouse_mode: Option<bool>,
   pub data_compact_columns: Option<bool>,
+  pub data_max_column_width: Option<u16>,
+  pub data_row_numbers: Option<bool>,
+  pub data_align_numbers: Option<bool>,
   pub data_row_spacer: Option<bool>,
+  pub data_wrap_text: Option<bool>,
+  pub data_wrap_max_lines: Option<u16>,
+  pub null_placeholder: Option<String>,
+  pub copy_row_delimiter: Option<CopyDelimiter>,
+  pub display_timezone: Option<String>,
+  pub data_inline_json: Option<bool>,
+  pub qualify_tables: Option<QualifyTables>,
+  pub page_size: Option<u64>,
+  pub stream_results: Option<bool>,
+  pub menu_system_schemas: Option<bool>,
+  pub menu_table_stats: Option<bool>,
+  pub confirm_destructive: Option<bool>,
+  pub history_max_entries: Option<usize>,
+  pub layout_menu_width: Option<u16>,
+  pub layout_editor_size: Option<u16>,
+  pub layout_split: Option<LayoutSplit>,
+  pub editor_autosave: Option<EditorAutosave>,
+  pub slow_query_ms: Option<u64>,
+  pub query_log: Option<bool>,
+  pub max_rows_fetched: Option<u64>,
+  pub vim_mode: Option<bool>,
+  pub editor_auto_pairs: Option<bool>,
+  pub chord_timeout_ms: Option<u64>,
+  pub tick_rate: Option<f64>,
+  pub frame_rate: Option<f64>,
+  pub scripts_dir: Option<PathBuf>,
+}
+
+// csv file export options. these are the defaults for each export, and can
+// be changed for a single export from the confirmation prompt.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+  pub delimiter: char,
+  pub quote: char,
+  pub include_header: bool,
+  pub line_terminator: LineTerminator,
+  // a utf-8 byte order mark, so excel doesn't mangle non-ascii text
+  pub bom: bool,
+  // written for SQL NULL in text formats, and when copying from the data table
+  pub null: String,
+}
+
+impl Default for ExportOptions {
+  fn default() -> Self {
+    Self {
+      delimiter: ',',
+      quote: '"',
+      include_header: true,
+      line_terminator: LineTerminator::default(),
+      bom: false,
+      null: "NULL".to_string(),
+    }
+  }
+}
+
+// how values are shown in the data table, beyond the theme's colors
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct DisplayOptions {
+  // `✓` and `✗` in place of true and false
+  pub boolean_symbols: bool,
+  // styles for the values of columns by type name, or `values` to give each
+  // distinct value its own color
+  pub types: HashMap<String, String>,
+}
+
+/// How the values of a column are styled by a `[display.types]` rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeDisplay {
+  Style(Style),
+  // each distinct value gets a color of its own, for enums and status columns
+  Values,
+}
+
+impl DisplayOptions {
+  /// The rule for columns of a type, matched without regard to case.
+  pub fn type_display(&self, type_name: &str) -> Option<TypeDisplay> {
+    let (_, rule) = self.types.iter().find(|(name, _)| name.eq_ignore_ascii_case(type_name))?;
+    match rule.trim() {
+      "values" => Some(TypeDisplay::Values),
+      style => Some(TypeDisplay::Style(parse_style(style))),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineTerminator {
+  #[default]
+  Lf,
+  Crlf,
+}
+
+// what happens to the contents of the query tabs between runs of rainfrog
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorAutosave {
+  // saved as they're edited, and put back at startup
+  #[default]
+  Restore,
+  // saved as they're edited, and put back at startup if confirmed
+  Ask,
+  Off,
+}
+
+// what separates the values of a row copied from the data table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyDelimiter {
+  // pastes into separate cells of a spreadsheet
+  #[default]
+  Tab,
+  Comma,
+}
+
+impl CopyDelimiter {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      CopyDelimiter::Tab => "\t",
+      CopyDelimiter::Comma => ", ",
+    }
+  }
+}
+
+// how the editor and results share the space to the right of the menu
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutSplit {
+  // editor above results
+  #[default]
+  Vertical,
+  // editor beside results
+  Horizontal,
+}
+
+impl LayoutSplit {
+  pub fn toggled(self) -> Self {
+    match self {
+      LayoutSplit::Vertical => LayoutSplit::Horizontal,
+      LayoutSplit::Horizontal => LayoutSplit::Vertical,
+    }
+  }
+}
+
+// what to do with unqualified table names that only exist outside the search path
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualifyTables {
+  #[default]
+  Off,
+  Warn,
+  Auto,
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
@@ -425,7 +854,8 @@ fn process_color_string(color_str: &str) -> (String, Modifier) {
     .replace("bright ", "")
     .replace("bold ", "")
     .replace("underline ", "")
-    .replace("inverse ", "");
+    .replace("inverse ", "")
+    .replace("dim ", "");
 
   let mut modifiers = Modifier::empty();
   if color_str.contains("underline") {
@@ -437,6 +867,9 @@ fn process_color_string(color_str: &str) -> (String, Modifier) {
   if color_str.contains("inverse") {
     modifiers |= Modifier::REVERSED;
   }
+  if color_str.contains("dim") {
+    modifiers |= Modifier::DIM;
+  }
 
   (color, modifiers)
 }
@@ -499,10 +932,25 @@ fn parse_color(s: &str) -> Option<Color> {
 
 #[cfg(test)]
 mod tests {
+  use crossterm::event::KeyEventState;
   use pretty_assertions::assert_eq;
 
   use super::*;
 
+  #[test]
+  fn test_type_display() {
+    let display = DisplayOptions {
+      boolean_symbols: false,
+      types: HashMap::from([
+        ("order_status".to_string(), "values".to_string()),
+        ("money".to_string(), "bold yellow".to_string()),
+      ]),
+    };
+    assert_eq!(display.type_display("ORDER_STATUS"), Some(TypeDisplay::Values));
+    assert_eq!(display.type_display("money"), Some(TypeDisplay::Style(parse_style("bold yellow"))));
+    assert_eq!(display.type_display("text"), None);
+  }
+
   #[test]
   fn test_parse_style_default() {
     let style = parse_style("");
@@ -552,15 +1000,57 @@ mod tests {
 
   #[test]
   fn test_config() -> Result<()> {
-    let c = Config::new()?;
+    let c = Config::with_default_theme("light")?;
     assert_eq!(
       c.keybindings.get(&Focus::Menu).unwrap().get(&parse_key_sequence("<q>").unwrap_or_default()).unwrap(),
       &Action::AbortQuery
     );
+    assert_eq!(
+      c.keybindings.get(&Focus::Editor).unwrap().get(&parse_key_sequence("<Ctrl-PageDown>").unwrap_or_default()),
+      Some(&Action::NextQueryTab)
+    );
     assert_eq!(c.settings.mouse_mode, Some(true));
+    assert_eq!(c.settings.qualify_tables, Some(QualifyTables::Off));
+    assert_eq!(c.settings.vim_mode, Some(true));
+    assert_eq!(c.settings.editor_auto_pairs, Some(true));
+    assert_eq!(c.settings.chord_timeout_ms, Some(500));
+    assert_eq!(c.settings.tick_rate, Some(4.0));
+    assert_eq!(c.settings.frame_rate, Some(15.0));
+    assert_eq!(c.settings.menu_system_schemas, Some(false));
+    assert_eq!(c.settings.menu_table_stats, Some(true));
+    assert_eq!(c.settings.page_size, Some(0));
+    assert_eq!(c.settings.stream_results, Some(false));
+    assert_eq!(c.settings.confirm_destructive, Some(true));
+    assert_eq!(c.settings.history_max_entries, Some(500));
+    assert_eq!(c.settings.layout_menu_width, Some(25));
+    assert_eq!(c.settings.layout_editor_size, Some(45));
+    assert_eq!(c.settings.layout_split, Some(LayoutSplit::Vertical));
+    assert_eq!(c.settings.editor_autosave, Some(EditorAutosave::Restore));
+    assert_eq!(c.settings.slow_query_ms, Some(1000));
+    assert_eq!(c.settings.query_log, Some(false));
+    assert_eq!(c.settings.max_rows_fetched, Some(100_000));
+    assert_eq!(c.settings.null_placeholder, Some("∅".to_string()));
+    assert_eq!(c.settings.copy_row_delimiter, Some(CopyDelimiter::Tab));
+    assert_eq!(c.settings.data_wrap_text, Some(false));
+    assert_eq!(c.settings.data_wrap_max_lines, Some(5));
+    assert_eq!(c.export, ExportOptions::default());
+    assert!(!c.display.boolean_symbols);
+    assert_eq!(c.display.type_display("ENUM"), Some(TypeDisplay::Values));
+    assert_eq!(Some(c.theme), Theme::builtin("light"));
     Ok(())
   }
 
+  #[test]
+  fn test_checked_rate() {
+    assert_eq!(checked_rate("frame_rate", None, FRAME_RATE_RANGE), None);
+    assert_eq!(checked_rate("frame_rate", Some(24.0), FRAME_RATE_RANGE), Some(24.0));
+    assert_eq!(checked_rate("frame_rate", Some(1000.0), FRAME_RATE_RANGE), Some(60.0));
+    assert_eq!(checked_rate("tick_rate", Some(0.1), TICK_RATE_RANGE), Some(1.0));
+    for rate in [0.0, -4.0, f64::NAN, f64::INFINITY] {
+      assert_eq!(checked_rate("tick_rate", Some(rate), TICK_RATE_RANGE), None, "Failed for rate: {rate}");
+    }
+  }
+
   #[test]
   fn test_simple_keys() {
     assert_eq!(parse_key_event("a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
@@ -612,4 +1102,45 @@ mod tests {
 
     assert_eq!(parse_key_event("AlT-eNtEr").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT));
   }
+
+  #[test]
+  fn test_uppercase_keys() {
+    let shift_g = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+    assert_eq!(parse_key_event("G").unwrap(), shift_g);
+    assert_eq!(parse_key_event("shift-g").unwrap(), shift_g);
+    assert_eq!(
+      parse_key_event("Ctrl-G").unwrap(),
+      KeyEvent::new(KeyCode::Char('G'), KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+    );
+    assert_ne!(parse_key_event("G").unwrap(), parse_key_event("g").unwrap());
+  }
+
+  #[test]
+  fn test_modified_keys_round_trip() {
+    assert_ne!(parse_key_event("Ctrl-Enter").unwrap(), parse_key_event("Enter").unwrap());
+    assert_ne!(parse_key_event("Alt-Enter").unwrap(), parse_key_event("Ctrl-Enter").unwrap());
+    for raw in
+      ["Ctrl-Enter", "Alt-Enter", "Ctrl-Alt-a", "Ctrl-Shift-Enter", "Shift-G", "F5", "Alt-F12", "Backtab", "Space"]
+    {
+      let key = parse_key_event(raw).unwrap();
+      assert_eq!(parse_key_event(&key_event_to_string(&key)).unwrap(), key, "{raw}");
+    }
+  }
+
+  #[test]
+  fn test_normalize_key_event() {
+    let keymap = parse_key_sequence("<Ctrl-Enter>").unwrap();
+    let mut pressed = KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL);
+    pressed.state = KeyEventState::CAPS_LOCK;
+    assert_eq!(vec![normalize_key_event(pressed)], keymap);
+
+    // terminals differ in whether they report shift along with a capital letter
+    let shift_g = parse_key_event("G").unwrap();
+    assert_eq!(normalize_key_event(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::empty())), shift_g);
+    assert_eq!(normalize_key_event(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::SHIFT)), shift_g);
+    assert_eq!(
+      normalize_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::empty())),
+      parse_key_event("backtab").unwrap()
+    );
+  }
 }
@@ -0,0 +1,203 @@
+// postgres arrays come back in postgres's own text form, like `{1,2,NULL}` or
+// `{{"a b",c},{d,e}}`, which is kept as the value so it can be copied and
+// exported as is. these read it back to show it as a list.
+
+/// An element of an array.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrayElement {
+  Null,
+  Value(String),
+  // an inner array of a multidimensional one
+  Array(Vec<ArrayElement>),
+}
+
+/// Whether values of the type are arrays, like postgres's `INT4[]`.
+pub fn is_array_type(type_name: &str) -> bool {
+  type_name.ends_with("[]")
+}
+
+/// Parses the text form of a postgres array. Returns `None` if the value
+/// isn't one.
+pub fn parse_array(value: &str) -> Option<Vec<ArrayElement>> {
+  // arrays that don't start at 1 are prefixed with their bounds, like `[0:1]={a,b}`
+  let value = match value.starts_with('[') {
+    true => value.split_once('=')?.1,
+    false => value,
+  };
+  let mut chars = value.trim().chars().peekable();
+  let elements = parse_elements(&mut chars)?;
+  chars.next().is_none().then_some(elements)
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn parse_elements(chars: &mut Chars) -> Option<Vec<ArrayElement>> {
+  if chars.next()? != '{' {
+    return None;
+  }
+  let mut elements = vec![];
+  skip_whitespace(chars);
+  if chars.next_if_eq(&'}').is_some() {
+    return Some(elements);
+  }
+  loop {
+    skip_whitespace(chars);
+    elements.push(parse_element(chars)?);
+    skip_whitespace(chars);
+    match chars.next()? {
+      ',' => {},
+      '}' => return Some(elements),
+      _ => return None,
+    }
+  }
+}
+
+fn parse_element(chars: &mut Chars) -> Option<ArrayElement> {
+  match chars.peek()? {
+    '{' => parse_elements(chars).map(ArrayElement::Array),
+    '"' => {
+      chars.next();
+      let mut value = String::new();
+      loop {
+        match chars.next()? {
+          '\\' => value.push(chars.next()?),
+          '"' => return Some(ArrayElement::Value(value)),
+          c => value.push(c),
+        }
+      }
+    },
+    _ => {
+      let mut value = String::new();
+      while let Some(c) = chars.next_if(|c| *c != ',' && *c != '}') {
+        match c {
+          '\\' => value.push(chars.next()?),
+          c => value.push(c),
+        }
+      }
+      // only unquoted NULLs are nulls, so `"NULL"` is the string
+      let value = value.trim_end();
+      match value.eq_ignore_ascii_case("null") {
+        true => Some(ArrayElement::Null),
+        false => Some(ArrayElement::Value(value.to_owned())),
+      }
+    },
+  }
+}
+
+fn skip_whitespace(chars: &mut Chars) {
+  while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// The array on one line, like `{a, b, c}`, with nulls shown as `null`.
+pub fn format_array_inline(elements: &[ArrayElement], null: &str) -> String {
+  let elements = elements
+    .iter()
+    .map(|element| match element {
+      ArrayElement::Null => null.to_owned(),
+      ArrayElement::Value(value) => value.clone(),
+      ArrayElement::Array(inner) => format_array_inline(inner, null),
+    })
+    .collect::<Vec<_>>();
+  format!("{{{}}}", elements.join(", "))
+}
+
+/// The array one element a line, each with its subscript, like `[1] a`. The
+/// elements of inner arrays are indented under their own subscripts.
+pub fn format_array_list(elements: &[ArrayElement], null: &str) -> String {
+  let mut lines = vec![];
+  push_lines(elements, null, 0, &mut lines);
+  lines.join("\n")
+}
+
+fn push_lines(elements: &[ArrayElement], null: &str, depth: usize, lines: &mut Vec<String>) {
+  let indent = "  ".repeat(depth);
+  for (index, element) in elements.iter().enumerate() {
+    let subscript = index + 1;
+    match element {
+      ArrayElement::Null => lines.push(format!("{indent}[{subscript}] {null}")),
+      ArrayElement::Value(value) => lines.push(format!("{indent}[{subscript}] {value}")),
+      ArrayElement::Array(inner) => {
+        lines.push(format!("{indent}[{subscript}]"));
+        push_lines(inner, null, depth + 1, lines);
+      },
+    }
+  }
+}
+
+/// The array as a json array of strings and nulls, nested like the array is.
+/// The elements of a `numeric` array are numbers instead, apart from any that
+/// json can't hold exactly, like `NaN` or more digits than a float keeps.
+pub fn array_to_json(elements: &[ArrayElement], numeric: bool) -> serde_json::Value {
+  serde_json::Value::Array(
+    elements
+      .iter()
+      .map(|element| match element {
+        ArrayElement::Null => serde_json::Value::Null,
+        ArrayElement::Value(value) => match numeric.then(|| exact_number(value)).flatten() {
+          Some(number) => serde_json::Value::Number(number),
+          None => serde_json::Value::String(value.clone()),
+        },
+        ArrayElement::Array(inner) => array_to_json(inner, numeric),
+      })
+      .collect(),
+  )
+}
+
+/// The value as a json number, if it reads back as the same digits, so
+/// numbers too precise for a float stay as they were written.
+pub fn exact_number(value: &str) -> Option<serde_json::Number> {
+  let number = value.parse::<serde_json::Number>().ok()?;
+  let digits = match value.contains('.') {
+    true => value.trim_end_matches('0').trim_end_matches('.'),
+    false => value,
+  };
+  (number.is_i64() || number.is_u64() || number.to_string() == digits).then_some(number)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ArrayElement::*, *};
+
+  fn value(value: &str) -> ArrayElement {
+    Value(value.to_owned())
+  }
+
+  #[test]
+  fn test_parse_array() {
+    assert_eq!(parse_array("{}"), Some(vec![]));
+    assert_eq!(parse_array("{1,2,NULL}"), Some(vec![value("1"), value("2"), Null]));
+    assert_eq!(
+      parse_array(r#"{"a b","c,d","NULL","say \"hi\"",e\,f}"#),
+      Some(vec![value("a b"), value("c,d"), value("NULL"), value(r#"say "hi""#), value("e,f")])
+    );
+    assert_eq!(
+      parse_array("{{1,2},{3,4}}"),
+      Some(vec![Array(vec![value("1"), value("2")]), Array(vec![value("3"), value("4")])])
+    );
+    assert_eq!(parse_array(r#"{"(1,a)","(2,b)"}"#), Some(vec![value("(1,a)"), value("(2,b)")]));
+    assert_eq!(parse_array("[0:1]={a,b}"), Some(vec![value("a"), value("b")]));
+    assert_eq!(parse_array("not an array"), None);
+    assert_eq!(parse_array("{1,2"), None);
+    assert_eq!(parse_array("{1,2}x"), None);
+  }
+
+  #[test]
+  fn test_format_array() {
+    let elements = parse_array(r#"{"a b",NULL,c}"#).unwrap();
+    assert_eq!(format_array_inline(&elements, "∅"), "{a b, ∅, c}");
+    assert_eq!(format_array_list(&elements, "∅"), "[1] a b\n[2] ∅\n[3] c");
+    assert_eq!(array_to_json(&elements, false), serde_json::json!(["a b", null, "c"]));
+
+    let nested = parse_array("{{1,2},{3,4}}").unwrap();
+    assert_eq!(format_array_inline(&nested, "∅"), "{{1, 2}, {3, 4}}");
+    assert_eq!(format_array_list(&nested, "∅"), "[1]\n  [1] 1\n  [2] 2\n[2]\n  [1] 3\n  [2] 4");
+    assert_eq!(array_to_json(&nested, false), serde_json::json!([["1", "2"], ["3", "4"]]));
+    assert_eq!(array_to_json(&nested, true), serde_json::json!([[1, 2], [3, 4]]));
+
+    let numbers = parse_array("{-1.50,NaN,NULL,0.1,123456789012345678901234567890.5,18446744073709551615}").unwrap();
+    assert_eq!(
+      array_to_json(&numbers, true),
+      serde_json::json!([-1.5, "NaN", null, 0.1, "123456789012345678901234567890.5", 18446744073709551615_u64])
+    );
+  }
+}
@@ -0,0 +1,98 @@
+// postgres composites (row types) come back in their text form, like
+// `(1,"a b",)`, which is kept as the value. these read it back to show each
+// field on its own, named by the field names the column's header keeps.
+
+/// The name of each of a composite's fields, falling back to postgres's own
+/// `f1`, `f2`... for fields the names given don't cover.
+fn field_names(names: &[String], count: usize) -> Vec<String> {
+  (0..count).map(|i| names.get(i).cloned().unwrap_or_else(|| format!("f{}", i + 1))).collect()
+}
+
+/// Parses the text form of a composite into its fields, with `None` for
+/// nulls. Returns `None` if the value isn't one.
+pub fn parse_composite(value: &str) -> Option<Vec<Option<String>>> {
+  let mut chars = value.strip_prefix('(')?.strip_suffix(')')?.chars().peekable();
+  let mut fields = vec![];
+  loop {
+    // a field with nothing in it is null, while `""` is an empty string
+    let mut field: Option<String> = None;
+    while let Some(c) = chars.next_if(|c| *c != ',') {
+      let text = field.get_or_insert_with(String::new);
+      match c {
+        '"' => loop {
+          match chars.next()? {
+            '"' if chars.next_if_eq(&'"').is_some() => text.push('"'),
+            '"' => break,
+            '\\' => text.push(chars.next()?),
+            c => text.push(c),
+          }
+        },
+        '\\' => text.push(chars.next()?),
+        c => text.push(c),
+      }
+    }
+    fields.push(field);
+    if chars.next().is_none() {
+      return Some(fields);
+    }
+  }
+}
+
+/// The composite on one line, like `(1, a b, null)`.
+pub fn format_composite_inline(fields: &[Option<String>], null: &str) -> String {
+  let fields = fields.iter().map(|field| field.as_deref().unwrap_or(null)).collect::<Vec<_>>();
+  format!("({})", fields.join(", "))
+}
+
+/// The composite one field a line, like `street: Main St`.
+pub fn format_composite_list(names: &[String], fields: &[Option<String>], null: &str) -> String {
+  let names = field_names(names, fields.len());
+  let lines = names.iter().zip(fields).map(|(name, field)| format!("{name}: {}", field.as_deref().unwrap_or(null)));
+  lines.collect::<Vec<_>>().join("\n")
+}
+
+/// The composite as the text of a json object of strings and nulls, keyed by
+/// field name in the order the type declares them.
+// written by hand rather than with serde_json's map, which sorts its keys.
+// composites nested in it are left as text, since in the text form they look
+// just like a string that happens to be in parentheses
+pub fn composite_to_json(names: &[String], fields: &[Option<String>]) -> String {
+  let names = field_names(names, fields.len());
+  let members = names.iter().zip(fields).map(|(name, field)| {
+    let value = field.as_ref().map_or(serde_json::Value::Null, |field| serde_json::Value::String(field.clone()));
+    format!("{}:{value}", serde_json::Value::String(name.clone()))
+  });
+  format!("{{{}}}", members.collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fields(fields: &[Option<&str>]) -> Vec<Option<String>> {
+    fields.iter().map(|field| field.map(String::from)).collect()
+  }
+
+  #[test]
+  fn test_parse_composite() {
+    assert_eq!(parse_composite("(1,a)"), Some(fields(&[Some("1"), Some("a")])));
+    assert_eq!(parse_composite(r#"(1,"a, ""b""",,"")"#), Some(fields(&[Some("1"), Some(r#"a, "b""#), None, Some("")])));
+    assert_eq!(parse_composite(r#"("(1,x)",2)"#), Some(fields(&[Some("(1,x)"), Some("2")])));
+    assert_eq!(parse_composite("()"), Some(fields(&[None])));
+    assert_eq!(parse_composite("not a composite"), None);
+    assert_eq!(parse_composite(r#"("unterminated)"#), None);
+  }
+
+  #[test]
+  fn test_format_composite() {
+    let value = parse_composite(r#"("Main St",,"(1,x)")"#).unwrap();
+    let names = ["street", "city", "location"].map(String::from);
+    assert_eq!(format_composite_inline(&value, "∅"), "(Main St, ∅, (1,x))");
+    assert_eq!(format_composite_list(&names, &value, "NULL"), "street: Main St\ncity: NULL\nlocation: (1,x)");
+    // fields keep the type's order rather than being sorted
+    assert_eq!(composite_to_json(&names, &value), r#"{"street":"Main St","city":null,"location":"(1,x)"}"#);
+    assert_eq!(composite_to_json(&names[..1], &value), r#"{"street":"Main St","f2":null,"f3":"(1,x)"}"#);
+    let quoted = parse_composite(r#"("say ""hi""")"#).unwrap();
+    assert_eq!(composite_to_json(&[r#"the "word""#.to_owned()], &quoted), r#"{"the \"word\"":"say \"hi\""}"#);
+  }
+}
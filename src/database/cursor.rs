@@ -0,0 +1,107 @@
+use color_eyre::eyre::Result;
+
+use super::{DbError, Rows, paginate};
+use crate::cli::Driver;
+
+/// A select whose rows are fetched a page at a time, so a large table can be
+/// shown before all of it has been read. Drivers with server-side cursors
+/// keep the query open between pages; the rest fetch each page with a limit
+/// and offset.
+#[derive(Clone, Debug)]
+pub struct Cursor {
+  pub query: String,
+  pub page_size: u64,
+  pub server_side: bool,
+  // rows fetched so far
+  pub fetched: u64,
+  // whether a page is being fetched
+  pub fetching: bool,
+  // the error that ended the cursor early, if one did
+  pub error: Option<DbError>,
+  exhausted: bool,
+}
+
+impl Cursor {
+  pub fn new(query: String, page_size: u64) -> Self {
+    Self { query, page_size, server_side: false, fetched: 0, fetching: false, error: None, exhausted: false }
+  }
+
+  /// Whether there may be rows past the ones fetched so far.
+  pub fn has_more(&self) -> bool {
+    !self.exhausted && self.page_size > 0
+  }
+
+  /// The query for the next page, for drivers without server-side cursors.
+  /// `None` once the cursor is exhausted or if the query can't be paged.
+  pub fn next_page_query(&self, driver: Driver) -> Option<String> {
+    match self.has_more() {
+      true => paginate(&self.query, driver, self.page_size, self.fetched),
+      false => None,
+    }
+  }
+
+  /// Records the results of fetching a page. A page that comes back short
+  /// is the last one, and an error ends the cursor.
+  pub fn record(&mut self, results: &Result<Rows>) {
+    self.fetching = false;
+    match results {
+      Ok(rows) => {
+        let count = rows.rows.len() as u64;
+        self.fetched = self.fetched.saturating_add(count);
+        self.exhausted = count < self.page_size;
+      },
+      Err(e) => {
+        self.error = Some(match e.downcast_ref::<DbError>() {
+          Some(e) => e.clone(),
+          None => DbError { message: e.to_string(), query: self.query.clone(), ..Default::default() },
+        });
+        self.exhausted = true;
+      },
+    }
+  }
+
+  /// Stops fetching, as when the fetch of a page was cancelled.
+  pub fn close(&mut self) {
+    self.fetching = false;
+    self.exhausted = true;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use color_eyre::eyre::{self, eyre};
+
+  use super::*;
+
+  fn rows(count: usize) -> Result<Rows> {
+    Ok(Rows { headers: vec![], rows: vec![vec!["1".to_owned()]; count], rows_affected: None })
+  }
+
+  #[test]
+  fn test_cursor_pages() {
+    let mut cursor = Cursor::new("select * from users".to_owned(), 100);
+    assert_eq!(cursor.next_page_query(Driver::Postgres).as_deref(), Some("select * from users LIMIT 100 OFFSET 0"));
+    cursor.fetching = true;
+    cursor.record(&rows(100));
+    assert!(!cursor.fetching);
+    assert!(cursor.has_more());
+    assert_eq!(cursor.next_page_query(Driver::Postgres).as_deref(), Some("select * from users LIMIT 100 OFFSET 100"));
+    cursor.record(&rows(40));
+    assert_eq!(cursor.fetched, 140);
+    assert!(!cursor.has_more());
+    assert_eq!(cursor.next_page_query(Driver::Postgres), None);
+  }
+
+  #[test]
+  fn test_cursor_error() {
+    let mut cursor = Cursor::new("select * from users".to_owned(), 100);
+    let error = DbError { message: "cursor does not exist".to_owned(), ..Default::default() };
+    cursor.record(&Err(eyre::Report::new(error.clone())));
+    assert!(!cursor.has_more());
+    assert_eq!(cursor.error, Some(error));
+
+    let mut cursor = Cursor::new("select * from users".to_owned(), 100);
+    cursor.record(&Err(eyre!("connection reset")));
+    assert_eq!(cursor.error.map(|e| e.message), Some("connection reset".to_owned()));
+  }
+}
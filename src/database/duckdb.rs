@@ -1,7 +1,7 @@
 use std::{
-  fmt::Write,
   io::{self, Write as _},
   string::String,
+  sync::Arc,
 };
 
 use async_trait::async_trait;
@@ -11,10 +11,15 @@ use duckdb::{
   Config, Connection,
   types::{OrderedMap, TimeUnit, Value as DuckValue},
 };
+use tokio::sync::Mutex;
 
 use crate::cli::{Cli, Driver};
 
-use super::{Database, DbTaskResult, Header, Headers, QueryResultsWithMetadata, QueryTask, Rows};
+use super::{
+  ConnectionInfo, Database, DbTaskResult, ExportTask, FOREIGN_KEY_ICON, Header, Headers, Interval, NULL,
+  PRIMARY_KEY_ICON, QueryResultsWithMetadata, QueryTask, RowSink, Rows, Startup, TableStatsTask, bytes_to_hex,
+  create_statements, quote_string,
+};
 
 enum DuckDbTask {
   Query(QueryTask),
@@ -24,26 +29,50 @@ enum DuckDbTask {
 pub struct DuckDbDriver {
   connection: Option<Connection>,
   task: Option<DuckDbTask>,
+  // the clone of the connection the statements of a script run on, when one is pinned
+  pinned_conn: Option<Arc<Mutex<Connection>>>,
+  path: Option<String>,
+  // the `max_rows_fetched` setting
+  max_rows: u64,
+  startup: Startup,
 }
 
 #[async_trait(?Send)]
 impl Database for DuckDbDriver {
   async fn init(&mut self, args: Cli) -> Result<()> {
+    // each query runs on a clone of the connection, which doesn't share its session
+    if args.startup_sql.is_some() {
+      self.startup.report("startup sql isn't supported for duckdb connections".to_owned());
+    }
     let (path, config) = super::DuckDbDriver::build_connection_opts(args)?;
-    let conn = Connection::open_with_flags(path, config)?;
+    let conn = Connection::open_with_flags(&path, config)?;
     self.connection = Some(conn);
+    self.path = Some(path);
     Ok(())
   }
 
+  fn take_startup_errors(&self) -> Vec<String> {
+    self.startup.take_errors()
+  }
+
   // since it's possible for raw_sql to execute multiple queries in a single string,
   // we only execute the first one and then drop the rest.
   async fn start_query(&mut self, query: String, bypass_parser: bool) -> Result<()> {
     let (first_query, statement_type) = super::get_first_query(query.clone(), Driver::DuckDb)?;
     // since Connection isn't Send/Sync, we need to clone it for each query:
     // https://github.com/duckdb/duckdb-rs/issues/378
-    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    let connection = match &self.pinned_conn {
+      Some(connection) => connection.clone(),
+      None => Arc::new(Mutex::new(self.connection.as_ref().unwrap().try_clone()?)),
+    };
+    let count_only = super::returns_count_only(&statement_type);
+    let max_rows = self.max_rows;
     self.task = Some(DuckDbTask::Query(tokio::spawn(async move {
-      let results = run_query(connection, first_query).await;
+      let connection = connection.lock().await;
+      let results = match count_only {
+        true => run_execute(&connection, &first_query),
+        false => run_query_with_cap(&connection, &first_query, max_rows),
+      };
       match results {
         Ok(rows) => QueryResultsWithMetadata { results: Ok(rows), statement_type: Some(statement_type) },
         Err(e) => QueryResultsWithMetadata { results: Err(e), statement_type: Some(statement_type) },
@@ -52,6 +81,18 @@ impl Database for DuckDbDriver {
     Ok(())
   }
 
+  async fn pin_connection(&mut self) -> Result<()> {
+    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    self.pinned_conn = Some(Arc::new(Mutex::new(connection)));
+    Ok(())
+  }
+
+  // closing the clone rolls back any transaction left open on it
+  async fn unpin_connection(&mut self) -> Result<()> {
+    self.pinned_conn = None;
+    Ok(())
+  }
+
   async fn abort_query(&mut self) -> Result<bool> {
     if let Some(task) = self.task.take() {
       match task {
@@ -91,11 +132,41 @@ impl Database for DuckDbDriver {
     Err(eyre::Report::msg("Transactions are not currently supported when using the DuckDB driver"))
   }
 
-  async fn load_menu(&self) -> Result<Rows> {
+  // duckdb runs in process and has no server-side cursors, so queries
+  // are paged with a limit and offset instead
+  async fn start_cursor(&mut self, _query: String, _page_size: u64) -> Result<bool> {
+    Ok(false)
+  }
+
+  async fn fetch_cursor(&mut self, _page_size: u64) -> Result<()> {
+    Err(eyre::Report::msg("Cursors are not currently supported when using the DuckDB driver"))
+  }
+
+  async fn close_cursor(&mut self) -> Result<()> {
+    Ok(())
+  }
+
+  fn start_export(&self, query: String, sink: Box<dyn RowSink>) -> Result<ExportTask> {
     let connection = self.connection.as_ref().unwrap().try_clone()?;
+    Ok(tokio::task::spawn_blocking(move || export_query(connection, query, sink)))
+  }
+
+  fn start_table_stats(&self) -> Result<Option<TableStatsTask>> {
+    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    Ok(Some(tokio::task::spawn_blocking(move || {
+      let mut statement =
+        connection.prepare("select schema_name, table_name, estimated_size, null::bigint from duckdb_tables()")?;
+      fetch_rows(statement.query([])?, 0)
+    })))
+  }
+
+  async fn load_menu(&self, system_schemas: bool) -> Result<Rows> {
+    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    let system_filter = if system_schemas { "true" } else { "table_schema != 'information_schema'" };
     run_query(
       connection,
-      "select table_schema,
+      format!(
+        "select table_schema,
         table_name,
         case
           when table_type = 'BASE TABLE' then 'table'
@@ -103,22 +174,89 @@ impl Database for DuckDbDriver {
           else 'table'
         end as object_kind
       from information_schema.tables
-      where table_schema != 'information_schema'
+      where {system_filter}
       group by table_schema, table_name, table_type
       order by table_schema, object_kind, table_name asc"
-        .to_string(),
+      ),
     )
     .await
   }
 
+  async fn search_path(&self) -> Result<Vec<String>> {
+    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    let rows = run_query(connection, "select unnest(current_schemas(false))".to_string()).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  async fn primary_key(&self, schema: Option<&str>, table: &str) -> Result<Vec<String>> {
+    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    let query = format!(
+      "select unnest(constraint_column_names)
+        from duckdb_constraints()
+        where constraint_type = 'PRIMARY KEY' and schema_name = {} and table_name = {}",
+      schema.map_or_else(|| "current_schema()".to_owned(), |schema| quote_string(schema, Driver::DuckDb)),
+      quote_string(table, Driver::DuckDb)
+    );
+    let rows = run_query(connection, query).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  fn connection_info(&self) -> ConnectionInfo {
+    ConnectionInfo { database: self.path.clone(), ..ConnectionInfo::default() }
+  }
+
+  async fn list_databases(&self) -> Result<(Vec<String>, Option<String>)> {
+    Err(eyre::Report::msg("DuckDB does not support switching databases, but other files can be attached with ATTACH"))
+  }
+
+  async fn use_database(&mut self, name: &str) -> Result<()> {
+    Err(eyre::Report::msg("DuckDB does not support switching databases, but other files can be attached with ATTACH"))
+  }
+
+  async fn load_routine_source(&self, schema: &str, routine: &str, procedure: bool) -> Result<String> {
+    Err(eyre::Report::msg("DuckDB does not support stored routines"))
+  }
+
+  // duckdb keeps the statements the table and its indexes were created with
+  async fn load_create_statement(&self, schema: &str, table: &str) -> Result<String> {
+    let connection = self.connection.as_ref().unwrap().try_clone()?;
+    let (schema, table_name) = (quote_string(schema, Driver::DuckDb), quote_string(table, Driver::DuckDb));
+    let query = format!(
+      "select 0 as position, sql from duckdb_tables() where schema_name = {schema} and table_name = {table_name}
+        union all
+        select 1, sql from duckdb_indexes() where schema_name = {schema} and table_name = {table_name}
+        order by position"
+    );
+    create_statements(run_query(connection, query).await?, 1, table)
+  }
+
   fn preview_rows_query(&self, schema: &str, table: &str) -> String {
     format!("select * from \"{}\".\"{}\" limit 100", schema, table)
   }
 
   fn preview_columns_query(&self, schema: &str, table: &str) -> String {
     format!(
-      "select column_name, * from information_schema.columns where table_schema = '{}' and table_name = '{}'",
-      schema, table
+      "select
+        concat_ws(' ',
+          case when exists (
+            select 1 from duckdb_constraints() k
+            where k.database_oid = c.database_oid and k.table_oid = c.table_oid
+              and k.constraint_type = 'PRIMARY KEY' and list_contains(k.constraint_column_names, c.column_name)
+          ) then '{PRIMARY_KEY_ICON}' end,
+          case when exists (
+            select 1 from duckdb_constraints() k
+            where k.database_oid = c.database_oid and k.table_oid = c.table_oid
+              and k.constraint_type = 'FOREIGN KEY' and list_contains(k.constraint_column_names, c.column_name)
+          ) then '{FOREIGN_KEY_ICON}' end
+        ) as key,
+        c.column_name,
+        c.data_type,
+        case when c.is_nullable then 'YES' else 'NO' end as is_nullable,
+        c.column_default,
+        c.comment
+      from duckdb_columns() c
+      where c.schema_name = '{schema}' and c.table_name = '{table}'
+      order by c.column_index"
     )
   }
 
@@ -137,6 +275,28 @@ impl Database for DuckDbDriver {
     "select 'DuckDB does not support row-level security policies' as message".to_owned()
   }
 
+  fn preview_foreign_keys_query(&self, schema: &str, table: &str) -> String {
+    // duckdb doesn't support referential actions, so there are no on delete
+    // or on update columns
+    format!(
+      "select case when table_name = '{table}' then 'outgoing' else 'incoming' end as direction,
+        constraint_name,
+        schema_name || '.' || table_name as from_table,
+        array_to_string(constraint_column_names, ', ') as from_columns,
+        referenced_table as to_table,
+        array_to_string(referenced_column_names, ', ') as to_columns
+      from duckdb_constraints()
+      where constraint_type = 'FOREIGN KEY'
+        and schema_name = '{schema}'
+        and (table_name = '{table}' or referenced_table = '{table}')
+      order by direction desc, constraint_name"
+    )
+  }
+
+  fn preview_triggers_query(&self, schema: &str, table: &str) -> String {
+    "select 'DuckDB does not support triggers' as message".to_owned()
+  }
+
   fn preview_view_definition_query(&self, schema: &str, view: &str, materialized: bool) -> String {
     if materialized {
       return "select 'DuckDB does not support materialized views' as message".to_owned();
@@ -147,46 +307,97 @@ impl Database for DuckDbDriver {
         where table_schema = '{schema}' and table_name = '{view}'"
     )
   }
+
+  fn preview_routine_source_query(&self, schema: &str, routine: &str, procedure: bool) -> String {
+    "select 'DuckDB does not support stored routines' as message".to_owned()
+  }
+
+  fn preview_foreign_table_query(&self, schema: &str, table: &str) -> String {
+    "select 'DuckDB does not support foreign tables' as message".to_owned()
+  }
 }
 
 async fn run_query(connection: Connection, query: String) -> Result<Rows> {
-  let mut statement = connection.prepare(query.as_str())?;
+  run_query_with_cap(&connection, &query, 0)
+}
+
+fn run_query_with_cap(connection: &Connection, query: &str, max_rows: u64) -> Result<Rows> {
+  let mut statement = connection.prepare(query)?;
   let rows = statement.query([])?;
-  fetch_rows(rows)
+  fetch_rows(rows, max_rows)
 }
 
-fn fetch_rows(mut rows: duckdb::Rows<'_>) -> Result<Rows> {
+// duckdb answers statements that only change rows with a count column, which is
+// reported as rows affected instead, like the other drivers
+fn run_execute(connection: &Connection, query: &str) -> Result<Rows> {
+  let rows_affected = connection.execute(query, [])?;
+  Ok(Rows { headers: vec![], rows: vec![], rows_affected: Some(rows_affected as u64) })
+}
+
+fn fetch_rows(mut rows: duckdb::Rows<'_>, max_rows: u64) -> Result<Rows> {
   let mut headers: Headers = Vec::new();
   let mut results: Vec<Vec<String>> = Vec::new();
   while let Ok(Some(row)) = rows.next() {
     if headers.is_empty() {
-      headers = row
-        .as_ref()
-        .column_names()
-        .iter()
-        .enumerate()
-        .map(|(i, col)| {
-          let type_name = row.as_ref().column_type(i);
-          Header { type_name: type_name.to_string(), name: col.to_string() }
-        })
-        .collect();
+      headers = get_headers(row.as_ref());
     }
-    let mut r: Vec<String> = Vec::new();
-    for i in 0..headers.len() {
-      let value = row.get::<usize, DuckValue>(i);
-      match value {
-        Ok(value) => r.push(duck_value_to_string(&value)),
-        Err(_) => r.push("_ERROR_".to_string()),
-      }
+    results.push(row_to_vec(row, headers.len()));
+    // the row past the cap shows there were more, and the rest aren't read
+    if super::row_cap_reached(results.len(), max_rows) {
+      break;
     }
-    results.push(r);
+  }
+  // without rows, the headers come from the statement, which has run by now
+  if headers.is_empty()
+    && let Some(statement) = rows.as_ref()
+  {
+    headers = get_headers(statement);
   }
   Ok(Rows { headers, rows: results, rows_affected: None })
 }
 
+fn export_query(connection: Connection, query: String, mut sink: Box<dyn RowSink>) -> Result<u64> {
+  let mut statement = connection.prepare(query.as_str())?;
+  let mut rows = statement.query([])?;
+  let mut column_count = 0;
+  let mut row_count: u64 = 0;
+  while let Some(row) = rows.next()? {
+    if row_count == 0 {
+      let headers = get_headers(row.as_ref());
+      column_count = headers.len();
+      sink.write_headers(&headers)?;
+    }
+    sink.write_row(&row_to_vec(row, column_count))?;
+    row_count += 1;
+  }
+  sink.finish()?;
+  Ok(row_count)
+}
+
+fn get_headers(statement: &duckdb::Statement<'_>) -> Headers {
+  statement
+    .column_names()
+    .iter()
+    .enumerate()
+    .map(|(i, col)| {
+      let type_name = statement.column_type(i);
+      Header { type_name: type_name.to_string(), name: col.to_string(), composite_fields: None }
+    })
+    .collect()
+}
+
+fn row_to_vec(row: &duckdb::Row<'_>, column_count: usize) -> Vec<String> {
+  (0..column_count)
+    .map(|i| match row.get::<usize, DuckValue>(i) {
+      Ok(value) => duck_value_to_string(&value),
+      Err(_) => "_ERROR_".to_string(),
+    })
+    .collect()
+}
+
 fn duck_value_to_string(value: &DuckValue) -> String {
   match value {
-    DuckValue::Null => "NULL".to_string(),
+    DuckValue::Null => NULL.to_string(),
     DuckValue::Boolean(v) => v.to_string(),
     DuckValue::TinyInt(v) => v.to_string(),
     DuckValue::SmallInt(v) => v.to_string(),
@@ -202,10 +413,12 @@ fn duck_value_to_string(value: &DuckValue) -> String {
     DuckValue::Decimal(v) => v.to_string(),
     DuckValue::Timestamp(unit, raw) => format_timestamp(*unit, *raw),
     DuckValue::Text(text) => text.clone(),
-    DuckValue::Blob(bytes) => bytes_to_string(bytes),
+    DuckValue::Blob(bytes) => bytes_to_hex(bytes),
     DuckValue::Date32(days) => format_date(*days),
     DuckValue::Time64(unit, raw) => format_time(*unit, *raw),
-    DuckValue::Interval { months, days, nanos } => format_interval(*months, *days, *nanos),
+    DuckValue::Interval { months, days, nanos } => {
+      Interval { months: i64::from(*months), days: i64::from(*days), micros: nanos / 1_000 }.to_string()
+    },
     DuckValue::List(values) | DuckValue::Array(values) => format_list(values),
     DuckValue::Enum(value) => value.clone(),
     DuckValue::Struct(map) => format_struct(map),
@@ -262,10 +475,6 @@ fn format_time(unit: TimeUnit, raw: i64) -> String {
     .unwrap_or_else(|| raw.to_string())
 }
 
-fn format_interval(months: i32, days: i32, nanos: i64) -> String {
-  format!("months={months}, days={days}, nanos={nanos}")
-}
-
 fn format_list(values: &[DuckValue]) -> String {
   let formatted: Vec<String> = values.iter().map(duck_value_to_string).collect();
   format!("[{}]", formatted.join(", "))
@@ -283,22 +492,9 @@ fn format_map(map: &OrderedMap<DuckValue, DuckValue>) -> String {
   format!("{{{}}}", formatted.join(", "))
 }
 
-fn bytes_to_string(bytes: &[u8]) -> String {
-  match std::str::from_utf8(bytes) {
-    Ok(text) => text.to_owned(),
-    Err(_) => {
-      let mut output = String::from("0x");
-      for b in bytes {
-        let _ = write!(&mut output, "{b:02X}");
-      }
-      output
-    },
-  }
-}
-
 impl DuckDbDriver {
-  pub fn new() -> Self {
-    DuckDbDriver { connection: None, task: None }
+  pub fn new(max_rows: u64) -> Self {
+    DuckDbDriver { connection: None, task: None, pinned_conn: None, path: None, max_rows, startup: Startup::default() }
   }
 
   fn build_connection_opts(args: crate::cli::Cli) -> Result<(String, Config)> {
@@ -434,7 +630,7 @@ mod tests {
       // ("COPY FROM DATABASE memory TO my_database;", ExecutionType::Normal),
     ];
 
-    let driver = DuckDbDriver::new();
+    let driver = DuckDbDriver::new(0);
 
     for (query, expected) in test_cases {
       assert_eq!(
@@ -445,4 +641,23 @@ mod tests {
       );
     }
   }
+
+  #[tokio::test]
+  async fn test_preview_foreign_keys_query() {
+    let connection = Connection::open_in_memory().unwrap();
+    connection
+      .execute_batch(
+        "create table users (id int primary key);
+        create table orders (id int primary key, user_id int references users (id));",
+      )
+      .unwrap();
+    let driver = DuckDbDriver::new(0);
+
+    let rows =
+      run_query(connection.try_clone().unwrap(), driver.preview_foreign_keys_query("main", "orders")).await.unwrap();
+    assert_eq!(rows.rows, vec![vec!["outgoing", "orders_user_id_id_fkey", "main.orders", "user_id", "users", "id"]]);
+
+    let rows = run_query(connection, driver.preview_foreign_keys_query("main", "users")).await.unwrap();
+    assert_eq!(rows.rows, vec![vec!["incoming", "orders_user_id_id_fkey", "main.orders", "user_id", "users", "id"]]);
+  }
 }
@@ -0,0 +1,227 @@
+// editing a cell of the results with an UPDATE of the row it came from. only
+// results of a plain select from one table can be edited, and only when the
+// table's primary key is in them, so the UPDATE changes exactly that row.
+use color_eyre::eyre::{Result, eyre};
+use sqlparser::ast::{GroupByExpr, Ident, SelectItem, SetExpr, Statement, TableFactor, TableWithJoins};
+
+use super::{Headers, is_binary_type, is_null, qualified_name, quote_identifier, quote_string};
+use crate::cli::Driver;
+
+/// The table the results were selected from, and the column each result
+/// column shows, if it shows one as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditableTable {
+  pub schema: Option<String>,
+  pub table: String,
+  columns: Vec<Option<String>>,
+  driver: Driver,
+}
+
+/// A cell to edit, with what's needed to find its row again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellEdit {
+  pub table: String,
+  pub column: String,
+  pub value: String,
+  // each primary key column, with its value in the row
+  key: Vec<(String, String)>,
+  schema: Option<String>,
+  driver: Driver,
+}
+
+/// Finds the table the results of `statement` came from, or why they can't be
+/// edited.
+pub fn editable_table(statement: &Statement, headers: &Headers, driver: Driver) -> Result<EditableTable> {
+  let not_editable = || eyre!("only results selected from a single table can be edited");
+  let Statement::Query(query) = statement else {
+    return Err(not_editable());
+  };
+  let SetExpr::Select(select) = query.body.as_ref() else {
+    return Err(not_editable());
+  };
+  let grouped = !matches!(&select.group_by, GroupByExpr::Expressions(exprs, _) if exprs.is_empty());
+  if query.with.is_some() || select.distinct.is_some() || select.having.is_some() || select.into.is_some() || grouped {
+    return Err(not_editable());
+  }
+  let [TableWithJoins { relation: TableFactor::Table { name, args: None, .. }, joins }] = select.from.as_slice() else {
+    return Err(not_editable());
+  };
+  if !joins.is_empty() {
+    return Err(not_editable());
+  }
+  let parts =
+    name.0.iter().map(|part| part.as_ident().map(|ident| catalog_name(ident, driver))).collect::<Option<Vec<_>>>();
+  let (schema, table) = match parts.as_deref() {
+    Some([table]) => (None, table.clone()),
+    Some([schema, table]) => (Some(schema.clone()), table.clone()),
+    _ => return Err(not_editable()),
+  };
+  let columns = match select.projection.as_slice() {
+    [SelectItem::Wildcard(options) | SelectItem::QualifiedWildcard(_, options)]
+      if options.opt_replace.is_none() && options.opt_rename.is_none() =>
+    {
+      headers.iter().map(|header| Some(header.name.clone())).collect()
+    },
+    items if items.len() == headers.len() => items
+      .iter()
+      .map(|item| match item {
+        SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => match expr {
+          sqlparser::ast::Expr::Identifier(ident) => Some(catalog_name(ident, driver)),
+          sqlparser::ast::Expr::CompoundIdentifier(parts) => parts.last().map(|ident| catalog_name(ident, driver)),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect(),
+    _ => return Err(not_editable()),
+  };
+  Ok(EditableTable { schema, table, columns, driver })
+}
+
+impl EditableTable {
+  /// The edit of the cell in `column` of `row`, given the table's primary key.
+  pub fn cell_edit(
+    &self,
+    primary_key: &[String],
+    column: usize,
+    row: &[String],
+    headers: &Headers,
+  ) -> Result<CellEdit> {
+    if primary_key.is_empty() {
+      return Err(eyre!("{} has no primary key to find the row by", self.table));
+    }
+    let name =
+      self.columns.get(column).cloned().flatten().ok_or_else(|| eyre!("the column isn't one of the table's"))?;
+    if headers.get(column).is_some_and(|header| is_binary_type(&header.type_name)) {
+      return Err(eyre!("binary values can't be edited"));
+    }
+    let key = primary_key
+      .iter()
+      .map(|key_column| {
+        let position = self.columns.iter().position(|c| c.as_deref().is_some_and(|c| self.same_column(c, key_column)));
+        match position.and_then(|position| row.get(position)) {
+          Some(value) if !is_null(value) => Ok((key_column.clone(), value.clone())),
+          _ => Err(eyre!("the results need the primary key column {key_column} to edit a row")),
+        }
+      })
+      .collect::<Result<Vec<_>>>()?;
+    Ok(CellEdit {
+      table: self.table.clone(),
+      column: name,
+      value: row.get(column).cloned().unwrap_or_default(),
+      key,
+      schema: self.schema.clone(),
+      driver: self.driver,
+    })
+  }
+
+  // postgres and oracle match names exactly once they're folded, while the others ignore case
+  fn same_column(&self, a: &str, b: &str) -> bool {
+    match self.driver {
+      Driver::Postgres | Driver::Oracle => a == b,
+      _ => a.eq_ignore_ascii_case(b),
+    }
+  }
+}
+
+impl CellEdit {
+  /// The UPDATE that sets the cell to `value`, or to NULL. Values are always
+  /// quoted, and the database converts them to the column's type.
+  pub fn update_query(&self, value: Option<&str>) -> String {
+    let table = qualified_name(self.schema.as_deref().unwrap_or_default(), &self.table, self.driver);
+    let value = value.map_or_else(|| "NULL".to_owned(), |value| quote_string(value, self.driver));
+    let conditions = self
+      .key
+      .iter()
+      .map(|(column, value)| {
+        format!("{} = {}", quote_identifier(column, self.driver), quote_string(value, self.driver))
+      })
+      .collect::<Vec<_>>();
+    format!(
+      "UPDATE {table} SET {} = {value} WHERE {}",
+      quote_identifier(&self.column, self.driver),
+      conditions.join(" AND ")
+    )
+  }
+}
+
+// the name as the database keeps it. postgres folds unquoted names to
+// lowercase and oracle to uppercase, while the others keep them as written.
+fn catalog_name(ident: &Ident, driver: Driver) -> String {
+  match (ident.quote_style, driver) {
+    (Some(_), _) => ident.value.clone(),
+    (None, Driver::Postgres) => ident.value.to_lowercase(),
+    (None, Driver::Oracle) => ident.value.to_uppercase(),
+    (None, _) => ident.value.clone(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+  use super::*;
+  use crate::database::{Header, NULL};
+
+  fn headers(names: &[&str]) -> Headers {
+    names
+      .iter()
+      .map(|name| Header { name: name.to_string(), type_name: "TEXT".to_owned(), composite_fields: None })
+      .collect()
+  }
+
+  fn table(query: &str, names: &[&str]) -> Result<EditableTable> {
+    let statement = Parser::parse_sql(&PostgreSqlDialect {}, query).unwrap().remove(0);
+    editable_table(&statement, &headers(names), Driver::Postgres)
+  }
+
+  #[test]
+  fn test_editable_table() {
+    let editable = table("select * from users where age > 3 order by id", &["id", "name"]).unwrap();
+    assert_eq!((editable.schema, editable.table.as_str()), (None, "users"));
+    assert_eq!(editable.columns, vec![Some("id".to_owned()), Some("name".to_owned())]);
+
+    let editable = table(r#"select ID, u."Name" as n, age + 1 from app."Users" u"#, &["id", "n", "?column?"]).unwrap();
+    assert_eq!((editable.schema.as_deref(), editable.table.as_str()), (Some("app"), "Users"));
+    assert_eq!(editable.columns, vec![Some("id".to_owned()), Some("Name".to_owned()), None]);
+
+    for query in [
+      "select * from users join posts on posts.user_id = users.id",
+      "select * from users, posts",
+      "select distinct * from users",
+      "select name, count(*) from users group by name",
+      "with u as (select * from users) select * from u",
+      "select * from users union select * from users",
+      "select * from generate_series(1, 3)",
+      "insert into users values (1)",
+    ] {
+      assert!(table(query, &["id"]).is_err(), "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_update_query() {
+    let editable = table("select name, id, org from app.users", &["name", "id", "org"]).unwrap();
+    let key = vec!["org".to_owned(), "id".to_owned()];
+    let row = vec!["o'brien".to_owned(), "7".to_owned(), "acme".to_owned()];
+    let edit = editable.cell_edit(&key, 0, &row, &headers(&["name", "id", "org"])).unwrap();
+    assert_eq!(edit.value, "o'brien");
+    assert_eq!(
+      edit.update_query(Some("it's")),
+      r#"UPDATE "app"."users" SET "name" = 'it''s' WHERE "org" = 'acme' AND "id" = '7'"#
+    );
+    assert_eq!(
+      edit.update_query(None),
+      r#"UPDATE "app"."users" SET "name" = NULL WHERE "org" = 'acme' AND "id" = '7'"#
+    );
+
+    // the key has to be in the results, and not NULL
+    assert!(editable.cell_edit(&["email".to_owned()], 0, &row, &headers(&["name", "id", "org"])).is_err());
+    assert!(editable.cell_edit(&[], 0, &row, &headers(&["name", "id", "org"])).is_err());
+    let row = vec!["a".to_owned(), NULL.to_owned(), "acme".to_owned()];
+    assert!(editable.cell_edit(&key, 0, &row, &headers(&["name", "id", "org"])).is_err());
+
+    let editable = table("select id, lower(name) from users", &["id", "lower"]).unwrap();
+    assert!(editable.cell_edit(&["id".to_owned()], 1, &["1".to_owned(), "a".to_owned()], &headers(&["id"])).is_err());
+  }
+}
@@ -0,0 +1,46 @@
+use color_eyre::eyre::{Result, eyre};
+use sqlformat::{FormatOptions, QueryParams};
+use sqlparser::{ast::Statement, parser::Parser};
+
+use super::get_dialect;
+use crate::cli::Driver;
+
+fn parse(query: &str, driver: Driver) -> Result<Vec<Statement>> {
+  Ok(Parser::parse_sql(get_dialect(driver).as_ref(), query)?)
+}
+
+/// Pretty-prints the query. The query has to parse, and the formatted
+/// query has to parse to the same statements, so formatting can never
+/// change what the query does.
+pub fn format_query(query: &str, driver: Driver) -> Result<String> {
+  let statements = parse(query, driver)?;
+  if statements.is_empty() {
+    return Err(eyre!("Nothing to format"));
+  }
+  let formatted = sqlformat::format(query, &QueryParams::None, &FormatOptions::default());
+  let formatted_statements = parse(&formatted, driver)?;
+  // comparing the rendered statements ignores source positions
+  let render = |statements: &[Statement]| statements.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+  if render(&statements) != render(&formatted_statements) {
+    return Err(eyre!("Formatting would change the meaning of the query"));
+  }
+  Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_query() {
+    let formatted =
+      format_query("select id, name from users where name = 'a  ,  b' and id in (1,2)", Driver::Postgres).unwrap();
+    assert_eq!(formatted, "select\n  id,\n  name\nfrom\n  users\nwhere\n  name = 'a  ,  b'\n  and id in (1, 2)");
+  }
+
+  #[test]
+  fn test_format_query_errors() {
+    assert!(format_query("selec id from users", Driver::Postgres).is_err());
+    assert!(format_query("-- nothing here", Driver::Postgres).is_err());
+  }
+}
@@ -0,0 +1,227 @@
+// intervals are shown the way postgres writes them by default, like
+// `1 year 2 mons 3 days 04:05:06`, whichever style the server is set to write
+// them in, so they read the same everywhere and can be pasted back into a query.
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+
+/// An interval as postgres keeps it, in months, days, and microseconds, which
+/// don't convert into each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Interval {
+  pub months: i64,
+  pub days: i64,
+  pub micros: i64,
+}
+
+impl Interval {
+  /// Parses an interval as postgres writes it in its `postgres`,
+  /// `postgres_verbose`, or `iso_8601` interval styles. Returns `None` for
+  /// anything else.
+  pub fn parse(value: &str) -> Option<Self> {
+    let value = value.trim();
+    match value.strip_prefix('P') {
+      Some(iso) => parse_iso(iso),
+      None => parse_words(value),
+    }
+  }
+}
+
+impl std::fmt::Display for Interval {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (years, months) = (self.months / 12, self.months % 12);
+    let mut parts = vec![];
+    // like postgres, a part after a negative one gets a `+` when it's positive,
+    // so `-1 days +02:00:00` isn't read as both being negative
+    let mut after_negative = false;
+    for (n, unit) in [(years, "year"), (months, "mon"), (self.days, "day")] {
+      if n != 0 {
+        let sign = if after_negative && n > 0 { "+" } else { "" };
+        parts.push(format!("{sign}{n} {unit}{}", if n == 1 { "" } else { "s" }));
+        after_negative = n < 0;
+      }
+    }
+    if self.micros != 0 || parts.is_empty() {
+      let sign = match self.micros {
+        micros if micros < 0 => "-",
+        _ if after_negative => "+",
+        _ => "",
+      };
+      let micros = self.micros.unsigned_abs();
+      let (hours, minutes) = (micros / MICROS_PER_HOUR as u64, micros / MICROS_PER_MINUTE as u64 % 60);
+      let (seconds, fraction) = (micros / MICROS_PER_SECOND as u64 % 60, micros % MICROS_PER_SECOND as u64);
+      let fraction = match fraction {
+        0 => String::new(),
+        fraction => format!(".{fraction:06}").trim_end_matches('0').to_owned(),
+      };
+      parts.push(format!("{sign}{hours:02}:{minutes:02}:{seconds:02}{fraction}"));
+    }
+    write!(f, "{}", parts.join(" "))
+  }
+}
+
+/// The interval in postgres's default style, or `None` if it doesn't parse.
+pub fn format_interval(value: &str) -> Option<String> {
+  Interval::parse(value).map(|interval| interval.to_string())
+}
+
+// like `-1 years +2 mons 3 days -04:05:06`, or `@ 1 year 2 mons 4 hours 5 mins ago`
+fn parse_words(value: &str) -> Option<Interval> {
+  let mut interval = Interval::default();
+  let mut tokens = value.split_whitespace().peekable();
+  tokens.next_if_eq(&"@");
+  let mut parsed_any = false;
+  while let Some(token) = tokens.next() {
+    if token == "ago" && tokens.peek().is_none() {
+      interval = Interval { months: -interval.months, days: -interval.days, micros: -interval.micros };
+      break;
+    }
+    parsed_any = true;
+    if token.contains(':') {
+      interval.micros = interval.micros.checked_add(parse_clock(token)?)?;
+      continue;
+    }
+    let unit = tokens.next()?;
+    let (whole, micros) = match unit {
+      "year" | "years" => (12, 0),
+      "mon" | "mons" | "month" | "months" => (1, 0),
+      "day" | "days" => (0, 0),
+      "hour" | "hours" => (0, MICROS_PER_HOUR),
+      "min" | "mins" | "minute" | "minutes" => (0, MICROS_PER_MINUTE),
+      "sec" | "secs" | "second" | "seconds" => (0, MICROS_PER_SECOND),
+      _ => return None,
+    };
+    match (whole, micros) {
+      (0, 0) => interval.days = interval.days.checked_add(token.parse::<i64>().ok()?)?,
+      (months, 0) => interval.months = interval.months.checked_add(token.parse::<i64>().ok()?.checked_mul(months)?)?,
+      (_, unit) => interval.micros = interval.micros.checked_add(parse_micros(token, unit)?)?,
+    }
+  }
+  parsed_any.then_some(interval)
+}
+
+// `-04:05:06.5` or `04:05`
+fn parse_clock(token: &str) -> Option<i64> {
+  let (negative, clock) = match token.strip_prefix('-') {
+    Some(clock) => (true, clock),
+    None => (false, token.strip_prefix('+').unwrap_or(token)),
+  };
+  let mut fields = clock.split(':');
+  let hours = fields.next()?.parse::<i64>().ok()?;
+  let minutes = fields.next()?.parse::<i64>().ok()?;
+  let seconds = fields.next().map_or(Some(0), |seconds| parse_micros(seconds, MICROS_PER_SECOND))?;
+  if fields.next().is_some() || hours < 0 || minutes < 0 || seconds < 0 {
+    return None;
+  }
+  let micros =
+    hours.checked_mul(MICROS_PER_HOUR)?.checked_add(minutes.checked_mul(MICROS_PER_MINUTE)?)?.checked_add(seconds)?;
+  Some(if negative { -micros } else { micros })
+}
+
+// a number of some unit, like the `6.5` of `6.5 secs`, in microseconds, without
+// going through a float
+fn parse_micros(number: &str, unit: i64) -> Option<i64> {
+  let (negative, digits) = match number.strip_prefix('-') {
+    Some(digits) => (true, digits),
+    None => (false, number.strip_prefix('+').unwrap_or(number)),
+  };
+  let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+  if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) || whole.is_empty() && fraction.is_empty() {
+    return None;
+  }
+  let whole = match whole {
+    "" => 0,
+    whole => whole.parse::<i64>().ok()?.checked_mul(unit)?,
+  };
+  // only as many digits as make a whole microsecond count
+  let mut fraction_micros = 0;
+  let mut scale = unit;
+  for digit in fraction.bytes() {
+    scale /= 10;
+    fraction_micros += i64::from(digit - b'0') * scale;
+  }
+  let micros = whole.checked_add(fraction_micros)?;
+  Some(if negative { -micros } else { micros })
+}
+
+// the part after the `P` of `P1Y2M3DT4H5M6.5S`
+fn parse_iso(value: &str) -> Option<Interval> {
+  let (date, time) = value.split_once('T').unwrap_or((value, ""));
+  let mut interval = Interval::default();
+  for (number, designator) in iso_fields(date)? {
+    match designator {
+      'Y' => interval.months = interval.months.checked_add(number.parse::<i64>().ok()?.checked_mul(12)?)?,
+      'M' => interval.months = interval.months.checked_add(number.parse::<i64>().ok()?)?,
+      'W' => interval.days = interval.days.checked_add(number.parse::<i64>().ok()?.checked_mul(7)?)?,
+      'D' => interval.days = interval.days.checked_add(number.parse::<i64>().ok()?)?,
+      _ => return None,
+    }
+  }
+  for (number, designator) in iso_fields(time)? {
+    let unit = match designator {
+      'H' => MICROS_PER_HOUR,
+      'M' => MICROS_PER_MINUTE,
+      'S' => MICROS_PER_SECOND,
+      _ => return None,
+    };
+    interval.micros = interval.micros.checked_add(parse_micros(number, unit)?)?;
+  }
+  Some(interval)
+}
+
+// splits `1Y-2M` into `("1", 'Y')` and `("-2", 'M')`
+fn iso_fields(value: &str) -> Option<Vec<(&str, char)>> {
+  let mut fields = vec![];
+  let mut start = 0;
+  for (i, c) in value.char_indices() {
+    if c.is_ascii_alphabetic() {
+      if start == i {
+        return None;
+      }
+      fields.push((&value[start..i], c));
+      start = i + 1;
+    }
+  }
+  (start == value.len()).then_some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_interval() {
+    let test_cases = vec![
+      // postgres
+      ("1 day 02:03:04", "1 day 02:03:04"),
+      ("1 year 2 mons 3 days 04:05:06.5", "1 year 2 mons 3 days 04:05:06.5"),
+      ("-1 days +02:03:04", "-1 days +02:03:04"),
+      ("1 day -02:03:04", "1 day -02:03:04"),
+      ("-1 years +2 mons -3 days +04:05:06", "-10 mons -3 days +04:05:06"),
+      ("-2 mons +3 days", "-2 mons +3 days"),
+      ("14 mons", "1 year 2 mons"),
+      ("00:00:00", "00:00:00"),
+      ("-00:00:00.000001", "-00:00:00.000001"),
+      ("25:00:00", "25:00:00"),
+      // postgres_verbose
+      ("@ 1 day 2 hours 3 mins 4.5 secs", "1 day 02:03:04.5"),
+      ("@ 1 year 2 mons ago", "-1 years -2 mons"),
+      // iso_8601
+      ("P1Y2M3DT4H5M6.5S", "1 year 2 mons 3 days 04:05:06.5"),
+      ("PT0S", "00:00:00"),
+      ("P-1DT-2H", "-1 days -02:00:00"),
+      ("P-1DT2H", "-1 days +02:00:00"),
+      ("P2W", "14 days"),
+    ];
+
+    for (value, expected) in test_cases {
+      assert_eq!(format_interval(value).as_deref(), Some(expected), "Failed for value: {value}");
+    }
+
+    // sql_standard style, and anything that isn't an interval, is left alone
+    for value in ["1-2 3 4:05:06", "", "1 fortnight", "P1X", "12:ab:00"] {
+      assert_eq!(format_interval(value), None, "Failed for value: {value}");
+    }
+  }
+}
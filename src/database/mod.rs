@@ -1,5 +1,7 @@
+use std::fmt::Write;
+
 use async_trait::async_trait;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, eyre};
 #[cfg(feature = "duckdb")]
 use sqlparser::dialect::DuckDbDialect;
 
@@ -8,32 +10,79 @@ use sqlparser::{
   dialect::{Dialect, GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect},
   keywords,
   parser::{Parser, ParserError},
+  tokenizer::{Location, Token},
 };
 use tokio::task::JoinHandle;
 
 use crate::cli::{Cli, Driver};
 
+mod array;
+mod composite;
+mod cursor;
 #[cfg(feature = "duckdb")]
 mod duckdb;
+mod edit;
+mod format;
+mod interval;
 mod mysql;
 mod oracle;
+mod paginate;
+mod placeholders;
 mod postgresql;
+mod qualify;
+mod range;
 mod sqlite;
+mod startup;
 
+pub use array::{
+  ArrayElement, array_to_json, exact_number, format_array_inline, format_array_list, is_array_type, parse_array,
+};
+pub use composite::{composite_to_json, format_composite_inline, format_composite_list, parse_composite};
+pub use cursor::Cursor;
 #[cfg(feature = "duckdb")]
 pub use duckdb::DuckDbDriver;
+pub use edit::{CellEdit, EditableTable, editable_table};
+pub use format::format_query;
+pub use interval::{Interval, format_interval};
 pub use mysql::MySqlDriver;
 pub use oracle::OracleDriver;
+pub use paginate::paginate;
+pub use placeholders::{fill_placeholders, find_placeholders};
 pub use postgresql::PostgresDriver;
+pub use qualify::{Qualification, SchemaCatalog};
+pub use range::{Range, format_range, format_range_list, is_range_type, parse_range};
 pub use sqlite::SqliteDriver;
+pub use startup::{Startup, StartupSql};
 
 #[derive(Debug, Clone)]
 pub struct Header {
   pub name: String,
   pub type_name: String,
+  /// The names of a postgres composite column's fields, in order. Only
+  /// composites have them, so they're also how a column is known to be one.
+  pub composite_fields: Option<Vec<String>>,
 }
 pub type Headers = Vec<Header>;
 
+/// SQL NULL as it appears in `Rows`. Values are all strings, so NULL needs a
+/// marker that no text from the database will produce; postgres rejects NUL
+/// bytes in text, and other databases practically never contain them.
+pub const NULL: &str = "\0NULL";
+
+// the nerd font icons that mark primary and foreign key columns in the
+// columns preview
+const PRIMARY_KEY_ICON: &str = "\u{f084}";
+const FOREIGN_KEY_ICON: &str = "\u{f0c1}";
+
+pub fn is_null(value: &str) -> bool {
+  value == NULL
+}
+
+/// The value with NULL swapped for `null`, for wherever rows are shown as text.
+pub fn display_value<'a>(value: &'a str, null: &'a str) -> &'a str {
+  if is_null(value) { null } else { value }
+}
+
 pub struct Value {
   pub parse_error: bool,
   pub is_null: bool,
@@ -53,6 +102,31 @@ pub struct QueryResultsWithMetadata {
   pub statement_type: Option<Statement>,
 }
 
+/// Who and where a connection is connected to, to show in the UI. Parts
+/// the driver doesn't have, like the host of a file database, are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionInfo {
+  pub user: Option<String>,
+  pub host: Option<String>,
+  pub database: Option<String>,
+}
+
+impl std::fmt::Display for ConnectionInfo {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match (&self.user, &self.host) {
+      (Some(user), Some(host)) => write!(f, "{user}@{host}")?,
+      (Some(user), None) => write!(f, "{user}")?,
+      (None, Some(host)) => write!(f, "{host}")?,
+      (None, None) => {},
+    }
+    match (&self.database, self.user.is_some() || self.host.is_some()) {
+      (Some(database), true) => write!(f, "/{database}"),
+      (Some(database), false) => write!(f, "{database}"),
+      (None, _) => Ok(()),
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExecutionType {
   Confirm,
@@ -73,7 +147,61 @@ impl std::fmt::Display for ParseError {
 }
 impl std::error::Error for ParseError {}
 
+/// An error reported by the database, with the fields it gave beyond its
+/// message. `position` is the 1-based character offset into `query` that
+/// the error points at.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbError {
+  pub message: String,
+  pub detail: Option<String>,
+  pub hint: Option<String>,
+  pub position: Option<usize>,
+  pub query: String,
+}
+
+impl DbError {
+  /// The 1-based line number and the text of the line that `position` falls
+  /// on, along with the part of the line before it.
+  pub fn location(&self) -> Option<(usize, &str, &str)> {
+    let position = self.position?.checked_sub(1)?;
+    let offset = match self.query.char_indices().nth(position) {
+      Some((offset, _)) => offset,
+      None if self.query.chars().count() == position => self.query.len(),
+      None => return None,
+    };
+    let line_start = self.query[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = self.query[offset..].find('\n').map_or(self.query.len(), |i| offset + i);
+    let line_number = self.query[..offset].matches('\n').count() + 1;
+    let line = self.query[line_start..line_end].trim_end_matches('\r');
+    Some((line_number, line, &self.query[line_start..offset]))
+  }
+}
+
+impl std::fmt::Display for DbError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.message)?;
+    if let Some(detail) = &self.detail {
+      write!(f, "\nDETAIL: {detail}")?;
+    }
+    if let Some(hint) = &self.hint {
+      write!(f, "\nHINT: {hint}")?;
+    }
+    Ok(())
+  }
+}
+impl std::error::Error for DbError {}
+
 pub type QueryTask = JoinHandle<QueryResultsWithMetadata>;
+pub type ExportTask = JoinHandle<Result<u64>>;
+pub type TableStatsTask = JoinHandle<Result<Rows>>;
+
+/// Receives the results of a streamed query one row at a time, so that
+/// they can be written out without first being collected into `Rows`.
+pub trait RowSink: Send {
+  fn write_headers(&mut self, headers: &Headers) -> Result<()>;
+  fn write_row(&mut self, row: &[String]) -> Result<()>;
+  fn finish(&mut self) -> Result<()>;
+}
 
 pub enum DbTaskResult {
   Finished(QueryResultsWithMetadata),
@@ -90,10 +218,25 @@ pub trait Database {
   /// calling `new()` does not connect).
   async fn init(&mut self, args: Cli) -> Result<()>;
 
+  /// Returns the failures of the connection's startup sql since they were
+  /// last taken. Unless the startup sql is required, they don't fail `init()`
+  /// or the connections made later, so they're only shown.
+  fn take_startup_errors(&self) -> Vec<String>;
+
   /// Spawns a tokio task that runs the query. The task should
   /// expect to be polled via the `get_query_results()` method.
   async fn start_query(&mut self, query: String, bypass_parser: bool) -> Result<()>;
 
+  /// Keeps one connection for the queries started after it, until
+  /// `unpin_connection()`, so the statements of a script share a session:
+  /// its transactions, settings, and temporary tables.
+  async fn pin_connection(&mut self) -> Result<()>;
+
+  /// Lets go of the connection kept by `pin_connection()`, rolling back any
+  /// transaction the script left open. Should do nothing if no connection is
+  /// pinned.
+  async fn unpin_connection(&mut self) -> Result<()>;
+
   /// Aborts the tokio task running the active query or transaction.
   /// Some drivers also kill the process that was running the query,
   /// so that the query does not continue running in the background.
@@ -118,9 +261,60 @@ pub trait Database {
   /// if no transaction is pending.
   async fn rollback_tx(&mut self) -> Result<()>;
 
+  /// Opens a server-side cursor for the query and spawns a task that fetches
+  /// its first `page_size` rows, to be polled like `start_query()`. Returns
+  /// false without running anything if the driver has no cursors, in which
+  /// case the caller pages through the query with a limit and offset.
+  async fn start_cursor(&mut self, query: String, page_size: u64) -> Result<bool>;
+
+  /// Spawns a task that fetches the next `page_size` rows of the cursor
+  /// opened by `start_cursor()`, to be polled like `start_query()`.
+  async fn fetch_cursor(&mut self, page_size: u64) -> Result<()>;
+
+  /// Closes the open cursor, if there is one. Should do nothing if there
+  /// isn't.
+  async fn close_cursor(&mut self) -> Result<()>;
+
+  /// Spawns a tokio task that runs the query on its own connection and
+  /// passes each row to the sink as it is fetched. The task resolves
+  /// to the number of rows written. Unlike `start_query()`, this task
+  /// is owned by the caller and doesn't affect the active query.
+  fn start_export(&self, query: String, sink: Box<dyn RowSink>) -> Result<ExportTask>;
+
+  /// Spawns a tokio task that estimates the size of each table, as rows of
+  /// schema, table name, row count, and size in bytes. Either estimate may
+  /// be NULL. These can be slow to find on large databases, so they're
+  /// kept out of `load_menu()`. Returns `None` if the database has no cheap
+  /// way to estimate them.
+  fn start_table_stats(&self) -> Result<Option<TableStatsTask>>;
+
   /// Returns rows representing the database menu. The menu component
-  /// expects each row to be combination of schema, object name, and kind.
-  async fn load_menu(&self) -> Result<Rows>;
+  /// expects each row to be combination of schema, object name, and kind,
+  /// optionally followed by a detail to show next to the name. The
+  /// database's own schemas are only included if `system_schemas` is set.
+  async fn load_menu(&self, system_schemas: bool) -> Result<Rows>;
+
+  /// Returns the schemas that unqualified table names are resolved
+  /// against, such as postgres's `search_path`.
+  async fn search_path(&self) -> Result<Vec<String>>;
+
+  /// Returns the columns of a table's primary key, in key order, or none if
+  /// it has no primary key. Without a schema, the table is looked up the
+  /// way an unqualified name in a query would be.
+  async fn primary_key(&self, schema: Option<&str>, table: &str) -> Result<Vec<String>>;
+
+  /// Returns who and where the connection is connected to, from the
+  /// options it was made with.
+  fn connection_info(&self) -> ConnectionInfo;
+
+  /// Returns the databases that `use_database()` can switch to, and the
+  /// one in use if there is one.
+  async fn list_databases(&self) -> Result<(Vec<String>, Option<String>)>;
+
+  /// Connects to another database in place of the current one, keeping
+  /// the rest of the connection options. Only called while no query or
+  /// transaction is running.
+  async fn use_database(&mut self, name: &str) -> Result<()>;
 
   /// Returns a query that can be used to preview the rows in a table.
   fn preview_rows_query(&self, schema: &str, table: &str) -> String;
@@ -137,25 +331,112 @@ pub trait Database {
   /// Returns a query that can be used to preview the policies in a table.
   fn preview_policies_query(&self, schema: &str, table: &str) -> String;
 
+  /// Returns a query that can be used to preview the foreign keys of a
+  /// table, both the ones it declares and the ones that reference it.
+  fn preview_foreign_keys_query(&self, schema: &str, table: &str) -> String;
+
+  /// Returns a query that can be used to preview the triggers on a table.
+  fn preview_triggers_query(&self, schema: &str, table: &str) -> String;
+
   /// Returns a query that can be used to preview the definition of a view.
   fn preview_view_definition_query(&self, schema: &str, view: &str, materialized: bool) -> String;
+
+  /// Returns a query that can be used to preview the source of a function
+  /// or procedure. `routine` is the name as the menu lists it, which may
+  /// include the argument list.
+  fn preview_routine_source_query(&self, schema: &str, routine: &str, procedure: bool) -> String;
+
+  /// Returns the source of a function or procedure, ready to be edited and
+  /// run again to replace it.
+  async fn load_routine_source(&self, schema: &str, routine: &str, procedure: bool) -> Result<String>;
+
+  /// Returns the statements that recreate a table: its CREATE TABLE, with
+  /// columns, types, defaults, and constraints, followed by its indexes.
+  async fn load_create_statement(&self, schema: &str, table: &str) -> Result<String>;
+
+  /// Returns a query that can be used to preview the server and options
+  /// behind a foreign table.
+  fn preview_foreign_table_query(&self, schema: &str, table: &str) -> String;
 }
 
 fn get_first_query(query: String, driver: Driver) -> Result<(String, Statement), ParseError> {
-  let ast = Parser::parse_sql(&*get_dialect(driver), &query);
-  match ast {
-    Ok(ast) if ast.len() > 1 => {
+  match parse_statements(&query, driver) {
+    Ok(statements) if statements.len() > 1 => {
       Err(ParseError::MoreThanOneStatement("Only one statement allowed per query".to_owned()))
     },
-    Ok(ast) if ast.is_empty() => Err(ParseError::EmptyQuery("Parsed query is empty".to_owned())),
-    Ok(ast) => {
-      let statement = ast[0].clone();
-      Ok((statement.to_string(), statement))
-    },
+    Ok(mut statements) => statements.pop().ok_or(ParseError::EmptyQuery("Parsed query is empty".to_owned())),
+    Err(e) => Err(ParseError::SqlParserError(e)),
+  }
+}
+
+/// Splits a query into its statements, each as it was written.
+pub fn split_statements(query: &str, driver: Driver) -> Result<Vec<String>, ParseError> {
+  match parse_statements(query, driver) {
+    Ok(statements) => Ok(statements.into_iter().map(|(text, _)| text).collect()),
     Err(e) => Err(ParseError::SqlParserError(e)),
   }
 }
 
+// the text of each statement of the query, from its first token to its last,
+// with the statement parsed from it. the text is run rather than the parsed
+// statement, so comments and syntax the parser only partly understands are
+// kept as they were written.
+fn parse_statements(query: &str, driver: Driver) -> Result<Vec<(String, Statement)>, ParserError> {
+  let dialect = get_dialect(driver);
+  let mut parser = Parser::new(&*dialect).try_with_sql(query)?;
+  let mut statements = vec![];
+  loop {
+    while parser.consume_token(&Token::SemiColon) {}
+    let start = parser.peek_token();
+    if start.token == Token::EOF {
+      return Ok(statements);
+    }
+    let statement = parser.parse_statement()?;
+    // the parser can be past whitespace and comments after the statement by now
+    let end = (0..=parser.get_current_index())
+      .rev()
+      .map(|index| parser.token_at(index))
+      .find(|token| !matches!(token.token, Token::Whitespace(_) | Token::EOF))
+      .map_or(start.span.end, |token| token.span.end);
+    let text = &query[location_offset(query, start.span.start)..location_offset(query, end)];
+    statements.push((text.to_owned(), statement));
+    // like the parser, statements have to be separated by semicolons
+    let next = parser.peek_token();
+    if !matches!(next.token, Token::SemiColon | Token::EOF) {
+      return parser.expected("end of statement", next);
+    }
+  }
+}
+
+// the byte offset of a location the tokenizer gave, which counts lines and
+// the characters of each line from 1
+fn location_offset(text: &str, location: Location) -> usize {
+  let line_start: usize =
+    text.split_inclusive('\n').take((location.line as usize).saturating_sub(1)).map(str::len).sum();
+  let column = (location.column as usize).saturating_sub(1);
+  text[line_start..].char_indices().nth(column).map_or(text.len(), |(offset, _)| line_start + offset)
+}
+
+/// The query as `EXPLAIN (FORMAT JSON)`, or `EXPLAIN (ANALYZE, FORMAT JSON)`,
+/// so its plan can be shown as a tree. Only postgres gives plans this way, and
+/// only one statement is explained at a time.
+pub fn explain_query(query: &str, driver: Driver, analyze: bool) -> Result<String> {
+  if driver != Driver::Postgres {
+    return Err(eyre!("query plans can only be shown as a tree for postgres"));
+  }
+  match split_statements(query, driver)?.as_slice() {
+    [] => Err(eyre!("there's no query to explain")),
+    [statement] if statement.to_ascii_uppercase().starts_with("EXPLAIN") => {
+      Err(eyre!("the query is already an EXPLAIN"))
+    },
+    [_] => {
+      let options = if analyze { "ANALYZE, FORMAT JSON" } else { "FORMAT JSON" };
+      Ok(format!("EXPLAIN ({options}) {}", query.trim()))
+    },
+    _ => Err(eyre!("only one statement can be explained at a time")),
+  }
+}
+
 pub fn get_execution_type(
   query: String,
   confirmed: bool,
@@ -175,6 +456,103 @@ pub fn get_execution_type(
   }
 }
 
+/// Whether the statement creates, changes, or drops something the menu lists,
+/// so the menu should be reloaded once it succeeds.
+pub fn changes_schema(statement: &Statement) -> bool {
+  matches!(
+    statement,
+    Statement::CreateTable { .. }
+      | Statement::CreateVirtualTable { .. }
+      | Statement::CreateView { .. }
+      | Statement::CreateSchema { .. }
+      | Statement::CreateFunction { .. }
+      | Statement::CreateProcedure { .. }
+      | Statement::AlterTable { .. }
+      | Statement::AlterView { .. }
+      | Statement::AlterSchema { .. }
+      | Statement::RenameTable { .. }
+      | Statement::Drop { .. }
+      | Statement::DropFunction { .. }
+      | Statement::DropProcedure { .. }
+  )
+}
+
+/// Whether a driver that has read `fetched` rows of a result is past the
+/// `max_rows_fetched` cap, and should stop reading. Drivers keep the one row
+/// past the cap, so `truncate_rows()` can tell the results were cut short. A
+/// cap of 0 keeps every row.
+pub fn row_cap_reached(fetched: usize, max_rows: u64) -> bool {
+  max_rows > 0 && fetched as u64 > max_rows
+}
+
+/// Drops the row a driver read past the `max_rows_fetched` cap. Returns
+/// whether there was one, and so whether the results were cut short.
+pub fn truncate_rows(results: &mut Result<Rows>, max_rows: u64) -> bool {
+  match results {
+    Ok(rows) if row_cap_reached(rows.rows.len(), max_rows) => {
+      rows.rows.truncate(max_rows as usize);
+      true
+    },
+    _ => false,
+  }
+}
+
+/// Whether the statement only reads, so it can't change data or the schema.
+/// EXPLAIN ANALYZE runs the statement it explains.
+pub fn is_read_only(statement: &Statement) -> bool {
+  match statement {
+    Statement::Query(_)
+    | Statement::ExplainTable { .. }
+    | Statement::ShowFunctions { .. }
+    | Statement::ShowVariable { .. }
+    | Statement::ShowStatus { .. }
+    | Statement::ShowVariables { .. }
+    | Statement::ShowCreate { .. }
+    | Statement::ShowColumns { .. }
+    | Statement::ShowDatabases { .. }
+    | Statement::ShowSchemas { .. }
+    | Statement::ShowTables { .. }
+    | Statement::ShowViews { .. }
+    | Statement::ShowCollation { .. } => true,
+    Statement::Explain { .. } => explain_analyzed(statement).is_none_or(is_read_only),
+    _ => false,
+  }
+}
+
+// the statement an EXPLAIN runs as well as explains, which it does with ANALYZE,
+// given as `EXPLAIN ANALYZE` or as an option like `EXPLAIN (ANALYZE, FORMAT JSON)`
+fn explain_analyzed(statement: &Statement) -> Option<&Statement> {
+  let Statement::Explain { statement, analyze, options, .. } = statement else {
+    return None;
+  };
+  let analyze_option = options.iter().flatten().any(|option| {
+    option.name.value.eq_ignore_ascii_case("analyze")
+      && option.arg.as_ref().is_none_or(|arg| !["false", "off", "0"].contains(&arg.to_string().to_lowercase().as_str()))
+  });
+  (*analyze || analyze_option).then_some(statement.as_ref())
+}
+
+/// Whether the statement deletes or overwrites data, so it should be confirmed
+/// before it runs. EXPLAIN ANALYZE runs the statement it explains.
+pub fn is_destructive(statement: &Statement) -> bool {
+  match statement {
+    Statement::Delete(_) | Statement::Update { .. } | Statement::Truncate { .. } | Statement::Drop { .. } => true,
+    Statement::Explain { .. } => explain_analyzed(statement).is_some_and(is_destructive),
+    _ => false,
+  }
+}
+
+/// Whether the statement is a DELETE or UPDATE that affects every row, because
+/// it has no WHERE clause.
+pub fn missing_where(statement: &Statement) -> bool {
+  match statement {
+    Statement::Delete(delete) => delete.selection.is_none(),
+    Statement::Update { selection, .. } => selection.is_none(),
+    Statement::Explain { .. } => explain_analyzed(statement).is_some_and(missing_where),
+    _ => false,
+  }
+}
+
 fn get_default_execution_type(statement: Statement, confirmed: bool) -> ExecutionType {
   if confirmed {
     return ExecutionType::Normal;
@@ -187,26 +565,11 @@ fn get_default_execution_type(statement: Statement, confirmed: bool) -> Executio
     | Statement::Drop { .. }
     | Statement::Truncate { .. } => ExecutionType::Confirm,
     Statement::Delete(_) | Statement::Update { .. } => ExecutionType::Transaction,
-    Statement::Explain { statement, analyze, .. }
-      if analyze
-        && matches!(
-          statement.as_ref(),
-          Statement::AlterIndex { .. }
-            | Statement::AlterView { .. }
-            | Statement::AlterRole { .. }
-            | Statement::AlterTable { .. }
-            | Statement::Drop { .. }
-            | Statement::Truncate { .. },
-        ) =>
-    {
-      ExecutionType::Confirm
+    // EXPLAIN ANALYZE runs the statement, so it's run the way the statement would be
+    Statement::Explain { .. } => match explain_analyzed(&statement) {
+      Some(explained) => get_default_execution_type(explained.clone(), confirmed),
+      None => ExecutionType::Normal,
     },
-    Statement::Explain { statement, analyze, .. }
-      if analyze && matches!(statement.as_ref(), Statement::Delete(_) | Statement::Update { .. }) =>
-    {
-      ExecutionType::Transaction
-    },
-    Statement::Explain { .. } => ExecutionType::Normal,
     _ => ExecutionType::Normal,
   }
 }
@@ -222,21 +585,204 @@ pub fn statement_type_string(statement: Option<Statement>) -> String {
   }
 }
 
-pub fn vec_to_string<T: std::string::ToString>(vec: Vec<T>) -> String {
-  let mut content = String::new();
-  for (i, elem) in vec.iter().enumerate() {
-    content.push_str(&elem.to_string());
-    if i != vec.len() - 1 {
-      content.push_str(", ");
+/// Whether the statement changes rows without returning any, so its only
+/// result is how many rows it affected.
+pub fn returns_count_only(statement: &Statement) -> bool {
+  match statement {
+    Statement::Insert(insert) => insert.returning.is_none(),
+    Statement::Update { returning, .. } => returning.is_none(),
+    Statement::Delete(delete) => delete.returning.is_none(),
+    Statement::Merge { .. } => true,
+    _ => false,
+  }
+}
+
+/// How psql reports a statement that returned no rows, like `UPDATE 12` or
+/// `INSERT 0 3`. Statements that don't affect rows are just named, like
+/// `CREATE TABLE`.
+pub fn command_tag(statement: &Statement, rows_affected: Option<u64>) -> String {
+  let count = rows_affected.filter(|_| returns_count_only(statement));
+  match (statement, count) {
+    // the 0 is the oid psql used to report for single-row inserts
+    (Statement::Insert(_), Some(n)) => format!("INSERT 0 {n}"),
+    (Statement::Drop { object_type, .. }, _) => format!("DROP {object_type}"),
+    (_, Some(n)) => format!("{} {n}", command_name(statement)),
+    (_, None) => command_name(statement),
+  }
+}
+
+// the statement type in words, so CreateTable becomes CREATE TABLE
+fn command_name(statement: &Statement) -> String {
+  let mut name = String::new();
+  for c in statement_type_string(Some(statement.clone())).chars() {
+    if c.is_uppercase() && !name.is_empty() {
+      name.push(' ');
     }
+    name.push(c.to_ascii_uppercase());
+  }
+  name
+}
+
+pub fn is_numeric_type(type_name: &str) -> bool {
+  let type_name = type_name.to_uppercase();
+  // mysql and oracle include lengths and precisions, e.g. `NUMBER(10)`
+  let base = type_name.split('(').next().unwrap_or_default().trim();
+  match base {
+    "INT2" | "INT4" | "INT8" | "SMALLINT" | "INT" | "INTEGER" | "BIGINT" | "TINYINT" | "MEDIUMINT" | "HUGEINT"
+    | "UTINYINT" | "USMALLINT" | "UINT" | "UBIGINT" | "SERIAL" | "SMALLSERIAL" | "BIGSERIAL" | "OID" | "FLOAT4"
+    | "FLOAT8" | "REAL" | "FLOAT" | "DOUBLE" | "DOUBLE PRECISION" | "NUMERIC" | "DECIMAL" | "NUMBER" => true,
+    _ => base.ends_with(" UNSIGNED"),
+  }
+}
+
+// mysql booleans are `TINYINT(1)` underneath
+pub fn is_boolean_type(type_name: &str) -> bool {
+  matches!(type_name.to_uppercase().as_str(), "BOOL" | "BOOLEAN" | "TINYINT(1)")
+}
+
+pub fn is_json_type(type_name: &str) -> bool {
+  matches!(type_name.to_uppercase().as_str(), "JSON" | "JSONB")
+}
+
+// mysql and oracle include lengths, e.g. `VARBINARY(16)`
+pub fn is_binary_type(type_name: &str) -> bool {
+  let type_name = type_name.to_uppercase();
+  let base = type_name.split('(').next().unwrap_or_default().trim();
+  matches!(
+    base,
+    "BYTEA" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" | "RAW" | "LONG RAW"
+  )
+}
+
+/// Formats binary values the way postgres does, e.g. `\x48656c6c6f`, so they
+/// look the same whichever database they came from.
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+  let mut output = String::with_capacity(2 + bytes.len() * 2);
+  output.push_str("\\x");
+  for b in bytes {
+    let _ = write!(output, "{b:02x}");
+  }
+  output
+}
+
+/// Reads a hex string back into bytes. The `\x` or `0x` prefix is optional,
+/// since oracle leaves it out.
+pub fn hex_to_bytes(value: &str) -> Option<Vec<u8>> {
+  let hex = value.strip_prefix("\\x").or_else(|| value.strip_prefix("0x")).unwrap_or(value);
+  if !hex.len().is_multiple_of(2) {
+    return None;
   }
-  "{ ".to_owned() + &*content + &*" }".to_owned()
+  (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
 }
 
 pub fn header_to_vec(headers: &Headers) -> Vec<String> {
   headers.iter().map(|h| h.name.to_string()).collect()
 }
 
+// always quotes, since an identifier can need quoting for several reasons
+// (mixed case, reserved words, special characters) that vary by database.
+pub fn quote_identifier(identifier: &str, driver: Driver) -> String {
+  match driver {
+    Driver::MySql => format!("`{}`", identifier.replace('`', "``")),
+    _ => format!("\"{}\"", identifier.replace('"', "\"\"")),
+  }
+}
+
+// numbers are left as they are, so they still work where a string wouldn't,
+// like in a limit. anything else becomes a string literal.
+pub fn quote_literal(value: &str, driver: Driver) -> String {
+  let number = value.trim();
+  let numeric = number.parse::<f64>().is_ok_and(f64::is_finite)
+    && number.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'));
+  match driver {
+    // a sign could make a comment out of a `-` before the placeholder
+    _ if numeric && number.starts_with(['-', '+']) => format!("({number})"),
+    _ if numeric => number.to_string(),
+    _ => quote_string(value, driver),
+  }
+}
+
+fn quote_string(value: &str, driver: Driver) -> String {
+  match driver {
+    // mysql treats backslashes in strings as escapes by default
+    Driver::MySql => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''")),
+    _ => format!("'{}'", value.replace('\'', "''")),
+  }
+}
+
+/// A `column IN (...)` condition matching any of `values`, to filter another
+/// query by the results of this one. NULLs are left out, as they never match,
+/// and repeated values are only listed once. Returns the condition and how many
+/// distinct values there were, of which at most `max` are listed, or nothing if
+/// there are no values.
+pub fn in_list(header: &Header, values: &[String], max: usize, driver: Driver) -> Option<(String, usize)> {
+  let mut seen = std::collections::HashSet::new();
+  let distinct: Vec<&str> =
+    values.iter().map(String::as_str).filter(|value| !is_null(value) && seen.insert(*value)).collect();
+  if distinct.is_empty() {
+    return None;
+  }
+  let unquoted = is_numeric_type(&header.type_name) || is_boolean_type(&header.type_name);
+  let literals: Vec<String> = distinct
+    .iter()
+    .take(max)
+    .map(|value| match value.trim() {
+      // anything else, like `NaN`, is left for the database to convert from a string
+      trimmed if unquoted && is_bare_literal(trimmed) => trimmed.to_owned(),
+      _ => quote_string(value, driver),
+    })
+    .collect();
+  let condition = format!("{} IN ({})", quote_identifier(&header.name, driver), literals.join(", "));
+  Some((condition, distinct.len()))
+}
+
+// whether a value can go in a query without quotes: a finite number, true or false
+fn is_bare_literal(value: &str) -> bool {
+  value.parse::<f64>().is_ok_and(f64::is_finite)
+    || value.eq_ignore_ascii_case("true")
+    || value.eq_ignore_ascii_case("false")
+}
+
+pub fn qualified_name(schema: &str, name: &str, driver: Driver) -> String {
+  if schema.is_empty() {
+    quote_identifier(name, driver)
+  } else {
+    format!("{}.{}", quote_identifier(schema, driver), quote_identifier(name, driver))
+  }
+}
+
+/// The name of a routine without the argument list the menu shows with it.
+pub fn routine_name(signature: &str) -> &str {
+  signature.split_once('(').map_or(signature, |(name, _)| name)
+}
+
+// the source of a routine, from the first row of a source query
+fn routine_source(rows: Rows, column: usize, routine: &str) -> Result<String> {
+  rows
+    .rows
+    .into_iter()
+    .next()
+    .and_then(|row| row.into_iter().nth(column))
+    .filter(|source| !is_null(source))
+    .ok_or_else(|| eyre!("Could not find the source of {routine}"))
+}
+
+// the statements that recreate a table, from the first column of each row,
+// each ending in a semicolon and a blank line apart
+fn create_statements(rows: Rows, column: usize, table: &str) -> Result<String> {
+  let statements = rows
+    .rows
+    .into_iter()
+    .filter_map(|row| row.into_iter().nth(column))
+    .filter(|statement| !is_null(statement))
+    .map(|statement| format!("{};", statement.trim().trim_end_matches(';')))
+    .collect::<Vec<_>>();
+  match statements.is_empty() {
+    true => Err(eyre!("Could not find the definition of {table}")),
+    false => Ok(statements.join("\n\n")),
+  }
+}
+
 pub fn get_keywords() -> Vec<String> {
   keywords::ALL_KEYWORDS.iter().map(|k| k.to_string()).collect()
 }
@@ -255,3 +801,261 @@ pub fn get_dialect(driver: Driver) -> Box<dyn Dialect + Send + Sync> {
 pub trait HasRowsAffected {
   fn rows_affected(&self) -> u64;
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_db_error_location() {
+    let error = |position: Option<usize>| DbError {
+      message: "syntax error at or near \"form\"".to_string(),
+      position,
+      query: "select *\n  form users;".to_string(),
+      ..Default::default()
+    };
+    assert_eq!(error(Some(12)).location(), Some((2, "  form users;", "  ")));
+    assert_eq!(error(Some(1)).location(), Some((1, "select *", "")));
+    assert_eq!(error(Some(23)).location(), Some((2, "  form users;", "  form users;")));
+    assert_eq!(error(Some(24)).location(), None);
+    assert_eq!(error(Some(0)).location(), None);
+    assert_eq!(error(None).location(), None);
+    assert_eq!(error(None).to_string(), "syntax error at or near \"form\"");
+    let hinted = DbError { hint: Some("check the spelling".to_string()), ..error(None) };
+    assert_eq!(hinted.to_string(), "syntax error at or near \"form\"\nHINT: check the spelling");
+  }
+
+  #[test]
+  fn test_connection_info() {
+    let info = |user: Option<&str>, host: Option<&str>, database: Option<&str>| ConnectionInfo {
+      user: user.map(String::from),
+      host: host.map(String::from),
+      database: database.map(String::from),
+    };
+    assert_eq!(info(Some("me"), Some("db.example.com:5432"), Some("app")).to_string(), "me@db.example.com:5432/app");
+    assert_eq!(info(None, Some("localhost:1521"), Some("XE")).to_string(), "localhost:1521/XE");
+    assert_eq!(info(Some("root"), Some("localhost:3306"), None).to_string(), "root@localhost:3306");
+    assert_eq!(info(None, None, Some("./my_database.db")).to_string(), "./my_database.db");
+    assert_eq!(ConnectionInfo::default().to_string(), "");
+  }
+
+  #[test]
+  fn test_qualified_name() {
+    let test_cases = vec![
+      ("public", "users", Driver::Postgres, "\"public\".\"users\""),
+      ("Sales", "Order", Driver::Postgres, "\"Sales\".\"Order\""),
+      ("public", "my \"quoted\" table", Driver::Postgres, "\"public\".\"my \"\"quoted\"\" table\""),
+      ("app", "select", Driver::MySql, "`app`.`select`"),
+      ("app", "odd`name", Driver::MySql, "`app`.`odd``name`"),
+      ("", "users", Driver::Sqlite, "\"users\""),
+      ("SCOTT", "EMP", Driver::Oracle, "\"SCOTT\".\"EMP\""),
+    ];
+
+    for (schema, name, driver, expected) in test_cases {
+      assert_eq!(qualified_name(schema, name, driver), expected, "Failed for {schema}.{name}");
+    }
+  }
+
+  #[test]
+  fn test_changes_schema() {
+    let test_cases = vec![
+      ("create table users (id int)", true),
+      ("create view active_users as select * from users", true),
+      ("alter table users add column name text", true),
+      ("drop table users", true),
+      ("create schema sales", true),
+      ("select * from users", false),
+      ("insert into users values (1)", false),
+      ("create index users_id on users (id)", false),
+    ];
+
+    for (query, expected) in test_cases {
+      let (_, statement) = get_first_query(query.to_owned(), Driver::Postgres).unwrap();
+      assert_eq!(changes_schema(&statement), expected, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_truncate_rows() {
+    let rows =
+      |count: usize| Ok(Rows { headers: vec![], rows: vec![vec!["1".to_owned()]; count], rows_affected: None });
+    assert!(!row_cap_reached(3, 3));
+    assert!(row_cap_reached(4, 3));
+    assert!(!row_cap_reached(1_000_000, 0));
+
+    let mut results = rows(4);
+    assert!(truncate_rows(&mut results, 3));
+    assert_eq!(results.unwrap().rows.len(), 3);
+    let mut results = rows(3);
+    assert!(!truncate_rows(&mut results, 3));
+    assert_eq!(results.unwrap().rows.len(), 3);
+    assert!(!truncate_rows(&mut rows(4), 0));
+  }
+
+  #[test]
+  fn test_is_read_only() {
+    let test_cases = vec![
+      ("select * from users", true),
+      ("explain select * from users", true),
+      ("explain delete from users", true),
+      ("explain analyze delete from users", false),
+      ("explain (analyze, format json) delete from users", false),
+      ("explain (analyze off) delete from users", true),
+      ("insert into users values (1)", false),
+      ("create table users (id int)", false),
+    ];
+
+    for (query, expected) in test_cases {
+      let (_, statement) = get_first_query(query.to_owned(), Driver::Postgres).unwrap();
+      assert_eq!(is_read_only(&statement), expected, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_is_destructive() {
+    let test_cases = vec![
+      ("delete from users", true, true),
+      ("delete from users where id = 1", true, false),
+      ("update users set name = 'a'", true, true),
+      ("update users set name = 'a' where id = 1", true, false),
+      ("truncate users", true, false),
+      ("drop table users", true, false),
+      ("explain analyze delete from users", true, true),
+      ("explain (analyze, format json) delete from users", true, true),
+      ("explain (analyze false) delete from users", false, false),
+      ("explain delete from users", false, false),
+      ("select * from users", false, false),
+      ("alter table users add column name text", false, false),
+    ];
+
+    for (query, destructive, no_where) in test_cases {
+      let (_, statement) = get_first_query(query.to_owned(), Driver::Postgres).unwrap();
+      assert_eq!(is_destructive(&statement), destructive, "Failed for query: {query}");
+      assert_eq!(missing_where(&statement), no_where, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_command_tag() {
+    let test_cases = vec![
+      ("update users set name = 'a' where id = 1", Some(12), "UPDATE 12"),
+      ("insert into users values (1), (2), (3)", Some(3), "INSERT 0 3"),
+      ("delete from users", Some(0), "DELETE 0"),
+      ("insert into users values (1) returning id", Some(1), "INSERT"),
+      ("create table users (id int)", Some(0), "CREATE TABLE"),
+      ("drop view active_users", None, "DROP VIEW"),
+      ("truncate users", Some(0), "TRUNCATE"),
+    ];
+
+    for (query, rows_affected, expected) in test_cases {
+      let (_, statement) = get_first_query(query.to_owned(), Driver::Postgres).unwrap();
+      assert_eq!(command_tag(&statement, rows_affected), expected, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_split_statements() {
+    assert_eq!(
+      split_statements("select 1; update users set a = 'x;y' where id = 2;\n-- done\n", Driver::Postgres).unwrap(),
+      vec!["select 1", "update users set a = 'x;y' where id = 2"]
+    );
+    assert_eq!(split_statements("select 1", Driver::Postgres).unwrap(), vec!["select 1"]);
+    assert!(split_statements("selec 1; select 2", Driver::Postgres).is_err());
+    assert!(split_statements("select 1 select 2", Driver::Postgres).is_err());
+
+    // statements keep their comments and text as written
+    let script = "-- setup\nSELECT /* one */ 1 ;;\ncreate function f() returns int as $$ select 1; $$ language sql;\n  select 'é',\n\t\"ü\" from t";
+    assert_eq!(
+      split_statements(script, Driver::Postgres).unwrap(),
+      vec![
+        "SELECT /* one */ 1",
+        "create function f() returns int as $$ select 1; $$ language sql",
+        "select 'é',\n\t\"ü\" from t"
+      ]
+    );
+  }
+
+  #[test]
+  fn test_explain_query() {
+    assert_eq!(
+      explain_query("select * from users\nwhere id = 1;\n", Driver::Postgres, false).unwrap(),
+      "EXPLAIN (FORMAT JSON) select * from users\nwhere id = 1;"
+    );
+    assert_eq!(explain_query("select 1", Driver::Postgres, true).unwrap(), "EXPLAIN (ANALYZE, FORMAT JSON) select 1");
+
+    assert!(explain_query("select 1; select 2", Driver::Postgres, false).is_err());
+    assert!(explain_query("explain select 1", Driver::Postgres, false).is_err());
+    assert!(explain_query("", Driver::Postgres, false).is_err());
+    assert!(explain_query("select 1", Driver::MySql, false).is_err());
+  }
+
+  #[test]
+  fn test_routine_name() {
+    assert_eq!(routine_name("add(a integer, b integer)"), "add");
+    assert_eq!(routine_name("noop()"), "noop");
+    assert_eq!(routine_name("REFRESH_STATS"), "REFRESH_STATS");
+  }
+
+  #[test]
+  fn test_create_statements() {
+    let rows = |values: &[&str]| Rows {
+      headers: vec![Header { name: "sql".to_owned(), type_name: "TEXT".to_owned(), composite_fields: None }],
+      rows: values.iter().map(|value| vec![value.to_string()]).collect(),
+      rows_affected: None,
+    };
+    assert_eq!(
+      create_statements(rows(&["CREATE TABLE t (id int)", NULL, "CREATE INDEX i ON t (id);\n"]), 0, "t").unwrap(),
+      "CREATE TABLE t (id int);\n\nCREATE INDEX i ON t (id);"
+    );
+    assert!(create_statements(rows(&[]), 0, "t").is_err());
+  }
+
+  #[test]
+  fn test_in_list() {
+    let header =
+      |type_name: &str| Header { name: "id".to_owned(), type_name: type_name.to_owned(), composite_fields: None };
+    let values: Vec<String> = ["3", "1", NULL, "3", "it's"].iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+      in_list(&header("TEXT"), &values, 10, Driver::Postgres),
+      Some(("\"id\" IN ('3', '1', 'it''s')".to_owned(), 3))
+    );
+    assert_eq!(in_list(&header("INT4"), &values[..4], 10, Driver::MySql), Some(("`id` IN (3, 1)".to_owned(), 2)));
+    assert_eq!(in_list(&header("INT4"), &values, 1, Driver::Postgres), Some(("\"id\" IN (3)".to_owned(), 3)));
+    assert_eq!(in_list(&header("INT4"), &[NULL.to_owned()], 10, Driver::Postgres), None);
+
+    // values that aren't plain numbers or booleans are quoted, so they can't change the query
+    let values: Vec<String> =
+      [" 1.5 ", "-2e3", "NaN", "inf", "1); drop table users; --"].iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+      in_list(&header("FLOAT8"), &values, 10, Driver::Postgres),
+      Some(("\"id\" IN (1.5, -2e3, 'NaN', 'inf', '1); drop table users; --')".to_owned(), 5))
+    );
+    let values: Vec<String> = ["true", "FALSE", "t"].iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+      in_list(&header("BOOL"), &values, 10, Driver::Postgres),
+      Some(("\"id\" IN (true, FALSE, 't')".to_owned(), 3))
+    );
+  }
+
+  #[test]
+  fn test_is_numeric_type() {
+    for type_name in ["INT4", "numeric", "NUMBER(10)", "DECIMAL(10, 2)", "INT UNSIGNED", "DOUBLE PRECISION"] {
+      assert!(is_numeric_type(type_name), "Failed for type: {type_name}");
+    }
+    for type_name in ["TEXT", "VARCHAR(10)", "TIMESTAMPTZ", "INTERVAL"] {
+      assert!(!is_numeric_type(type_name), "Failed for type: {type_name}");
+    }
+  }
+
+  #[test]
+  fn test_hex() {
+    assert_eq!(bytes_to_hex(b"Hello"), r"\x48656c6c6f");
+    assert_eq!(bytes_to_hex(&[]), r"\x");
+    assert_eq!(hex_to_bytes(r"\x48656c6c6f").as_deref(), Some(&b"Hello"[..]));
+    assert_eq!(hex_to_bytes("0x00FF").as_deref(), Some(&[0, 255][..]));
+    assert_eq!(hex_to_bytes("00ff").as_deref(), Some(&[0, 255][..]));
+    assert_eq!(hex_to_bytes(r"\x0"), None);
+    assert_eq!(hex_to_bytes("zz"), None);
+    assert_eq!(hex_to_bytes("日本"), None);
+  }
+}
@@ -1,5 +1,4 @@
 use std::{
-  fmt::Write,
   io::{self, Write as _},
   str::FromStr,
   string::String,
@@ -11,13 +10,17 @@ use color_eyre::eyre::{self, Result};
 use futures::stream::StreamExt;
 use sqlparser::ast::Statement;
 use sqlx::{
-  Column, Either, MySqlConnection, Row, ValueRef,
+  Column, Either, MySqlConnection, Row, Statement as _, ValueRef,
   mysql::{MySql, MySqlConnectOptions, MySqlPoolOptions},
   pool::PoolConnection,
 };
 use tokio::{sync::Mutex, task::JoinHandle};
 
-use super::{Database, DbTaskResult, Driver, Header, Headers, QueryResultsWithMetadata, QueryTask, Rows, Value};
+use super::{
+  ConnectionInfo, Database, DbTaskResult, Driver, ExportTask, FOREIGN_KEY_ICON, Header, Headers, NULL,
+  PRIMARY_KEY_ICON, QueryResultsWithMetadata, QueryTask, RowSink, Rows, Startup, TableStatsTask, Value, bytes_to_hex,
+  create_statements, qualified_name, quote_string, routine_name, routine_source,
+};
 
 type MySqlTransaction<'a> = sqlx::Transaction<'a, MySql>;
 type TransactionTask<'a> = JoinHandle<(QueryResultsWithMetadata, MySqlTransaction<'a>)>;
@@ -30,20 +33,40 @@ enum MySqlTask<'a> {
 #[derive(Default)]
 pub struct MySqlDriver<'a> {
   pool: Option<Arc<sqlx::Pool<MySql>>>,
+  // kept to connect to another database on the same server
+  opts: Option<MySqlConnectOptions>,
   task: Option<MySqlTask<'a>>,
   querying_conn: Option<Arc<Mutex<PoolConnection<MySql>>>>,
+  // the connection the statements of a script run on, when one is pinned
+  pinned_conn: Option<Arc<Mutex<PoolConnection<MySql>>>>,
   querying_pid: Option<String>,
+  // the `max_rows_fetched` setting
+  max_rows: u64,
+  startup: Startup,
 }
 
 #[async_trait(?Send)]
 impl Database for MySqlDriver<'_> {
   async fn init(&mut self, args: crate::cli::Cli) -> Result<()> {
+    self.startup = Startup::new(args.startup_sql.as_ref(), args.startup_sql_required)?;
     let opts = super::mysql::MySqlDriver::<'_>::build_connection_opts(args)?;
-    let pool = Arc::new(MySqlPoolOptions::new().max_connections(3).connect_with(opts).await?);
+    let pool = Arc::new(
+      MySqlPoolOptions::new()
+        .max_connections(3)
+        .after_connect(self.startup.after_connect::<MySqlConnection>())
+        .connect_with(opts.clone())
+        .await?,
+    );
+    self.startup.check()?;
     self.pool = Some(pool);
+    self.opts = Some(opts);
     Ok(())
   }
 
+  fn take_startup_errors(&self) -> Vec<String> {
+    self.startup.take_errors()
+  }
+
   // since it's possible for raw_sql to execute multiple queries in a single string,
   // we only execute the first one and then drop the rest.
   async fn start_query(&mut self, query: String, bypass_parser: bool) -> Result<()> {
@@ -54,16 +77,28 @@ impl Database for MySqlDriver<'_> {
         (first, Some(stmt))
       },
     };
+    let describe = matches!(statement_type, Some(Statement::Query(_)));
     let pool = self.pool.clone().unwrap();
-    self.querying_conn = Some(Arc::new(Mutex::new(pool.acquire().await?)));
+    self.querying_conn = Some(match &self.pinned_conn {
+      Some(conn) => conn.clone(),
+      None => Arc::new(Mutex::new(pool.acquire().await?)),
+    });
     let conn = self.querying_conn.clone().unwrap();
     let conn_for_task = conn.clone();
     let pid_row = sqlx::raw_sql("SELECT CONNECTION_ID()").fetch_one(conn.lock().await.as_mut()).await?;
     let pid = pid_row.try_get::<u64, _>(0).unwrap_or_else(|_| pid_row.get::<i64, _>(0) as u64);
     log::info!("Starting query with PID {}", pid.clone());
     self.querying_pid = Some(pid.to_string());
+    let max_rows = self.max_rows;
     self.task = Some(MySqlTask::Query(tokio::spawn(async move {
-      let results = query_with_conn(conn_for_task.lock().await.as_mut(), first_query.clone()).await;
+      let mut conn = conn_for_task.lock().await;
+      let mut results = query_with_conn(conn.as_mut(), first_query.clone(), max_rows).await;
+      if let Ok(rows) = results.as_mut()
+        && describe
+        && rows.headers.is_empty()
+      {
+        rows.headers = prepared_headers(conn.as_mut(), &first_query).await;
+      }
       match results {
         Ok(ref rows) => {
           log::info!("{:?} rows, {:?} affected", rows.rows.len(), rows.rows_affected);
@@ -77,6 +112,21 @@ impl Database for MySqlDriver<'_> {
     Ok(())
   }
 
+  async fn pin_connection(&mut self) -> Result<()> {
+    let pool = self.pool.clone().unwrap();
+    self.pinned_conn = Some(Arc::new(Mutex::new(pool.acquire().await?)));
+    Ok(())
+  }
+
+  // the connection is closed rather than given back to the pool, which ends
+  // its session along with anything the script left in it
+  async fn unpin_connection(&mut self) -> Result<()> {
+    if let Some(conn) = self.pinned_conn.take() {
+      conn.lock().await.close_on_drop();
+    }
+    Ok(())
+  }
+
   async fn abort_query(&mut self) -> Result<bool> {
     match self.task.take() {
       Some(task) => {
@@ -204,34 +254,165 @@ impl Database for MySqlDriver<'_> {
     Ok(())
   }
 
-  async fn load_menu(&self) -> Result<Rows> {
+  // mysql only has cursors inside stored routines, so queries
+  // are paged with a limit and offset instead
+  async fn start_cursor(&mut self, _query: String, _page_size: u64) -> Result<bool> {
+    Ok(false)
+  }
+
+  async fn fetch_cursor(&mut self, _page_size: u64) -> Result<()> {
+    Err(eyre::Report::msg("MySQL does not support cursors"))
+  }
+
+  async fn close_cursor(&mut self) -> Result<()> {
+    Ok(())
+  }
+
+  fn start_export(&self, query: String, sink: Box<dyn RowSink>) -> Result<ExportTask> {
+    let pool = self.pool.clone().unwrap();
+    Ok(tokio::spawn(async move { export_with_pool(pool, query, sink).await }))
+  }
+
+  fn start_table_stats(&self) -> Result<Option<TableStatsTask>> {
+    let pool = self.pool.clone().unwrap();
+    // table_rows is only an estimate for innodb tables
+    let query = "select table_schema, table_name, table_rows, data_length + index_length
+      from information_schema.tables
+      where table_type = 'BASE TABLE'"
+      .to_owned();
+    Ok(Some(tokio::spawn(async move { query_with_pool(pool, query).await })))
+  }
+
+  async fn load_menu(&self, system_schemas: bool) -> Result<Rows> {
+    let system_filter = |column: &str| {
+      if system_schemas {
+        "true".to_owned()
+      } else {
+        format!("{column} not in ('mysql', 'information_schema', 'performance_schema', 'sys')")
+      }
+    };
     query_with_pool(
       self.pool.clone().unwrap(),
-      "select table_schema as table_schema,
+      format!(
+        "select table_schema as table_schema,
         table_name as table_name,
         case
           when table_type = 'BASE TABLE' then 'table'
           when table_type = 'VIEW' then 'view'
           else 'table'
-        end as object_kind
+        end as object_kind,
+        cast(null as char) as detail
       from information_schema.tables
-      where table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
-      order by table_schema, object_kind, table_name asc"
-        .to_owned(),
+      where {}
+      union all
+      select r.routine_schema,
+        concat(r.routine_name, '(', coalesce((
+          select group_concat(concat_ws(' ', p.parameter_mode, p.parameter_name, p.dtd_identifier)
+            order by p.ordinal_position separator ', ')
+          from information_schema.parameters p
+          where p.specific_schema = r.routine_schema
+            and p.specific_name = r.specific_name
+            and p.ordinal_position > 0
+        ), ''), ')'),
+        lower(r.routine_type),
+        r.dtd_identifier
+      from information_schema.routines r
+      where {}
+      order by table_schema, object_kind, table_name asc",
+        system_filter("table_schema"),
+        system_filter("r.routine_schema")
+      )
+      .to_owned(),
     )
     .await
   }
 
+  async fn search_path(&self) -> Result<Vec<String>> {
+    let rows =
+      query_with_pool(self.pool.clone().unwrap(), "select database() where database() is not null".to_owned()).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  async fn primary_key(&self, schema: Option<&str>, table: &str) -> Result<Vec<String>> {
+    let query = format!(
+      "select column_name
+        from information_schema.key_column_usage
+        where constraint_name = 'PRIMARY' and table_schema = {} and table_name = {}
+        order by ordinal_position",
+      schema.map_or_else(|| "database()".to_owned(), |schema| quote_string(schema, Driver::MySql)),
+      quote_string(table, Driver::MySql)
+    );
+    let rows = query_with_pool(self.pool.clone().unwrap(), query).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  fn connection_info(&self) -> ConnectionInfo {
+    let Some(opts) = self.opts.as_ref() else {
+      return ConnectionInfo::default();
+    };
+    ConnectionInfo {
+      user: Some(opts.get_username().to_owned()),
+      host: Some(format!("{}:{}", opts.get_host(), opts.get_port())),
+      database: opts.get_database().map(String::from),
+    }
+  }
+
+  async fn list_databases(&self) -> Result<(Vec<String>, Option<String>)> {
+    let databases = query_with_pool(self.pool.clone().unwrap(), "show databases".to_owned()).await?;
+    Ok((
+      databases.rows.into_iter().filter_map(|row| row.into_iter().next()).collect(),
+      self.search_path().await?.into_iter().next(),
+    ))
+  }
+
+  // `use` only changes the connection it runs on, so every connection in
+  // the pool is replaced instead
+  async fn use_database(&mut self, name: &str) -> Result<()> {
+    let opts = self.opts.clone().unwrap().database(name);
+    let pool = Arc::new(
+      MySqlPoolOptions::new()
+        .max_connections(3)
+        .after_connect(self.startup.after_connect::<MySqlConnection>())
+        .connect_with(opts.clone())
+        .await?,
+    );
+    self.startup.check()?;
+    self.pool = Some(pool);
+    self.opts = Some(opts);
+    Ok(())
+  }
+
+  async fn load_routine_source(&self, schema: &str, routine: &str, procedure: bool) -> Result<String> {
+    let query = self.preview_routine_source_query(schema, routine, procedure);
+    // the statement is the third column, after the name and sql mode
+    routine_source(query_with_pool(self.pool.clone().unwrap(), query).await?, 2, routine)
+  }
+
+  // the statement is the second column, after the table's name
+  async fn load_create_statement(&self, schema: &str, table: &str) -> Result<String> {
+    let query = format!("show create table {}", qualified_name(schema, table, Driver::MySql));
+    create_statements(query_with_pool(self.pool.clone().unwrap(), query).await?, 1, table)
+  }
+
   fn preview_rows_query(&self, schema: &str, table: &str) -> String {
     format!("select * from `{schema}`.`{table}` limit 100")
   }
 
   fn preview_columns_query(&self, schema: &str, table: &str) -> String {
     format!(
-      "select column_name, data_type, is_nullable, column_default, extra, column_comment
-        from information_schema.columns
-        where table_schema = '{schema}' and table_name = '{table}'
-        order by ordinal_position"
+      "select
+        concat_ws(' ',
+          case when c.column_key = 'PRI' then '{PRIMARY_KEY_ICON}' end,
+          case when exists (
+            select 1 from information_schema.key_column_usage k
+            where k.table_schema = c.table_schema and k.table_name = c.table_name
+              and k.column_name = c.column_name and k.referenced_table_name is not null
+          ) then '{FOREIGN_KEY_ICON}' end
+        ) as `key`,
+        c.column_name, c.column_type as data_type, c.is_nullable, c.column_default, c.extra, c.column_comment
+        from information_schema.columns c
+        where c.table_schema = '{schema}' and c.table_name = '{table}'
+        order by c.ordinal_position"
     )
   }
 
@@ -260,6 +441,42 @@ impl Database for MySqlDriver<'_> {
     "select 'MySQL does not support row-level security policies' as message".to_owned()
   }
 
+  fn preview_foreign_keys_query(&self, schema: &str, table: &str) -> String {
+    format!(
+      "select case when k.table_schema = '{schema}' and k.table_name = '{table}' then 'outgoing' else 'incoming' end as direction,
+        k.constraint_name,
+        concat(k.table_schema, '.', k.table_name) as from_table,
+        group_concat(k.column_name order by k.ordinal_position separator ', ') as from_columns,
+        concat(k.referenced_table_schema, '.', k.referenced_table_name) as to_table,
+        group_concat(k.referenced_column_name order by k.ordinal_position separator ', ') as to_columns,
+        r.delete_rule as on_delete,
+        r.update_rule as on_update
+      from information_schema.key_column_usage k
+      join information_schema.referential_constraints r
+        on r.constraint_schema = k.constraint_schema
+        and r.constraint_name = k.constraint_name
+        and r.table_name = k.table_name
+      where (k.table_schema = '{schema}' and k.table_name = '{table}')
+        or (k.referenced_table_schema = '{schema}' and k.referenced_table_name = '{table}')
+      group by k.constraint_schema, k.constraint_name, k.table_schema, k.table_name,
+        k.referenced_table_schema, k.referenced_table_name, r.delete_rule, r.update_rule
+      order by direction desc, k.constraint_name"
+    )
+  }
+
+  fn preview_triggers_query(&self, schema: &str, table: &str) -> String {
+    format!(
+      "select trigger_name as name,
+        action_timing as timing,
+        event_manipulation as events,
+        action_orientation as level,
+        action_statement as source
+      from information_schema.triggers
+      where event_object_schema = '{schema}' and event_object_table = '{table}'
+      order by action_timing, event_manipulation, action_order"
+    )
+  }
+
   fn preview_view_definition_query(&self, schema: &str, view: &str, materialized: bool) -> String {
     if materialized {
       return "select 'MySQL does not support materialized views' as message".to_owned();
@@ -270,11 +487,29 @@ impl Database for MySqlDriver<'_> {
         where table_schema = '{schema}' and table_name = '{view}'"
     )
   }
+
+  fn preview_routine_source_query(&self, schema: &str, routine: &str, procedure: bool) -> String {
+    let kind = if procedure { "procedure" } else { "function" };
+    format!("show create {kind} `{schema}`.`{}`", routine_name(routine))
+  }
+
+  fn preview_foreign_table_query(&self, schema: &str, table: &str) -> String {
+    "select 'MySQL does not support foreign tables' as message".to_owned()
+  }
 }
 
 impl MySqlDriver<'_> {
-  pub fn new() -> Self {
-    Self { pool: None, task: None, querying_conn: None, querying_pid: None }
+  pub fn new(max_rows: u64) -> Self {
+    Self {
+      pool: None,
+      opts: None,
+      task: None,
+      querying_conn: None,
+      pinned_conn: None,
+      querying_pid: None,
+      max_rows,
+      startup: Startup::default(),
+    }
   }
 
   fn build_connection_opts(
@@ -359,14 +594,52 @@ impl MySqlDriver<'_> {
 }
 
 async fn query_with_pool(pool: Arc<sqlx::Pool<MySql>>, query: String) -> Result<Rows> {
-  query_with_stream(&*pool.clone(), &query).await
+  query_with_stream(&*pool.clone(), &query, 0).await
 }
 
-async fn query_with_conn(conn: &mut MySqlConnection, query: String) -> Result<Rows> {
-  query_with_stream(conn, &query).await
+// headers are taken from the rows, so a query that returned none is prepared
+// to still find its columns
+async fn prepared_headers<'a, E>(e: E, query: &'a str) -> Headers
+where
+  E: sqlx::Executor<'a, Database = MySql>,
+{
+  match e.prepare(query).await {
+    Ok(statement) => statement
+      .columns()
+      .iter()
+      .map(|col| Header {
+        name: col.name().to_string(),
+        type_name: col.type_info().to_string(),
+        composite_fields: None,
+      })
+      .collect(),
+    Err(e) => {
+      log::warn!("couldn't find the columns of an empty result: {e}");
+      vec![]
+    },
+  }
+}
+
+async fn query_with_conn(conn: &mut MySqlConnection, query: String, max_rows: u64) -> Result<Rows> {
+  query_with_stream(conn, &query, max_rows).await
+}
+
+async fn export_with_pool(pool: Arc<sqlx::Pool<MySql>>, query: String, mut sink: Box<dyn RowSink>) -> Result<u64> {
+  let mut stream = sqlx::raw_sql(&query).fetch(&*pool);
+  let mut row_count: u64 = 0;
+  while let Some(row) = stream.next().await {
+    let row = row?;
+    if row_count == 0 {
+      sink.write_headers(&get_headers(&row))?;
+    }
+    sink.write_row(&row_to_vec(&row))?;
+    row_count += 1;
+  }
+  sink.finish()?;
+  Ok(row_count)
 }
 
-async fn query_with_stream<'a, E>(e: E, query: &'a str) -> Result<Rows>
+async fn query_with_stream<'a, E>(e: E, query: &'a str, max_rows: u64) -> Result<Rows>
 where
   E: sqlx::Executor<'a, Database = sqlx::MySql>,
 {
@@ -386,6 +659,10 @@ where
         if headers.is_empty() {
           headers = get_headers(&row);
         }
+        // the row past the cap shows there were more, and the rest aren't read
+        if super::row_cap_reached(query_rows.len(), max_rows) {
+          break;
+        }
       },
       Err(e) => return Err(eyre::Report::new(e)),
     }
@@ -405,7 +682,7 @@ where
   match first_query {
     Ok((first_query, statement_type)) => match statement_type {
       Statement::Explain { .. } => {
-        let result = query_with_stream(&mut *tx, &first_query).await;
+        let result = query_with_stream(&mut *tx, &first_query, 0).await;
         match result {
           Ok(result) => (Ok(Either::Right(result)), tx),
           Err(e) => (Err(e), tx),
@@ -427,7 +704,7 @@ fn get_headers(row: &<sqlx::MySql as sqlx::Database>::Row) -> Headers {
   row
     .columns()
     .iter()
-    .map(|col| Header { name: col.name().to_string(), type_name: col.type_info().to_string() })
+    .map(|col| Header { name: col.name().to_string(), type_name: col.type_info().to_string(), composite_fields: None })
     .collect()
 }
 
@@ -439,7 +716,7 @@ fn row_to_vec(row: &<sqlx::MySql as sqlx::Database>::Row) -> Vec<String> {
 fn parse_value(row: &<MySql as sqlx::Database>::Row, col: &<MySql as sqlx::Database>::Column) -> Option<Value> {
   let col_type = col.type_info().to_string();
   if row.try_get_raw(col.ordinal()).is_ok_and(|v| v.is_null()) {
-    return Some(Value { parse_error: false, string: "NULL".to_string(), is_null: true });
+    return Some(Value { parse_error: false, string: NULL.to_string(), is_null: true });
   }
   match col_type.to_uppercase().as_str() {
     "TINYINT(1)" | "BOOLEAN" | "BOOL" => Some(row.try_get::<bool, usize>(col.ordinal()).map_or(
@@ -486,27 +763,21 @@ fn parse_value(row: &<MySql as sqlx::Database>::Row, col: &<MySql as sqlx::Datab
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
       |received| Value { parse_error: false, string: received.to_string(), is_null: false },
     )),
-    "VARCHAR" | "CHAR" | "TEXT" | "BINARY" => Some(row.try_get::<String, usize>(col.ordinal()).map_or(
+    // decimals are kept as mysql wrote them, since they can be more precise than any float
+    "DECIMAL" => Some(row.try_get_unchecked::<String, usize>(col.ordinal()).map_or(
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-      |received| Value { parse_error: false, string: received.to_string(), is_null: false },
+      |received| Value { parse_error: false, string: received, is_null: false },
     )),
-    "VARBINARY" | "BLOB" => Some(row.try_get::<Vec<u8>, usize>(col.ordinal()).map_or(
+    "VARCHAR" | "CHAR" | "TEXT" => Some(row.try_get::<String, usize>(col.ordinal()).map_or(
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-      |received| {
-        if let Ok(s) = String::from_utf8(received.clone()) {
-          Value { parse_error: false, string: s, is_null: false }
-        } else {
-          Value {
-            parse_error: false,
-            string: received.iter().fold(String::new(), |mut output, b| {
-              let _ = write!(output, "{b:02X}");
-              output
-            }),
-            is_null: false,
-          }
-        }
-      },
+      |received| Value { parse_error: false, string: received.to_string(), is_null: false },
     )),
+    "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+      Some(row.try_get::<Vec<u8>, usize>(col.ordinal()).map_or(
+        Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
+        |received| Value { parse_error: false, string: bytes_to_hex(&received), is_null: false },
+      ))
+    },
     "INET4" | "INET6" => Some(row.try_get::<std::net::IpAddr, usize>(col.ordinal()).map_or(
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
       |received| Value { parse_error: false, string: received.to_string(), is_null: false },
@@ -579,16 +850,19 @@ mod tests {
       // lowercase
       (
         "select * from `users`",
-        Ok(("SELECT * FROM `users`".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from `users`".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // newlines
-      ("select *\nfrom users;", Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_)))))),
+      (
+        "select *\nfrom users;",
+        Ok(("select *\nfrom users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+      ),
       // comment-only
       ("-- select * from users;", Err(ParseError::EmptyQuery("Parsed query is empty".to_owned()))),
       // commented line(s)
       (
         "-- select blah;\nselect * from users",
-        Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // update
       (
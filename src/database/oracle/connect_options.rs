@@ -5,6 +5,8 @@ use std::{
 
 use color_eyre::eyre::Result;
 
+use crate::database::ConnectionInfo;
+
 pub struct OracleConnectOptions {
   user: Option<String>,
   password: Option<String>,
@@ -21,6 +23,14 @@ impl OracleConnectOptions {
     let port = self.port.unwrap_or(1521u16);
     format!("//{}:{}/{}", self.host, port, self.database)
   }
+  pub fn connection_info(&self) -> ConnectionInfo {
+    ConnectionInfo {
+      user: self.user.clone(),
+      host: Some(format!("{}:{}", self.host, self.port.unwrap_or(1521u16))),
+      database: Some(self.database.clone()),
+    }
+  }
+
   pub fn get_connection_options(&self) -> std::result::Result<(String, String, String), String> {
     let user = self.user.clone().ok_or("User is required for Oracle connection".to_string())?;
     let password = self.password.clone().ok_or("Password is required for Oracle connection".to_string())?;
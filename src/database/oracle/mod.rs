@@ -11,7 +11,11 @@ use tokio::task::JoinHandle;
 
 use crate::cli::Driver;
 
-use super::{Database, DbTaskResult, Header, QueryResultsWithMetadata, QueryTask, Rows};
+use super::{
+  ConnectionInfo, Database, DbTaskResult, ExportTask, FOREIGN_KEY_ICON, Header, NULL, PRIMARY_KEY_ICON,
+  QueryResultsWithMetadata, QueryTask, RowSink, Rows, Startup, TableStatsTask, create_statements, quote_string,
+  routine_name,
+};
 
 type TransactionTask = JoinHandle<Result<QueryResultsWithMetadata>>;
 enum OracleTask {
@@ -25,27 +29,50 @@ pub struct OracleDriver {
   pool: Option<Arc<oracle::pool::Pool>>,
   task: Option<OracleTask>,
   querying_conn: Option<Arc<Connection>>,
+  // the connection the statements of a script run on, when one is pinned
+  pinned_conn: Option<Arc<Connection>>,
+  info: ConnectionInfo,
+  // the `max_rows_fetched` setting
+  max_rows: u64,
+  startup: Startup,
 }
 
 impl OracleDriver {
-  pub fn new() -> Self {
-    OracleDriver { pool: None, task: None, querying_conn: None }
+  pub fn new(max_rows: u64) -> Self {
+    OracleDriver {
+      pool: None,
+      task: None,
+      querying_conn: None,
+      pinned_conn: None,
+      info: ConnectionInfo::default(),
+      max_rows,
+      startup: Startup::default(),
+    }
   }
 }
 
 #[async_trait(?Send)]
 impl Database for OracleDriver {
   async fn init(&mut self, args: crate::cli::Cli) -> Result<()> {
+    // oracle's pool has no hook to run sql on each new session
+    if args.startup_sql.is_some() {
+      self.startup.report("startup sql isn't supported for oracle connections".to_owned());
+    }
     let connection_opts = OracleConnectOptions::build_connection_opts(args)?;
 
     let (user, password, connection_string) =
       connection_opts.get_connection_options().map_err(|e| color_eyre::eyre::eyre!(e))?;
     let pool = Arc::new(oracle::pool::PoolBuilder::new(user, password, connection_string).max_connections(3).build()?);
     self.pool = Some(pool);
+    self.info = connection_opts.connection_info();
 
     Ok(())
   }
 
+  fn take_startup_errors(&self) -> Vec<String> {
+    self.startup.take_errors()
+  }
+
   async fn start_query(&mut self, query: String, bypass_parser: bool) -> Result<()> {
     let (first_query, statement_type) = if bypass_parser {
       (query, None)
@@ -55,12 +82,21 @@ impl Database for OracleDriver {
     };
     let pool = self.pool.clone().unwrap();
 
-    let conn = Arc::new(pool.get()?);
+    let conn = match &self.pinned_conn {
+      Some(conn) => conn.clone(),
+      None => Arc::new(pool.get()?),
+    };
     let query_conn = conn.clone();
     self.querying_conn = Some(conn);
+    let max_rows = self.max_rows;
     let task = match statement_type {
       Some(Statement::Query(_)) => OracleTask::Query(tokio::spawn(async move {
-        let results = query_with_conn(query_conn.as_ref(), &first_query);
+        let results = query_with_conn(query_conn.as_ref(), &first_query, max_rows);
+        QueryResultsWithMetadata { results, statement_type }
+      })),
+      // a pinned connection commits each statement itself, so there's nothing to confirm
+      _ if self.pinned_conn.is_some() => OracleTask::Query(tokio::spawn(async move {
+        let results = execute_with_conn(query_conn.as_ref(), &first_query);
         QueryResultsWithMetadata { results, statement_type }
       })),
       _ => OracleTask::TxStart(tokio::spawn(async move {
@@ -82,6 +118,22 @@ impl Database for OracleDriver {
     Ok(())
   }
 
+  // the statements of a script commit as they go, like they would in the
+  // other databases, since oracle has no statement to begin a transaction
+  async fn pin_connection(&mut self) -> Result<()> {
+    let mut conn = self.pool.clone().unwrap().get()?;
+    conn.set_autocommit(true);
+    self.pinned_conn = Some(Arc::new(conn));
+    Ok(())
+  }
+
+  async fn unpin_connection(&mut self) -> Result<()> {
+    if let Some(conn) = self.pinned_conn.take() {
+      conn.rollback()?;
+    }
+    Ok(())
+  }
+
   async fn abort_query(&mut self) -> Result<bool> {
     if let Some(task) = self.task.take() {
       match task {
@@ -168,28 +220,170 @@ impl Database for OracleDriver {
     }
   }
 
-  async fn load_menu(&self) -> Result<Rows> {
+  // cursors aren't kept open between queries on oracle, so queries
+  // are paged with a limit and offset instead
+  async fn start_cursor(&mut self, _query: String, _page_size: u64) -> Result<bool> {
+    Ok(false)
+  }
+
+  async fn fetch_cursor(&mut self, _page_size: u64) -> Result<()> {
+    Err(color_eyre::eyre::eyre!("Cursors are not kept open between queries when using the Oracle driver"))
+  }
+
+  async fn close_cursor(&mut self) -> Result<()> {
+    Ok(())
+  }
+
+  fn start_export(&self, query: String, sink: Box<dyn RowSink>) -> Result<ExportTask> {
+    let pool = self.pool.clone().unwrap();
+    Ok(tokio::task::spawn_blocking(move || export_with_pool(&pool, &query, sink)))
+  }
+
+  fn start_table_stats(&self) -> Result<Option<TableStatsTask>> {
+    let pool = self.pool.clone().unwrap();
+    // num_rows is as of the last time statistics were gathered
+    Ok(Some(tokio::task::spawn_blocking(move || {
+      query_with_pool(
+        &pool,
+        "select user,
+          t.table_name,
+          t.num_rows,
+          (select sum(s.bytes) from user_segments s where s.segment_name = t.table_name)
+        from user_tables t",
+      )
+    })))
+  }
+
+  // only the user's own objects are listed, so there are no system schemas
+  async fn load_menu(&self, system_schemas: bool) -> Result<Rows> {
     query_with_pool(
       self.pool.as_ref().unwrap(),
-      "select user, table_name, 'table' as object_kind
+      "select user, table_name, 'table' as object_kind, cast(null as varchar2(128)) as detail
         from user_tables
         where tablespace_name is not null
       union all
-      select user, view_name, 'view' as object_kind
+      select user, view_name, 'view' as object_kind, null
         from user_views
       union all
-      select user, mview_name, 'materialized_view' as object_kind
+      select user, mview_name, 'materialized_view' as object_kind, null
         from user_mviews
+      union all
+      select user, table_name, 'foreign_table' as object_kind, null
+        from user_external_tables
+      union all
+      select user,
+        o.object_name || '(' || (
+          select listagg(a.argument_name || ' ' || a.data_type, ', ') within group (order by a.position)
+            from user_arguments a
+            where a.object_id = o.object_id and a.position > 0 and a.data_level = 0
+        ) || ')',
+        lower(o.object_type),
+        (select a.data_type
+          from user_arguments a
+          where a.object_id = o.object_id and a.position = 0 and a.data_level = 0)
+        from user_objects o
+        where o.object_type in ('FUNCTION', 'PROCEDURE')
       order by 1, 3, 2",
     )
   }
 
+  async fn search_path(&self) -> Result<Vec<String>> {
+    let rows =
+      query_with_pool(self.pool.as_ref().unwrap(), "select sys_context('USERENV', 'CURRENT_SCHEMA') from dual")?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  async fn primary_key(&self, schema: Option<&str>, table: &str) -> Result<Vec<String>> {
+    let query = format!(
+      "select cc.column_name
+        from all_constraints c
+        join all_cons_columns cc on cc.owner = c.owner and cc.constraint_name = c.constraint_name
+        where c.constraint_type = 'P' and c.owner = {} and c.table_name = {}
+        order by cc.position",
+      schema.map_or_else(
+        || "sys_context('USERENV', 'CURRENT_SCHEMA')".to_owned(),
+        |schema| quote_string(schema, Driver::Oracle)
+      ),
+      quote_string(table, Driver::Oracle)
+    );
+    let rows = query_with_pool(self.pool.as_ref().unwrap(), &query)?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  fn connection_info(&self) -> ConnectionInfo {
+    self.info.clone()
+  }
+
+  async fn list_databases(&self) -> Result<(Vec<String>, Option<String>)> {
+    Err(color_eyre::eyre::eyre!("Oracle does not support switching databases on a connection"))
+  }
+
+  async fn use_database(&mut self, name: &str) -> Result<()> {
+    Err(color_eyre::eyre::eyre!("Oracle does not support switching databases on a connection"))
+  }
+
+  async fn load_routine_source(&self, schema: &str, routine: &str, procedure: bool) -> Result<String> {
+    let query = self.preview_routine_source_query(schema, routine, procedure);
+    let rows = query_with_pool(self.pool.as_ref().unwrap(), &query)?;
+    // each line already ends in its newline
+    let source = rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect::<String>();
+    match source.is_empty() {
+      true => Err(color_eyre::eyre::eyre!("Could not find the source of {routine}")),
+      false => Ok(source),
+    }
+  }
+
+  // indexes made for constraints come with the table, so only the others follow it
+  async fn load_create_statement(&self, schema: &str, table: &str) -> Result<String> {
+    let (owner, table_name) = (quote_string(schema, Driver::Oracle), quote_string(table, Driver::Oracle));
+    let query = format!(
+      "select 0 as position, dbms_metadata.get_ddl('TABLE', {table_name}, {owner}) as ddl from dual
+        union all
+        select 1, dbms_metadata.get_ddl('INDEX', index_name, owner) from all_indexes
+          where table_owner = {owner} and table_name = {table_name} and index_name not in (
+            select index_name from all_constraints
+              where owner = {owner} and table_name = {table_name} and index_name is not null
+          )
+        order by position"
+    );
+    create_statements(query_with_pool(self.pool.as_ref().unwrap(), &query)?, 1, table)
+  }
+
   fn preview_rows_query(&self, schema: &str, table: &str) -> String {
     format!("select * from \"{}\".\"{}\" where rownum <= 100", schema, table)
   }
 
   fn preview_columns_query(&self, schema: &str, table: &str) -> String {
-    format!("select * from user_tab_columns where table_name = '{}' and user = '{}'", table, schema)
+    // oracle treats an empty string as NULL, so a column that's in neither kind
+    // of key gets a space
+    let key = |constraint_type: &str| {
+      format!(
+        "exists (
+          select 1 from all_constraints k
+          join all_cons_columns kc on kc.owner = k.owner and kc.constraint_name = k.constraint_name
+          where k.owner = c.owner and k.table_name = c.table_name
+            and k.constraint_type = '{constraint_type}' and kc.column_name = c.column_name
+        )"
+      )
+    };
+    format!(
+      "select
+        case
+          when {primary} and {foreign} then '{PRIMARY_KEY_ICON} {FOREIGN_KEY_ICON}'
+          when {primary} then '{PRIMARY_KEY_ICON}'
+          when {foreign} then '{FOREIGN_KEY_ICON}'
+          else ' '
+        end as key,
+        c.column_name,
+        c.data_type,
+        decode(c.nullable, 'N', 'NO', 'YES') as is_nullable,
+        c.data_default as column_default
+      from all_tab_columns c
+      where c.owner = '{schema}' and c.table_name = '{table}'
+      order by c.column_id",
+      primary = key("P"),
+      foreign = key("R"),
+    )
   }
 
   fn preview_constraints_query(&self, schema: &str, table: &str) -> String {
@@ -204,6 +398,37 @@ impl Database for OracleDriver {
     format!("select * from user_policies where object_name = '{}' and user = '{}'", table, schema)
   }
 
+  fn preview_foreign_keys_query(&self, schema: &str, table: &str) -> String {
+    // oracle has no referential actions on update
+    format!(
+      "select case when c.table_name = '{table}' then 'outgoing' else 'incoming' end as direction,
+        c.constraint_name,
+        c.table_name as from_table,
+        (select listagg(column_name, ', ') within group (order by position)
+          from user_cons_columns where constraint_name = c.constraint_name) as from_columns,
+        r.table_name as to_table,
+        (select listagg(column_name, ', ') within group (order by position)
+          from user_cons_columns where constraint_name = r.constraint_name) as to_columns,
+        c.delete_rule as on_delete
+      from user_constraints c
+      join user_constraints r on r.constraint_name = c.r_constraint_name
+      where c.constraint_type = 'R'
+        and (c.table_name = '{table}' or r.table_name = '{table}')
+        and user = '{schema}'
+      order by 1 desc, 2"
+    )
+  }
+
+  fn preview_triggers_query(&self, schema: &str, table: &str) -> String {
+    format!(
+      "select trigger_name as name, trigger_type as timing, triggering_event as events, status, trigger_body as source
+        from user_triggers
+        where table_name = '{}' and user = '{}'
+        order by trigger_name",
+      table, schema
+    )
+  }
+
   fn preview_view_definition_query(&self, schema: &str, view: &str, materialized: bool) -> String {
     if materialized {
       return format!(
@@ -213,46 +438,86 @@ impl Database for OracleDriver {
     }
     format!("select text as definition from user_views where view_name = '{}' and user = '{}'", view, schema)
   }
+
+  fn preview_routine_source_query(&self, schema: &str, routine: &str, procedure: bool) -> String {
+    // user_source keeps the source without the create statement around it, one
+    // row per line. they aren't joined here, since listagg can't return more than
+    // 4000 bytes and most routines are longer
+    format!(
+      "select case when line = 1 then 'CREATE OR REPLACE ' end || text as source
+        from user_source
+        where name = '{}' and type = '{}' and user = '{}'
+        order by line",
+      routine_name(routine),
+      if procedure { "PROCEDURE" } else { "FUNCTION" },
+      schema
+    )
+  }
+
+  fn preview_foreign_table_query(&self, schema: &str, table: &str) -> String {
+    format!("select * from user_external_tables where table_name = '{}' and user = '{}'", table, schema)
+  }
 }
 
 fn query_with_pool(pool: &Pool, query: &str) -> Result<Rows> {
   let conn = pool.get()?;
-  query_with_conn(&conn, query)
+  query_with_conn(&conn, query, 0)
 }
 
-fn query_with_conn(conn: &Connection, query: &str) -> Result<Rows> {
-  let mut headers = Vec::new();
-  let rows = conn
-    .query(query, &[])
-    .map_err(|e| color_eyre::eyre::eyre!("Error executing query: {}", e))?
-    .filter_map(|row| row.ok())
-    .map(|row| {
-      if headers.is_empty() {
-        headers = get_headers(&row);
-      }
-
-      row_to_vec(&row)
-    })
-    .collect::<Vec<_>>();
+// the headers come from the result set rather than a row, so a query that
+// returns no rows still has them
+fn query_with_conn(conn: &Connection, query: &str, max_rows: u64) -> Result<Rows> {
+  let result_set = conn.query(query, &[]).map_err(|e| color_eyre::eyre::eyre!("Error executing query: {}", e))?;
+  let headers = column_headers(result_set.column_info());
+  let mut rows = vec![];
+  for row in result_set.filter_map(|row| row.ok()) {
+    rows.push(row_to_vec(&row));
+    // the row past the cap shows there were more, and the rest aren't read
+    if super::row_cap_reached(rows.len(), max_rows) {
+      break;
+    }
+  }
 
   Ok(Rows { headers, rows, rows_affected: None })
 }
 
+fn export_with_pool(pool: &Pool, query: &str, mut sink: Box<dyn RowSink>) -> Result<u64> {
+  let conn = pool.get()?;
+  let mut row_count: u64 = 0;
+  for row in conn.query(query, &[]).map_err(|e| color_eyre::eyre::eyre!("Error executing query: {}", e))? {
+    let row = row?;
+    if row_count == 0 {
+      sink.write_headers(&get_headers(&row))?;
+    }
+    sink.write_row(&row_to_vec(&row))?;
+    row_count += 1;
+  }
+  sink.finish()?;
+  Ok(row_count)
+}
+
 fn execute_with_conn(conn: &Connection, statement: &str) -> Result<Rows> {
   let result = conn.execute(statement, &[]).map_err(|e| color_eyre::eyre::eyre!("Error executing statement: {}", e))?;
   Ok(Rows { headers: Vec::new(), rows: Vec::new(), rows_affected: result.row_count().ok() })
 }
 
 fn get_headers(row: &oracle::Row) -> Vec<Header> {
-  row
-    .column_info()
+  column_headers(row.column_info())
+}
+
+fn column_headers(columns: &[oracle::ColumnInfo]) -> Vec<Header> {
+  columns
     .iter()
-    .map(|col| Header { name: col.name().to_string(), type_name: col.oracle_type().to_string() })
+    .map(|col| Header {
+      name: col.name().to_string(),
+      type_name: col.oracle_type().to_string(),
+      composite_fields: None,
+    })
     .collect()
 }
 
 fn row_to_vec(row: &oracle::Row) -> Vec<String> {
-  row.sql_values().iter().map(|v| v.to_string()).collect()
+  row.sql_values().iter().map(|v| if v.is_null().unwrap_or(false) { NULL.to_string() } else { v.to_string() }).collect()
 }
 
 #[cfg(test)]
@@ -286,16 +551,19 @@ mod tests {
       // lowercase
       (
         "select * from `users`",
-        Ok(("SELECT * FROM `users`".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from `users`".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // newlines
-      ("select *\nfrom users;", Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_)))))),
+      (
+        "select *\nfrom users;",
+        Ok(("select *\nfrom users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+      ),
       // comment-only
       ("-- select * from users;", Err(ParseError::EmptyQuery("Parsed query is empty".to_owned()))),
       // commented line(s)
       (
         "-- select blah;\nselect * from users",
-        Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // update
       (
@@ -0,0 +1,63 @@
+use sqlparser::ast::{SetExpr, Statement};
+
+use super::parse_statements;
+use crate::cli::Driver;
+
+/// Adds a limit and offset to the end of a query, so a large table isn't
+/// fetched all at once. Returns `None` for anything that shouldn't be paged:
+/// statements that aren't a plain select, `SELECT ... INTO`, or queries that
+/// already have a limit, offset or fetch.
+pub fn paginate(query: &str, driver: Driver, page_size: u64, offset: u64) -> Option<String> {
+  let statements = parse_statements(query, driver).ok()?;
+  let [(text, Statement::Query(query))] = statements.as_slice() else {
+    return None;
+  };
+  let pageable = match query.body.as_ref() {
+    SetExpr::Select(select) => select.into.is_none(),
+    SetExpr::SetOperation { .. } | SetExpr::Query(_) => true,
+    _ => false,
+  };
+  if !pageable || query.limit_clause.is_some() || query.fetch.is_some() {
+    return None;
+  }
+  // the query is kept as it was written, without any semicolon or comment after it
+  match driver {
+    // oracle only has the standard `OFFSET ... FETCH` syntax
+    Driver::Oracle => Some(format!("{text} OFFSET {offset} ROWS FETCH FIRST {page_size} ROWS ONLY")),
+    _ => Some(format!("{text} LIMIT {page_size} OFFSET {offset}")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_paginate() {
+    let test_cases = vec![
+      ("select * from users", Driver::Postgres, Some("select * from users LIMIT 100 OFFSET 200")),
+      (
+        "select id from a union select id from b order by id",
+        Driver::MySql,
+        Some("select id from a union select id from b order by id LIMIT 100 OFFSET 200"),
+      ),
+      ("select * from users", Driver::Oracle, Some("select * from users OFFSET 200 ROWS FETCH FIRST 100 ROWS ONLY")),
+      (
+        "select '--', x::text\nfrom users; -- every user\n",
+        Driver::Postgres,
+        Some("select '--', x::text\nfrom users LIMIT 100 OFFSET 200"),
+      ),
+      ("select * from users limit 5", Driver::Postgres, None),
+      ("select * from users offset 5", Driver::Postgres, None),
+      ("select * from users fetch first 5 rows only", Driver::Postgres, None),
+      ("select * into copy from users", Driver::Postgres, None),
+      ("delete from users", Driver::Postgres, None),
+      ("select 1; select 2", Driver::Postgres, None),
+      ("selec * from users", Driver::Postgres, None),
+    ];
+
+    for (query, driver, expected) in test_cases {
+      assert_eq!(paginate(query, driver, 100, 200).as_deref(), expected, "Failed for query: {query}");
+    }
+  }
+}
@@ -0,0 +1,161 @@
+use std::{collections::HashMap, ops::Range};
+
+use super::quote_literal;
+use crate::cli::Driver;
+
+fn is_name_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
+
+// the placeholders in a query and where they are, skipping anything inside
+// string literals, quoted identifiers, dollar quoted bodies and comments. `::`
+// casts, `:=`, and the `:new.` and `:old.` of oracle triggers aren't placeholders.
+fn scan(query: &str) -> Vec<(Range<usize>, String)> {
+  let chars = query.char_indices().collect::<Vec<_>>();
+  let char_at = |i: usize| chars.get(i).map(|(_, c)| *c);
+  let byte_at = |i: usize| chars.get(i).map_or(query.len(), |(b, _)| *b);
+  let mut placeholders = vec![];
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i].1;
+    match c {
+      '\'' | '"' | '`' => {
+        i += 1;
+        while i < chars.len() && chars[i].1 != c {
+          // mysql, and postgres's `E'...'` strings, escape quotes with a backslash
+          if chars[i].1 == '\\' && c != '`' {
+            i += 1;
+          }
+          i += 1;
+        }
+      },
+      // postgres's `$$...$$` or `$body$...$body$`, but not `$1`
+      '$' if char_at(i + 1).is_some_and(|next| next == '$' || (is_name_char(next) && !next.is_ascii_digit())) => {
+        let mut tag_end = i + 1;
+        while char_at(tag_end).is_some_and(is_name_char) {
+          tag_end += 1;
+        }
+        if char_at(tag_end) == Some('$') {
+          let tag = &query[byte_at(i)..byte_at(tag_end + 1)];
+          i = match query[byte_at(tag_end + 1)..].find(tag) {
+            Some(offset) => chars.partition_point(|(b, _)| *b < byte_at(tag_end + 1) + offset + tag.len()) - 1,
+            None => chars.len(),
+          };
+        }
+      },
+      '-' if char_at(i + 1) == Some('-') => {
+        while i < chars.len() && chars[i].1 != '\n' {
+          i += 1;
+        }
+      },
+      '/' if char_at(i + 1) == Some('*') => {
+        i += 2;
+        while i < chars.len() && !(chars[i].1 == '*' && char_at(i + 1) == Some('/')) {
+          i += 1;
+        }
+        i += 1;
+      },
+      '{' if char_at(i + 1) == Some('{') => {
+        let start = i;
+        let mut end = i + 2;
+        while end < chars.len() && !(chars[end].1 == '}' && char_at(end + 1) == Some('}')) {
+          end += 1;
+        }
+        let name = query[byte_at(start + 2)..byte_at(end)].trim();
+        if end < chars.len() && !name.is_empty() && name.chars().all(is_name_char) {
+          placeholders.push((byte_at(start)..byte_at(end + 2), name.to_string()));
+          i = end + 1;
+        }
+      },
+      ':'
+        if i.checked_sub(1).and_then(char_at).is_none_or(|prev| prev != ':' && !is_name_char(prev))
+          && char_at(i + 1).is_some_and(|next| next.is_alphabetic() || next == '_') =>
+      {
+        let start = i;
+        let mut end = i + 1;
+        while char_at(end).is_some_and(is_name_char) {
+          end += 1;
+        }
+        let name = &query[byte_at(start + 1)..byte_at(end)];
+        if char_at(end) == Some('.') && (name.eq_ignore_ascii_case("new") || name.eq_ignore_ascii_case("old")) {
+          i = end;
+          continue;
+        }
+        placeholders.push((byte_at(start)..byte_at(end), name.to_string()));
+        i = end - 1;
+      },
+      _ => {},
+    }
+    i += 1;
+  }
+  placeholders
+}
+
+/// The names of the `:name` and `{{name}}` placeholders in a query, in the
+/// order they first appear.
+pub fn find_placeholders(query: &str) -> Vec<String> {
+  let mut names: Vec<String> = vec![];
+  for (_, name) in scan(query) {
+    if !names.contains(&name) {
+      names.push(name);
+    }
+  }
+  names
+}
+
+/// Replaces each placeholder with its value as a literal, quoted for the
+/// database so that values can't change the rest of the query. Placeholders
+/// without a value are left alone.
+pub fn fill_placeholders(query: &str, values: &HashMap<String, String>, driver: Driver) -> String {
+  let mut filled = query.to_string();
+  for (range, name) in scan(query).into_iter().rev() {
+    if let Some(value) = values.get(&name) {
+      filled.replace_range(range, &quote_literal(value, driver));
+    }
+  }
+  filled
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_find_placeholders() {
+    let test_cases = vec![
+      ("select * from users where id = :user_id", vec!["user_id"]),
+      ("select * from users where id = {{user_id}} and created > {{ since }}", vec!["user_id", "since"]),
+      ("select :a, :b, :a", vec!["a", "b"]),
+      ("select created::date, x := 1 from t", vec![]),
+      ("select ':not_me', \"{{nor_me}}\" -- :or_me\nfrom t /* {{or_me}} */ where a = :me", vec!["me"]),
+      ("select arr[1:n] from t", vec![]),
+      ("select {{}} from t", vec![]),
+      ("select $$ :not_me $$, $body$ {{nor_me}} $ $$ $body$, $1, :me", vec!["me"]),
+      ("select $$ unterminated :not_me", vec![]),
+      ("select 'it\\'s :not_me', \"a\\\" :nor_me\", :me", vec!["me"]),
+      ("begin :NEW.total := :old.total + :amount; end;", vec!["amount"]),
+    ];
+    for (query, expected) in test_cases {
+      assert_eq!(find_placeholders(query), expected, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_fill_placeholders() {
+    let values = HashMap::from([
+      ("id".to_owned(), " 42".to_owned()),
+      ("offset".to_owned(), "-1".to_owned()),
+      ("name".to_owned(), "o'brien\\".to_owned()),
+      ("attack".to_owned(), "1; drop table users".to_owned()),
+    ]);
+    let query = "select * from users where id = :id and name = {{name}} or id = :attack and x = :missing - :offset";
+    assert_eq!(
+      fill_placeholders(query, &values, Driver::Postgres),
+      "select * from users where id = 42 and name = 'o''brien\\' or id = '1; drop table users' and x = :missing - (-1)"
+    );
+    assert_eq!(
+      fill_placeholders(query, &values, Driver::MySql),
+      "select * from users where id = 42 and name = 'o''brien\\\\' or id = '1; drop table users' and x = :missing - (-1)"
+    );
+  }
+}
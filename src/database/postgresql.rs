@@ -1,5 +1,4 @@
 use std::{
-  fmt::Write,
   io::{self, Write as _},
   str::FromStr,
   string::String,
@@ -11,16 +10,21 @@ use color_eyre::eyre::{self, Result};
 use futures::stream::StreamExt;
 use sqlparser::ast::Statement;
 use sqlx::{
-  Column, Either, Row, ValueRef,
+  Column, Either, Row, Statement as _, ValueRef,
   pool::PoolConnection,
-  postgres::{PgConnectOptions, PgConnection, PgPoolOptions, Postgres},
+  postgres::{
+    PgColumn, PgConnectOptions, PgConnection, PgDatabaseError, PgErrorPosition, PgPoolOptions, PgTypeKind, Postgres,
+    types::PgTimeTz,
+  },
   types::Uuid,
 };
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
 use super::{
-  Database, DbTaskResult, Driver, Header, Headers, QueryResultsWithMetadata, QueryTask, Rows, Value, vec_to_string,
+  ConnectionInfo, Database, DbError, DbTaskResult, Driver, ExportTask, FOREIGN_KEY_ICON, Header, Headers, NULL,
+  PRIMARY_KEY_ICON, QueryResultsWithMetadata, QueryTask, RowSink, Rows, Startup, TableStatsTask, Value, bytes_to_hex,
+  create_statements, format_interval, is_array_type, qualified_name, quote_string, routine_source,
 };
 
 type PostgresTransaction<'a> = sqlx::Transaction<'a, Postgres>;
@@ -34,20 +38,54 @@ enum PostgresTask<'a> {
 #[derive(Default)]
 pub struct PostgresDriver<'a> {
   pool: Option<Arc<sqlx::Pool<Postgres>>>,
+  // kept to connect to another database on the same server
+  opts: Option<PgConnectOptions>,
   task: Option<PostgresTask<'a>>,
   querying_conn: Option<Arc<Mutex<PoolConnection<Postgres>>>>,
+  // the connection the statements of a script run on, when one is pinned
+  pinned_conn: Option<Arc<Mutex<PoolConnection<Postgres>>>>,
   querying_pid: Option<String>,
+  // the connection an open cursor was declared on, which stays checked out of
+  // the pool until the cursor is closed, and the statement it was declared for
+  cursor_conn: Option<Arc<Mutex<PoolConnection<Postgres>>>>,
+  cursor_pid: Option<String>,
+  cursor_statement: Option<Statement>,
+  // the `max_rows_fetched` setting
+  max_rows: u64,
+  startup: Startup,
 }
 
+// rainfrog only keeps one cursor open at a time, so it can have a fixed name
+const CURSOR_NAME: &str = "rainfrog_cursor";
+
 #[async_trait(?Send)]
 impl Database for PostgresDriver<'_> {
   async fn init(&mut self, args: crate::cli::Cli) -> Result<()> {
-    let opts = super::postgresql::PostgresDriver::<'_>::build_connection_opts(args)?;
-    let pool = Arc::new(PgPoolOptions::new().max_connections(3).connect_with(opts).await?);
+    let schema = args.schema.clone();
+    self.startup = Startup::new(args.startup_sql.as_ref(), args.startup_sql_required)?;
+    let mut opts = super::postgresql::PostgresDriver::<'_>::build_connection_opts(args)?;
+    // set when each connection starts, so every connection in the pool has it.
+    // spaces in startup options need escaping, as they separate the options
+    if let Some(schema) = schema.filter(|schema| !schema.trim().is_empty()) {
+      opts = opts.options([("search_path", schema.trim().replace('\\', "\\\\").replace(' ', "\\ "))]);
+    }
+    let pool = Arc::new(
+      PgPoolOptions::new()
+        .max_connections(3)
+        .after_connect(self.startup.after_connect::<PgConnection>())
+        .connect_with(opts.clone())
+        .await?,
+    );
+    self.startup.check()?;
     self.pool = Some(pool);
+    self.opts = Some(opts);
     Ok(())
   }
 
+  fn take_startup_errors(&self) -> Vec<String> {
+    self.startup.take_errors()
+  }
+
   // since it's possible for raw_sql to execute multiple queries in a single string,
   // we only execute the first one and then drop the rest.
   async fn start_query(&mut self, query: String, bypass_parser: bool) -> Result<()> {
@@ -58,15 +96,27 @@ impl Database for PostgresDriver<'_> {
         (first, Some(stmt))
       },
     };
+    let describe = matches!(statement_type, Some(Statement::Query(_)));
     let pool = self.pool.clone().unwrap();
-    self.querying_conn = Some(Arc::new(Mutex::new(pool.acquire().await?)));
+    self.querying_conn = Some(match &self.pinned_conn {
+      Some(conn) => conn.clone(),
+      None => Arc::new(Mutex::new(pool.acquire().await?)),
+    });
     let conn = self.querying_conn.clone().unwrap();
     let conn_for_task = conn.clone();
     let pid = sqlx::raw_sql("SELECT pg_backend_pid()").fetch_one(conn.lock().await.as_mut()).await?.get::<i32, _>(0);
     log::info!("Starting query with PID {}", pid.clone());
     self.querying_pid = Some(pid.to_string().clone());
+    let max_rows = self.max_rows;
     self.task = Some(PostgresTask::Query(tokio::spawn(async move {
-      let results = query_with_conn(conn_for_task.lock().await.as_mut(), first_query.clone()).await;
+      let mut conn = conn_for_task.lock().await;
+      let mut results = query_with_conn(conn.as_mut(), first_query.clone(), max_rows).await;
+      if let Ok(rows) = results.as_mut()
+        && describe
+        && rows.headers.is_empty()
+      {
+        rows.headers = prepared_headers(conn.as_mut(), &first_query).await;
+      }
       match results {
         Ok(ref rows) => {
           log::info!("{:?} rows, {:?} affected", rows.rows.len(), rows.rows_affected);
@@ -80,6 +130,21 @@ impl Database for PostgresDriver<'_> {
     Ok(())
   }
 
+  async fn pin_connection(&mut self) -> Result<()> {
+    let pool = self.pool.clone().unwrap();
+    self.pinned_conn = Some(Arc::new(Mutex::new(pool.acquire().await?)));
+    Ok(())
+  }
+
+  // the connection is closed rather than given back to the pool, which ends
+  // its session along with anything the script left in it
+  async fn unpin_connection(&mut self) -> Result<()> {
+    if let Some(conn) = self.pinned_conn.take() {
+      conn.lock().await.close_on_drop();
+    }
+    Ok(())
+  }
+
   async fn abort_query(&mut self) -> Result<bool> {
     match self.task.take() {
       Some(task) => {
@@ -219,34 +284,285 @@ impl Database for PostgresDriver<'_> {
     Ok(())
   }
 
-  async fn load_menu(&self) -> Result<Rows> {
+  // cursors only live as long as the transaction they're declared in
+  async fn start_cursor(&mut self, query: String, page_size: u64) -> Result<bool> {
+    self.close_cursor().await?;
+    let (first_query, statement_type) = super::get_first_query(query, Driver::Postgres)?;
+    let pool = self.pool.clone().unwrap();
+    let conn = Arc::new(Mutex::new(pool.acquire().await?));
+    let conn_for_task = conn.clone();
+    let pid = sqlx::raw_sql("SELECT pg_backend_pid()").fetch_one(conn.lock().await.as_mut()).await?.get::<i32, _>(0);
+    log::info!("Opening cursor with PID {pid}");
+    self.querying_pid = Some(pid.to_string());
+    self.cursor_pid = Some(pid.to_string());
+    self.cursor_conn = Some(conn);
+    self.cursor_statement = Some(statement_type.clone());
+    self.task = Some(PostgresTask::Query(tokio::spawn(async move {
+      let mut conn = conn_for_task.lock().await;
+      let mut results = match declare_cursor(conn.as_mut(), &first_query).await {
+        Ok(()) => fetch_from_cursor(conn.as_mut(), page_size).await,
+        Err(e) => Err(e),
+      };
+      if let Ok(rows) = results.as_mut()
+        && rows.headers.is_empty()
+      {
+        rows.headers = prepared_headers(conn.as_mut(), &first_query).await;
+      }
+      match results {
+        Ok(ref rows) => log::info!("{:?} rows fetched from cursor", rows.rows.len()),
+        Err(ref e) => log::error!("{e:?}"),
+      };
+      QueryResultsWithMetadata { results, statement_type: Some(statement_type) }
+    })));
+    Ok(true)
+  }
+
+  async fn fetch_cursor(&mut self, page_size: u64) -> Result<()> {
+    let (Some(conn), Some(statement_type)) = (self.cursor_conn.clone(), self.cursor_statement.clone()) else {
+      return Err(eyre::Report::msg("No cursor is open"));
+    };
+    self.querying_pid = self.cursor_pid.clone();
+    self.task = Some(PostgresTask::Query(tokio::spawn(async move {
+      let mut conn = conn.lock().await;
+      let results = fetch_from_cursor(conn.as_mut(), page_size).await;
+      match results {
+        Ok(ref rows) => log::info!("{:?} rows fetched from cursor", rows.rows.len()),
+        Err(ref e) => log::error!("{e:?}"),
+      };
+      QueryResultsWithMetadata { results, statement_type: Some(statement_type) }
+    })));
+    Ok(())
+  }
+
+  // ending the transaction closes the cursor. a connection that can't be
+  // rolled back isn't given back to the pool.
+  async fn close_cursor(&mut self) -> Result<()> {
+    self.cursor_pid = None;
+    self.cursor_statement = None;
+    if let Some(conn) = self.cursor_conn.take() {
+      let mut conn = conn.lock().await;
+      if let Err(e) = sqlx::raw_sql("ROLLBACK").execute(conn.as_mut()).await {
+        conn.close_on_drop();
+        return Err(e.into());
+      }
+    }
+    Ok(())
+  }
+
+  fn start_export(&self, query: String, sink: Box<dyn RowSink>) -> Result<ExportTask> {
+    let pool = self.pool.clone().unwrap();
+    Ok(tokio::spawn(async move { export_with_pool(pool, query, sink).await }))
+  }
+
+  fn start_table_stats(&self) -> Result<Option<TableStatsTask>> {
+    let pool = self.pool.clone().unwrap();
+    // reltuples is -1 for tables that haven't been vacuumed or analyzed yet
+    let query = "select n.nspname,
+        c.relname,
+        case when c.reltuples < 0 then null else c.reltuples::bigint end,
+        pg_total_relation_size(c.oid)
+      from pg_class c
+      join pg_namespace n on n.oid = c.relnamespace
+      where c.relkind in ('r', 'p', 'm')"
+      .to_owned();
+    Ok(Some(tokio::spawn(async move { query_with_pool(pool, query).await })))
+  }
+
+  async fn load_menu(&self, system_schemas: bool) -> Result<Rows> {
+    let system_filter = if system_schemas { "true" } else { "n.nspname not in ('pg_catalog', 'information_schema')" };
+    // routines that belong to extensions are left out, since they'd crowd
+    // out the schema's own
     query_with_pool(
       self.pool.clone().unwrap(),
-      "select n.nspname as table_schema,
+      format!(
+        "select n.nspname as table_schema,
         c.relname as table_name,
         case
-          when c.relkind in ('r', 'p', 'f') then 'table'
+          when c.relkind in ('r', 'p') then 'table'
+          when c.relkind = 'f' then 'foreign_table'
           when c.relkind = 'v' then 'view'
           when c.relkind = 'm' then 'materialized_view'
-        end as object_kind
+        end as object_kind,
+        null as detail
       from pg_class c
       join pg_namespace n on n.oid = c.relnamespace
-      where n.nspname != 'pg_catalog'
-        and n.nspname != 'information_schema'
+      where {system_filter}
         and c.relkind in ('r', 'p', 'f', 'v', 'm')
-      order by n.nspname, object_kind, c.relname asc"
-        .to_owned(),
+      union all
+      select n.nspname,
+        p.proname || '(' || pg_get_function_identity_arguments(p.oid) || ')',
+        case when p.prokind = 'p' then 'procedure' else 'function' end,
+        pg_get_function_result(p.oid)
+      from pg_proc p
+      join pg_namespace n on n.oid = p.pronamespace
+      where {system_filter}
+        and p.prokind in ('f', 'p')
+        and not exists (
+          select 1 from pg_depend d
+          where d.classid = 'pg_proc'::regclass and d.objid = p.oid and d.deptype = 'e'
+        )
+      order by table_schema, object_kind, table_name asc"
+      )
+      .to_owned(),
     )
     .await
   }
 
+  async fn search_path(&self) -> Result<Vec<String>> {
+    let rows = query_with_pool(self.pool.clone().unwrap(), "select unnest(current_schemas(false))".to_owned()).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  // to_regclass finds a name without a schema through the search path, and is null if there's no such table
+  async fn primary_key(&self, schema: Option<&str>, table: &str) -> Result<Vec<String>> {
+    let name = qualified_name(schema.unwrap_or_default(), table, Driver::Postgres);
+    let query = format!(
+      "select a.attname
+        from pg_index i
+        cross join unnest(i.indkey) with ordinality as k(attnum, position)
+        join pg_attribute a on a.attrelid = i.indrelid and a.attnum = k.attnum
+        where i.indrelid = to_regclass({}) and i.indisprimary
+        order by k.position",
+      quote_string(&name, Driver::Postgres)
+    );
+    let rows = query_with_pool(self.pool.clone().unwrap(), query).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  // postgres connects to the database named after the user if none is given
+  fn connection_info(&self) -> ConnectionInfo {
+    let Some(opts) = self.opts.as_ref() else {
+      return ConnectionInfo::default();
+    };
+    ConnectionInfo {
+      user: Some(opts.get_username().to_owned()),
+      host: Some(format!("{}:{}", opts.get_host(), opts.get_port())),
+      database: Some(opts.get_database().unwrap_or(opts.get_username()).to_owned()),
+    }
+  }
+
+  async fn list_databases(&self) -> Result<(Vec<String>, Option<String>)> {
+    let pool = self.pool.clone().unwrap();
+    let query = "select datname from pg_database where datallowconn and not datistemplate order by datname";
+    let databases = query_with_pool(pool.clone(), query.to_owned()).await?;
+    let current = query_with_pool(pool, "select current_database()".to_owned()).await?;
+    Ok((
+      databases.rows.into_iter().filter_map(|row| row.into_iter().next()).collect(),
+      current.rows.into_iter().find_map(|row| row.into_iter().next()),
+    ))
+  }
+
+  // a connection can't change databases in postgres, so this makes a new pool
+  async fn use_database(&mut self, name: &str) -> Result<()> {
+    self.close_cursor().await?;
+    let opts = self.opts.clone().unwrap().database(name);
+    let pool = Arc::new(
+      PgPoolOptions::new()
+        .max_connections(3)
+        .after_connect(self.startup.after_connect::<PgConnection>())
+        .connect_with(opts.clone())
+        .await?,
+    );
+    self.startup.check()?;
+    self.pool = Some(pool);
+    self.opts = Some(opts);
+    Ok(())
+  }
+
+  async fn load_routine_source(&self, schema: &str, routine: &str, procedure: bool) -> Result<String> {
+    let query = self.preview_routine_source_query(schema, routine, procedure);
+    routine_source(query_with_pool(self.pool.clone().unwrap(), query).await?, 0, routine)
+  }
+
+  // postgres has no statement for this, so the create table is put together from
+  // the catalog: each column with its type, default, and nullability, followed
+  // by the constraints, primary key first. indexes made for constraints come
+  // with them, so only the others are listed after it.
+  async fn load_create_statement(&self, schema: &str, table: &str) -> Result<String> {
+    let pool = self.pool.clone().unwrap();
+    let name = qualified_name(schema, table, Driver::Postgres);
+    let oid = format!("{}::regclass", quote_string(&name, Driver::Postgres));
+    let definitions = query_with_pool(
+      pool.clone(),
+      format!(
+        "select definition from (
+          select a.attnum as position,
+            quote_ident(a.attname) || ' ' || format_type(a.atttypid, a.atttypmod)
+              || case
+                when a.attidentity = 'a' then ' GENERATED ALWAYS AS IDENTITY'
+                when a.attidentity = 'd' then ' GENERATED BY DEFAULT AS IDENTITY'
+                when a.attgenerated = 's' then ' GENERATED ALWAYS AS (' || pg_get_expr(d.adbin, d.adrelid) || ') STORED'
+                when d.adbin is not null then ' DEFAULT ' || pg_get_expr(d.adbin, d.adrelid)
+                else ''
+              end
+              || case when a.attnotnull then ' NOT NULL' else '' end as definition
+            from pg_attribute a
+            left join pg_attrdef d on d.adrelid = a.attrelid and d.adnum = a.attnum
+            where a.attrelid = {oid} and a.attnum > 0 and not a.attisdropped
+          union all
+          select 32768 + row_number() over (
+              order by array_position(array['p', 'u', 'c', 'f', 'x'], c.contype::text), c.conname
+            ),
+            'CONSTRAINT ' || quote_ident(c.conname) || ' ' || pg_get_constraintdef(c.oid, true)
+            from pg_constraint c
+            where c.conrelid = {oid}
+        ) definitions
+        order by position"
+      ),
+    )
+    .await?;
+    let mut indexes = query_with_pool(
+      pool,
+      format!(
+        "select pg_get_indexdef(i.indexrelid)
+          from pg_index i
+          where i.indrelid = {oid}
+            and not exists (select 1 from pg_constraint c where c.conindid = i.indexrelid and c.conrelid = i.indrelid)
+          order by i.indexrelid::regclass::text"
+      ),
+    )
+    .await?;
+    let definitions = definitions.rows.into_iter().filter_map(|row| row.into_iter().next()).collect::<Vec<_>>();
+    // a table can have no columns
+    let body = match definitions.is_empty() {
+      true => String::new(),
+      false => format!("\n  {}\n", definitions.join(",\n  ")),
+    };
+    indexes.rows.insert(0, vec![format!("CREATE TABLE {name} ({body})")]);
+    create_statements(indexes, 0, table)
+  }
+
   fn preview_rows_query(&self, schema: &str, table: &str) -> String {
     format!("select * from \"{schema}\".\"{table}\" limit 100")
   }
 
   fn preview_columns_query(&self, schema: &str, table: &str) -> String {
     format!(
-      "select column_name, * from information_schema.columns where table_schema = '{schema}' and table_name = '{table}'"
+      "select
+        concat_ws(' ',
+          case when exists (
+            select 1 from pg_constraint k where k.conrelid = a.attrelid and k.contype = 'p' and a.attnum = any(k.conkey)
+          ) then '{PRIMARY_KEY_ICON}' end,
+          case when exists (
+            select 1 from pg_constraint k where k.conrelid = a.attrelid and k.contype = 'f' and a.attnum = any(k.conkey)
+          ) then '{FOREIGN_KEY_ICON}' end
+        ) as key,
+        a.attname as column_name,
+        format_type(a.atttypid, a.atttypmod) as data_type,
+        case when a.attnotnull then 'NO' else 'YES' end as is_nullable,
+        case
+          when a.attidentity = 'a' then 'generated always as identity'
+          when a.attidentity = 'd' then 'generated by default as identity'
+          when a.attgenerated = 's' then 'generated always as (' || pg_get_expr(d.adbin, d.adrelid) || ') stored'
+          else pg_get_expr(d.adbin, d.adrelid)
+        end as column_default,
+        col_description(a.attrelid, a.attnum) as comment
+      from pg_attribute a
+      join pg_class c on c.oid = a.attrelid
+      join pg_namespace n on n.oid = c.relnamespace
+      left join pg_attrdef d on d.adrelid = a.attrelid and d.adnum = a.attnum
+      where n.nspname = '{schema}' and c.relname = '{table}' and a.attnum > 0 and not a.attisdropped
+      order by a.attnum"
     )
   }
 
@@ -264,6 +580,82 @@ impl Database for PostgresDriver<'_> {
     format!("select * from pg_policies where schemaname = '{schema}' and tablename = '{table}'")
   }
 
+  fn preview_foreign_keys_query(&self, schema: &str, table: &str) -> String {
+    format!(
+      "select case when c.conrelid = t.oid then 'outgoing' else 'incoming' end as direction,
+        c.conname as constraint_name,
+        format('%I.%I', src_ns.nspname, src.relname) as from_table,
+        (select string_agg(a.attname, ', ' order by k.ord)
+          from unnest(c.conkey) with ordinality k(attnum, ord)
+          join pg_attribute a on a.attrelid = c.conrelid and a.attnum = k.attnum) as from_columns,
+        format('%I.%I', ref_ns.nspname, ref.relname) as to_table,
+        (select string_agg(a.attname, ', ' order by k.ord)
+          from unnest(c.confkey) with ordinality k(attnum, ord)
+          join pg_attribute a on a.attrelid = c.confrelid and a.attnum = k.attnum) as to_columns,
+        case c.confdeltype
+          when 'a' then 'NO ACTION' when 'r' then 'RESTRICT' when 'c' then 'CASCADE'
+          when 'n' then 'SET NULL' when 'd' then 'SET DEFAULT'
+        end as on_delete,
+        case c.confupdtype
+          when 'a' then 'NO ACTION' when 'r' then 'RESTRICT' when 'c' then 'CASCADE'
+          when 'n' then 'SET NULL' when 'd' then 'SET DEFAULT'
+        end as on_update
+      from pg_constraint c
+      join pg_class src on src.oid = c.conrelid
+      join pg_namespace src_ns on src_ns.oid = src.relnamespace
+      join pg_class ref on ref.oid = c.confrelid
+      join pg_namespace ref_ns on ref_ns.oid = ref.relnamespace
+      join pg_class t on t.oid in (c.conrelid, c.confrelid)
+      join pg_namespace n on n.oid = t.relnamespace
+      where c.contype = 'f'
+        and n.nspname = '{schema}'
+        and t.relname = '{table}'
+      order by direction desc, constraint_name"
+    )
+  }
+
+  fn preview_triggers_query(&self, schema: &str, table: &str) -> String {
+    // rules rewrite statements much like triggers do, so they're listed too
+    format!(
+      "select 'trigger' as kind,
+        t.tgname as name,
+        case when t.tgtype & 2 = 2 then 'BEFORE' when t.tgtype & 64 = 64 then 'INSTEAD OF' else 'AFTER' end as timing,
+        concat_ws(' OR ',
+          case when t.tgtype & 4 = 4 then 'INSERT' end,
+          case when t.tgtype & 8 = 8 then 'DELETE' end,
+          case when t.tgtype & 16 = 16 then 'UPDATE' end,
+          case when t.tgtype & 32 = 32 then 'TRUNCATE' end) as events,
+        case when t.tgtype & 1 = 1 then 'ROW' else 'STATEMENT' end as level,
+        format('%I.%I', pn.nspname, p.proname) as function,
+        t.tgenabled != 'D' as enabled,
+        pg_get_functiondef(p.oid) as source
+      from pg_trigger t
+      join pg_class c on c.oid = t.tgrelid
+      join pg_namespace n on n.oid = c.relnamespace
+      join pg_proc p on p.oid = t.tgfoid
+      join pg_namespace pn on pn.oid = p.pronamespace
+      where not t.tgisinternal
+        and n.nspname = '{schema}'
+        and c.relname = '{table}'
+      union all
+      select 'rule',
+        r.rulename,
+        case when r.is_instead then 'INSTEAD' else 'ALSO' end,
+        case r.ev_type when '1' then 'SELECT' when '2' then 'UPDATE' when '3' then 'INSERT' when '4' then 'DELETE' end,
+        null,
+        null,
+        r.ev_enabled != 'D',
+        pg_get_ruledef(r.oid, true)
+      from pg_rewrite r
+      join pg_class c on c.oid = r.ev_class
+      join pg_namespace n on n.oid = c.relnamespace
+      where r.rulename != '_RETURN'
+        and n.nspname = '{schema}'
+        and c.relname = '{table}'
+      order by kind desc, name"
+    )
+  }
+
   fn preview_view_definition_query(&self, schema: &str, view: &str, materialized: bool) -> String {
     let relkind = if materialized { "m" } else { "v" };
     format!(
@@ -275,11 +667,47 @@ impl Database for PostgresDriver<'_> {
           and c.relkind = '{relkind}'"
     )
   }
+
+  fn preview_routine_source_query(&self, schema: &str, routine: &str, procedure: bool) -> String {
+    // routines can be overloaded, so they're matched on their arguments too
+    format!(
+      "select pg_get_functiondef(p.oid) as source
+        from pg_proc p
+        join pg_namespace n on n.oid = p.pronamespace
+        where n.nspname = '{schema}'
+          and p.proname || '(' || pg_get_function_identity_arguments(p.oid) || ')' = '{routine}'"
+    )
+  }
+
+  fn preview_foreign_table_query(&self, schema: &str, table: &str) -> String {
+    format!(
+      "select s.srvname as server, w.fdwname as wrapper, ft.ftoptions as options, s.srvoptions as server_options
+        from pg_foreign_table ft
+        join pg_class c on c.oid = ft.ftrelid
+        join pg_namespace n on n.oid = c.relnamespace
+        join pg_foreign_server s on s.oid = ft.ftserver
+        join pg_foreign_data_wrapper w on w.oid = s.srvfdw
+        where n.nspname = '{schema}'
+          and c.relname = '{table}'"
+    )
+  }
 }
 
 impl PostgresDriver<'_> {
-  pub fn new() -> Self {
-    Self { pool: None, task: None, querying_conn: None, querying_pid: None }
+  pub fn new(max_rows: u64) -> Self {
+    Self {
+      max_rows,
+      pool: None,
+      opts: None,
+      task: None,
+      querying_conn: None,
+      pinned_conn: None,
+      querying_pid: None,
+      cursor_conn: None,
+      cursor_pid: None,
+      cursor_statement: None,
+      startup: Startup::default(),
+    }
   }
 
   fn build_connection_opts(
@@ -360,14 +788,63 @@ impl PostgresDriver<'_> {
 }
 
 async fn query_with_pool(pool: Arc<sqlx::Pool<Postgres>>, query: String) -> Result<Rows> {
-  query_with_stream(&*pool.clone(), &query).await
+  query_with_stream(&*pool.clone(), &query, 0).await
+}
+
+// headers are taken from the rows, so a query that returned none is prepared
+// to still find its columns
+async fn prepared_headers<'a, E>(e: E, query: &'a str) -> Headers
+where
+  E: sqlx::Executor<'a, Database = Postgres>,
+{
+  match e.prepare(query).await {
+    Ok(statement) => statement.columns().iter().map(column_header).collect(),
+    Err(e) => {
+      log::warn!("couldn't find the columns of an empty result: {e}");
+      vec![]
+    },
+  }
+}
+
+// errors point into the query as it was written, not the declaration around it
+async fn declare_cursor(conn: &mut PgConnection, query: &str) -> Result<()> {
+  let declaration = format!("BEGIN; DECLARE {CURSOR_NAME} NO SCROLL CURSOR FOR ");
+  match query_with_conn(conn, format!("{declaration}{query}"), 0).await {
+    Ok(_) => Ok(()),
+    Err(mut report) => {
+      if let Some(error) = report.downcast_mut::<DbError>() {
+        error.position = error.position.and_then(|position| position.checked_sub(declaration.chars().count()));
+        error.query = query.to_owned();
+      }
+      Err(report)
+    },
+  }
 }
 
-async fn query_with_conn(conn: &mut PgConnection, query: String) -> Result<Rows> {
-  query_with_stream(conn, &query).await
+async fn fetch_from_cursor(conn: &mut PgConnection, page_size: u64) -> Result<Rows> {
+  query_with_conn(conn, format!("FETCH FORWARD {page_size} FROM {CURSOR_NAME}"), 0).await
+}
+
+async fn query_with_conn(conn: &mut PgConnection, query: String, max_rows: u64) -> Result<Rows> {
+  query_with_stream(conn, &query, max_rows).await
+}
+
+async fn export_with_pool(pool: Arc<sqlx::Pool<Postgres>>, query: String, mut sink: Box<dyn RowSink>) -> Result<u64> {
+  let mut stream = sqlx::raw_sql(&query).fetch(&*pool);
+  let mut row_count: u64 = 0;
+  while let Some(row) = stream.next().await {
+    let row = row?;
+    if row_count == 0 {
+      sink.write_headers(&get_headers(&row))?;
+    }
+    sink.write_row(&row_to_vec(&row))?;
+    row_count += 1;
+  }
+  sink.finish()?;
+  Ok(row_count)
 }
 
-async fn query_with_stream<'a, E>(e: E, query: &'a str) -> Result<Rows>
+async fn query_with_stream<'a, E>(e: E, query: &'a str, max_rows: u64) -> Result<Rows>
 where
   E: sqlx::Executor<'a, Database = sqlx::Postgres>,
 {
@@ -387,8 +864,12 @@ where
         if headers.is_empty() {
           headers = get_headers(&row);
         }
+        // the row past the cap shows there were more, and the rest aren't read
+        if super::row_cap_reached(query_rows.len(), max_rows) {
+          break;
+        }
       },
-      Err(e) => return Err(eyre::Report::new(e)),
+      Err(e) => return Err(query_error(e, query)),
     }
   }
   Ok(Rows { rows_affected: query_rows_affected, headers, rows: query_rows })
@@ -406,7 +887,7 @@ where
   match first_query {
     Ok((first_query, statement_type)) => match statement_type {
       Statement::Explain { .. } => {
-        let result = query_with_stream(&mut *tx, &first_query).await;
+        let result = query_with_stream(&mut *tx, &first_query, 0).await;
         match result {
           Ok(result) => (Ok(Either::Right(result)), tx),
           Err(e) => (Err(e), tx),
@@ -416,7 +897,7 @@ where
         let result = sqlx::query(&first_query).execute(&mut *tx).await;
         match result {
           Ok(result) => (Ok(Either::Left(result.rows_affected())), tx),
-          Err(e) => (Err(e.into()), tx),
+          Err(e) => (Err(query_error(e, &first_query)), tx),
         }
       },
     },
@@ -424,12 +905,35 @@ where
   }
 }
 
+// keeps the detail, hint, and position postgres gives with an error, so
+// they can be shown against the query
+fn query_error(e: sqlx::Error, query: &str) -> eyre::Report {
+  match e.as_database_error().and_then(|e| e.try_downcast_ref::<PgDatabaseError>()) {
+    Some(pg) => eyre::Report::new(DbError {
+      message: pg.message().to_string(),
+      detail: pg.detail().map(String::from),
+      hint: pg.hint().map(String::from),
+      position: match pg.position() {
+        Some(PgErrorPosition::Original(position)) => Some(position),
+        _ => None,
+      },
+      query: query.to_string(),
+    }),
+    None => eyre::Report::new(e),
+  }
+}
+
 fn get_headers(row: &<sqlx::Postgres as sqlx::Database>::Row) -> Headers {
-  row
-    .columns()
-    .iter()
-    .map(|col| Header { name: col.name().to_string(), type_name: col.type_info().to_string() })
-    .collect()
+  row.columns().iter().map(column_header).collect()
+}
+
+// composites keep the names of their fields, so they can be shown with each field
+fn column_header(col: &PgColumn) -> Header {
+  let composite_fields = match col.type_info().kind() {
+    PgTypeKind::Composite(fields) => Some(fields.iter().map(|(name, _)| name.clone()).collect()),
+    _ => None,
+  };
+  Header { name: col.name().to_string(), type_name: col.type_info().to_string(), composite_fields }
 }
 
 fn row_to_vec(row: &<sqlx::Postgres as sqlx::Database>::Row) -> Vec<String> {
@@ -440,7 +944,7 @@ fn row_to_vec(row: &<sqlx::Postgres as sqlx::Database>::Row) -> Vec<String> {
 fn parse_value(row: &<Postgres as sqlx::Database>::Row, col: &<Postgres as sqlx::Database>::Column) -> Option<Value> {
   let col_type = col.type_info().to_string();
   if row.try_get_raw(col.ordinal()).is_ok_and(|v| v.is_null()) {
-    return Some(Value { parse_error: false, string: "NULL".to_string(), is_null: true });
+    return Some(Value { parse_error: false, string: NULL.to_string(), is_null: true });
   }
   match col_type.to_uppercase().as_str() {
     "TIMESTAMPTZ" => Some(row.try_get::<chrono::DateTime<chrono::Utc>, usize>(col.ordinal()).map_or(
@@ -459,6 +963,23 @@ fn parse_value(row: &<Postgres as sqlx::Database>::Row, col: &<Postgres as sqlx:
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
       |received| Value { parse_error: false, string: received.to_string(), is_null: false },
     )),
+    // the offset is always written out in full, like `04:05:06+02:00`
+    "TIMETZ" => Some(row.try_get::<PgTimeTz<chrono::NaiveTime, chrono::FixedOffset>, usize>(col.ordinal()).map_or(
+      Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
+      |received| Value { parse_error: false, string: format!("{}{}", received.time, received.offset), is_null: false },
+    )),
+    // sqlx can't decode intervals from text, and the text depends on the server's
+    // `IntervalStyle`, so it's written out the same way whatever that's set to
+    "INTERVAL" => Some(row.try_get_unchecked::<String, usize>(col.ordinal()).map_or(
+      Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
+      |received| Value { parse_error: false, string: format_interval(&received).unwrap_or(received), is_null: false },
+    )),
+    // numerics are kept as postgres wrote them, since they can be more precise than any float
+    "NUMERIC" | "MONEY" => Some(row.try_get_unchecked::<String, usize>(col.ordinal()).map_or(
+      Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
+      |received| Value { parse_error: false, string: received, is_null: false },
+    )),
+    // uuids are shown in their canonical hyphenated form, whatever form they were written in
     "UUID" => Some(row.try_get::<Uuid, usize>(col.ordinal()).map_or(
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
       |received| Value { parse_error: false, string: received.to_string(), is_null: false },
@@ -503,97 +1024,16 @@ fn parse_value(row: &<Postgres as sqlx::Database>::Row, col: &<Postgres as sqlx:
     },
     "BYTEA" => Some(row.try_get::<Vec<u8>, usize>(col.ordinal()).map_or(
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-      |received| Value {
-        parse_error: false,
-        string: received.iter().fold(String::new(), |mut output, b| {
-          let _ = write!(output, "{b:02X}");
-          output
-        }),
-        is_null: false,
-      },
+      |received| Value { parse_error: false, string: bytes_to_hex(&received), is_null: false },
     )),
     "VOID" => Some(Value { parse_error: false, string: "".to_string(), is_null: false }),
-    _ if col_type.to_uppercase().ends_with("[]") => {
-      let array_type = col_type.to_uppercase().replace("[]", "");
-      match array_type.as_str() {
-        "TIMESTAMPTZ" => Some(row.try_get::<Vec<chrono::DateTime<chrono::Utc>>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "TIMESTAMP" => Some(row.try_get::<Vec<chrono::NaiveDateTime>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "DATE" => Some(row.try_get::<Vec<chrono::NaiveDate>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "TIME" => Some(row.try_get::<Vec<chrono::NaiveTime>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "UUID" => Some(row.try_get::<Vec<Uuid>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "INET" | "CIDR" => Some(row.try_get::<Vec<std::net::IpAddr>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "JSON" | "JSONB" => Some(row.try_get::<Vec<serde_json::Value>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "BOOL" => Some(row.try_get::<Vec<bool>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "SMALLINT" | "SMALLSERIAL" | "INT2" => Some(row.try_get::<Vec<i16>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "INT" | "SERIAL" | "INT4" => Some(row.try_get::<Vec<i32>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "BIGINT" | "BIGSERIAL" | "INT8" => Some(row.try_get::<Vec<i64>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "REAL" | "FLOAT4" => Some(row.try_get::<Vec<f32>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "DOUBLE PRECISION" | "FLOAT8" => Some(row.try_get::<Vec<f64>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-        )),
-        "TEXT" | "VARCHAR" | "NAME" | "CITEXT" | "BPCHAR" | "CHAR" => {
-          Some(row.try_get::<Vec<String>, usize>(col.ordinal()).map_or(
-            Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-            |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-          ))
-        },
-        "BYTEA" => Some(row.try_get::<Vec<u8>, usize>(col.ordinal()).map_or(
-          Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-          |received| Value {
-            parse_error: false,
-            string: received.iter().fold(String::new(), |mut output, b| {
-              let _ = write!(output, "{b:02X}");
-              output
-            }),
-            is_null: false,
-          },
-        )),
-        _ => {
-          // try to cast custom or other types to strings
-          Some(row.try_get_unchecked::<Vec<String>, usize>(col.ordinal()).map_or(
-            Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-            |received| Value { parse_error: false, string: vec_to_string(received), is_null: false },
-          ))
-        },
-      }
-    },
+    // rows come back in text form, and an array's is kept as it is, like
+    // `{1,2,NULL}`, since decoding it element by element can't handle nulls
+    // or more than one dimension
+    _ if is_array_type(&col_type) => Some(row.try_get_unchecked::<String, usize>(col.ordinal()).map_or(
+      Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
+      |received| Value { parse_error: false, string: received, is_null: false },
+    )),
     _ => {
       // try to cast custom or other types to strings
       Some(row.try_get_unchecked::<String, usize>(col.ordinal()).map_or(
@@ -635,20 +1075,23 @@ mod tests {
       // lowercase
       (
         "select * from \"public\".\"users\"",
-        Ok(("SELECT * FROM \"public\".\"users\"".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from \"public\".\"users\"".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // newlines
-      ("select *\nfrom users;", Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_)))))),
+      (
+        "select *\nfrom users;",
+        Ok(("select *\nfrom users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+      ),
       // comment-only
       ("-- select * from users;", Err(ParseError::EmptyQuery("Parsed query is empty".to_owned()))),
       // commented line(s)
       (
         "-- select blah;\nselect * from users",
-        Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       (
         "-- select blah;\nselect * from users\n-- insert blah",
-        Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // update
       (
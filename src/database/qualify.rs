@@ -0,0 +1,233 @@
+use std::{collections::HashSet, ops::ControlFlow};
+
+use sqlparser::{
+  ast::{Ident, ObjectName, ObjectNamePart, Query, Statement, Visit, Visitor, visit_relations_mut},
+  tokenizer::Span,
+};
+
+use super::{Rows, location_offset, parse_statements, quote_identifier};
+use crate::cli::Driver;
+
+/// Tables known from the menu, along with the schemas that unqualified
+/// names resolve against. Used to find table references that would
+/// only resolve if they were schema-qualified.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaCatalog {
+  search_path: Vec<String>,
+  tables: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Qualification {
+  pub table: String,
+  pub qualified: String,
+}
+
+#[derive(Debug, Default)]
+struct CteNames(HashSet<String>);
+
+impl Visitor for CteNames {
+  type Break = ();
+
+  fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+    if let Some(with) = &query.with {
+      for cte in &with.cte_tables {
+        self.0.insert(cte.alias.name.value.to_lowercase());
+      }
+    }
+    ControlFlow::Continue(())
+  }
+}
+
+impl SchemaCatalog {
+  /// `menu` is expected to be the rows returned by `Database::load_menu()`.
+  pub fn new(search_path: Vec<String>, menu: &Rows) -> Self {
+    let tables = menu
+      .rows
+      .iter()
+      .filter(|row| !matches!(row.get(2).map(String::as_str), Some("function" | "procedure")))
+      .filter_map(|row| match (row.first(), row.get(1)) {
+        (Some(schema), Some(name)) => Some((schema.clone(), name.clone())),
+        _ => None,
+      })
+      .collect();
+    Self { search_path, tables }
+  }
+
+  /// Qualifies unqualified table references in the query that don't
+  /// resolve on the search path, but exist in exactly one other schema.
+  /// Ambiguous references are left alone. The text is kept as it was written
+  /// apart from the schemas put in front of table names. Returns the
+  /// rewritten query and the qualifications made, if anything was qualified.
+  pub fn qualify_query(&self, query: &str, driver: Driver) -> Option<(String, Vec<Qualification>)> {
+    let mut statements = parse_statements(query, driver).ok()?;
+    let (_, mut statement) = statements.pop().filter(|_| statements.is_empty())?;
+    let mut qualified = query.to_owned();
+    let mut names = self.qualify_names(&mut statement, driver);
+    // inserted from the end, so the offsets of the ones before stay put
+    names.sort_by_key(|(_, span)| std::cmp::Reverse(span.start));
+    let mut qualifications = vec![];
+    for (qualification, span) in names {
+      // a name the parser didn't say the place of can't be qualified in the text
+      if span == Span::empty() {
+        continue;
+      }
+      let schema = qualification.qualified.strip_suffix(&qualification.table).unwrap_or_default();
+      qualified.insert_str(location_offset(query, span.start), schema);
+      qualifications.insert(0, qualification);
+    }
+    if qualifications.is_empty() { None } else { Some((qualified, qualifications)) }
+  }
+
+  // the qualifications made, with where each table name was in the query
+  fn qualify_names(&self, statement: &mut Statement, driver: Driver) -> Vec<(Qualification, Span)> {
+    if !matches!(
+      statement,
+      Statement::Query(_) | Statement::Insert(_) | Statement::Update { .. } | Statement::Delete(_)
+    ) {
+      return vec![];
+    }
+    let mut cte_names = CteNames::default();
+    let _ = statement.visit(&mut cte_names);
+
+    let mut qualifications = vec![];
+    let _ = visit_relations_mut(statement, |name: &mut ObjectName| {
+      if let [ObjectNamePart::Identifier(ident)] = name.0.as_slice()
+        && !cte_names.0.contains(&ident.value.to_lowercase())
+        && let Some(schema) = self.resolve(ident)
+      {
+        let schema_ident = match driver {
+          Driver::MySql => Ident::with_quote('`', schema.clone()),
+          _ => Ident::with_quote('"', schema.clone()),
+        };
+        let qualification = Qualification {
+          table: ident.to_string(),
+          qualified: format!("{}.{}", quote_identifier(&schema, driver), ident),
+        };
+        qualifications.push((qualification, ident.span));
+        name.0.insert(0, ObjectNamePart::Identifier(schema_ident));
+      }
+      ControlFlow::<()>::Continue(())
+    });
+    qualifications
+  }
+
+  // returns the one schema outside of the search path that contains the table,
+  // or nothing if the table is on the search path, missing, or ambiguous.
+  fn resolve(&self, ident: &Ident) -> Option<String> {
+    // unquoted identifiers are matched case-insensitively, since databases
+    // differ in whether they fold them to upper or lower case.
+    let matches_ident = |name: &str| match ident.quote_style {
+      Some(_) => name == ident.value,
+      None => name.eq_ignore_ascii_case(&ident.value),
+    };
+    let on_search_path = |schema: &str| self.search_path.iter().any(|s| s == schema);
+    if self.tables.iter().any(|(schema, name)| on_search_path(schema) && matches_ident(name)) {
+      return None;
+    }
+    // sqlite has no schemas, so its tables are listed with an empty one
+    let candidates = self
+      .tables
+      .iter()
+      .filter(|(schema, name)| !schema.is_empty() && matches_ident(name))
+      .map(|(schema, _)| schema.clone())
+      .collect::<HashSet<_>>();
+    match candidates.len() {
+      1 => candidates.into_iter().next(),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn catalog() -> SchemaCatalog {
+    let rows = Rows {
+      headers: vec![],
+      rows: [
+        ("public", "orders"),
+        ("sales", "users"),
+        ("sales", "accounts"),
+        ("billing", "accounts"),
+        ("public", "invoices"),
+        ("billing", "invoices"),
+      ]
+      .iter()
+      .map(|(schema, name)| vec![schema.to_string(), name.to_string(), "table".to_string()])
+      .collect(),
+      rows_affected: None,
+    };
+    SchemaCatalog::new(vec!["public".to_string()], &rows)
+  }
+
+  fn qualify(query: &str) -> (String, Vec<Qualification>) {
+    catalog().qualify_query(query, Driver::Postgres).unwrap_or_else(|| (query.to_owned(), vec![]))
+  }
+
+  #[test]
+  fn test_qualify() {
+    let test_cases = vec![
+      // only exists outside the search path
+      ("SELECT * FROM users", "SELECT * FROM \"sales\".users"),
+      (
+        "SELECT * FROM USERS u JOIN orders o ON u.id = o.user_id",
+        "SELECT * FROM \"sales\".USERS u JOIN orders o ON u.id = o.user_id",
+      ),
+      ("DELETE FROM users WHERE id = 1", "DELETE FROM \"sales\".users WHERE id = 1"),
+      // already qualified
+      ("SELECT * FROM sales.users", "SELECT * FROM sales.users"),
+      // on the search path, even though it also exists elsewhere
+      ("SELECT * FROM invoices", "SELECT * FROM invoices"),
+      // ambiguous
+      ("SELECT * FROM accounts", "SELECT * FROM accounts"),
+      // unknown
+      ("SELECT * FROM missing", "SELECT * FROM missing"),
+      // quoted names must match exactly
+      ("SELECT * FROM \"Users\"", "SELECT * FROM \"Users\""),
+      // cte names shadow tables
+      ("WITH users AS (SELECT 1) SELECT * FROM users", "WITH users AS (SELECT 1) SELECT * FROM users"),
+      // ddl is never rewritten
+      ("DROP TABLE users", "DROP TABLE users"),
+    ];
+
+    for (query, expected) in test_cases {
+      assert_eq!(qualify(query).0, expected, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_qualify_query() {
+    let test_cases = vec![
+      ("select * from users", Some("select * from \"sales\".users")),
+      (
+        "-- who ordered\nselect u.name, 'from users' from USERS u\n  join orders o on u.id = o.user_id;",
+        Some(
+          "-- who ordered\nselect u.name, 'from users' from \"sales\".USERS u\n  join orders o on u.id = o.user_id;",
+        ),
+      ),
+      (
+        "select * from users join users_sales using (id)",
+        Some("select * from \"sales\".users join users_sales using (id)"),
+      ),
+      ("select 'ü', * from users, users", Some("select 'ü', * from \"sales\".users, \"sales\".users")),
+      ("select * from orders", None),
+      ("select * from users; select 1", None),
+    ];
+
+    for (query, expected) in test_cases {
+      let qualified = catalog().qualify_query(query, Driver::Postgres).map(|(query, _)| query);
+      assert_eq!(qualified.as_deref(), expected, "Failed for query: {query}");
+    }
+  }
+
+  #[test]
+  fn test_qualifications_reported() {
+    let (_, qualifications) = qualify("SELECT * FROM users");
+    assert_eq!(
+      qualifications,
+      vec![Qualification { table: "users".to_string(), qualified: "\"sales\".users".to_string() }]
+    );
+  }
+}
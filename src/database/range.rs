@@ -0,0 +1,136 @@
+// postgres ranges come back in their text form, like `[1,10)` or
+// `["2024-01-01 00:00:00","2024-02-01 00:00:00")`, which is kept as the value.
+// these read it back to show it without the quoting.
+
+/// A range, with its bounds `None` where it's unbounded on that side.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Range {
+  Empty,
+  Bounded { lower: Option<String>, upper: Option<String>, lower_inclusive: bool, upper_inclusive: bool },
+}
+
+/// Whether values of the type are ranges, like postgres's `INT4RANGE`.
+pub fn is_range_type(type_name: &str) -> bool {
+  type_name.to_ascii_uppercase().ends_with("RANGE")
+}
+
+/// Parses the text form of a range. Returns `None` if the value isn't one.
+pub fn parse_range(value: &str) -> Option<Range> {
+  let value = value.trim();
+  if value.eq_ignore_ascii_case("empty") {
+    return Some(Range::Empty);
+  }
+  let lower_inclusive = match value.chars().next()? {
+    '[' => true,
+    '(' => false,
+    _ => return None,
+  };
+  let upper_inclusive = match value.chars().last()? {
+    ']' => true,
+    ')' => false,
+    _ => return None,
+  };
+  let mut chars = value.get(1..value.len() - 1)?.chars().peekable();
+  let lower = parse_bound(&mut chars)?;
+  chars.next_if_eq(&',')?;
+  let upper = parse_bound(&mut chars)?;
+  chars.next().is_none().then_some(Range::Bounded { lower, upper, lower_inclusive, upper_inclusive })
+}
+
+// a bound with nothing in it is unbounded, while `""` is an empty string
+fn parse_bound(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<Option<String>> {
+  let mut bound: Option<String> = None;
+  while let Some(c) = chars.next_if(|c| *c != ',') {
+    let text = bound.get_or_insert_with(String::new);
+    match c {
+      '"' => loop {
+        match chars.next()? {
+          '"' if chars.next_if_eq(&'"').is_some() => text.push('"'),
+          '"' => break,
+          '\\' => text.push(chars.next()?),
+          c => text.push(c),
+        }
+      },
+      '\\' => text.push(chars.next()?),
+      c => text.push(c),
+    }
+  }
+  Some(bound)
+}
+
+/// The range on one line, like `[1, 10)` or `[2024-01-01, ∞)`. Returns `None`
+/// if the value isn't a range.
+pub fn format_range(value: &str) -> Option<String> {
+  match parse_range(value)? {
+    Range::Empty => Some("empty".to_owned()),
+    Range::Bounded { lower, upper, lower_inclusive, upper_inclusive } => Some(format!(
+      "{}{}, {}{}",
+      if lower_inclusive { '[' } else { '(' },
+      lower.as_deref().unwrap_or("-∞"),
+      upper.as_deref().unwrap_or("∞"),
+      if upper_inclusive { ']' } else { ')' },
+    )),
+  }
+}
+
+/// The range's bounds one a line, saying whether each is included.
+pub fn format_range_list(value: &str) -> Option<String> {
+  let bound = |name: &str, bound: &Option<String>, inclusive: bool| match (bound, inclusive) {
+    (None, _) => format!("{name}: unbounded"),
+    (Some(bound), true) => format!("{name}: {bound} (inclusive)"),
+    (Some(bound), false) => format!("{name}: {bound} (exclusive)"),
+  };
+  match parse_range(value)? {
+    Range::Empty => Some("empty".to_owned()),
+    Range::Bounded { lower, upper, lower_inclusive, upper_inclusive } => {
+      Some(format!("{}\n{}", bound("lower", &lower, lower_inclusive), bound("upper", &upper, upper_inclusive)))
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_range() {
+    assert_eq!(
+      parse_range("[1,10)"),
+      Some(Range::Bounded {
+        lower: Some("1".to_owned()),
+        upper: Some("10".to_owned()),
+        lower_inclusive: true,
+        upper_inclusive: false
+      })
+    );
+    assert_eq!(
+      parse_range(r#"("2024-01-01 00:00:00",]"#),
+      Some(Range::Bounded {
+        lower: Some("2024-01-01 00:00:00".to_owned()),
+        upper: None,
+        lower_inclusive: false,
+        upper_inclusive: true
+      })
+    );
+    assert_eq!(parse_range("empty"), Some(Range::Empty));
+    assert_eq!(parse_range("1,10"), None);
+    assert_eq!(parse_range("[1,2,3)"), None);
+    assert_eq!(parse_range("["), None);
+  }
+
+  #[test]
+  fn test_format_range() {
+    assert!(is_range_type("INT4RANGE"));
+    assert!(is_range_type("tstzrange"));
+    assert!(!is_range_type("INT4"));
+    assert_eq!(format_range("[1,10)").as_deref(), Some("[1, 10)"));
+    assert_eq!(
+      format_range(r#"["2024-01-01 00:00:00","2024-02-01 00:00:00")"#).as_deref(),
+      Some("[2024-01-01 00:00:00, 2024-02-01 00:00:00)")
+    );
+    assert_eq!(format_range("(,5]").as_deref(), Some("(-∞, 5]"));
+    assert_eq!(format_range("empty").as_deref(), Some("empty"));
+    assert_eq!(format_range("nope"), None);
+    assert_eq!(format_range_list("[1,)").as_deref(), Some("lower: 1 (inclusive)\nupper: unbounded"));
+  }
+}
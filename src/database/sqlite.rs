@@ -1,6 +1,6 @@
 use std::{
-  fmt::Write,
   io::{self, Write as _},
+  path::Path,
   str::FromStr,
   string::String,
   sync::Arc,
@@ -11,12 +11,18 @@ use color_eyre::eyre::{self, Result};
 use futures::stream::StreamExt;
 use sqlparser::ast::Statement;
 use sqlx::{
-  Column, Either, Row, ValueRef,
-  sqlite::{Sqlite, SqliteConnectOptions, SqlitePoolOptions},
+  Column, Either, Row, Statement as _, ValueRef,
+  pool::PoolConnection,
+  sqlite::{Sqlite, SqliteConnectOptions, SqliteConnection, SqlitePoolOptions},
   types::uuid,
 };
+use tokio::sync::Mutex;
 
-use super::{Database, DbTaskResult, Driver, Header, Headers, QueryResultsWithMetadata, QueryTask, Rows, Value};
+use super::{
+  ConnectionInfo, Database, DbTaskResult, Driver, ExportTask, FOREIGN_KEY_ICON, Header, Headers, NULL,
+  PRIMARY_KEY_ICON, QueryResultsWithMetadata, QueryTask, RowSink, Rows, Startup, TableStatsTask, Value, bytes_to_hex,
+  create_statements, quote_string,
+};
 
 type SqliteTransaction<'a> = sqlx::Transaction<'a, Sqlite>;
 type TransactionTask<'a> = tokio::task::JoinHandle<(QueryResultsWithMetadata, SqliteTransaction<'a>)>;
@@ -29,18 +35,38 @@ enum SqliteTask<'a> {
 #[derive(Default)]
 pub struct SqliteDriver<'a> {
   pool: Option<Arc<sqlx::Pool<Sqlite>>>,
+  // kept to open another file with the same options
+  opts: Option<SqliteConnectOptions>,
   task: Option<SqliteTask<'a>>,
+  // the connection the statements of a script run on, when one is pinned
+  pinned_conn: Option<Arc<Mutex<PoolConnection<Sqlite>>>>,
+  // the `max_rows_fetched` setting
+  max_rows: u64,
+  startup: Startup,
 }
 
 #[async_trait(?Send)]
 impl Database for SqliteDriver<'_> {
   async fn init(&mut self, args: crate::cli::Cli) -> Result<()> {
+    self.startup = Startup::new(args.startup_sql.as_ref(), args.startup_sql_required)?;
     let opts = super::sqlite::SqliteDriver::<'_>::build_connection_opts(args)?;
-    let pool = Arc::new(SqlitePoolOptions::new().max_connections(3).connect_with(opts).await?);
+    let pool = Arc::new(
+      SqlitePoolOptions::new()
+        .max_connections(3)
+        .after_connect(self.startup.after_connect::<SqliteConnection>())
+        .connect_with(opts.clone())
+        .await?,
+    );
+    self.startup.check()?;
     self.pool = Some(pool);
+    self.opts = Some(opts);
     Ok(())
   }
 
+  fn take_startup_errors(&self) -> Vec<String> {
+    self.startup.take_errors()
+  }
+
   // since it's possible for raw_sql to execute multiple queries in a single string,
   // we only execute the first one and then drop the rest.
   async fn start_query(&mut self, query: String, bypass_parser: bool) -> Result<()> {
@@ -51,9 +77,21 @@ impl Database for SqliteDriver<'_> {
         (first, Some(stmt))
       },
     };
-    let pool = self.pool.clone().unwrap();
+    let describe = matches!(statement_type, Some(Statement::Query(_)));
+    let conn = match &self.pinned_conn {
+      Some(conn) => conn.clone(),
+      None => Arc::new(Mutex::new(self.pool.clone().unwrap().acquire().await?)),
+    };
+    let max_rows = self.max_rows;
     self.task = Some(SqliteTask::Query(tokio::spawn(async move {
-      let results = query_with_pool(pool, first_query.clone()).await;
+      let mut conn = conn.lock().await;
+      let mut results = query_with_stream(conn.as_mut(), &first_query, max_rows).await;
+      if let Ok(rows) = results.as_mut()
+        && describe
+        && rows.headers.is_empty()
+      {
+        rows.headers = prepared_headers(conn.as_mut(), &first_query).await;
+      }
       match results {
         Ok(ref rows) => {
           log::info!("{:?} rows, {:?} affected", rows.rows.len(), rows.rows_affected);
@@ -67,6 +105,22 @@ impl Database for SqliteDriver<'_> {
     Ok(())
   }
 
+  async fn pin_connection(&mut self) -> Result<()> {
+    let pool = self.pool.clone().unwrap();
+    self.pinned_conn = Some(Arc::new(Mutex::new(pool.acquire().await?)));
+    Ok(())
+  }
+
+  // an in-memory database only lasts as long as its connections, so the
+  // connection goes back to the pool instead of being closed. rolling back
+  // fails when there's no transaction, which is fine.
+  async fn unpin_connection(&mut self) -> Result<()> {
+    if let Some(conn) = self.pinned_conn.take() {
+      sqlx::raw_sql("ROLLBACK").execute(conn.lock().await.as_mut()).await.ok();
+    }
+    Ok(())
+  }
+
   async fn abort_query(&mut self) -> Result<bool> {
     match self.task.take() {
       Some(task) => {
@@ -170,10 +224,36 @@ impl Database for SqliteDriver<'_> {
     Ok(())
   }
 
-  async fn load_menu(&self) -> Result<Rows> {
+  // sqlite has no server-side cursors, so queries are paged with a limit and offset instead
+  async fn start_cursor(&mut self, _query: String, _page_size: u64) -> Result<bool> {
+    Ok(false)
+  }
+
+  async fn fetch_cursor(&mut self, _page_size: u64) -> Result<()> {
+    Err(eyre::Report::msg("SQLite does not support cursors"))
+  }
+
+  async fn close_cursor(&mut self) -> Result<()> {
+    Ok(())
+  }
+
+  fn start_export(&self, query: String, sink: Box<dyn RowSink>) -> Result<ExportTask> {
+    let pool = self.pool.clone().unwrap();
+    Ok(tokio::spawn(async move { export_with_pool(pool, query, sink).await }))
+  }
+
+  fn start_table_stats(&self) -> Result<Option<TableStatsTask>> {
+    Ok(None)
+  }
+
+  async fn load_menu(&self, system_schemas: bool) -> Result<Rows> {
+    // sqlite has no schemas to leave out, but it does keep its own tables, like
+    // sqlite_sequence, alongside the user's
+    let system_filter = if system_schemas { "true" } else { "name not like 'sqlite_%'" };
     query_with_pool(
       self.pool.clone().unwrap(),
-      "select '' as table_schema,
+      format!(
+        "select '' as table_schema,
         name as table_name,
         case
           when type = 'table' then 'table'
@@ -182,19 +262,98 @@ impl Database for SqliteDriver<'_> {
         end as object_kind
       from sqlite_master
       where type in ('table', 'view')
-      and name not like 'sqlite_%'
+      and {system_filter}
       order by object_kind, name asc"
-        .to_owned(),
+      ),
     )
     .await
   }
 
+  async fn search_path(&self) -> Result<Vec<String>> {
+    Ok(vec![])
+  }
+
+  // pk is each column's position in the primary key, or 0 for columns outside it
+  async fn primary_key(&self, schema: Option<&str>, table: &str) -> Result<Vec<String>> {
+    let schema = schema.map(|schema| format!(", {}", quote_string(schema, Driver::Sqlite))).unwrap_or_default();
+    let query = format!(
+      "select name from pragma_table_info({}{schema}) where pk > 0 order by pk",
+      quote_string(table, Driver::Sqlite)
+    );
+    let rows = query_with_pool(self.pool.clone().unwrap(), query).await?;
+    Ok(rows.rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+  }
+
+  fn connection_info(&self) -> ConnectionInfo {
+    ConnectionInfo {
+      database: self.opts.as_ref().map(|opts| opts.get_filename().display().to_string()),
+      ..ConnectionInfo::default()
+    }
+  }
+
+  // each database is its own file, so these are the ones next to the open file
+  async fn list_databases(&self) -> Result<(Vec<String>, Option<String>)> {
+    let path = self.opts.as_ref().unwrap().get_filename();
+    match (path.is_file(), path.parent(), path.file_name()) {
+      (true, Some(dir), Some(current)) => Ok((
+        database_files(if dir.as_os_str().is_empty() { Path::new(".") } else { dir })?,
+        current.to_str().map(String::from),
+      )),
+      _ => Err(eyre::Report::msg("An in-memory SQLite database has no other files to switch to")),
+    }
+  }
+
+  async fn use_database(&mut self, name: &str) -> Result<()> {
+    let opts = self.opts.clone().unwrap();
+    let path = opts.get_filename().with_file_name(name);
+    let opts = opts.filename(path);
+    let pool = Arc::new(
+      SqlitePoolOptions::new()
+        .max_connections(3)
+        .after_connect(self.startup.after_connect::<SqliteConnection>())
+        .connect_with(opts.clone())
+        .await?,
+    );
+    self.startup.check()?;
+    self.pool = Some(pool);
+    self.opts = Some(opts);
+    Ok(())
+  }
+
+  async fn load_routine_source(&self, schema: &str, routine: &str, procedure: bool) -> Result<String> {
+    Err(eyre::Report::msg("SQLite does not support stored routines"))
+  }
+
+  // sqlite keeps the statements each was created with, including its indexes.
+  // ones it made itself, for primary keys and unique constraints, have none.
+  async fn load_create_statement(&self, schema: &str, table: &str) -> Result<String> {
+    let query = format!(
+      "select sql from sqlite_master where tbl_name = {} and sql is not null order by type != 'table', name",
+      quote_string(table, Driver::Sqlite)
+    );
+    create_statements(query_with_pool(self.pool.clone().unwrap(), query).await?, 0, table)
+  }
+
   fn preview_rows_query(&self, schema: &str, table: &str) -> String {
     format!("select * from \"{table}\" limit 100")
   }
 
   fn preview_columns_query(&self, schema: &str, table: &str) -> String {
-    format!("pragma table_info(\"{table}\")")
+    format!(
+      "select
+        trim(
+          case when c.pk > 0 then '{PRIMARY_KEY_ICON}' else '' end || ' ' ||
+          case when exists (
+            select 1 from pragma_foreign_key_list('{table}') f where f.\"from\" = c.name
+          ) then '{FOREIGN_KEY_ICON}' else '' end
+        ) as key,
+        c.name as column_name,
+        c.type as data_type,
+        case when c.\"notnull\" then 'NO' else 'YES' end as is_nullable,
+        c.dflt_value as column_default
+      from pragma_table_info('{table}') c
+      order by c.cid"
+    )
   }
 
   fn preview_constraints_query(&self, schema: &str, table: &str) -> String {
@@ -209,17 +368,61 @@ impl Database for SqliteDriver<'_> {
     "select 'SQLite does not support row-level security policies' as message".to_owned()
   }
 
+  fn preview_foreign_keys_query(&self, schema: &str, table: &str) -> String {
+    // sqlite only reports the keys a table declares, so references to this
+    // table are found by checking every other table
+    format!(
+      "select 'outgoing' as direction,
+        '{table}' as from_table,
+        group_concat(\"from\", ', ') as from_columns,
+        \"table\" as to_table,
+        group_concat(\"to\", ', ') as to_columns,
+        on_delete,
+        on_update
+      from pragma_foreign_key_list('{table}')
+      group by id
+      union all
+      select 'incoming',
+        m.name,
+        group_concat(f.\"from\", ', '),
+        f.\"table\",
+        group_concat(f.\"to\", ', '),
+        f.on_delete,
+        f.on_update
+      from sqlite_master m
+      join pragma_foreign_key_list(m.name) f
+      where m.type = 'table'
+        and m.name != '{table}'
+        and f.\"table\" = '{table}' collate nocase
+      group by m.name, f.id"
+    )
+  }
+
+  fn preview_triggers_query(&self, schema: &str, table: &str) -> String {
+    format!(
+      "select name, sql as source from sqlite_master where type = 'trigger' and tbl_name = '{table}' order by name"
+    )
+  }
+
   fn preview_view_definition_query(&self, schema: &str, view: &str, materialized: bool) -> String {
     if materialized {
       return "select 'SQLite does not support materialized views' as message".to_owned();
     }
     format!("select sql as definition from sqlite_master where type = 'view' and name = '{view}'")
   }
+
+  fn preview_routine_source_query(&self, schema: &str, routine: &str, procedure: bool) -> String {
+    "select 'SQLite does not support stored routines' as message".to_owned()
+  }
+
+  fn preview_foreign_table_query(&self, schema: &str, table: &str) -> String {
+    "select 'SQLite does not support foreign tables' as message".to_owned()
+  }
 }
 
 impl SqliteDriver<'_> {
-  pub fn new() -> Self {
-    Self { pool: None, task: None }
+  pub fn new(max_rows: u64) -> Self {
+    Self { pool: None, opts: None, task: None, pinned_conn: None, max_rows, startup: Startup::default() }
   }
 
   fn build_connection_opts(
@@ -249,11 +452,67 @@ impl SqliteDriver<'_> {
   }
 }
 
+// the names of the files in the directory with a sqlite extension, sorted
+fn database_files(dir: &Path) -> Result<Vec<String>> {
+  let mut files: Vec<String> = std::fs::read_dir(dir)?
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| {
+      path.is_file()
+        && path
+          .extension()
+          .and_then(|extension| extension.to_str())
+          .is_some_and(|extension| matches!(extension, "db" | "db3" | "sqlite" | "sqlite3"))
+    })
+    .filter_map(|path| path.file_name().and_then(|name| name.to_str()).map(String::from))
+    .collect();
+  files.sort();
+  Ok(files)
+}
+
+// headers are taken from the rows, so a query that returned none is prepared
+// to still find its columns
+async fn prepared_headers<'a, E>(e: E, query: &'a str) -> Headers
+where
+  E: sqlx::Executor<'a, Database = Sqlite>,
+{
+  match e.prepare(query).await {
+    Ok(statement) => statement
+      .columns()
+      .iter()
+      .map(|col| Header {
+        name: col.name().to_string(),
+        type_name: col.type_info().to_string(),
+        composite_fields: None,
+      })
+      .collect(),
+    Err(e) => {
+      log::warn!("couldn't find the columns of an empty result: {e}");
+      vec![]
+    },
+  }
+}
+
 async fn query_with_pool(pool: Arc<sqlx::Pool<Sqlite>>, query: String) -> Result<Rows> {
-  query_with_stream(&*pool.clone(), &query).await
+  query_with_stream(&*pool.clone(), &query, 0).await
 }
 
-async fn query_with_stream<'a, E>(e: E, query: &'a str) -> Result<Rows>
+async fn export_with_pool(pool: Arc<sqlx::Pool<Sqlite>>, query: String, mut sink: Box<dyn RowSink>) -> Result<u64> {
+  let mut stream = sqlx::raw_sql(&query).fetch(&*pool);
+  let mut row_count: u64 = 0;
+  while let Some(row) = stream.next().await {
+    let row = row?;
+    if row_count == 0 {
+      sink.write_headers(&get_headers(&row))?;
+    }
+    sink.write_row(&row_to_vec(&row))?;
+    row_count += 1;
+  }
+  sink.finish()?;
+  Ok(row_count)
+}
+
+async fn query_with_stream<'a, E>(e: E, query: &'a str, max_rows: u64) -> Result<Rows>
 where
   E: sqlx::Executor<'a, Database = sqlx::Sqlite>,
 {
@@ -273,6 +532,10 @@ where
         if headers.is_empty() {
           headers = get_headers(&row);
         }
+        // the row past the cap shows there were more, and the rest aren't read
+        if super::row_cap_reached(query_rows.len(), max_rows) {
+          break;
+        }
       },
       Err(e) => return Err(eyre::Report::new(e)),
     }
@@ -292,7 +555,7 @@ where
   match first_query {
     Ok((first_query, statement_type)) => match statement_type {
       Statement::Explain { .. } => {
-        let result = query_with_stream(&mut *tx, &first_query).await;
+        let result = query_with_stream(&mut *tx, &first_query, 0).await;
         match result {
           Ok(result) => (Ok(Either::Right(result)), tx),
           Err(e) => (Err(e), tx),
@@ -314,7 +577,7 @@ fn get_headers(row: &<sqlx::Sqlite as sqlx::Database>::Row) -> Headers {
   row
     .columns()
     .iter()
-    .map(|col| Header { name: col.name().to_string(), type_name: col.type_info().to_string() })
+    .map(|col| Header { name: col.name().to_string(), type_name: col.type_info().to_string(), composite_fields: None })
     .collect()
 }
 
@@ -326,7 +589,7 @@ fn row_to_vec(row: &<sqlx::Sqlite as sqlx::Database>::Row) -> Vec<String> {
 fn parse_value(row: &<Sqlite as sqlx::Database>::Row, col: &<Sqlite as sqlx::Database>::Column) -> Option<Value> {
   let col_type = col.type_info().to_string();
   if row.try_get_raw(col.ordinal()).is_ok_and(|v| v.is_null()) {
-    return Some(Value { parse_error: false, string: "NULL".to_string(), is_null: true });
+    return Some(Value { parse_error: false, string: NULL.to_string(), is_null: true });
   }
   match col_type.to_uppercase().as_str() {
     "BOOLEAN" => Some(row.try_get::<bool, usize>(col.ordinal()).map_or(
@@ -368,20 +631,7 @@ fn parse_value(row: &<Sqlite as sqlx::Database>::Row, col: &<Sqlite as sqlx::Dat
     },
     "BLOB" => Some(row.try_get::<Vec<u8>, usize>(col.ordinal()).map_or(
       Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
-      |received| {
-        if let Ok(s) = String::from_utf8(received.clone()) {
-          Value { parse_error: false, string: s, is_null: false }
-        } else {
-          Value {
-            parse_error: false,
-            string: received.iter().fold(String::new(), |mut output, b| {
-              let _ = write!(output, "{b:02X}");
-              output
-            }),
-            is_null: false,
-          }
-        }
-      },
+      |received| Value { parse_error: false, string: bytes_to_hex(&received), is_null: false },
     )),
     "DATETIME" => {
       // Similar to TEXT, but we'll try timestamp first
@@ -457,16 +707,19 @@ mod tests {
       // lowercase
       (
         "select * from `users`",
-        Ok(("SELECT * FROM `users`".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from `users`".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // newlines
-      ("select *\nfrom users;", Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_)))))),
+      (
+        "select *\nfrom users;",
+        Ok(("select *\nfrom users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+      ),
       // comment-only
       ("-- select * from users;", Err(ParseError::EmptyQuery("Parsed query is empty".to_owned()))),
       // commented line(s)
       (
         "-- select blah;\nselect * from users",
-        Ok(("SELECT * FROM users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
+        Ok(("select * from users".to_owned(), Box::new(|s| matches!(s, Statement::Query(_))))),
       ),
       // update
       (
@@ -535,4 +788,15 @@ mod tests {
       );
     }
   }
+
+  #[test]
+  fn test_database_files() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_sqlite_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested.db")).unwrap();
+    for file in ["b.sqlite", "a.db", "notes.txt", "c.sqlite3"] {
+      std::fs::write(dir.join(file), "").unwrap();
+    }
+    assert_eq!(database_files(&dir).unwrap(), vec!["a.db", "b.sqlite", "c.sqlite3"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
 }
@@ -0,0 +1,176 @@
+// sql run on each new connection before it's used, like `SET statement_timeout`
+// or temporary views. settings and temporary objects only last as long as the
+// session they were made in, so it runs on every connection of the pool.
+use std::{
+  convert::Infallible,
+  path::PathBuf,
+  str::FromStr,
+  sync::{Arc, Mutex, PoisonError},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use sqlx::{Executor, pool::PoolConnectionMetadata};
+
+/// The startup sql of a connection, as a file or as statements listed in the
+/// config.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum StartupSql {
+  File(PathBuf),
+  Statements(Vec<String>),
+}
+
+// the command line takes a file
+impl FromStr for StartupSql {
+  type Err = Infallible;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(Self::File(PathBuf::from(s)))
+  }
+}
+
+impl StartupSql {
+  // each script runs as a whole, so a file can hold several statements. the
+  // label says which one failed.
+  fn scripts(&self) -> Result<Vec<(String, String)>> {
+    match self {
+      Self::File(path) => std::fs::read_to_string(path)
+        .map(|script| vec![(path.display().to_string(), script)])
+        .map_err(|e| eyre!("couldn't read {}: {e}", path.display())),
+      Self::Statements(statements) => Ok(
+        statements
+          .iter()
+          .enumerate()
+          .map(|(i, statement)| (format!("statement {}", i + 1), statement.clone()))
+          .collect(),
+      ),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Startup {
+  scripts: Arc<Vec<(String, String)>>,
+  // a failure fails the connection, instead of only being reported
+  required: bool,
+  // failures from any connection of the pool, until they're taken to be shown
+  errors: Arc<Mutex<Vec<String>>>,
+}
+
+impl Startup {
+  /// Reads the startup sql. A file that can't be read only fails if the
+  /// startup sql is required, and is reported otherwise.
+  pub fn new(sql: Option<&StartupSql>, required: bool) -> Result<Self> {
+    let startup = Self { required, ..Self::default() };
+    match sql.map(StartupSql::scripts).transpose() {
+      Ok(scripts) => Ok(Self { scripts: Arc::new(scripts.unwrap_or_default()), ..startup }),
+      Err(e) if required => Err(e),
+      Err(e) => {
+        startup.report(e.to_string());
+        Ok(startup)
+      },
+    }
+  }
+
+  /// Runs the startup sql on each new connection of a pool, as its
+  /// `after_connect`. Failures are only reported here, since the pool
+  /// retries connections that fail `after_connect` until it times out.
+  pub fn after_connect<C>(
+    &self,
+  ) -> impl for<'c> Fn(&'c mut C, PoolConnectionMetadata) -> BoxFuture<'c, Result<(), sqlx::Error>> + Send + Sync + 'static
+  where
+    C: Send,
+    for<'c> &'c mut C: Executor<'c>,
+  {
+    let startup = self.clone();
+    move |conn, _| {
+      let startup = startup.clone();
+      Box::pin(async move {
+        for (label, script) in startup.scripts.iter() {
+          if let Err(e) = sqlx::raw_sql(script).execute(&mut *conn).await {
+            startup.report(format!("{label}: {e}"));
+          }
+        }
+        Ok(())
+      })
+    }
+  }
+
+  /// Fails with the failures so far if the startup sql is required, once the
+  /// first connection of the pool is made.
+  pub fn check(&self) -> Result<()> {
+    match self.take_errors() {
+      errors if self.required && !errors.is_empty() => Err(eyre!("startup sql failed: {}", errors.join("; "))),
+      errors => {
+        // put back to be shown
+        self.errors.lock().unwrap_or_else(PoisonError::into_inner).extend(errors);
+        Ok(())
+      },
+    }
+  }
+
+  /// Logs a failure, and keeps it to be shown. The connections of a pool
+  /// fail the same way, so each failure is kept once.
+  pub fn report(&self, error: String) {
+    log::error!("startup sql failed: {error}");
+    let mut errors = self.errors.lock().unwrap_or_else(PoisonError::into_inner);
+    if !errors.contains(&error) {
+      errors.push(error);
+    }
+  }
+
+  /// The failures since they were last taken.
+  pub fn take_errors(&self) -> Vec<String> {
+    std::mem::take(&mut *self.errors.lock().unwrap_or_else(PoisonError::into_inner))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_startup_sql() {
+    let sql = StartupSql::Statements(vec!["set statement_timeout = '30s'".to_owned(), "select 1".to_owned()]);
+    let startup = Startup::new(Some(&sql), false).unwrap();
+    assert_eq!(startup.scripts[1], ("statement 2".to_owned(), "select 1".to_owned()));
+    assert!(startup.take_errors().is_empty());
+
+    // a missing file is reported, unless the startup sql is required
+    let sql = StartupSql::File(PathBuf::from("/nonexistent/startup.sql"));
+    assert!(Startup::new(Some(&sql), true).is_err());
+    let startup = Startup::new(Some(&sql), false).unwrap();
+    assert!(startup.scripts.is_empty());
+    startup.report("statement 1: oops".to_owned());
+    startup.report("statement 1: oops".to_owned());
+    assert_eq!(startup.take_errors().len(), 2);
+    assert!(startup.take_errors().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_startup_sql_runs_on_connect() {
+    use sqlx::sqlite::{SqliteConnection, SqlitePoolOptions};
+
+    let connect = |startup: Startup| {
+      SqlitePoolOptions::new()
+        .max_connections(1)
+        .after_connect(startup.after_connect::<SqliteConnection>())
+        .connect("sqlite::memory:")
+    };
+    let sql = StartupSql::Statements(vec!["create temp view answer as select 42".to_owned(), "selec 1".to_owned()]);
+    let startup = Startup::new(Some(&sql), false).unwrap();
+    let pool = connect(startup.clone()).await.unwrap();
+    let answer: (i64,) = sqlx::query_as("select * from answer").fetch_one(&pool).await.unwrap();
+    assert_eq!(answer.0, 42);
+    assert!(startup.check().is_ok());
+    let errors = startup.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("statement 2: "));
+
+    let startup = Startup::new(Some(&sql), true).unwrap();
+    connect(startup.clone()).await.unwrap();
+    assert!(startup.check().is_err());
+  }
+}
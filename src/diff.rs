@@ -0,0 +1,130 @@
+// comparing query results with results pinned earlier, to see which rows a
+// change to a query added, removed, or changed
+use std::collections::{HashMap, VecDeque};
+
+use color_eyre::eyre::{Result, eyre};
+
+use crate::database::Rows;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowChange {
+  Unchanged,
+  Added,
+  Removed,
+  // the columns whose values differ from the pinned row's
+  Changed(Vec<usize>),
+}
+
+/// The rows of both results, with how each changed.
+#[derive(Debug, Clone)]
+pub struct ResultDiff {
+  pub rows: Rows,
+  pub changes: Vec<RowChange>,
+}
+
+impl ResultDiff {
+  /// How many rows were added, removed, and changed, like `2 added, 1 removed`.
+  pub fn summary(&self) -> String {
+    let count = |matches: fn(&RowChange) -> bool| self.changes.iter().filter(|change| matches(change)).count();
+    let counts = [
+      (count(|change| *change == RowChange::Added), "added"),
+      (count(|change| *change == RowChange::Removed), "removed"),
+      (count(|change| matches!(change, RowChange::Changed(_))), "changed"),
+    ];
+    let counts = counts.iter().filter(|(count, _)| *count > 0).map(|(count, change)| format!("{count} {change}"));
+    match counts.collect::<Vec<_>>() {
+      counts if counts.is_empty() => "no differences".to_owned(),
+      counts => counts.join(", "),
+    }
+  }
+}
+
+/// Compares `current` with `pinned` results. With a key column, rows are
+/// matched by their value in it, so a row whose other values differ is
+/// changed. Without one, rows are matched by all their values, so each is in
+/// both results or was added or removed. Rows are listed in the order of
+/// `current`, followed by the removed rows in the order they were pinned.
+pub fn diff_results(pinned: &Rows, current: &Rows, key: Option<usize>) -> Result<ResultDiff> {
+  let names = |rows: &Rows| rows.headers.iter().map(|header| header.name.clone()).collect::<Vec<_>>();
+  if names(pinned) != names(current) {
+    return Err(eyre!("the results have different columns than the pinned ones"));
+  }
+  let key_of = |row: &[String]| -> Vec<String> {
+    match key {
+      Some(column) => row.get(column).cloned().into_iter().collect(),
+      None => row.to_vec(),
+    }
+  };
+  // rows that share a key are matched in order
+  let mut unmatched = HashMap::<Vec<String>, VecDeque<usize>>::new();
+  for (index, row) in pinned.rows.iter().enumerate() {
+    unmatched.entry(key_of(row)).or_default().push_back(index);
+  }
+  let mut rows = Vec::with_capacity(current.rows.len());
+  let mut changes = Vec::with_capacity(current.rows.len());
+  for row in &current.rows {
+    let change = match unmatched.get_mut(&key_of(row)).and_then(VecDeque::pop_front) {
+      Some(index) => {
+        let pinned_row = &pinned.rows[index];
+        match (0..row.len()).filter(|column| pinned_row.get(*column) != row.get(*column)).collect::<Vec<_>>() {
+          changed if changed.is_empty() => RowChange::Unchanged,
+          changed => RowChange::Changed(changed),
+        }
+      },
+      None => RowChange::Added,
+    };
+    rows.push(row.clone());
+    changes.push(change);
+  }
+  let mut removed = unmatched.into_values().flatten().collect::<Vec<_>>();
+  removed.sort_unstable();
+  for index in removed {
+    rows.push(pinned.rows[index].clone());
+    changes.push(RowChange::Removed);
+  }
+  Ok(ResultDiff { rows: Rows { headers: current.headers.clone(), rows, rows_affected: None }, changes })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::Header;
+
+  fn rows(columns: &[&str], rows: &[&[&str]]) -> Rows {
+    Rows {
+      headers: columns
+        .iter()
+        .map(|name| Header { name: name.to_string(), type_name: "TEXT".to_owned(), composite_fields: None })
+        .collect(),
+      rows: rows.iter().map(|row| row.iter().map(|value| value.to_string()).collect()).collect(),
+      rows_affected: None,
+    }
+  }
+
+  #[test]
+  fn test_diff_full_rows() {
+    let pinned = rows(&["id", "name"], &[&["1", "a"], &["2", "b"], &["2", "b"], &["3", "c"]]);
+    let current = rows(&["id", "name"], &[&["2", "b"], &["1", "x"], &["3", "c"]]);
+    let diff = diff_results(&pinned, &current, None).unwrap();
+    assert_eq!(
+      diff.changes,
+      vec![RowChange::Unchanged, RowChange::Added, RowChange::Unchanged, RowChange::Removed, RowChange::Removed]
+    );
+    // the removed rows are the pinned ones
+    assert_eq!(diff.rows.rows[3], vec!["1", "a"]);
+    assert_eq!(diff.rows.rows[4], vec!["2", "b"]);
+    assert_eq!(diff.summary(), "1 added, 2 removed");
+  }
+
+  #[test]
+  fn test_diff_by_key() {
+    let pinned = rows(&["id", "name", "age"], &[&["1", "a", "30"], &["2", "b", "40"]]);
+    let current = rows(&["id", "name", "age"], &[&["1", "a", "31"], &["3", "c", "50"]]);
+    let diff = diff_results(&pinned, &current, Some(0)).unwrap();
+    assert_eq!(diff.changes, vec![RowChange::Changed(vec![2]), RowChange::Added, RowChange::Removed]);
+    assert_eq!(diff.summary(), "1 added, 1 removed, 1 changed");
+
+    assert_eq!(diff_results(&pinned, &pinned, Some(0)).unwrap().summary(), "no differences");
+    assert!(diff_results(&pinned, &rows(&["id", "name"], &[]), None).is_err());
+  }
+}
@@ -0,0 +1,397 @@
+use std::{
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+};
+
+use color_eyre::eyre::{Result, eyre};
+use csv::{Terminator, Writer, WriterBuilder};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+  action::{Action, ExportFormat},
+  config::{ExportOptions, LineTerminator},
+  database::{
+    Header, Headers, RowSink, Rows, array_to_json, composite_to_json, display_value, exact_number, header_to_vec,
+    is_array_type, is_null, is_numeric_type, parse_array, parse_composite,
+  },
+};
+
+#[cfg(feature = "parquet")]
+mod parquet;
+
+// how many rows are written between each progress update
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+pub fn file_extension(format: &ExportFormat) -> &'static str {
+  match format {
+    ExportFormat::CSV => "csv",
+    ExportFormat::TSV => "tsv",
+    ExportFormat::JSON => "json",
+    ExportFormat::Markdown => "md",
+    #[cfg(feature = "parquet")]
+    ExportFormat::Parquet => "parquet",
+  }
+}
+
+// the export options only apply to csv and tsv (and `null` to markdown). the
+// other formats have a fixed shape that doesn't leave room for them.
+pub fn new_sink<'a, W: Write + Send + 'a>(
+  format: &ExportFormat,
+  options: &ExportOptions,
+  writer: W,
+) -> Result<Box<dyn RowSink + 'a>> {
+  match format {
+    ExportFormat::CSV => csv_sink(writer, options.delimiter, options),
+    ExportFormat::TSV => csv_sink(writer, '\t', options),
+    ExportFormat::JSON => Ok(Box::new(JsonSink { writer, headers: vec![], columns: vec![], row_count: 0 })),
+    ExportFormat::Markdown => Ok(Box::new(MarkdownSink { writer, null: options.null.clone() })),
+    #[cfg(feature = "parquet")]
+    ExportFormat::Parquet => Ok(Box::new(parquet::ParquetSink::new(writer))),
+  }
+}
+
+pub fn new_file_sink(format: &ExportFormat, options: &ExportOptions, path: &Path) -> Result<Box<dyn RowSink>> {
+  new_sink(format, options, BufWriter::new(File::create(path)?))
+}
+
+// formats rows that are already in memory, e.g. for copying to the clipboard
+pub fn format_rows(rows: &Rows, format: &ExportFormat, options: &ExportOptions) -> Result<String> {
+  let mut buf = Vec::new();
+  {
+    let mut sink = new_sink(format, options, &mut buf)?;
+    sink.write_headers(&rows.headers)?;
+    for row in &rows.rows {
+      sink.write_row(row)?;
+    }
+    sink.finish()?;
+  }
+  Ok(String::from_utf8(buf)?)
+}
+
+fn csv_sink<'a, W: Write + Send + 'a>(
+  mut writer: W,
+  delimiter: char,
+  options: &ExportOptions,
+) -> Result<Box<dyn RowSink + 'a>> {
+  let ascii = |c: char, name: &str| match c.is_ascii() {
+    true => Ok(c as u8),
+    false => Err(eyre!("Export {name} must be a single ASCII character, got '{c}'")),
+  };
+  let terminator = match options.line_terminator {
+    LineTerminator::Lf => Terminator::Any(b'\n'),
+    LineTerminator::Crlf => Terminator::CRLF,
+  };
+  let mut builder = WriterBuilder::new();
+  builder.delimiter(ascii(delimiter, "delimiter")?).quote(ascii(options.quote, "quote")?).terminator(terminator);
+  if options.bom {
+    writer.write_all(UTF8_BOM)?;
+  }
+  Ok(Box::new(CsvSink {
+    writer: builder.from_writer(writer),
+    include_header: options.include_header,
+    null: options.null.clone(),
+  }))
+}
+
+struct CsvSink<W: Write> {
+  writer: Writer<W>,
+  include_header: bool,
+  null: String,
+}
+
+impl<W: Write + Send> RowSink for CsvSink<W> {
+  fn write_headers(&mut self, headers: &Headers) -> Result<()> {
+    if self.include_header {
+      self.writer.write_record(header_to_vec(headers))?;
+    }
+    Ok(())
+  }
+
+  fn write_row(&mut self, row: &[String]) -> Result<()> {
+    self.writer.write_record(row.iter().map(|value| display_value(value, &self.null)))?;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<()> {
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+// an array of objects keyed by column name. written by hand rather than with
+// serde_json's map, which doesn't keep the columns in order.
+struct JsonSink<W: Write> {
+  writer: W,
+  headers: Vec<String>,
+  // each column, so arrays and composites are written as json arrays and
+  // objects rather than as their text
+  columns: Headers,
+  row_count: u64,
+}
+
+impl<W: Write + Send> RowSink for JsonSink<W> {
+  fn write_headers(&mut self, headers: &Headers) -> Result<()> {
+    self.headers = header_to_vec(headers).iter().map(serde_json::to_string).collect::<Result<_, _>>()?;
+    self.columns = headers.clone();
+    write!(self.writer, "[")?;
+    Ok(())
+  }
+
+  fn write_row(&mut self, row: &[String]) -> Result<()> {
+    let fields = self
+      .headers
+      .iter()
+      .zip(&self.columns)
+      .zip(row)
+      .map(|((header, column), value)| match is_null(value) {
+        true => Ok(format!("{header}: null")),
+        false => Ok(format!("{header}: {}", json_value(column, value))),
+      })
+      .collect::<Result<Vec<String>>>()?;
+    let separator = if self.row_count == 0 { "" } else { "," };
+    write!(self.writer, "{separator}\n  {{{}}}", fields.join(", "))?;
+    self.row_count += 1;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<()> {
+    if self.headers.is_empty() {
+      write!(self.writer, "[")?;
+    }
+    writeln!(self.writer, "\n]")?;
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+// the value as json text
+fn json_value(column: &Header, value: &str) -> String {
+  let structured = match &column.composite_fields {
+    Some(names) => {
+      return parse_composite(value).map_or_else(|| json_string(value), |fields| composite_to_json(names, &fields));
+    },
+    None if is_array_type(&column.type_name) => {
+      let numeric = is_numeric_type(column.type_name.trim_end_matches("[]"));
+      parse_array(value).map(|elements| array_to_json(&elements, numeric))
+    },
+    None if is_numeric_type(&column.type_name) => exact_number(value).map(serde_json::Value::Number),
+    None => None,
+  };
+  structured.map_or_else(|| json_string(value), |structured| structured.to_string())
+}
+
+fn json_string(value: &str) -> String {
+  serde_json::Value::String(value.to_owned()).to_string()
+}
+
+struct MarkdownSink<W: Write> {
+  writer: W,
+  null: String,
+}
+
+fn write_markdown_cells<'a>(writer: &mut impl Write, cells: impl Iterator<Item = &'a str>) -> Result<()> {
+  let cells = cells.map(|cell| cell.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>"));
+  writeln!(writer, "| {} |", cells.collect::<Vec<String>>().join(" | "))?;
+  Ok(())
+}
+
+impl<W: Write + Send> RowSink for MarkdownSink<W> {
+  fn write_headers(&mut self, headers: &Headers) -> Result<()> {
+    write_markdown_cells(&mut self.writer, headers.iter().map(|h| h.name.as_str()))?;
+    write_markdown_cells(&mut self.writer, headers.iter().map(|_| "---"))
+  }
+
+  fn write_row(&mut self, row: &[String]) -> Result<()> {
+    write_markdown_cells(&mut self.writer, row.iter().map(|value| display_value(value, &self.null)))
+  }
+
+  fn finish(&mut self) -> Result<()> {
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+// wraps another sink to periodically report how many rows have been written
+pub struct ProgressSink<'a> {
+  inner: Box<dyn RowSink + 'a>,
+  row_count: u64,
+  progress_tx: UnboundedSender<Action>,
+}
+
+impl<'a> ProgressSink<'a> {
+  pub fn new(inner: Box<dyn RowSink + 'a>, progress_tx: UnboundedSender<Action>) -> Self {
+    Self { inner, row_count: 0, progress_tx }
+  }
+}
+
+impl RowSink for ProgressSink<'_> {
+  fn write_headers(&mut self, headers: &Headers) -> Result<()> {
+    self.inner.write_headers(headers)
+  }
+
+  fn write_row(&mut self, row: &[String]) -> Result<()> {
+    self.inner.write_row(row)?;
+    self.row_count += 1;
+    if self.row_count.is_multiple_of(PROGRESS_INTERVAL) {
+      self.progress_tx.send(Action::ExportDataProgress(self.row_count))?;
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<()> {
+    self.inner.finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::{Header, NULL};
+
+  fn rows() -> Rows {
+    Rows {
+      headers: vec![
+        Header { name: "id".to_string(), type_name: "INT4".to_string(), composite_fields: None },
+        Header { name: "name".to_string(), type_name: "TEXT".to_string(), composite_fields: None },
+      ],
+      rows: vec![vec!["1".to_string(), "a, \"b\"".to_string()], vec!["2".to_string(), "c|d\ne".to_string()]],
+      rows_affected: None,
+    }
+  }
+
+  #[test]
+  fn test_format_rows() {
+    let test_cases = vec![
+      (ExportFormat::CSV, "id,name\n1,\"a, \"\"b\"\"\"\n2,\"c|d\ne\"\n"),
+      (ExportFormat::TSV, "id\tname\n1\t\"a, \"\"b\"\"\"\n2\t\"c|d\ne\"\n"),
+      (ExportFormat::JSON, "[\n  {\"id\": 1, \"name\": \"a, \\\"b\\\"\"},\n  {\"id\": 2, \"name\": \"c|d\\ne\"}\n]\n"),
+      (ExportFormat::Markdown, "| id | name |\n| --- | --- |\n| 1 | a, \"b\" |\n| 2 | c\\|d<br>e |\n"),
+    ];
+
+    for (format, expected) in test_cases {
+      assert_eq!(
+        format_rows(&rows(), &format, &ExportOptions::default()).unwrap(),
+        expected,
+        "Failed for format: {format}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_csv_options() {
+    let options = ExportOptions {
+      delimiter: ';',
+      quote: '\'',
+      include_header: false,
+      line_terminator: LineTerminator::Crlf,
+      bom: true,
+      null: String::new(),
+    };
+    let mut buf = Vec::new();
+    {
+      let mut sink = new_sink(&ExportFormat::CSV, &options, &mut buf).unwrap();
+      let rows = rows();
+      sink.write_headers(&rows.headers).unwrap();
+      for row in &rows.rows {
+        sink.write_row(row).unwrap();
+      }
+      sink.finish().unwrap();
+    }
+    assert_eq!(buf, b"\xEF\xBB\xBF1;a, \"b\"\r\n2;'c|d\ne'\r\n");
+
+    let options = ExportOptions { delimiter: '§', ..ExportOptions::default() };
+    assert!(new_sink(&ExportFormat::CSV, &options, Vec::new()).is_err());
+  }
+
+  #[test]
+  fn test_null_values() {
+    let rows = Rows {
+      headers: vec![Header { name: "a".to_string(), type_name: "TEXT".to_string(), composite_fields: None }],
+      rows: vec![vec![NULL.to_string()], vec![String::new()], vec!["NULL".to_string()]],
+      rows_affected: None,
+    };
+    let options = ExportOptions { null: "\\N".to_string(), ..ExportOptions::default() };
+    let test_cases = vec![
+      (ExportFormat::CSV, "a\n\\N\n\"\"\nNULL\n"),
+      (ExportFormat::JSON, "[\n  {\"a\": null},\n  {\"a\": \"\"},\n  {\"a\": \"NULL\"}\n]\n"),
+      (ExportFormat::Markdown, "| a |\n| --- |\n| \\N |\n|  |\n| NULL |\n"),
+    ];
+
+    for (format, expected) in test_cases {
+      assert_eq!(format_rows(&rows, &format, &options).unwrap(), expected, "Failed for format: {format}");
+    }
+  }
+
+  #[test]
+  fn test_numeric_precision() {
+    let numeric = "123456789012345678901234567890.123456789012345678901234567890";
+    let rows = Rows {
+      headers: vec![Header { name: "n".to_string(), type_name: "NUMERIC".to_string(), composite_fields: None }],
+      rows: vec![vec![numeric.to_string()]],
+      rows_affected: None,
+    };
+    let test_cases = vec![
+      (ExportFormat::CSV, format!("n\n{numeric}\n")),
+      (ExportFormat::JSON, format!("[\n  {{\"n\": \"{numeric}\"}}\n]\n")),
+    ];
+
+    for (format, expected) in test_cases {
+      assert_eq!(
+        format_rows(&rows, &format, &ExportOptions::default()).unwrap(),
+        expected,
+        "Failed for format: {format}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_array_and_composite_values() {
+    let rows = Rows {
+      headers: vec![
+        Header { name: "tags".to_string(), type_name: "TEXT[]".to_string(), composite_fields: None },
+        Header { name: "scores".to_string(), type_name: "INT4[]".to_string(), composite_fields: None },
+        // scalar numbers are written the same way as the elements of numeric arrays
+        Header { name: "score".to_string(), type_name: "INT4".to_string(), composite_fields: None },
+        Header { name: "note".to_string(), type_name: "TEXT".to_string(), composite_fields: None },
+        Header {
+          name: "home".to_string(),
+          type_name: "address".to_string(),
+          composite_fields: Some(vec!["street".to_string(), "city".to_string()]),
+        },
+        // only columns of a composite type are, whatever the type name or value looks like
+        Header { name: "code".to_string(), type_name: "NUMBER(10)".to_string(), composite_fields: None },
+      ],
+      rows: vec![vec![
+        r#"{a,NULL,"b c"}"#.to_string(),
+        "{10,NULL}".to_string(),
+        "10".to_string(),
+        "{1,2}".to_string(),
+        r#"("Main St",)"#.to_string(),
+        "(1,2)".to_string(),
+      ]],
+      rows_affected: None,
+    };
+    let test_cases = vec![
+      (
+        ExportFormat::CSV,
+        "tags,scores,score,note,home,code\n\"{a,NULL,\"\"b c\"\"}\",\"{10,NULL}\",10,\"{1,2}\",\"(\"\"Main St\"\",)\",\"(1,2)\"\n",
+      ),
+      (
+        ExportFormat::JSON,
+        "[\n  {\"tags\": [\"a\",null,\"b c\"], \"scores\": [10,null], \"score\": 10, \"note\": \"{1,2}\", \"home\": {\"street\":\"Main St\",\"city\":null}, \
+         \"code\": \"(1,2)\"}\n]\n",
+      ),
+    ];
+
+    for (format, expected) in test_cases {
+      assert_eq!(
+        format_rows(&rows, &format, &ExportOptions::default()).unwrap(),
+        expected,
+        "Failed for format: {format}"
+      );
+    }
+  }
+}
@@ -0,0 +1,271 @@
+use std::{io::Write, sync::Arc};
+
+use arrow_array::{
+  ArrayRef, RecordBatch,
+  builder::{BooleanBuilder, Date32Builder, Float64Builder, Int64Builder, StringBuilder, TimestampMicrosecondBuilder},
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime};
+use color_eyre::eyre::{Result, eyre};
+use parquet::{
+  arrow::ArrowWriter,
+  basic::Compression,
+  file::properties::{WriterProperties, WriterVersion},
+};
+
+use crate::database::{Headers, RowSink, is_null};
+
+// rows are buffered into a record batch of this size before being handed to
+// the parquet writer, which flushes a row group every ROW_GROUP_SIZE rows.
+// together they keep memory bounded no matter how big the export is.
+const BATCH_SIZE: usize = 8_192;
+const ROW_GROUP_SIZE: usize = 131_072;
+
+// maps a database type name to the arrow type its values are written as. anything
+// that doesn't map cleanly (e.g. decimals, unsigned bigints) is kept as text so no
+// precision is lost.
+pub fn arrow_type(type_name: &str) -> DataType {
+  let type_name = type_name.to_uppercase();
+  // mysql and oracle include lengths and precisions, e.g. `NUMBER(10)`
+  let base = type_name.split('(').next().unwrap_or_default().trim();
+  match base {
+    "BOOL" | "BOOLEAN" => DataType::Boolean,
+    "INT2" | "INT4" | "INT8" | "SMALLINT" | "SMALLSERIAL" | "INT" | "INTEGER" | "SERIAL" | "BIGINT" | "BIGSERIAL"
+    | "TINYINT" | "MEDIUMINT" | "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+    | "UTINYINT" | "USMALLINT" | "UINT" => DataType::Int64,
+    "FLOAT4" | "FLOAT8" | "REAL" | "FLOAT" | "DOUBLE" | "DOUBLE PRECISION" => DataType::Float64,
+    "DATE" | "DATE32" => DataType::Date32,
+    "TIMESTAMP" | "DATETIME" => DataType::Timestamp(TimeUnit::Microsecond, None),
+    "TIMESTAMPTZ" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+    _ => DataType::Utf8,
+  }
+}
+
+enum ColumnBuilder {
+  Boolean(BooleanBuilder),
+  Int64(Int64Builder),
+  Float64(Float64Builder),
+  Date32(Date32Builder),
+  Timestamp(TimestampMicrosecondBuilder),
+  Utf8(StringBuilder),
+}
+
+impl ColumnBuilder {
+  fn new(data_type: &DataType) -> Self {
+    match data_type {
+      DataType::Boolean => Self::Boolean(BooleanBuilder::new()),
+      DataType::Int64 => Self::Int64(Int64Builder::new()),
+      DataType::Float64 => Self::Float64(Float64Builder::new()),
+      DataType::Date32 => Self::Date32(Date32Builder::new()),
+      DataType::Timestamp(_, Some(tz)) => Self::Timestamp(TimestampMicrosecondBuilder::new().with_timezone(tz.clone())),
+      DataType::Timestamp(_, None) => Self::Timestamp(TimestampMicrosecondBuilder::new()),
+      _ => Self::Utf8(StringBuilder::new()),
+    }
+  }
+
+  // returns None if the value can't be converted to the column's type
+  fn append(&mut self, value: &str) -> Option<()> {
+    if is_null(value) {
+      match self {
+        Self::Boolean(b) => b.append_null(),
+        Self::Int64(b) => b.append_null(),
+        Self::Float64(b) => b.append_null(),
+        Self::Date32(b) => b.append_null(),
+        Self::Timestamp(b) => b.append_null(),
+        Self::Utf8(b) => b.append_null(),
+      }
+      return Some(());
+    }
+    match self {
+      Self::Boolean(b) => b.append_value(match value.to_lowercase().as_str() {
+        "true" | "t" | "1" => true,
+        "false" | "f" | "0" => false,
+        _ => return None,
+      }),
+      Self::Int64(b) => b.append_value(value.parse().ok()?),
+      Self::Float64(b) => b.append_value(value.parse().ok()?),
+      Self::Date32(b) => b.append_value(parse_date(value)?),
+      Self::Timestamp(b) => b.append_value(parse_timestamp(value)?),
+      Self::Utf8(b) => b.append_value(value),
+    }
+    Some(())
+  }
+
+  fn finish(&mut self) -> ArrayRef {
+    match self {
+      Self::Boolean(b) => Arc::new(b.finish()),
+      Self::Int64(b) => Arc::new(b.finish()),
+      Self::Float64(b) => Arc::new(b.finish()),
+      Self::Date32(b) => Arc::new(b.finish()),
+      Self::Timestamp(b) => Arc::new(b.finish()),
+      Self::Utf8(b) => Arc::new(b.finish()),
+    }
+  }
+}
+
+// days since the unix epoch
+fn parse_date(value: &str) -> Option<i32> {
+  let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+  i32::try_from(date.signed_duration_since(NaiveDate::default()).num_days()).ok()
+}
+
+// microseconds since the unix epoch. timezone-aware values are always rendered in utc.
+fn parse_timestamp(value: &str) -> Option<i64> {
+  let value = value.strip_suffix(" UTC").unwrap_or(value);
+  let timestamp = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+    .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+    .ok()?;
+  Some(timestamp.and_utc().timestamp_micros())
+}
+
+pub struct ParquetSink<W: Write + Send> {
+  // the parquet writer needs the schema up front, so it isn't created until the headers arrive
+  writer: Option<W>,
+  parquet_writer: Option<ArrowWriter<W>>,
+  schema: SchemaRef,
+  columns: Vec<ColumnBuilder>,
+  buffered_rows: usize,
+}
+
+impl<W: Write + Send> ParquetSink<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer: Some(writer),
+      parquet_writer: None,
+      schema: Arc::new(Schema::empty()),
+      columns: vec![],
+      buffered_rows: 0,
+    }
+  }
+
+  fn flush_batch(&mut self) -> Result<()> {
+    if self.buffered_rows == 0 {
+      return Ok(());
+    }
+    let Some(parquet_writer) = self.parquet_writer.as_mut() else {
+      return Err(eyre!("Parquet headers must be written before rows"));
+    };
+    let columns = self.columns.iter_mut().map(ColumnBuilder::finish).collect();
+    parquet_writer.write(&RecordBatch::try_new(self.schema.clone(), columns)?)?;
+    self.buffered_rows = 0;
+    Ok(())
+  }
+}
+
+impl<W: Write + Send> RowSink for ParquetSink<W> {
+  fn write_headers(&mut self, headers: &Headers) -> Result<()> {
+    let Some(writer) = self.writer.take() else {
+      return Err(eyre!("Parquet headers were already written"));
+    };
+    let fields =
+      headers.iter().map(|header| Field::new(&header.name, arrow_type(&header.type_name), true)).collect::<Vec<_>>();
+    self.columns = fields.iter().map(|field| ColumnBuilder::new(field.data_type())).collect();
+    self.schema = Arc::new(Schema::new(fields));
+    let props = WriterProperties::builder()
+      .set_writer_version(WriterVersion::PARQUET_2_0)
+      .set_compression(Compression::SNAPPY)
+      .set_max_row_group_size(ROW_GROUP_SIZE)
+      .build();
+    self.parquet_writer = Some(ArrowWriter::try_new(writer, self.schema.clone(), Some(props))?);
+    Ok(())
+  }
+
+  fn write_row(&mut self, row: &[String]) -> Result<()> {
+    for (i, (column, value)) in self.columns.iter_mut().zip(row).enumerate() {
+      if column.append(value).is_none() {
+        let field = self.schema.field(i);
+        return Err(eyre!("Could not write '{value}' in column {} as {}", field.name(), field.data_type()));
+      }
+    }
+    self.buffered_rows += 1;
+    if self.buffered_rows >= BATCH_SIZE {
+      self.flush_batch()?;
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<()> {
+    // a query with no rows never sends headers, but should still produce a valid file
+    if self.writer.is_some() {
+      self.write_headers(&vec![])?;
+    }
+    self.flush_batch()?;
+    if let Some(parquet_writer) = self.parquet_writer.take() {
+      parquet_writer.close()?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arrow_array::{Array, Int64Array, StringArray, TimestampMicrosecondArray};
+  use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+  use super::*;
+  use crate::database::{Header, NULL};
+
+  #[test]
+  fn test_arrow_type() {
+    let test_cases = vec![
+      ("INT4", DataType::Int64),
+      ("BIGINT", DataType::Int64),
+      ("BIGINT UNSIGNED", DataType::Utf8),
+      ("NUMBER(10)", DataType::Utf8),
+      ("Double", DataType::Float64),
+      ("bool", DataType::Boolean),
+      ("DATE", DataType::Date32),
+      ("TIMESTAMP(6)", DataType::Timestamp(TimeUnit::Microsecond, None)),
+      ("TIMESTAMPTZ", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))),
+      ("NUMERIC", DataType::Utf8),
+      ("TEXT", DataType::Utf8),
+    ];
+
+    for (type_name, expected) in test_cases {
+      assert_eq!(arrow_type(type_name), expected, "Failed for type: {type_name}");
+    }
+  }
+
+  #[test]
+  fn test_parquet_sink() {
+    let headers = vec![
+      Header { name: "id".to_string(), type_name: "INT8".to_string(), composite_fields: None },
+      Header { name: "name".to_string(), type_name: "TEXT".to_string(), composite_fields: None },
+      Header { name: "created_at".to_string(), type_name: "TIMESTAMPTZ".to_string(), composite_fields: None },
+    ];
+    let path = std::env::temp_dir().join(format!("rainfrog_test_{}.parquet", std::process::id()));
+    let mut sink = ParquetSink::new(std::fs::File::create(&path).unwrap());
+    sink.write_headers(&headers).unwrap();
+    sink.write_row(&["1".to_string(), "a".to_string(), "2024-01-02 03:04:05.000006 UTC".to_string()]).unwrap();
+    sink.write_row(&[NULL.to_string(), "NULL".to_string(), NULL.to_string()]).unwrap();
+    sink.finish().unwrap();
+
+    let batch = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+      .unwrap()
+      .build()
+      .unwrap()
+      .next()
+      .unwrap()
+      .unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(ids.value(0), 1);
+    assert!(ids.is_null(1));
+    let names = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(names.value(0), "a");
+    assert_eq!(names.value(1), "NULL");
+    let created_at = batch.column(2).as_any().downcast_ref::<TimestampMicrosecondArray>().unwrap();
+    assert_eq!(created_at.value(0), 1_704_164_645_000_006);
+    assert_eq!(created_at.timezone(), Some("UTC"));
+    assert!(created_at.is_null(1));
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn test_parquet_sink_rejects_mismatched_values() {
+    let headers = vec![Header { name: "id".to_string(), type_name: "INT4".to_string(), composite_fields: None }];
+    let mut sink = ParquetSink::new(Vec::new());
+    sink.write_headers(&headers).unwrap();
+    assert!(sink.write_row(&["abc".to_string()]).is_err());
+  }
+}
@@ -0,0 +1,159 @@
+// quick filtering of query results that are already loaded
+use color_eyre::eyre::Result;
+use regex::{Regex, RegexBuilder};
+
+use crate::database::{Headers, is_null};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RowFilter {
+  pub text: String,
+  pub regex: bool,
+}
+
+impl RowFilter {
+  /// Builds a matcher for the filter. Matching ignores case. `column:text` only
+  /// looks in the column with that name, if there is one; otherwise the whole
+  /// filter is looked for in every column.
+  pub fn matcher(&self, headers: &Headers) -> Result<RowMatcher> {
+    let (column, text) = match self.text.split_once(':') {
+      Some((name, text)) => match headers.iter().position(|h| h.name.eq_ignore_ascii_case(name.trim())) {
+        Some(column) => (Some(column), text),
+        None => (None, self.text.as_str()),
+      },
+      None => (None, self.text.as_str()),
+    };
+    let pattern = if self.regex { text.to_string() } else { regex::escape(text) };
+    Ok(RowMatcher { column, pattern: RegexBuilder::new(&pattern).case_insensitive(true).build()? })
+  }
+
+  pub fn indicator(&self) -> &'static str {
+    if self.regex { "[regex]" } else { "[text]" }
+  }
+}
+
+pub struct RowMatcher {
+  column: Option<usize>,
+  pattern: Regex,
+}
+
+impl RowMatcher {
+  // nulls never match, since they have no text
+  pub fn matches(&self, row: &[String]) -> bool {
+    let matches = |value: &String| !is_null(value) && self.pattern.is_match(value);
+    match self.column {
+      Some(column) => row.get(column).is_some_and(matches),
+      None => row.iter().any(matches),
+    }
+  }
+}
+
+/// Finds the cells containing some text, without hiding any rows.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CellSearch {
+  pub text: String,
+  pub case_sensitive: bool,
+}
+
+impl CellSearch {
+  /// The (column, row) of each cell containing the text, row by row. Nulls
+  /// never match, and neither does an empty search.
+  pub fn matches(&self, rows: &[Vec<String>]) -> Vec<(usize, usize)> {
+    if self.text.is_empty() {
+      return vec![];
+    }
+    let text = if self.case_sensitive { self.text.clone() } else { self.text.to_lowercase() };
+    let contains = |value: &String| match self.case_sensitive {
+      true => value.contains(&text),
+      false => value.to_lowercase().contains(&text),
+    };
+    rows
+      .iter()
+      .enumerate()
+      .flat_map(|(y, row)| {
+        row.iter().enumerate().filter(|(_, value)| !is_null(value) && contains(value)).map(move |(x, _)| (x, y))
+      })
+      .collect()
+  }
+
+  pub fn indicator(&self) -> &'static str {
+    if self.case_sensitive { "[case]" } else { "[any case]" }
+  }
+}
+
+/// The match after (or before) the cell at `from`, going row by row and
+/// wrapping around at the ends. `from` itself counts when `inclusive`.
+pub fn next_match(
+  matches: &[(usize, usize)],
+  from: (usize, usize),
+  forwards: bool,
+  inclusive: bool,
+) -> Option<(usize, usize)> {
+  let position = |(x, y): (usize, usize)| (y, x);
+  let from = position(from);
+  let found = match forwards {
+    true => matches.iter().find(|m| position(**m) > from || (inclusive && position(**m) == from)),
+    false => matches.iter().rev().find(|m| position(**m) < from || (inclusive && position(**m) == from)),
+  };
+  let wrapped = if forwards { matches.first() } else { matches.last() };
+  found.or(wrapped).copied()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::{Header, NULL};
+
+  #[test]
+  fn test_row_filter() {
+    let headers = ["id", "name"]
+      .iter()
+      .map(|name| Header { name: name.to_string(), type_name: "TEXT".to_string(), composite_fields: None })
+      .collect::<Headers>();
+    let rows = [
+      vec!["1".to_string(), "Alice".to_string()],
+      vec!["2".to_string(), "bob.1".to_string()],
+      vec!["3".to_string(), NULL.to_string()],
+    ];
+    let test_cases = vec![
+      ("1", false, vec!["1", "2"]),
+      ("ALICE", false, vec!["1"]),
+      ("name:1", false, vec!["2"]),
+      ("id:1", false, vec!["1"]),
+      ("other:1", false, vec![]),
+      ("b.b", false, vec![]),
+      ("b.b", true, vec!["2"]),
+      ("name:^a", true, vec!["1"]),
+      ("NULL", false, vec![]),
+    ];
+
+    for (text, regex, expected) in test_cases {
+      let matcher = RowFilter { text: text.to_string(), regex }.matcher(&headers).unwrap();
+      let ids = rows.iter().filter(|row| matcher.matches(row)).map(|row| row[0].as_str()).collect::<Vec<_>>();
+      assert_eq!(ids, expected, "Failed for filter: {text}");
+    }
+    assert!(RowFilter { text: "(".to_string(), regex: true }.matcher(&headers).is_err());
+  }
+
+  #[test]
+  fn test_cell_search() {
+    let rows = vec![
+      vec!["Alice".to_string(), "alice@example.com".to_string()],
+      vec!["bob".to_string(), NULL.to_string()],
+      vec!["carol".to_string(), "ALICE's friend".to_string()],
+    ];
+    let search =
+      |text: &str, case_sensitive: bool| CellSearch { text: text.to_string(), case_sensitive }.matches(&rows);
+    assert_eq!(search("alice", false), vec![(0, 0), (1, 0), (1, 2)]);
+    assert_eq!(search("alice", true), vec![(1, 0)]);
+    assert_eq!(search("null", false), vec![]);
+    assert_eq!(search("", false), vec![]);
+
+    let matches = search("alice", false);
+    assert_eq!(next_match(&matches, (0, 0), true, false), Some((1, 0)));
+    assert_eq!(next_match(&matches, (0, 0), true, true), Some((0, 0)));
+    assert_eq!(next_match(&matches, (0, 1), true, true), Some((1, 2)));
+    assert_eq!(next_match(&matches, (1, 2), true, false), Some((0, 0)));
+    assert_eq!(next_match(&matches, (0, 0), false, false), Some((1, 2)));
+    assert_eq!(next_match(&[], (0, 0), true, true), None);
+  }
+}
@@ -1,6 +1,8 @@
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 
+use crate::theme::Theme;
+
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Focus {
   #[default]
@@ -13,9 +15,9 @@ pub enum Focus {
 }
 
 impl Focus {
-  pub fn tab_color(&self) -> Color {
+  pub fn tab_color(&self, theme: &Theme) -> Color {
     match self {
-      Focus::Editor | Focus::History | Focus::Favorites => Color::Green,
+      Focus::Editor | Focus::History | Focus::Favorites => theme.focused_border.fg.unwrap_or_default(),
       Focus::Menu | Focus::Data | Focus::PopUp => Color::default(),
     }
   }
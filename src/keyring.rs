@@ -5,6 +5,7 @@ use keyring::Entry;
 
 use crate::Result;
 
+#[derive(Default)]
 pub struct Password(String);
 
 impl AsRef<str> for Password {
@@ -49,3 +50,14 @@ pub fn get_password(connection_name: &str, username: &str) -> Result<Password> {
     },
   }
 }
+
+/// Looks up a saved password without prompting for one, for connecting once
+/// the terminal is taken over by the ui.
+pub fn find_password(connection_name: &str, username: &str) -> Result<Option<Password>> {
+  let entry = Entry::new("rainfrog", &format!("{connection_name}-{username}"))?;
+  match entry.get_password() {
+    Ok(password) => Ok(Some(Password(password))),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(e) => Err(eyre::Report::msg(format!("Failed to extract password from secret: {e:?}"))),
+  }
+}
@@ -4,15 +4,28 @@
 
 pub mod action;
 pub mod app;
+pub mod chord;
 pub mod cli;
 pub mod components;
 pub mod config;
 pub mod database;
+pub mod diff;
+pub mod export;
+pub mod filter;
 pub mod focus;
 pub mod keyring;
+pub mod plan;
 pub mod popups;
+pub mod query_log;
+pub mod search;
+pub mod session;
+pub mod slow_log;
+pub mod sort;
+pub mod theme;
+pub mod timezone;
 pub mod tui;
 pub mod ui;
+pub mod undo;
 pub mod utils;
 pub mod vim;
 
@@ -33,14 +46,16 @@ use crate::{
   utils::{initialize_logging, initialize_panic_handler},
 };
 
-async fn run_app(mut args: Cli, config: Config, driver: Driver) -> Result<()> {
+async fn run_app(mut args: Cli, config: Config, driver: Driver, connection_name: Option<String>) -> Result<()> {
   let mouse_mode = args.mouse_mode.take();
   let mut app = App::new(mouse_mode, config)?;
+  app.connection_name = connection_name;
   app.run(driver, args).await?;
   Ok(())
 }
 
-fn resolve_driver(args: &mut Cli, config: &Config) -> Result<Driver> {
+// also returns the name of the connection from the config that was picked, if one was
+fn resolve_driver(args: &mut Cli, config: &Config) -> Result<(Driver, Option<String>)> {
   let url = args.connection_url.clone().or_else(|| {
     env::var("DATABASE_URL").map_or(None, |url| {
       if url.is_empty() {
@@ -58,15 +73,14 @@ fn resolve_driver(args: &mut Cli, config: &Config) -> Result<Driver> {
     || args.port.is_some()
     || args.database.is_some();
 
-  let (driver, url) = match (url, has_cli_input) {
-    (Some(u), _) => {
-      if let Some(driver) = args.driver.take() { Ok(driver) } else { extract_driver_from_url(&u) }.map(|d| (d, Some(u)))
-    },
+  let (driver, url, name) = match (url, has_cli_input) {
+    (Some(u), _) => if let Some(driver) = args.driver.take() { Ok(driver) } else { extract_driver_from_url(&u) }
+      .map(|d| (d, Some(u), None)),
     (None, true) => {
       if let Some(driver) = args.driver.take() {
-        Ok((driver, None))
+        Ok((driver, None, None))
       } else {
-        Ok((prompt_for_driver()?, None))
+        Ok((prompt_for_driver()?, None, None))
       }
     },
     (None, false) => Ok(match prompt_for_database_selection(config)? {
@@ -79,15 +93,19 @@ fn resolve_driver(args: &mut Cli, config: &Config) -> Result<Driver> {
           },
         }?;
 
-        (conn.driver, Some(url))
+        // a schema given on the command line wins over the connection's
+        args.schema = args.schema.take().or(conn.schema);
+        args.startup_sql = args.startup_sql.take().or(conn.startup_sql);
+        args.startup_sql_required |= conn.startup_sql_required;
+        (conn.driver, Some(url), Some(name))
       },
-      None => (prompt_for_driver()?, None),
+      None => (prompt_for_driver()?, None, None),
     }),
   }?;
 
   args.connection_url = url;
 
-  Ok(driver)
+  Ok((driver, name))
 }
 
 async fn tokio_main() -> Result<()> {
@@ -98,9 +116,9 @@ async fn tokio_main() -> Result<()> {
   let mut args = Cli::parse();
   dotenv().ok();
   let config = Config::new()?;
-  let driver = resolve_driver(&mut args, &config)?;
+  let (driver, connection_name) = resolve_driver(&mut args, &config)?;
 
-  run_app(args, config, driver).await
+  run_app(args, config, driver, connection_name).await
 }
 
 #[tokio::main]
@@ -0,0 +1,313 @@
+// query plans as postgres gives them for `EXPLAIN (FORMAT JSON)`, read into a
+// tree whose nodes can be folded away to look through a big plan a part at a time
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+// a node whose own share of the plan's cost (or time, with ANALYZE) is at least
+// this much is one of its most expensive
+const EXPENSIVE_SHARE: f64 = 0.25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+  // like `Hash Left Join` or `Index Scan`
+  pub node_type: String,
+  // what the node works on, like `using users_pkey on users u`
+  pub detail: Option<String>,
+  pub startup_cost: f64,
+  pub total_cost: f64,
+  pub plan_rows: f64,
+  // only with ANALYZE. rows and time are each loop's average, as postgres gives them
+  pub actual_rows: Option<f64>,
+  pub actual_loops: Option<f64>,
+  pub actual_total_time: Option<f64>,
+  pub children: Vec<PlanNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+  pub root: PlanNode,
+  // in milliseconds, only with ANALYZE
+  pub planning_time: Option<f64>,
+  pub execution_time: Option<f64>,
+}
+
+/// Reads the json postgres gives for `EXPLAIN (FORMAT JSON)`. Returns `None`
+/// if the value isn't a plan.
+pub fn parse_plan(json: &str) -> Option<Plan> {
+  let value = serde_json::from_str::<Value>(json).ok()?;
+  // a one element array, though it's the object itself in some clients
+  let explained = match &value {
+    Value::Array(plans) => plans.first()?.as_object()?,
+    Value::Object(explained) => explained,
+    _ => return None,
+  };
+  Some(Plan {
+    root: parse_node(explained.get("Plan")?.as_object()?)?,
+    planning_time: explained.get("Planning Time").and_then(Value::as_f64),
+    execution_time: explained.get("Execution Time").and_then(Value::as_f64),
+  })
+}
+
+fn parse_node(node: &Map<String, Value>) -> Option<PlanNode> {
+  let text = |key: &str| node.get(key).and_then(Value::as_str);
+  let number = |key: &str| node.get(key).and_then(Value::as_f64);
+  let node_type = text("Node Type")?;
+  // joins are named the way the text plan names them, like `Hash Left Join`
+  let node_type = match text("Join Type").filter(|join| *join != "Inner") {
+    Some(join) if node_type.ends_with(" Join") => node_type.replace(" Join", &format!(" {join} Join")),
+    Some(join) => format!("{node_type} {join} Join"),
+    None => node_type.to_owned(),
+  };
+  let mut detail = vec![];
+  if let Some(index) = text("Index Name") {
+    detail.push(format!("using {index}"));
+  }
+  if let Some(relation) = text("Relation Name").or(text("CTE Name")).or(text("Function Name")) {
+    detail.push(format!("on {relation}"));
+    if let Some(alias) = text("Alias").filter(|alias| *alias != relation) {
+      detail.push(alias.to_owned());
+    }
+  }
+  let children = match node.get("Plans") {
+    Some(Value::Array(plans)) => plans.iter().map(|plan| parse_node(plan.as_object()?)).collect::<Option<_>>()?,
+    _ => vec![],
+  };
+  Some(PlanNode {
+    node_type,
+    detail: (!detail.is_empty()).then(|| detail.join(" ")),
+    startup_cost: number("Startup Cost")?,
+    total_cost: number("Total Cost")?,
+    plan_rows: number("Plan Rows")?,
+    actual_rows: number("Actual Rows"),
+    actual_loops: number("Actual Loops"),
+    actual_total_time: number("Actual Total Time"),
+    children,
+  })
+}
+
+impl PlanNode {
+  fn analyzed(&self) -> bool {
+    self.actual_total_time.is_some()
+  }
+
+  // the time spent in the node over all its loops, or its cost without ANALYZE
+  fn weight(&self, analyzed: bool) -> f64 {
+    match (analyzed, self.actual_total_time) {
+      (true, Some(time)) => time * self.actual_loops.unwrap_or(1.0),
+      _ => self.total_cost,
+    }
+  }
+
+  /// The node's weight less its children's, which is what it spent itself.
+  pub fn own_weight(&self, analyzed: bool) -> f64 {
+    let children = self.children.iter().map(|child| child.weight(analyzed)).sum::<f64>();
+    (self.weight(analyzed) - children).max(0.0)
+  }
+
+  /// How many times more (or, below 1, fewer) rows the node gave than were
+  /// estimated, with ANALYZE.
+  pub fn misestimate(&self) -> Option<f64> {
+    let actual = self.actual_rows?;
+    // postgres estimates at least one row, and a node can give none
+    Some(actual.max(1.0) / self.plan_rows.max(1.0))
+  }
+}
+
+/// A node of the plan as it's shown, with the path of child indexes that leads
+/// to it from the root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisibleNode<'a> {
+  pub path: Vec<usize>,
+  pub node: &'a PlanNode,
+  pub collapsed: bool,
+  // the share of the whole plan's cost, or time with ANALYZE, spent in the node itself
+  pub share: f64,
+  pub expensive: bool,
+}
+
+/// A plan being looked through, with the nodes that are folded away and the
+/// one under the cursor.
+#[derive(Debug, Clone)]
+pub struct PlanTree {
+  pub plan: Plan,
+  // the json the plan was read from, to copy as is
+  pub json: String,
+  collapsed: HashSet<Vec<usize>>,
+  selected: usize,
+}
+
+impl PlanTree {
+  pub fn new(plan: Plan, json: String) -> Self {
+    Self { plan, json, collapsed: HashSet::new(), selected: 0 }
+  }
+
+  pub fn analyzed(&self) -> bool {
+    self.plan.root.analyzed()
+  }
+
+  /// The nodes that aren't folded away, in order, each after its parent.
+  pub fn visible(&self) -> Vec<VisibleNode<'_>> {
+    let analyzed = self.analyzed();
+    let total = self.plan.root.weight(analyzed);
+    let mut nodes = vec![];
+    let mut stack = vec![(vec![], &self.plan.root)];
+    while let Some((path, node)) = stack.pop() {
+      let collapsed = self.collapsed.contains(&path);
+      if !collapsed {
+        for (i, child) in node.children.iter().enumerate().rev() {
+          stack.push(([path.as_slice(), &[i]].concat(), child));
+        }
+      }
+      let share = if total > 0.0 { node.own_weight(analyzed) / total } else { 0.0 };
+      nodes.push(VisibleNode { path, node, collapsed, share, expensive: share >= EXPENSIVE_SHARE });
+    }
+    nodes
+  }
+
+  pub fn selected(&self) -> usize {
+    self.selected
+  }
+
+  pub fn select_by(&mut self, offset: isize) {
+    let count = self.visible().len();
+    self.selected = self.selected.saturating_add_signed(offset).min(count.saturating_sub(1));
+  }
+
+  pub fn select_first(&mut self) {
+    self.selected = 0;
+  }
+
+  pub fn select_last(&mut self) {
+    self.selected = self.visible().len().saturating_sub(1);
+  }
+
+  /// Folds or unfolds the children of the selected node.
+  pub fn toggle(&mut self) {
+    let Some(path) = self.selected_path() else {
+      return;
+    };
+    if !self.collapsed.remove(&path) {
+      self.collapse();
+    }
+  }
+
+  /// Folds away the children of the selected node, or if it has none or
+  /// they're already folded, selects its parent.
+  pub fn collapse(&mut self) {
+    let visible = self.visible();
+    let Some(selected) = visible.get(self.selected) else {
+      return;
+    };
+    if !selected.node.children.is_empty() && !selected.collapsed {
+      self.collapsed.insert(selected.path.clone());
+    } else if let Some((_, parent)) = selected.path.split_last() {
+      self.selected = visible.iter().position(|node| node.path == parent).unwrap_or_default();
+    }
+  }
+
+  /// Unfolds the children of the selected node.
+  pub fn expand(&mut self) {
+    if let Some(path) = self.selected_path() {
+      self.collapsed.remove(&path);
+    }
+  }
+
+  fn selected_path(&self) -> Option<Vec<usize>> {
+    self.visible().get(self.selected).map(|node| node.path.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PLAN: &str = r#"[{
+    "Plan": {
+      "Node Type": "Hash Join", "Join Type": "Left", "Startup Cost": 10.0, "Total Cost": 100.0, "Plan Rows": 50,
+      "Plans": [
+        {"Node Type": "Seq Scan", "Relation Name": "orders", "Alias": "o", "Startup Cost": 0.0,
+         "Total Cost": 70.0, "Plan Rows": 1000},
+        {"Node Type": "Hash", "Startup Cost": 5.0, "Total Cost": 5.0, "Plan Rows": 10,
+         "Plans": [
+           {"Node Type": "Index Scan", "Index Name": "users_pkey", "Relation Name": "users", "Alias": "users",
+            "Startup Cost": 0.0, "Total Cost": 5.0, "Plan Rows": 10}
+         ]}
+      ]
+    }
+  }]"#;
+
+  const ANALYZED: &str = r#"[{
+    "Plan": {
+      "Node Type": "Nested Loop", "Join Type": "Inner", "Startup Cost": 0.0, "Total Cost": 20.0, "Plan Rows": 1,
+      "Actual Rows": 400, "Actual Loops": 1, "Actual Total Time": 10.0,
+      "Plans": [
+        {"Node Type": "Seq Scan", "Relation Name": "a", "Startup Cost": 0.0, "Total Cost": 1.0, "Plan Rows": 20,
+         "Actual Rows": 20, "Actual Loops": 1, "Actual Total Time": 1.0},
+        {"Node Type": "Index Scan", "Relation Name": "b", "Startup Cost": 0.0, "Total Cost": 1.0, "Plan Rows": 1,
+         "Actual Rows": 20, "Actual Loops": 20, "Actual Total Time": 0.4}
+      ]
+    },
+    "Planning Time": 0.1,
+    "Execution Time": 10.5
+  }]"#;
+
+  #[test]
+  fn test_parse_plan() {
+    let plan = parse_plan(PLAN).unwrap();
+    assert_eq!(plan.root.node_type, "Hash Left Join");
+    assert_eq!(plan.root.children.len(), 2);
+    assert_eq!(plan.root.children[0].detail.as_deref(), Some("on orders o"));
+    assert_eq!(plan.root.children[1].children[0].detail.as_deref(), Some("using users_pkey on users"));
+    assert_eq!(plan.planning_time, None);
+    assert_eq!(plan.root.misestimate(), None);
+
+    let analyzed = parse_plan(ANALYZED).unwrap();
+    assert_eq!(analyzed.root.node_type, "Nested Loop");
+    assert_eq!(analyzed.execution_time, Some(10.5));
+    assert_eq!(analyzed.root.misestimate(), Some(400.0));
+
+    assert_eq!(parse_plan("[1, 2]"), None);
+    assert_eq!(parse_plan(r#"[{"Plan": {"Node Type": "Result"}}]"#), None);
+    assert_eq!(parse_plan("Seq Scan on users  (cost=0.00..1.00 rows=1 width=4)"), None);
+  }
+
+  #[test]
+  fn test_expensive_nodes() {
+    let tree = PlanTree::new(parse_plan(PLAN).unwrap(), PLAN.to_owned());
+    let visible = tree.visible();
+    let types = visible.iter().map(|node| node.node.node_type.as_str()).collect::<Vec<_>>();
+    assert_eq!(types, vec!["Hash Left Join", "Seq Scan", "Hash", "Index Scan"]);
+    assert_eq!(visible[1].path, vec![0]);
+    assert_eq!(visible[3].path, vec![1, 0]);
+    // the join costs 25 of its own, and the scan of orders 70
+    assert_eq!(visible.iter().map(|node| node.expensive).collect::<Vec<_>>(), vec![true, true, false, false]);
+    assert!((visible[1].share - 0.7).abs() < 1e-9);
+
+    // with ANALYZE, by the time spent, over every loop
+    let tree = PlanTree::new(parse_plan(ANALYZED).unwrap(), ANALYZED.to_owned());
+    let shares = tree.visible().iter().map(|node| (node.share * 100.0).round()).collect::<Vec<_>>();
+    assert_eq!(shares, vec![10.0, 10.0, 80.0]);
+  }
+
+  #[test]
+  fn test_fold_plan() {
+    let mut tree = PlanTree::new(parse_plan(PLAN).unwrap(), PLAN.to_owned());
+    tree.select_by(2);
+    tree.toggle();
+    assert_eq!(tree.visible().len(), 3);
+    assert!(tree.visible()[2].collapsed);
+    tree.expand();
+    assert_eq!(tree.visible().len(), 4);
+
+    // collapsing a leaf goes to its parent
+    tree.select_last();
+    tree.collapse();
+    assert_eq!(tree.selected(), 2);
+    tree.select_first();
+    tree.collapse();
+    assert_eq!(tree.visible().len(), 1);
+    tree.select_by(5);
+    assert_eq!(tree.selected(), 0);
+  }
+}
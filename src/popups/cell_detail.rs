@@ -0,0 +1,208 @@
+use std::cell::Cell;
+
+use crossterm::event::KeyCode;
+use ratatui::{
+  Frame,
+  layout::{Constraint, Rect},
+  style::{Color, Style},
+  text::Line,
+  widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  database::{
+    format_array_list, format_composite_list, format_range_list, hex_to_bytes, is_array_type, is_binary_type,
+    is_json_type, is_range_type, parse_array, parse_composite,
+  },
+  ui::center,
+};
+
+// shows the full value of a data table cell, which can be truncated or too
+// wide to read in the table itself, or a statement to read before running it
+#[derive(Debug)]
+pub struct CellDetail {
+  title: String,
+  // the value as it was fetched, for copying
+  raw: String,
+  bytes: Option<Vec<u8>>,
+  value: String,
+  // whether the value is a statement, which can be opened in the editor
+  statement: bool,
+  scroll: u16,
+  // how far the value can scroll, which depends on the width it was last drawn at
+  max_scroll: Cell<u16>,
+}
+
+impl CellDetail {
+  pub fn new(column_name: &str, type_name: &str, composite_fields: Option<&[String]>, value: &str) -> Self {
+    let bytes = if is_binary_type(type_name) { hex_to_bytes(value) } else { None };
+    Self {
+      title: format!(" {column_name} ({type_name}) "),
+      raw: value.to_string(),
+      value: match &bytes {
+        Some(bytes) => hex_dump(bytes),
+        None => pretty_value(type_name, composite_fields, value),
+      },
+      bytes,
+      statement: false,
+      scroll: 0,
+      max_scroll: Cell::new(u16::MAX),
+    }
+  }
+
+  /// The statements that recreate the table `name`.
+  pub fn statement(name: &str, statement: String) -> Self {
+    Self {
+      title: format!(" CREATE TABLE {name} "),
+      raw: statement.clone(),
+      bytes: None,
+      value: statement,
+      statement: true,
+      scroll: 0,
+      max_scroll: Cell::new(u16::MAX),
+    }
+  }
+
+  fn scroll_by(&mut self, lines: i32) {
+    self.scroll = (i32::from(self.scroll) + lines).clamp(0, i32::from(self.max_scroll.get())) as u16;
+  }
+}
+
+// json is pretty-printed, arrays and composites are listed one element or field a line, and
+// ranges have their bounds spelled out; everything else, including values that don't parse, is
+// shown as is
+fn pretty_value(type_name: &str, composite_fields: Option<&[String]>, value: &str) -> String {
+  if is_array_type(type_name) {
+    return match parse_array(value) {
+      Some(elements) => format!("{} elements\n\n{}", elements.len(), format_array_list(&elements, "NULL")),
+      None => value.to_string(),
+    };
+  }
+  if is_range_type(type_name) {
+    return format_range_list(value).map_or_else(|| value.to_string(), |bounds| format!("{value}\n\n{bounds}"));
+  }
+  if let Some(names) = composite_fields {
+    return match parse_composite(value) {
+      Some(fields) => format_composite_list(names, &fields, "NULL"),
+      None => value.to_string(),
+    };
+  }
+  if !is_json_type(type_name) {
+    return value.to_string();
+  }
+  serde_json::from_str::<serde_json::Value>(value)
+    .and_then(|json| serde_json::to_string_pretty(&json))
+    .unwrap_or_else(|_| value.to_string())
+}
+
+// 16 bytes a line, as offset, hex and ascii columns like `hexdump -C`
+fn hex_dump(bytes: &[u8]) -> String {
+  let lines = bytes.chunks(16).enumerate().map(|(line, chunk)| {
+    let hex = (0..16)
+      .map(|i| {
+        let gap = if i == 8 { " " } else { "" };
+        match chunk.get(i) {
+          Some(b) => format!("{gap}{b:02x} "),
+          None => format!("{gap}   "),
+        }
+      })
+      .collect::<String>();
+    let ascii =
+      chunk.iter().map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' }).collect::<String>();
+    format!("{:08x}  {hex} |{ascii}|", line * 16)
+  });
+  lines.collect::<Vec<_>>().join("\n")
+}
+
+impl PopUp for CellDetail {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Char('y') => return Ok(Some(PopUpPayload::Copy(self.raw.clone()))),
+      KeyCode::Char('e') if self.statement => return Ok(Some(PopUpPayload::QueryToEditor(self.raw.clone()))),
+      // the clipboard only holds text, so bytes that aren't utf-8 are replaced
+      KeyCode::Char('Y') if self.bytes.is_some() => {
+        let text = String::from_utf8_lossy(self.bytes.as_deref().unwrap_or_default()).into_owned();
+        return Ok(Some(PopUpPayload::Copy(text)));
+      },
+      KeyCode::Down | KeyCode::Char('j') => self.scroll_by(1),
+      KeyCode::Up | KeyCode::Char('k') => self.scroll_by(-1),
+      KeyCode::PageDown | KeyCode::Char('}') => self.scroll_by(10),
+      KeyCode::PageUp | KeyCode::Char('{') => self.scroll_by(-10),
+      KeyCode::Char('g') => self.scroll = 0,
+      KeyCode::Char('G') => self.scroll = self.max_scroll.get(),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    let area = center(area, Constraint::Percentage(80), Constraint::Percentage(80));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(Style::default().fg(Color::Yellow))
+      .title(Line::from(self.title.as_str()).centered())
+      .title_bottom(
+        Line::from(match (&self.bytes, self.statement) {
+          (Some(_), _) => " [j|↓] down [k|↑] up [g] top [G] bottom [y] copy hex [Y] copy as text [<esc>] close ",
+          (None, true) => " [j|↓] down [k|↑] up [g] top [G] bottom [y] copy [e] open in editor [<esc>] close ",
+          (None, false) => " [j|↓] down [k|↑] up [g] top [G] bottom [y] copy [<esc>] close ",
+        })
+        .centered(),
+      )
+      .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    let paragraph = Paragraph::new(self.value.as_str()).wrap(Wrap { trim: false });
+    let max_scroll = paragraph.line_count(inner.width).saturating_sub(inner.height as usize);
+    self.max_scroll.set(u16::try_from(max_scroll).unwrap_or(u16::MAX));
+    let scroll = self.scroll.min(self.max_scroll.get());
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph.scroll((scroll, 0)).block(block), area);
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_pretty_value() {
+    let test_cases = vec![
+      ("JSONB", r#"{"a": [1, 2]}"#, "{\n  \"a\": [\n    1,\n    2\n  ]\n}"),
+      ("json", "not json", "not json"),
+      ("TEXT", r#"{"a": 1}"#, r#"{"a": 1}"#),
+      ("TEXT[]", r#"{a,NULL,"b c"}"#, "3 elements\n\n[1] a\n[2] NULL\n[3] b c"),
+      ("INT4[]", "not an array", "not an array"),
+      ("INT4RANGE", "[1,10)", "[1,10)\n\nlower: 1 (inclusive)\nupper: 10 (exclusive)"),
+      // only composite columns have their fields listed, whatever their values look like
+      ("NUMBER(10)", "(1,a)", "(1,a)"),
+      ("?", "(1,a)", "(1,a)"),
+    ];
+
+    for (type_name, value, expected) in test_cases {
+      assert_eq!(pretty_value(type_name, None, value), expected, "Failed for value: {value}");
+    }
+    let names = ["street", "city"].map(String::from);
+    assert_eq!(pretty_value("address", Some(&names), r#"("Main St",)"#), "street: Main St\ncity: NULL");
+    assert_eq!(pretty_value("address", Some(&names[..1]), "(1,a)"), "street: 1\nf2: a");
+  }
+
+  #[test]
+  fn test_hex_dump() {
+    assert_eq!(
+      hex_dump(b"Hello, world!\n\x00\xff rest"),
+      [
+        "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|",
+        "00000010  20 72 65 73 74                                    | rest|",
+      ]
+      .join("\n")
+    );
+    assert_eq!(hex_dump(&[]), "");
+  }
+}
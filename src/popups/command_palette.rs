@@ -0,0 +1,206 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  Frame,
+  layout::{Constraint, Rect},
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+use super::{PopUp, PopUpPayload, keybinding_help::key_label};
+use crate::{
+  action::Action, components::menu::fuzzy_match, config::KeyBindings, focus::Focus, theme::Theme, ui::center,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Command {
+  action: Action,
+  label: &'static str,
+  // the keys bound to it in the pane the palette was opened from, if any are
+  keys: Option<String>,
+}
+
+// every action that can be run by hand, to find by typing part of its name and
+// run without knowing its keys
+#[derive(Debug)]
+pub struct CommandPalette {
+  commands: Vec<Command>,
+  theme: Theme,
+  search: String,
+  selected: usize,
+}
+
+impl CommandPalette {
+  pub fn new(focus: Focus, keybindings: &KeyBindings, theme: Theme) -> Self {
+    let keymap = keybindings.get(&focus);
+    let commands = Action::palette()
+      .into_iter()
+      .filter_map(|action| {
+        let label = action.label()?;
+        // the shortest binding, so the same one is shown every time
+        let keys = keymap
+          .into_iter()
+          .flatten()
+          .filter(|(_, bound)| **bound == action)
+          .map(|(keys, _)| keys.iter().map(key_label).collect::<Vec<_>>().join(" "))
+          .min_by_key(|keys| (keys.chars().count(), keys.clone()));
+        Some(Command { action, label, keys })
+      })
+      .collect();
+    Self { commands, theme, search: String::new(), selected: 0 }
+  }
+
+  // the commands matching the search, closest matches first
+  fn matches(&self) -> Vec<(&Command, Vec<usize>)> {
+    if self.search.trim().is_empty() {
+      return self.commands.iter().map(|command| (command, vec![])).collect();
+    }
+    let mut matches = self
+      .commands
+      .iter()
+      .filter_map(|command| fuzzy_match(&self.search, command.label).map(|positions| (command, positions)))
+      .collect::<Vec<_>>();
+    // letters next to each other, and nearer the start, match more closely
+    matches.sort_by_key(|(_, positions)| {
+      let first = positions.first().copied().unwrap_or_default();
+      (positions.last().copied().unwrap_or_default() - first, first)
+    });
+    matches
+  }
+
+  fn selected_action(&self) -> Option<Action> {
+    self.matches().get(self.selected).map(|(command, _)| command.action.clone())
+  }
+
+  fn select_by(&mut self, offset: isize) {
+    let count = self.matches().len();
+    if count > 0 {
+      self.selected = self.selected.saturating_add_signed(offset).min(count - 1);
+    }
+  }
+
+  fn lines(&self) -> Vec<Line<'_>> {
+    let matches = self.matches();
+    let label_width = matches.iter().map(|(command, _)| command.label.chars().count()).max().unwrap_or_default();
+    let lines = matches.iter().enumerate().map(|(i, (command, positions))| {
+      let mut spans = vec![Span::raw(if i == self.selected { "> " } else { "  " })];
+      for (position, c) in command.label.chars().enumerate() {
+        match positions.contains(&position) {
+          true => spans.push(Span::styled(c.to_string(), self.theme.highlight)),
+          false => spans.push(Span::raw(c.to_string())),
+        }
+      }
+      let padding = label_width - command.label.chars().count();
+      spans.push(Span::styled(
+        format!("{}  {}", " ".repeat(padding), command.keys.as_deref().unwrap_or_default()),
+        self.theme.muted,
+      ));
+      let line = Line::from(spans);
+      if i == self.selected { line.style(self.theme.selection) } else { line }
+    });
+    let lines = lines.collect::<Vec<_>>();
+    match lines.is_empty() {
+      true => vec![Line::styled("no matching commands", self.theme.muted)],
+      false => lines,
+    }
+  }
+}
+
+impl PopUp for CommandPalette {
+  fn handle_key_events(
+    &mut self,
+    key: KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+      KeyCode::Esc => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => return Ok(self.selected_action().map(PopUpPayload::RunAction)),
+      KeyCode::Down | KeyCode::Tab => self.select_by(1),
+      KeyCode::Up | KeyCode::BackTab => self.select_by(-1),
+      KeyCode::Char('n') if control => self.select_by(1),
+      KeyCode::Char('p') if control => self.select_by(-1),
+      KeyCode::Backspace => {
+        self.search.pop();
+        self.selected = 0;
+      },
+      KeyCode::Char(c) if !control => {
+        self.search.push(c);
+        self.selected = 0;
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    let area = center(area, Constraint::Percentage(50), Constraint::Percentage(60));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(self.theme.focused_border)
+      .title(Line::from(" commands ").centered())
+      .title_bottom(Line::from(" type to search [↓|↑] select [<enter>] run [<esc>] close ").centered())
+      .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    let search = Line::from(vec![Span::styled("> ", self.theme.muted), Span::raw(self.search.as_str())]);
+    let list_height = inner.height.saturating_sub(2);
+    // keeps the selected command in view
+    let scroll = u16::try_from(self.selected).unwrap_or(u16::MAX).saturating_sub(list_height.saturating_sub(1));
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(Paragraph::new(search), Rect { height: 1.min(inner.height), ..inner });
+    frame.render_widget(
+      Paragraph::new(self.lines()).scroll((scroll, 0)),
+      Rect { y: inner.y + 2.min(inner.height), height: list_height, ..inner },
+    );
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::config::parse_key_sequence;
+
+  fn palette() -> CommandPalette {
+    let keymap = [("<F5>", Action::SubmitEditorQuery), ("<Ctrl-Enter>", Action::SubmitEditorQuery)]
+      .into_iter()
+      .map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action))
+      .collect::<HashMap<_, _>>();
+    let keybindings = KeyBindings(HashMap::from([(Focus::Editor, keymap)]));
+    CommandPalette::new(Focus::Editor, &keybindings, Theme::default())
+  }
+
+  #[test]
+  fn test_command_palette_search() {
+    let mut palette = palette();
+    assert_eq!(palette.matches().len(), Action::palette().len());
+    let run = palette.commands.iter().find(|command| command.action == Action::SubmitEditorQuery).unwrap();
+    assert_eq!(run.keys.as_deref(), Some("<f5>"));
+
+    palette.search = "zoom".to_owned();
+    assert_eq!(palette.matches().iter().map(|(c, _)| c.label).collect::<Vec<_>>(), vec!["toggle zoom"]);
+    // closer matches come first
+    palette.search = "run q".to_owned();
+    assert_eq!(palette.matches()[0].0.label, "run query");
+    palette.search = "xyzzy".to_owned();
+    assert!(palette.matches().is_empty());
+  }
+
+  #[test]
+  fn test_command_palette_selection() {
+    let mut palette = palette();
+    palette.search = "fcs res".to_owned();
+    assert_eq!(palette.selected_action(), Some(Action::FocusData));
+    palette.select_by(1);
+    assert_eq!(palette.selected, 1);
+    palette.select_by(10);
+    assert_eq!(palette.selected, palette.matches().len() - 1);
+    palette.select_by(-10);
+    assert_eq!(palette.selected_action(), Some(Action::FocusData));
+
+    palette.search = "xyzzy".to_owned();
+    assert_eq!(palette.selected_action(), None);
+  }
+}
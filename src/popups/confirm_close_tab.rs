@@ -0,0 +1,36 @@
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+
+#[derive(Debug)]
+pub struct ConfirmCloseTab {
+  name: String,
+}
+
+impl ConfirmCloseTab {
+  pub fn new(name: String) -> Self {
+    Self { name }
+  }
+}
+
+impl PopUp for ConfirmCloseTab {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('Y') => Ok(Some(PopUpPayload::CloseQueryTab)),
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    format!("{} has unsaved changes in the editor. Are you sure you want to close it?", self.name)
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    "[Y]es to close | [N]o to cancel".to_string()
+  }
+}
@@ -1,18 +1,36 @@
 use crossterm::event::KeyCode;
+use strum::IntoEnumIterator;
 
 use super::{PopUp, PopUpPayload};
+use crate::{
+  action::ExportFormat,
+  config::{ExportOptions, LineTerminator},
+  export::file_extension,
+};
+
+// delimiters to cycle through when changing the options for a single export
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+const QUOTES: [char; 2] = ['"', '\''];
 
 #[derive(Debug)]
 pub struct ConfirmExport {
   row_count: i64,
+  format: ExportFormat,
+  options: ExportOptions,
 }
 
 impl ConfirmExport {
-  pub fn new(row_count: i64) -> Self {
-    Self { row_count }
+  pub fn new(row_count: i64, options: ExportOptions) -> Self {
+    Self { row_count, format: ExportFormat::CSV, options }
   }
 }
 
+// the next item after `current`, or the first one if `current` isn't listed
+fn cycle(choices: &[char], current: char) -> char {
+  let next = choices.iter().position(|c| *c == current).map_or(0, |i| (i + 1) % choices.len());
+  choices[next]
+}
+
 impl PopUp for ConfirmExport {
   fn handle_key_events(
     &mut self,
@@ -20,16 +38,55 @@ impl PopUp for ConfirmExport {
     app_state: &mut crate::app::AppState,
   ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
     match key.code {
-      KeyCode::Char('Y') => Ok(Some(PopUpPayload::ConfirmExport(true))),
-      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::ConfirmExport(false))),
+      KeyCode::Char('Y') => Ok(Some(PopUpPayload::ConfirmExport(Some((self.format.clone(), self.options.clone()))))),
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::ConfirmExport(None))),
+      KeyCode::Char('f') => {
+        let formats = ExportFormat::iter().collect::<Vec<_>>();
+        let next = formats.iter().position(|f| *f == self.format).map_or(0, |i| (i + 1) % formats.len());
+        self.format = formats[next].clone();
+        Ok(None)
+      },
+      KeyCode::Char('d') => {
+        self.options.delimiter = cycle(&DELIMITERS, self.options.delimiter);
+        Ok(None)
+      },
+      KeyCode::Char('q') => {
+        self.options.quote = cycle(&QUOTES, self.options.quote);
+        Ok(None)
+      },
+      KeyCode::Char('h') => {
+        self.options.include_header = !self.options.include_header;
+        Ok(None)
+      },
+      KeyCode::Char('l') => {
+        self.options.line_terminator = match self.options.line_terminator {
+          LineTerminator::Lf => LineTerminator::Crlf,
+          LineTerminator::Crlf => LineTerminator::Lf,
+        };
+        Ok(None)
+      },
+      KeyCode::Char('b') => {
+        self.options.bom = !self.options.bom;
+        Ok(None)
+      },
       _ => Ok(None),
     }
   }
 
   fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
     format!(
-      "Are you sure you want to export {} rows? Exporting too many rows may cause the app to hang.",
+      "Are you sure you want to export {} rows? Exporting too many rows may cause the app to hang. [f]ormat: {} | [d]elimiter: {} | [q]uote: {} | [h]eader: {} | [l]ine endings: {} | [b]om: {}",
       self.row_count,
+      file_extension(&self.format),
+      self.options.delimiter.escape_default(),
+      self.options.quote,
+      yes_no(self.options.include_header),
+      match self.options.line_terminator {
+        LineTerminator::Lf => "LF",
+        LineTerminator::Crlf => "CRLF",
+      },
+      yes_no(self.options.bom),
     )
   }
 
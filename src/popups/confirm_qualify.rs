@@ -0,0 +1,42 @@
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+use crate::database::Qualification;
+
+#[derive(Debug)]
+pub struct ConfirmQualify {
+  query: String,
+  qualified_query: String,
+  qualifications: Vec<Qualification>,
+}
+
+impl ConfirmQualify {
+  pub fn new(query: String, qualified_query: String, qualifications: Vec<Qualification>) -> Self {
+    Self { query, qualified_query, qualifications }
+  }
+}
+
+impl PopUp for ConfirmQualify {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('Y') => Ok(Some(PopUpPayload::ConfirmQualify(self.qualified_query.to_owned()))),
+      KeyCode::Char('W') => Ok(Some(PopUpPayload::QueryAsWritten(self.query.to_owned()))),
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    let suggestions =
+      self.qualifications.iter().map(|q| format!("{} -> {}", q.table, q.qualified)).collect::<Vec<String>>().join(", ");
+    format!("Some tables aren't on the search path. Did you mean: {suggestions}?")
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    "[Y]es to run qualified | [W] to run as written | [N]o to cancel".to_string()
+  }
+}
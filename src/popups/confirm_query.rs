@@ -1,35 +1,50 @@
 use crossterm::event::KeyCode;
+use ratatui::{
+  Frame,
+  layout::{Constraint, Layout, Rect},
+  style::{Color, Style, Stylize},
+  text::Line,
+  widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
 use sqlparser::ast::Statement;
 
 use super::{PopUp, PopUpPayload};
-use crate::database::statement_type_string;
+use crate::{
+  database::{missing_where, statement_type_string},
+  ui::center,
+};
 
 #[derive(Debug)]
 pub struct ConfirmQuery {
   pending_query: String,
   statement_type: Statement,
+  // whether the prompt only comes from the `confirm_destructive` setting, so
+  // it can be turned off from here. DROP and the like are always asked about.
+  // these are the statements that would otherwise run in a transaction, which
+  // they still do once confirmed.
+  skippable: bool,
+  // how many statements the query has, when it's a script that's confirmed as a whole
+  script_len: Option<usize>,
 }
 
 impl ConfirmQuery {
-  pub fn new(pending_query: String, statement_type: Statement) -> Self {
-    Self { pending_query, statement_type }
+  pub fn new(pending_query: String, statement_type: Statement, skippable: bool) -> Self {
+    Self { pending_query, statement_type, skippable, script_len: None }
   }
-}
 
-impl PopUp for ConfirmQuery {
-  fn handle_key_events(
-    &mut self,
-    key: crossterm::event::KeyEvent,
-    app_state: &mut crate::app::AppState,
-  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
-    match key.code {
-      KeyCode::Char('Y') => Ok(Some(PopUpPayload::ConfirmQuery(self.pending_query.to_owned()))),
-      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::SetDataTable(None, None))),
-      _ => Ok(None),
-    }
+  /// Confirms a script of several statements at once, naming the first
+  /// statement in it that needs confirming.
+  pub fn script(pending_query: String, statement_type: Statement, script_len: usize) -> Self {
+    Self { pending_query, statement_type, skippable: false, script_len: Some(script_len) }
   }
 
-  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+  fn cta(&self) -> String {
+    if let Some(script_len) = self.script_len {
+      return format!(
+        "Are you sure you want to run a script of {script_len} statements with a {} statement?",
+        statement_type_string(Some(self.statement_type.clone())).to_uppercase()
+      );
+    }
     match self.statement_type.clone() {
       Statement::Explain { statement, .. } => {
         format!(
@@ -46,7 +61,85 @@ impl PopUp for ConfirmQuery {
     }
   }
 
+  fn actions(&self) -> &'static str {
+    match self.skippable {
+      true => "[Y]es to confirm | [A]lways for this session | [N]o to cancel",
+      false => "[Y]es to confirm | [N]o to cancel",
+    }
+  }
+
+  fn warning(&self) -> Option<String> {
+    missing_where(&self.statement_type).then(|| {
+      format!(
+        "This {} has no WHERE clause, so it affects every row in the table!",
+        statement_type_string(Some(self.statement_type.clone())).to_uppercase()
+      )
+    })
+  }
+}
+
+impl PopUp for ConfirmQuery {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let confirmed = match self.skippable {
+      true => PopUpPayload::ConfirmTxQuery(self.pending_query.clone()),
+      false => PopUpPayload::ConfirmQuery(self.pending_query.clone()),
+    };
+    match key.code {
+      KeyCode::Char('Y') => Ok(Some(confirmed)),
+      KeyCode::Char('A') if self.skippable => {
+        app_state.confirm_destructive = false;
+        Ok(Some(confirmed))
+      },
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::SetDataTable(None, None))),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    self.cta()
+  }
+
   fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
-    "[Y]es to confirm | [N]o to cancel".to_string()
+    self.actions().to_string()
+  }
+
+  // the statement is shown as well, so it can be checked before it runs
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    let area = center(area, Constraint::Percentage(60), Constraint::Percentage(60));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(Style::default().fg(Color::Yellow))
+      .title(Line::from(" Confirm Action ").centered())
+      .padding(Padding::uniform(1));
+    let warning = self.warning();
+    let [cta_area, query_area, warning_area, actions_area] = Layout::vertical([
+      Constraint::Length(2),
+      Constraint::Fill(1),
+      Constraint::Length(if warning.is_some() { 2 } else { 0 }),
+      Constraint::Length(1),
+    ])
+    .areas(block.inner(area));
+    let cta = Paragraph::new(Line::from(self.cta()).centered()).wrap(Wrap { trim: false });
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(cta, cta_area);
+    frame.render_widget(
+      Paragraph::new(self.pending_query.as_str())
+        .block(Block::default().borders(Borders::LEFT).dim())
+        .wrap(Wrap { trim: false }),
+      query_area,
+    );
+    if let Some(warning) = warning {
+      frame.render_widget(
+        Paragraph::new(Line::from(warning).centered()).red().bold().wrap(Wrap { trim: false }),
+        warning_area,
+      );
+    }
+    frame.render_widget(Paragraph::new(Line::from(self.actions()).centered()), actions_area);
+    true
   }
 }
@@ -0,0 +1,39 @@
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+
+#[derive(Debug)]
+pub struct ConfirmRestore {
+  tab_count: usize,
+}
+
+impl ConfirmRestore {
+  pub fn new(tab_count: usize) -> Self {
+    Self { tab_count }
+  }
+}
+
+impl PopUp for ConfirmRestore {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('Y') => Ok(Some(PopUpPayload::RestoreScratch)),
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::DiscardScratch)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    match self.tab_count {
+      1 => "The editor had unsaved queries when rainfrog was last closed. Do you want them back?".to_string(),
+      n => format!("{n} query tabs had unsaved queries when rainfrog was last closed. Do you want them back?"),
+    }
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    "[Y]es to restore | [N]o to start empty".to_string()
+  }
+}
@@ -0,0 +1,43 @@
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+use crate::action::ExportFormat;
+
+#[derive(Debug)]
+pub struct CopyAs {
+  row_count: i64,
+}
+
+impl CopyAs {
+  pub fn new(row_count: i64) -> Self {
+    Self { row_count }
+  }
+}
+
+impl PopUp for CopyAs {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('c') => Ok(Some(PopUpPayload::CopyAs(ExportFormat::CSV))),
+      KeyCode::Char('t') => Ok(Some(PopUpPayload::CopyAs(ExportFormat::TSV))),
+      KeyCode::Char('j') => Ok(Some(PopUpPayload::CopyAs(ExportFormat::JSON))),
+      KeyCode::Char('m') => Ok(Some(PopUpPayload::CopyAs(ExportFormat::Markdown))),
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    format!(
+      "Copy {} rows to the clipboard as which format? Copying too many rows may cause the app to hang.",
+      self.row_count
+    )
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    "[c]sv | [t]sv | [j]son | [m]arkdown | [Esc] to cancel".to_string()
+  }
+}
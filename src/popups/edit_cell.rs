@@ -0,0 +1,103 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+  Frame,
+  layout::{Constraint, Layout, Rect},
+  style::{Color, Style, Stylize},
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  database::{CellEdit, is_null},
+  ui::center,
+};
+
+// takes a new value for a cell of the results, showing the UPDATE that will
+// set it. the UPDATE runs in a transaction, to commit once its rows affected
+// are seen.
+#[derive(Debug)]
+pub struct EditCell {
+  edit: CellEdit,
+  // `None` while the value is set to NULL
+  value: Option<String>,
+}
+
+impl EditCell {
+  pub fn new(edit: CellEdit) -> Self {
+    let value = (!is_null(&edit.value)).then(|| edit.value.clone());
+    Self { edit, value }
+  }
+
+  fn query(&self) -> String {
+    self.edit.update_query(self.value.as_deref())
+  }
+
+  fn unchanged(&self) -> bool {
+    match &self.value {
+      Some(value) => *value == self.edit.value,
+      None => is_null(&self.edit.value),
+    }
+  }
+
+  fn actions(&self) -> &'static str {
+    "[Enter] run | [Ctrl-n] set NULL | [Esc] cancel"
+  }
+}
+
+impl PopUp for EditCell {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+      KeyCode::Esc => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter if self.unchanged() => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => return Ok(Some(PopUpPayload::Query(self.query()))),
+      KeyCode::Char('n') if control => self.value = None,
+      KeyCode::Char('u') if control => self.value = Some(String::new()),
+      KeyCode::Backspace => {
+        self.value.get_or_insert_with(String::new).pop();
+      },
+      // typing over NULL starts a new value
+      KeyCode::Char(c) if !control => self.value.get_or_insert_with(String::new).push(c),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    format!("Type a new value for {} in {}.", self.edit.column, self.edit.table)
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    self.actions().to_string()
+  }
+
+  // the UPDATE is shown as it's typed, so it can be checked before it runs
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    let area = center(area, Constraint::Percentage(60), Constraint::Percentage(60));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(Style::default().fg(Color::Yellow))
+      .title(Line::from(format!(" Edit {} ", self.edit.column)).centered())
+      .padding(Padding::uniform(1));
+    let [value_area, query_area, actions_area] =
+      Layout::vertical([Constraint::Length(2), Constraint::Fill(1), Constraint::Length(1)]).areas(block.inner(area));
+    let value = match &self.value {
+      Some(value) => Span::raw(format!("{value}▏")),
+      None => Span::raw("NULL").dim().italic(),
+    };
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(Paragraph::new(Line::from(vec![Span::raw("> ").dim(), value])), value_area);
+    frame.render_widget(
+      Paragraph::new(self.query()).block(Block::default().borders(Borders::LEFT).dim()).wrap(Wrap { trim: false }),
+      query_area,
+    );
+    frame.render_widget(Paragraph::new(Line::from(self.actions()).centered()), actions_area);
+    true
+  }
+}
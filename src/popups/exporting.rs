@@ -1,11 +1,19 @@
+use crossterm::event::KeyCode;
+
 use super::{PopUp, PopUpPayload};
 
 #[derive(Debug, Default)]
-pub struct Exporting {}
+pub struct Exporting {
+  rows_exported: Option<u64>,
+}
 
 impl Exporting {
   pub fn new() -> Self {
-    Self {}
+    Self { rows_exported: None }
+  }
+
+  pub fn with_progress(rows_exported: u64) -> Self {
+    Self { rows_exported: Some(rows_exported) }
   }
 }
 
@@ -15,14 +23,20 @@ impl PopUp for Exporting {
     key: crossterm::event::KeyEvent,
     app_state: &mut crate::app::AppState,
   ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
-    Ok(None)
+    match key.code {
+      KeyCode::Esc => Ok(Some(PopUpPayload::AbortExport)),
+      _ => Ok(None),
+    }
   }
 
   fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
-    "Exporting...".to_string()
+    match self.rows_exported {
+      Some(rows) => format!("Exporting... ({rows} rows so far)"),
+      None => "Exporting...".to_string(),
+    }
   }
 
   fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
-    "".to_string()
+    "[Esc] to cancel".to_string()
   }
 }
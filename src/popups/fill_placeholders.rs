@@ -0,0 +1,74 @@
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+
+// asks for the value of each placeholder in a favorite before it runs
+#[derive(Debug)]
+pub struct FillPlaceholders {
+  query_lines: Vec<String>,
+  names: Vec<String>,
+  values: Vec<String>,
+  current: usize,
+}
+
+impl FillPlaceholders {
+  pub fn new(query_lines: Vec<String>, names: Vec<String>) -> Self {
+    let values = vec![String::new(); names.len()];
+    Self { query_lines, names, values, current: 0 }
+  }
+}
+
+impl PopUp for FillPlaceholders {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char(c) => {
+        self.values[self.current].push(c);
+        Ok(None)
+      },
+      KeyCode::Backspace => {
+        self.values[self.current].pop();
+        Ok(None)
+      },
+      KeyCode::Tab | KeyCode::Down => {
+        self.current = (self.current + 1).min(self.names.len().saturating_sub(1));
+        Ok(None)
+      },
+      KeyCode::BackTab | KeyCode::Up => {
+        self.current = self.current.saturating_sub(1);
+        Ok(None)
+      },
+      KeyCode::Enter if self.current + 1 < self.names.len() => {
+        self.current += 1;
+        Ok(None)
+      },
+      KeyCode::Enter => Ok(Some(PopUpPayload::FilledPlaceholders(
+        self.query_lines.clone(),
+        self.names.iter().cloned().zip(self.values.iter().cloned()).collect(),
+      ))),
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    format!(
+      "Input a value for {} ({} of {}) and then press [Enter]; press [Esc] to cancel. Numbers are used as they are, anything else is quoted as a string.",
+      self.names[self.current],
+      self.current + 1,
+      self.names.len()
+    )
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    format!(
+      "{} = {}{}",
+      self.names[self.current],
+      self.values[self.current],
+      if self.current + 1 < self.names.len() { "  [Tab|Enter] next" } else { "  [Enter] run query" }
+    )
+  }
+}
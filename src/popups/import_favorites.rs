@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+use crate::components::favorites::BundledFavorite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolution {
+  Skip,
+  Overwrite,
+  Rename,
+}
+
+// asks what to do with each imported favorite whose name is already taken
+#[derive(Debug)]
+pub struct ImportFavorites {
+  conflicts: VecDeque<BundledFavorite>,
+}
+
+impl ImportFavorites {
+  pub fn new(conflicts: Vec<BundledFavorite>) -> Self {
+    Self { conflicts: conflicts.into() }
+  }
+
+  fn resolve(&mut self, resolution: Resolution, app_state: &mut crate::app::AppState) {
+    if let Some(favorite) = self.conflicts.pop_front() {
+      let query_lines = favorite.query.split('\n').map(|s| s.to_string()).collect();
+      match resolution {
+        Resolution::Skip => {},
+        Resolution::Overwrite => app_state.favorites.add_entry(favorite.name, query_lines),
+        Resolution::Rename => {
+          let name = app_state.favorites.unused_name(&favorite.name);
+          app_state.favorites.add_entry(name, query_lines);
+        },
+      }
+    }
+  }
+}
+
+impl PopUp for ImportFavorites {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let (resolution, all) = match key.code {
+      KeyCode::Char('s') => (Resolution::Skip, false),
+      KeyCode::Char('o') => (Resolution::Overwrite, false),
+      KeyCode::Char('r') => (Resolution::Rename, false),
+      KeyCode::Char('S') | KeyCode::Esc => (Resolution::Skip, true),
+      KeyCode::Char('O') => (Resolution::Overwrite, true),
+      KeyCode::Char('R') => (Resolution::Rename, true),
+      _ => return Ok(None),
+    };
+    self.resolve(resolution, app_state);
+    while all && !self.conflicts.is_empty() {
+      self.resolve(resolution, app_state);
+    }
+    match self.conflicts.is_empty() {
+      true => Ok(Some(PopUpPayload::Cancel)),
+      false => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    match self.conflicts.front() {
+      Some(favorite) => format!(
+        "A favorite named {} already exists with a different query. {} conflict{} left to resolve.",
+        favorite.name,
+        self.conflicts.len(),
+        if self.conflicts.len() == 1 { "" } else { "s" },
+      ),
+      None => String::new(),
+    }
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    let renamed = self.conflicts.front().map(|favorite| app_state.favorites.unused_name(&favorite.name));
+    format!(
+      "[s]kip | [o]verwrite | [r]ename to {} | [S|O|R] for all remaining | [Esc] skip all",
+      renamed.unwrap_or_default()
+    )
+  }
+}
@@ -0,0 +1,243 @@
+use std::cell::Cell;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  Frame,
+  layout::{Constraint, Rect},
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  action::Action,
+  components::menu::fuzzy_match,
+  config::{KeyBindings, key_event_to_string},
+  focus::Focus,
+  theme::Theme,
+  ui::center,
+};
+
+// order the categories are listed in
+const CATEGORIES: [&str; 6] = ["general", "queries", "files", "navigation", "layout", "other"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Binding {
+  category: &'static str,
+  keys: String,
+  action: String,
+}
+
+// lists the keybindings of the focused pane, as they're configured, so
+// custom bindings show up too
+#[derive(Debug)]
+pub struct KeybindingHelp {
+  title: String,
+  bindings: Vec<Binding>,
+  theme: Theme,
+  search: String,
+  scroll: u16,
+  // how far the list can scroll, which depends on the height it was last drawn at
+  max_scroll: Cell<u16>,
+}
+
+impl KeybindingHelp {
+  pub fn new(focus: Focus, keybindings: &KeyBindings, theme: Theme) -> Self {
+    let mut bindings: Vec<Binding> = keybindings
+      .get(&focus)
+      .into_iter()
+      .flatten()
+      .map(|(keys, action)| Binding {
+        category: category(action),
+        keys: keys.iter().map(key_label).collect::<Vec<_>>().join(" "),
+        action: action.to_string(),
+      })
+      .collect();
+    bindings.sort_by_key(|binding| {
+      (CATEGORIES.iter().position(|c| *c == binding.category), binding.action.clone(), binding.keys.clone())
+    });
+    Self {
+      title: format!(" {} keybindings ", format!("{focus:?}").to_lowercase()),
+      bindings,
+      theme,
+      search: String::new(),
+      scroll: 0,
+      max_scroll: Cell::new(u16::MAX),
+    }
+  }
+
+  fn matches(&self) -> Vec<&Binding> {
+    self
+      .bindings
+      .iter()
+      .filter(|binding| {
+        self.search.is_empty()
+          || fuzzy_match(&self.search, &binding.action).is_some()
+          || binding.keys.to_lowercase().contains(&self.search.to_lowercase())
+      })
+      .collect()
+  }
+
+  fn lines(&self) -> Vec<Line<'_>> {
+    let matches = self.matches();
+    let key_width = matches.iter().map(|binding| binding.keys.chars().count()).max().unwrap_or_default();
+    let mut lines = vec![];
+    let mut last_category = None;
+    for binding in matches {
+      if last_category != Some(binding.category) {
+        if last_category.is_some() {
+          lines.push(Line::default());
+        }
+        lines.push(Line::styled(binding.category, self.theme.title));
+        last_category = Some(binding.category);
+      }
+      lines.push(Line::from(vec![
+        Span::styled(format!("  {:<key_width$}  ", binding.keys), self.theme.highlight),
+        Span::raw(binding.action.as_str()),
+      ]));
+    }
+    if lines.is_empty() {
+      lines.push(Line::styled("no matching keybindings", self.theme.muted));
+    }
+    lines
+  }
+
+  fn scroll_by(&mut self, lines: i32) {
+    self.scroll = (i32::from(self.scroll) + lines).clamp(0, i32::from(self.max_scroll.get())) as u16;
+  }
+}
+
+fn category(action: &Action) -> &'static str {
+  match action {
+    Action::Quit
+    | Action::AbortQuery
+    | Action::Help
+    | Action::CommandPalette
+    | Action::RequestSwitchConnection
+    | Action::RequestUseDatabase => "general",
+    Action::SubmitEditorQuery
+    | Action::SubmitEditorQueryAndFocusData
+    | Action::SubmitEditorQueryBypassParser
+    | Action::ExplainEditorQuery
+    | Action::ExplainAnalyzeEditorQuery
+    | Action::FormatQuery
+    | Action::ToggleComment => "queries",
+    Action::RequestLoadSqlFile | Action::RequestSaveSqlFile => "files",
+    Action::FocusMenu
+    | Action::FocusEditor
+    | Action::FocusData
+    | Action::FocusHistory
+    | Action::FocusFavorites
+    | Action::CycleFocusForwards
+    | Action::CycleFocusBackwards
+    | Action::NewQueryTab
+    | Action::CloseQueryTab
+    | Action::NextQueryTab
+    | Action::PreviousQueryTab
+    | Action::SelectQueryTab(_) => "navigation",
+    Action::ToggleLayoutSplit | Action::ToggleZoom => "layout",
+    _ => "other",
+  }
+}
+
+/// Plain characters as they are, and anything else in angle brackets like the
+/// config file.
+pub fn key_label(key: &KeyEvent) -> String {
+  match key.code {
+    KeyCode::Char(c) if key.modifiers.difference(crossterm::event::KeyModifiers::SHIFT).is_empty() && c != ' ' => {
+      c.to_string()
+    },
+    _ => format!("<{}>", key_event_to_string(key)),
+  }
+}
+
+impl PopUp for KeybindingHelp {
+  fn handle_key_events(
+    &mut self,
+    key: KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc if !self.search.is_empty() => {
+        self.search.clear();
+        self.scroll = 0;
+      },
+      KeyCode::Esc | KeyCode::Char('?') => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Down => self.scroll_by(1),
+      KeyCode::Up => self.scroll_by(-1),
+      KeyCode::PageDown => self.scroll_by(10),
+      KeyCode::PageUp => self.scroll_by(-10),
+      KeyCode::Backspace => {
+        self.search.pop();
+        self.scroll = 0;
+      },
+      KeyCode::Char(c) => {
+        self.search.push(c);
+        self.scroll = 0;
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    let area = center(area, Constraint::Percentage(60), Constraint::Percentage(70));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(self.theme.focused_border)
+      .title(Line::from(self.title.as_str()).centered())
+      .title_bottom(Line::from(" type to search [↓|↑] scroll [<esc>|?] close ").centered())
+      .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+    let search = Line::from(vec![Span::styled("search: ", self.theme.muted), Span::raw(self.search.as_str())]);
+    let lines = self.lines();
+    let list_height = inner.height.saturating_sub(2);
+    self.max_scroll.set(u16::try_from(lines.len().saturating_sub(list_height as usize)).unwrap_or(u16::MAX));
+    let scroll = self.scroll.min(self.max_scroll.get());
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(Paragraph::new(search), Rect { height: 1.min(inner.height), ..inner });
+    frame.render_widget(
+      Paragraph::new(lines).scroll((scroll, 0)),
+      Rect { y: inner.y + 2.min(inner.height), height: list_height, ..inner },
+    );
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crossterm::event::KeyModifiers;
+
+  use super::*;
+  use crate::config::parse_key_sequence;
+
+  #[test]
+  fn test_keybinding_help() {
+    let keymap = [("<Ctrl-c>", Action::Quit), ("<F5>", Action::SubmitEditorQuery), ("<Alt-1>", Action::FocusMenu)]
+      .into_iter()
+      .map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action))
+      .collect::<HashMap<_, _>>();
+    let keybindings = KeyBindings(HashMap::from([(Focus::Editor, keymap)]));
+
+    let mut help = KeybindingHelp::new(Focus::Editor, &keybindings, Theme::default());
+    assert_eq!(
+      help.bindings.iter().map(|b| (b.category, b.keys.as_str(), b.action.as_str())).collect::<Vec<_>>(),
+      vec![
+        ("general", "<ctrl-c>", "Quit"),
+        ("queries", "<f5>", "SubmitEditorQuery"),
+        ("navigation", "<alt-1>", "FocusMenu")
+      ]
+    );
+
+    help.search = "menu".to_owned();
+    assert_eq!(help.matches().iter().map(|b| b.action.as_str()).collect::<Vec<_>>(), vec!["FocusMenu"]);
+    help.search = "f5".to_owned();
+    assert_eq!(help.matches().iter().map(|b| b.action.as_str()).collect::<Vec<_>>(), vec!["SubmitEditorQuery"]);
+    assert_eq!(key_label(&KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)), "G");
+
+    assert!(KeybindingHelp::new(Focus::Data, &keybindings, Theme::default()).matches().is_empty());
+  }
+}
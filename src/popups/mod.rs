@@ -1,16 +1,37 @@
+use std::path::PathBuf;
+
 use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
+use ratatui::{Frame, layout::Rect};
 use sqlparser::ast::Statement;
 
-use crate::{app::AppState, database::Rows};
+use self::sql_file::SqlFileMode;
+use crate::{
+  action::{Action, ExportFormat},
+  app::AppState,
+  config::ExportOptions,
+  database::Rows,
+};
 
+pub mod cell_detail;
+pub mod command_palette;
 pub mod confirm_bypass;
+pub mod confirm_close_tab;
 pub mod confirm_export;
+pub mod confirm_qualify;
 pub mod confirm_query;
+pub mod confirm_restore;
 pub mod confirm_tx;
 pub mod confirm_yank;
+pub mod copy_as;
+pub mod edit_cell;
 pub mod exporting;
+pub mod fill_placeholders;
+pub mod import_favorites;
+pub mod keybinding_help;
 pub mod name_favorite;
+pub mod sql_file;
+pub mod switch_connection;
 
 // since popups are meant to overlay the entire app and capture
 // all input, we have a payload representing when a popup is exited
@@ -23,10 +44,39 @@ pub enum PopUpPayload {
   CommitTx,
   RollbackTx,
   ConfirmQuery(String),
+  // a confirmed query that should still run in a transaction
+  ConfirmTxQuery(String),
+  // a query to run like one from the editor, asking first if it would be asked about
+  Query(String),
+  ConfirmQualify(String),
+  // a query to run without qualifying its tables
+  QueryAsWritten(String),
   ConfirmBypass(String),
-  ConfirmExport(bool),
+  ConfirmExport(Option<(ExportFormat, ExportOptions)>),
+  AbortExport,
   ConfirmYank(bool),
+  CopyAs(ExportFormat),
+  Copy(String),
+  // replaces the query in the editor
+  QueryToEditor(String),
   NamedFavorite(String, Vec<String>),
+  // old name, new name
+  RenameFavorite(String, String),
+  // query lines, and the value of each placeholder in them
+  FilledPlaceholders(Vec<String>, Vec<(String, String)>),
+  SqlFile(SqlFileMode, PathBuf),
+  // the name of a connection from the config
+  SwitchConnection(String),
+  // the name of another database on the connected server
+  UseDatabase(String),
+  // closes the shown query tab without asking again
+  CloseQueryTab,
+  // puts back the query tabs saved when rainfrog was last closed
+  RestoreScratch,
+  // starts with empty query tabs rather than the saved ones, which can be saved over from then on
+  DiscardScratch,
+  // an action picked from the command palette, run once the pane it was opened from is focused again
+  RunAction(Action),
 }
 
 pub trait PopUp {
@@ -42,4 +92,11 @@ pub trait PopUp {
   fn get_actions_text(&self, app_state: &AppState) -> String {
     "".to_string()
   }
+
+  // popups that need more than a prompt and a line of actions can draw
+  // themselves instead. returns whether they did.
+  #[allow(unused_variables)]
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    false
+  }
 }
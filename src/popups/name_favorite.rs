@@ -1,17 +1,30 @@
 use crossterm::event::KeyCode;
 
 use super::{PopUp, PopUpPayload};
+use crate::components::favorites::FavoriteEntry;
 
 #[derive(Debug)]
 pub struct NameFavorite {
   name: String,
   existing_names: Vec<String>,
   query_lines: Vec<String>,
+  // the current name of the favorite being renamed or moved
+  renaming: Option<String>,
 }
 
 impl NameFavorite {
   pub fn new(existing_names: Vec<String>, query_lines: Vec<String>) -> Self {
-    Self { name: "".to_string(), existing_names, query_lines }
+    Self { name: "".to_string(), existing_names, query_lines, renaming: None }
+  }
+
+  pub fn rename(existing_names: Vec<String>, name: String) -> Self {
+    Self { name: name.clone(), existing_names, query_lines: vec![], renaming: Some(name) }
+  }
+
+  // whether saving under the typed name would replace a different favorite
+  fn name_taken(&self) -> bool {
+    self.renaming.as_deref() != Some(self.name.trim())
+      && self.existing_names.iter().any(|n| n.trim() == self.name.trim())
   }
 }
 
@@ -24,7 +37,10 @@ impl PopUp for NameFavorite {
     match key.code {
       KeyCode::Char(c) => {
         // ignore invalid characters
-        if c.is_whitespace() || c.is_ascii_whitespace() || (c.is_ascii_punctuation() && c != '_' && c != '-') {
+        if c.is_whitespace()
+          || c.is_ascii_whitespace()
+          || (c.is_ascii_punctuation() && c != '_' && c != '-' && c != '/')
+        {
           return Ok(None);
         }
         self.name.push(c);
@@ -32,10 +48,15 @@ impl PopUp for NameFavorite {
       },
       KeyCode::Enter => {
         let favorite_name = self.name.trim();
-        if !favorite_name.is_empty() {
-          return Ok(Some(PopUpPayload::NamedFavorite(favorite_name.to_string(), self.query_lines.clone())));
+        if !FavoriteEntry::is_valid_name(favorite_name) {
+          return Ok(None);
+        }
+        match &self.renaming {
+          Some(old_name) if old_name == favorite_name => Ok(Some(PopUpPayload::Cancel)),
+          Some(_) if self.name_taken() => Ok(None),
+          Some(old_name) => Ok(Some(PopUpPayload::RenameFavorite(old_name.clone(), favorite_name.to_string()))),
+          None => Ok(Some(PopUpPayload::NamedFavorite(favorite_name.to_string(), self.query_lines.clone()))),
         }
-        Ok(None)
       },
       KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
       KeyCode::Backspace => {
@@ -49,15 +70,22 @@ impl PopUp for NameFavorite {
   }
 
   fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
-    "Input a name for the favorite and then press [Enter]; press [Esc] to cancel. No spaces or special characters allowed.".to_string()
+    format!(
+      "Input a {}name for the favorite and then press [Enter]; press [Esc] to cancel. Use / to save it in a folder, like reports/signups. No spaces or other special characters allowed.",
+      if self.renaming.is_some() { "new " } else { "" }
+    )
   }
 
   fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
     format!(
       "{}.sql{}",
       self.name,
-      if self.existing_names.iter().any(|n| n.as_str().trim() == self.name.as_str().trim()) {
+      if self.name_taken() && self.renaming.is_some() {
+        " (a favorite with this name already exists, pick another name.)"
+      } else if self.name_taken() {
         " (WARNING! a favorite with this name already exists, saving now will overwrite it.)"
+      } else if !self.name.is_empty() && !FavoriteEntry::is_valid_name(self.name.trim()) {
+        " (folder and file names can't be empty, . or ..)"
       } else {
         ""
       }
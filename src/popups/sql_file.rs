@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use crossterm::event::KeyCode;
+
+use super::{PopUp, PopUpPayload};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlFileMode {
+  Load,
+  Save,
+  ExportFavorites,
+  ImportFavorites,
+}
+
+#[derive(Debug)]
+pub struct SqlFile {
+  mode: SqlFileMode,
+  base_dir: PathBuf,
+  path: String,
+  confirm_overwrite: bool,
+}
+
+impl SqlFile {
+  pub fn new(mode: SqlFileMode, base_dir: PathBuf, last_path: Option<PathBuf>) -> Self {
+    let path = last_path.map(|p| p.display().to_string()).unwrap_or_default();
+    Self { mode, base_dir, path, confirm_overwrite: false }
+  }
+
+  // relative paths are relative to the scripts directory, or the export
+  // directory for favorites bundles
+  fn resolved_path(&self) -> PathBuf {
+    self.base_dir.join(self.path.trim())
+  }
+
+  fn overwrites_file(&self) -> bool {
+    matches!(self.mode, SqlFileMode::Save | SqlFileMode::ExportFavorites) && self.resolved_path().is_file()
+  }
+}
+
+impl PopUp for SqlFile {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char(c) => {
+        self.path.push(c);
+        self.confirm_overwrite = false;
+        Ok(None)
+      },
+      KeyCode::Backspace => {
+        self.path.pop();
+        self.confirm_overwrite = false;
+        Ok(None)
+      },
+      KeyCode::Enter if self.path.trim().is_empty() => Ok(None),
+      // the first enter only warns when saving over an existing file
+      KeyCode::Enter if self.overwrites_file() && !self.confirm_overwrite => {
+        self.confirm_overwrite = true;
+        Ok(None)
+      },
+      KeyCode::Enter => Ok(Some(PopUpPayload::SqlFile(self.mode, self.resolved_path()))),
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState) -> String {
+    format!(
+      "Input the path of the {} and then press [Enter]; press [Esc] to cancel. Relative paths are relative to {}",
+      match self.mode {
+        SqlFileMode::Load => ".sql file to load into the editor",
+        SqlFileMode::Save => ".sql file to save the editor to",
+        SqlFileMode::ExportFavorites => ".json file to export all favorites to",
+        SqlFileMode::ImportFavorites => ".json file of favorites to import",
+      },
+      self.base_dir.display()
+    )
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState) -> String {
+    format!(
+      "{}{}",
+      self.path,
+      match (self.overwrites_file(), self.confirm_overwrite) {
+        (true, false) => " (WARNING! this file already exists, saving will overwrite it.)",
+        (true, true) => " (press [Enter] again to overwrite the existing file.)",
+        _ => "",
+      }
+    )
+  }
+}
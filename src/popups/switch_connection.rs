@@ -0,0 +1,122 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+  Frame,
+  layout::{Constraint, Rect},
+  style::{Color, Style, Stylize},
+  text::Line,
+  widgets::{Block, Borders, Clear, List, ListState, Padding},
+};
+
+use super::{PopUp, PopUpPayload};
+use crate::ui::center;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchTarget {
+  // a connection from the config
+  Connection,
+  // another database on the connected server
+  Database,
+}
+
+// lists the connections from the config, or the databases on the server, to
+// switch to one without restarting
+#[derive(Debug)]
+pub struct SwitchConnection {
+  target: SwitchTarget,
+  names: Vec<String>,
+  current: Option<String>,
+  selected: usize,
+}
+
+impl SwitchConnection {
+  pub fn new(target: SwitchTarget, mut names: Vec<String>, current: Option<String>) -> Self {
+    names.sort();
+    let selected = current.as_ref().and_then(|current| names.iter().position(|name| name == current)).unwrap_or(0);
+    Self { target, names, current, selected }
+  }
+}
+
+impl PopUp for SwitchConnection {
+  fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(self.names.len().saturating_sub(1)),
+      KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Enter => {
+        return Ok(Some(match self.names.get(self.selected) {
+          Some(name) if self.current.as_ref() != Some(name) => match self.target {
+            SwitchTarget::Connection => PopUpPayload::SwitchConnection(name.clone()),
+            SwitchTarget::Database => PopUpPayload::UseDatabase(name.clone()),
+          },
+          _ => PopUpPayload::Cancel,
+        }));
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&self, frame: &mut Frame, area: Rect) -> bool {
+    let height = u16::try_from(self.names.len()).unwrap_or(u16::MAX).saturating_add(4).max(5);
+    let area = center(area, Constraint::Percentage(40), Constraint::Length(height));
+    let block = Block::default()
+      .borders(Borders::ALL)
+      .border_style(Style::default().fg(Color::Yellow))
+      .title(
+        Line::from(match self.target {
+          SwitchTarget::Connection => " Switch Connection ",
+          SwitchTarget::Database => " Use Database ",
+        })
+        .centered(),
+      )
+      .title_bottom(Line::from(" [j|↓] down [k|↑] up [<enter>] connect [<esc>] cancel ").centered())
+      .padding(Padding::uniform(1));
+    let items = match self.names.is_empty() {
+      true => vec![
+        Line::from(match self.target {
+          SwitchTarget::Connection => "no connections in the [db] section of the config",
+          SwitchTarget::Database => "no other databases found",
+        })
+        .dim(),
+      ],
+      false => self
+        .names
+        .iter()
+        .map(|name| match self.current.as_ref() == Some(name) {
+          true => Line::from(format!(
+            "{name} ({})",
+            match self.target {
+              SwitchTarget::Connection => "connected",
+              SwitchTarget::Database => "in use",
+            }
+          )),
+          false => Line::from(name.as_str()),
+        })
+        .collect(),
+    };
+    let list = List::new(items).block(block).highlight_style(Style::default().reversed()).highlight_symbol("> ");
+    let mut state = ListState::default().with_selected((!self.names.is_empty()).then_some(self.selected));
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut state);
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_switch_connection() {
+    let names = vec!["prod".to_owned(), "dev".to_owned(), "local".to_owned()];
+    let popup = SwitchConnection::new(SwitchTarget::Database, names.clone(), Some("local".to_owned()));
+    assert_eq!(popup.names, vec!["dev", "local", "prod"]);
+    assert_eq!(popup.selected, 1);
+    assert_eq!(SwitchConnection::new(SwitchTarget::Connection, names, None).selected, 0);
+    assert_eq!(SwitchConnection::new(SwitchTarget::Connection, vec![], Some("gone".to_owned())).selected, 0);
+  }
+}
@@ -0,0 +1,142 @@
+// every query that ran, kept as json lines when the `query_log` setting is
+// on, so what was run against which connection can be pieced together later.
+// lines are written on a thread of their own so a slow disk doesn't hold up
+// the ui.
+use std::{
+  fs::OpenOptions,
+  io::Write,
+  path::{Path, PathBuf},
+  sync::mpsc,
+  thread::JoinHandle,
+};
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+const QUERY_LOG_FILE: &str = "queries.jsonl";
+// once the log is this big it's moved aside to `queries.jsonl.1`, replacing the
+// one moved aside before it, so at most about twice this is kept
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// One line of the query log.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct QueryLogEntry {
+  pub timestamp: String,
+  pub connection: Option<String>,
+  pub query: String,
+  pub duration_ms: Option<f64>,
+  pub row_count: Option<u64>,
+  pub error: Option<String>,
+  // whether the statement could change data or the schema
+  pub write: bool,
+}
+
+pub struct QueryLog {
+  sender: Option<mpsc::Sender<String>>,
+  writer: Option<JoinHandle<()>>,
+}
+
+impl QueryLog {
+  /// A log in `data_dir`, or one that keeps nothing when it isn't `enabled`.
+  pub fn new(data_dir: &Path, enabled: bool) -> Self {
+    if !enabled {
+      return Self { sender: None, writer: None };
+    }
+    let path = data_dir.join(QUERY_LOG_FILE);
+    let (sender, receiver) = mpsc::channel::<String>();
+    let writer = std::thread::spawn(move || {
+      for line in receiver {
+        if let Err(e) = append_line(&path, &line, MAX_LOG_BYTES) {
+          log::error!("failed to write to the query log: {e:?}");
+        }
+      }
+    });
+    Self { sender: Some(sender), writer: Some(writer) }
+  }
+
+  /// Queues the entry to be written, without waiting for it.
+  pub fn record(&self, entry: &QueryLogEntry) {
+    let Some(sender) = &self.sender else {
+      return;
+    };
+    match serde_json::to_string(entry) {
+      Ok(line) => {
+        if sender.send(line).is_err() {
+          log::error!("the query log's writer has stopped");
+        }
+      },
+      Err(e) => log::error!("failed to serialize a query log entry: {e:?}"),
+    }
+  }
+}
+
+// lets the writer finish the lines it was sent, so none are lost on quit
+impl Drop for QueryLog {
+  fn drop(&mut self) {
+    self.sender.take();
+    if let Some(writer) = self.writer.take() {
+      let _ = writer.join();
+    }
+  }
+}
+
+// appends the line, first moving the log aside if it has grown past `max_bytes`
+pub(crate) fn append_line(path: &PathBuf, line: &str, max_bytes: u64) -> Result<()> {
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  if std::fs::metadata(path).is_ok_and(|metadata| metadata.len() >= max_bytes) {
+    let mut rotated = path.clone().into_os_string();
+    rotated.push(".1");
+    std::fs::rename(path, rotated)?;
+  }
+  let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+  writeln!(file, "{line}")?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_query_log() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_query_log_{}", std::process::id()));
+    let entry = QueryLogEntry {
+      timestamp: "2024-01-01T00:00:00+00:00".to_owned(),
+      connection: Some("prod".to_owned()),
+      query: "delete from orders where id = 1".to_owned(),
+      duration_ms: Some(1.5),
+      row_count: Some(1),
+      error: None,
+      write: true,
+    };
+    let log = QueryLog::new(&dir, true);
+    log.record(&entry);
+    log.record(&QueryLogEntry { query: "select".to_owned(), error: Some("syntax error".to_owned()), ..entry.clone() });
+    drop(log);
+    let content = std::fs::read_to_string(dir.join(QUERY_LOG_FILE)).unwrap();
+    let lines: Vec<serde_json::Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["connection"], "prod");
+    assert_eq!(lines[0]["write"], true);
+    assert_eq!(lines[0]["row_count"], 1);
+    assert_eq!(lines[1]["error"], "syntax error");
+
+    QueryLog::new(&dir, false).record(&entry);
+    assert_eq!(std::fs::read_to_string(dir.join(QUERY_LOG_FILE)).unwrap(), content);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_query_log_rotation() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_query_log_rotation_{}", std::process::id()));
+    let path = dir.join(QUERY_LOG_FILE);
+    append_line(&path, "first", 10).unwrap();
+    append_line(&path, "second", 10).unwrap();
+    append_line(&path, "third", 10).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "third\n");
+    assert_eq!(std::fs::read_to_string(dir.join("queries.jsonl.1")).unwrap(), "first\nsecond\n");
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,197 @@
+// search and replace for the query editor, loosely following vim's `/` and `:s`
+use color_eyre::eyre::{Result, eyre};
+use regex::Regex;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+  pub ignore_case: bool,
+  pub whole_word: bool,
+}
+
+impl SearchOptions {
+  // builds the regex for a pattern typed by the user
+  pub fn pattern(&self, pattern: &str) -> String {
+    let pattern = if self.whole_word { format!(r"\b(?:{pattern})\b") } else { pattern.to_string() };
+    if self.ignore_case { format!("(?i){pattern}") } else { pattern }
+  }
+
+  pub fn indicator(&self) -> String {
+    format!("[{}] [{}]", if self.ignore_case { "aa" } else { "Aa" }, if self.whole_word { "word" } else { "any" })
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Substitute {
+  pub whole_buffer: bool,
+  pub pattern: String,
+  pub replacement: String,
+  pub global: bool,
+  pub ignore_case: bool,
+}
+
+impl Substitute {
+  /// Parses a command like `s/old/new/g` or `%s/old/new/gi`. `%` applies it to
+  /// the whole buffer instead of the current line, `g` replaces every match
+  /// instead of the first on each line, and `i` ignores case. `/` can be
+  /// escaped as `\/`.
+  pub fn parse(command: &str) -> Result<Self> {
+    let (whole_buffer, rest) = match command.strip_prefix('%') {
+      Some(rest) => (true, rest),
+      None => (false, command),
+    };
+    let rest = rest.strip_prefix("s/").ok_or_else(|| eyre!("Not an editor command: {command}"))?;
+
+    let mut parts = vec![String::new()];
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+      match c {
+        '\\' => match chars.next() {
+          Some('/') => parts.last_mut().unwrap().push('/'),
+          Some(escaped) => {
+            parts.last_mut().unwrap().push('\\');
+            parts.last_mut().unwrap().push(escaped);
+          },
+          None => parts.last_mut().unwrap().push('\\'),
+        },
+        '/' if parts.len() < 3 => parts.push(String::new()),
+        c => parts.last_mut().unwrap().push(c),
+      }
+    }
+    let [pattern, replacement, flags] = match parts.len() {
+      2 => [parts.remove(0), parts.remove(0), String::new()],
+      3 => [parts.remove(0), parts.remove(0), parts.remove(0)],
+      _ => return Err(eyre!("Expected s/pattern/replacement/flags")),
+    };
+    if pattern.is_empty() {
+      return Err(eyre!("Empty search pattern"));
+    }
+    if let Some(flag) = flags.chars().find(|c| !matches!(c, 'g' | 'i')) {
+      return Err(eyre!("Unknown flag: {flag}"));
+    }
+    Ok(Self {
+      whole_buffer,
+      pattern,
+      replacement: convert_replacement(&replacement),
+      global: flags.contains('g'),
+      ignore_case: flags.contains('i'),
+    })
+  }
+
+  /// Applies the substitution to each line of `text`, returning the new text and
+  /// how many matches were replaced. With `whole_buffer`, patterns can match
+  /// across lines.
+  pub fn apply(&self, text: &str, options: SearchOptions) -> Result<(String, usize)> {
+    let options = SearchOptions { ignore_case: options.ignore_case || self.ignore_case, ..options };
+    let regex = Regex::new(&options.pattern(&self.pattern))?;
+    let count = |s: &str| if self.global { regex.find_iter(s).count() } else { usize::from(regex.is_match(s)) };
+    if self.whole_buffer && self.global {
+      return Ok((regex.replace_all(text, self.replacement.as_str()).into_owned(), count(text)));
+    }
+    // without `g`, only the first match on each line is replaced
+    let mut replaced = 0;
+    let lines = text
+      .split('\n')
+      .map(|line| {
+        replaced += count(line);
+        match self.global {
+          true => regex.replace_all(line, self.replacement.as_str()),
+          false => regex.replace(line, self.replacement.as_str()),
+        }
+      })
+      .collect::<Vec<_>>();
+    Ok((lines.join("\n"), replaced))
+  }
+}
+
+// converts vim's replacement syntax (`\1`, `&`, `\n`) to the regex crate's
+fn convert_replacement(replacement: &str) -> String {
+  let mut converted = String::new();
+  let mut chars = replacement.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some(d) if d.is_ascii_digit() => converted.push_str(&format!("${{{d}}}")),
+        Some('n') => converted.push('\n'),
+        Some('t') => converted.push('\t'),
+        Some('&') => converted.push('&'),
+        Some(other) => converted.push(other),
+        None => converted.push('\\'),
+      },
+      '&' => converted.push_str("${0}"),
+      '$' => converted.push_str("$$"),
+      c => converted.push(c),
+    }
+  }
+  converted
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_substitute() {
+    assert_eq!(
+      Substitute::parse("%s/user_id/account_id/g").unwrap(),
+      Substitute {
+        whole_buffer: true,
+        pattern: "user_id".to_string(),
+        replacement: "account_id".to_string(),
+        global: true,
+        ignore_case: false,
+      }
+    );
+    assert_eq!(
+      Substitute::parse(r"s/a\/b/(\1)&$/i").unwrap(),
+      Substitute {
+        whole_buffer: false,
+        pattern: "a/b".to_string(),
+        replacement: "(${1})${0}$$".to_string(),
+        global: false,
+        ignore_case: true,
+      }
+    );
+    assert!(Substitute::parse("s/old").is_err());
+    assert!(Substitute::parse("s//new/").is_err());
+    assert!(Substitute::parse("s/old/new/x").is_err());
+    assert!(Substitute::parse("w file.sql").is_err());
+  }
+
+  #[test]
+  fn test_apply_substitute() {
+    let text = "select id, user_id\nfrom orders\nwhere user_id = 1 and USER_ID > 0";
+    let test_cases = vec![
+      (
+        "%s/user_id/account_id/g",
+        SearchOptions::default(),
+        "select id, account_id\nfrom orders\nwhere account_id = 1 and USER_ID > 0",
+        2,
+      ),
+      (
+        "%s/user_id/account_id/gi",
+        SearchOptions::default(),
+        "select id, account_id\nfrom orders\nwhere account_id = 1 and account_id > 0",
+        3,
+      ),
+      (
+        "%s/user_id/account_id/",
+        SearchOptions { ignore_case: true, whole_word: false },
+        "select id, account_id\nfrom orders\nwhere account_id = 1 and USER_ID > 0",
+        2,
+      ),
+      (
+        "%s/id/key/g",
+        SearchOptions { ignore_case: false, whole_word: true },
+        "select key, user_id\nfrom orders\nwhere user_id = 1 and USER_ID > 0",
+        1,
+      ),
+      ("%s/,\\n/, /g", SearchOptions::default(), text, 0),
+      ("%s/\\n/ /g", SearchOptions::default(), "select id, user_id from orders where user_id = 1 and USER_ID > 0", 2),
+    ];
+
+    for (command, options, expected, count) in test_cases {
+      let result = Substitute::parse(command).unwrap().apply(text, options).unwrap();
+      assert_eq!(result, (expected.to_string(), count), "Failed for command: {command}");
+    }
+  }
+}
@@ -0,0 +1,83 @@
+// how rainfrog was left for a connection, so the next launch picks up where
+// the last one stopped. the query tabs themselves are kept by the scratch file.
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::LayoutSplit, focus::Focus, utils::connection_file_name};
+
+const SESSION_DIR: &str = "sessions";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+  pub focus: Focus,
+  // `None` keeps the layout from the config
+  pub layout_split: Option<LayoutSplit>,
+  pub zoomed: bool,
+  pub query_tab: usize,
+  // put in the editor when there are no saved tabs to restore
+  pub last_query: Option<String>,
+}
+
+/// Where the session of a connection from the config is kept, or of
+/// connections given on the command line when there's no name.
+pub struct SessionFile {
+  path: PathBuf,
+}
+
+impl SessionFile {
+  pub fn new(data_dir: &Path, connection_name: Option<&str>) -> Self {
+    Self { path: data_dir.join(SESSION_DIR).join(format!("{}.json", connection_file_name(connection_name))) }
+  }
+
+  /// Reads the saved session, if there is one.
+  pub fn load(&self) -> Result<Option<Session>> {
+    if !self.path.exists() {
+      return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&std::fs::read_to_string(&self.path)?)?))
+  }
+
+  /// Saves the session. Like the scratch file, it's written next to the old
+  /// one and moved over it.
+  pub fn save(&self, session: &Session) -> Result<()> {
+    if let Some(dir) = self.path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    let partial = self.path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_string(session)?)?;
+    std::fs::rename(&partial, &self.path)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_session_file() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_session_{}", std::process::id()));
+    let file = SessionFile::new(&dir, Some("prod/orders db"));
+    assert_eq!(file.path, dir.join("sessions").join("prod_orders_db.json"));
+    assert!(file.load().unwrap().is_none());
+
+    let session = Session {
+      focus: Focus::Data,
+      layout_split: Some(LayoutSplit::Horizontal),
+      zoomed: true,
+      query_tab: 2,
+      last_query: Some("select *\nfrom orders;".to_owned()),
+    };
+    file.save(&session).unwrap();
+    assert_eq!(file.load().unwrap(), Some(session));
+    assert!(SessionFile::new(&dir, None).load().unwrap().is_none());
+
+    // sessions saved before a field was added still load
+    std::fs::write(&file.path, r#"{"focus":"Editor"}"#).unwrap();
+    assert_eq!(file.load().unwrap(), Some(Session { focus: Focus::Editor, ..Session::default() }));
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
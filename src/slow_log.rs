@@ -0,0 +1,120 @@
+// queries that took longer than the `slow_query_ms` setting, kept in a plain
+// text file so they can be read, grepped or tailed outside of rainfrog
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::Result;
+
+use crate::query_log::append_line;
+
+const SLOW_LOG_FILE: &str = "slow_queries.log";
+// once the log is this big it's moved aside to `slow_queries.log.1`, like the query log
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+pub struct SlowQueryLog {
+  path: PathBuf,
+  threshold: chrono::Duration,
+}
+
+impl SlowQueryLog {
+  /// A log of queries that took at least `threshold_ms`. Nothing is logged
+  /// when it's 0.
+  pub fn new(data_dir: &Path, threshold_ms: u64) -> Self {
+    Self {
+      path: data_dir.join(SLOW_LOG_FILE),
+      threshold: chrono::Duration::milliseconds(threshold_ms.min(i64::MAX as u64) as i64),
+    }
+  }
+
+  /// Appends the query if it was slow, as one line with when it finished, how
+  /// long it took and the query itself. Returns whether it was logged.
+  pub fn record(
+    &self,
+    query: &str,
+    duration: chrono::Duration,
+    finished_at: chrono::DateTime<chrono::Local>,
+  ) -> Result<bool> {
+    if self.threshold.is_zero() || duration < self.threshold {
+      return Ok(false);
+    }
+    let line = format!("{}\t{}\t{}", finished_at.to_rfc3339(), format_duration(duration), one_line(query));
+    append_line(&self.path, &line, MAX_LOG_BYTES)?;
+    Ok(true)
+  }
+}
+
+// the query on one line. whitespace between tokens is collapsed to a space, while
+// quoted text is kept as it was, apart from its line breaks and tabs being escaped
+fn one_line(query: &str) -> String {
+  let mut line = String::with_capacity(query.len());
+  let mut quote = None;
+  let mut space = false;
+  for c in query.trim().chars() {
+    match quote {
+      Some(open) => {
+        match c {
+          '\n' => line.push_str("\\n"),
+          '\r' => line.push_str("\\r"),
+          '\t' => line.push_str("\\t"),
+          _ => line.push(c),
+        }
+        // a doubled quote closes and reopens, which leaves it open
+        if c == open {
+          quote = None;
+        }
+      },
+      None if c.is_whitespace() => space = true,
+      None => {
+        if std::mem::take(&mut space) {
+          line.push(' ');
+        }
+        if matches!(c, '\'' | '"' | '`') {
+          quote = Some(c);
+        }
+        line.push(c);
+      },
+    }
+  }
+  line
+}
+
+/// Formats how long a query took, to a tenth of a millisecond under a second
+/// and a thousandth of a second above it.
+pub fn format_duration(duration: chrono::Duration) -> String {
+  match duration.num_microseconds() {
+    Some(micros) if micros < 1_000_000 => format!("{:.1}ms", micros as f64 / 1000_f64),
+    _ => format!("{:.3}s", duration.num_milliseconds() as f64 / 1000_f64),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_duration() {
+    assert_eq!(format_duration(chrono::Duration::microseconds(13_240)), "13.2ms");
+    assert_eq!(format_duration(chrono::Duration::microseconds(400)), "0.4ms");
+    assert_eq!(format_duration(chrono::Duration::milliseconds(1_532)), "1.532s");
+  }
+
+  #[test]
+  fn test_slow_query_log() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_slow_log_{}", std::process::id()));
+    let log = SlowQueryLog::new(&dir, 500);
+    let finished_at = chrono::Local::now();
+    assert!(!log.record("select 1", chrono::Duration::milliseconds(499), finished_at).unwrap());
+    assert!(log.record("select *\n  from orders", chrono::Duration::milliseconds(1_200), finished_at).unwrap());
+    let content = std::fs::read_to_string(dir.join(SLOW_LOG_FILE)).unwrap();
+    assert_eq!(content, format!("{}\t1.200s\tselect * from orders\n", finished_at.to_rfc3339()));
+
+    // what's in quotes keeps its spacing
+    assert_eq!(
+      one_line("select  'a  b\n\tc', \"my  col\"\nfrom t\n where x = 'it''s  so'"),
+      "select 'a  b\\n\\tc', \"my  col\" from t where x = 'it''s  so'"
+    );
+
+    let off = SlowQueryLog::new(&dir, 0);
+    assert!(!off.record("select 1", chrono::Duration::seconds(60), finished_at).unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
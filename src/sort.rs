@@ -0,0 +1,191 @@
+// in-memory sorting of query results, so they can be re-sorted without re-running the query
+use std::cmp::Ordering;
+
+use crate::database::{is_null, is_numeric_type};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+  Ascending,
+  Descending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+  Number,
+  // dates, times and timestamps are rendered in iso format by every driver, and
+  // booleans as true/false or 1/0, so they all sort correctly as text
+  Text,
+}
+
+impl ColumnKind {
+  fn of(type_name: &str) -> Self {
+    if is_numeric_type(type_name) { Self::Number } else { Self::Text }
+  }
+}
+
+// numbers are compared exactly when they're integers or plain decimals, since
+// big or precise ones lose precision as floats. anything that doesn't parse
+// sorts after the numbers.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+  if let (Ok(a), Ok(b)) = (a.parse::<i128>(), b.parse::<i128>()) {
+    return a.cmp(&b);
+  }
+  if let (Some(a), Some(b)) = (Decimal::parse(a), Decimal::parse(b)) {
+    return a.cmp(&b);
+  }
+  match (a.parse::<f64>(), b.parse::<f64>()) {
+    (Ok(a), Ok(b)) => a.total_cmp(&b),
+    (Ok(_), Err(_)) => Ordering::Less,
+    (Err(_), Ok(_)) => Ordering::Greater,
+    (Err(_), Err(_)) => a.cmp(b),
+  }
+}
+
+// a decimal without an exponent, like `-12.340`, split into its digits so it
+// can be compared without rounding
+#[derive(Debug, PartialEq, Eq)]
+struct Decimal<'a> {
+  negative: bool,
+  // without leading zeros
+  integer: &'a str,
+  // without trailing zeros
+  fraction: &'a str,
+}
+
+impl<'a> Decimal<'a> {
+  fn parse(value: &'a str) -> Option<Self> {
+    let (negative, digits) = match value.strip_prefix('-') {
+      Some(digits) => (true, digits),
+      None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if integer.is_empty() && fraction.is_empty()
+      || !(integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()))
+    {
+      return None;
+    }
+    let integer = integer.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+    // so -0 equals 0
+    let negative = negative && !(integer.is_empty() && fraction.is_empty());
+    Some(Self { negative, integer, fraction })
+  }
+}
+
+impl Ord for Decimal<'_> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // fractions compare as text once trailing zeros are gone, as .5 > .45
+    let magnitude = self
+      .integer
+      .len()
+      .cmp(&other.integer.len())
+      .then_with(|| self.integer.cmp(other.integer))
+      .then_with(|| self.fraction.cmp(other.fraction));
+    match (self.negative, other.negative) {
+      (false, false) => magnitude,
+      (true, true) => magnitude.reverse(),
+      (true, false) => Ordering::Less,
+      (false, true) => Ordering::Greater,
+    }
+  }
+}
+
+impl PartialOrd for Decimal<'_> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+// nulls always sort last, whichever the order
+fn compare_values(a: &str, b: &str, kind: ColumnKind, order: SortOrder) -> Ordering {
+  match (is_null(a), is_null(b)) {
+    (true, true) => Ordering::Equal,
+    (true, false) => Ordering::Greater,
+    (false, true) => Ordering::Less,
+    (false, false) => {
+      let ordering = match kind {
+        ColumnKind::Number => compare_numbers(a, b),
+        ColumnKind::Text => a.cmp(b),
+      };
+      match order {
+        SortOrder::Ascending => ordering,
+        SortOrder::Descending => ordering.reverse(),
+      }
+    },
+  }
+}
+
+/// Sorts `rows` by `column`, whose type is `type_name`. `positions` holds the
+/// position each row was fetched in, and is kept in step with the rows so that
+/// `unsort_rows` can put them back. It's filled in if it's empty.
+pub fn sort_rows(
+  rows: &mut Vec<Vec<String>>,
+  positions: &mut Vec<usize>,
+  column: usize,
+  type_name: &str,
+  order: SortOrder,
+) {
+  if positions.len() != rows.len() {
+    *positions = (0..rows.len()).collect();
+  }
+  let kind = ColumnKind::of(type_name);
+  let mut pairs = positions.drain(..).zip(rows.drain(..)).collect::<Vec<_>>();
+  pairs.sort_by(|(_, a), (_, b)| {
+    compare_values(a.get(column).map_or("", String::as_str), b.get(column).map_or("", String::as_str), kind, order)
+  });
+  (*positions, *rows) = pairs.into_iter().unzip();
+}
+
+/// Restores the order rows were fetched in after `sort_rows`.
+pub fn unsort_rows(rows: &mut Vec<Vec<String>>, positions: &mut Vec<usize>) {
+  if positions.len() != rows.len() {
+    return;
+  }
+  let mut pairs = positions.drain(..).zip(rows.drain(..)).collect::<Vec<_>>();
+  pairs.sort_by_key(|(position, _)| *position);
+  *rows = pairs.into_iter().map(|(_, row)| row).collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::NULL;
+
+  fn rows(values: &[&str]) -> Vec<Vec<String>> {
+    values.iter().map(|v| vec![v.to_string()]).collect()
+  }
+
+  #[test]
+  fn test_sort_rows() {
+    let test_cases = vec![
+      ("INT4", SortOrder::Ascending, vec!["-1", "2", "10", NULL]),
+      ("INT4", SortOrder::Descending, vec!["10", "2", "-1", NULL]),
+      ("NUMERIC(10, 2)", SortOrder::Ascending, vec!["-1", "2", "10", NULL]),
+      ("TEXT", SortOrder::Ascending, vec!["-1", "10", "2", NULL]),
+      ("TEXT", SortOrder::Descending, vec!["2", "10", "-1", NULL]),
+    ];
+
+    for (type_name, order, expected) in test_cases {
+      let mut sorted = rows(&["10", NULL, "2", "-1"]);
+      let mut positions = vec![];
+      sort_rows(&mut sorted, &mut positions, 0, type_name, order);
+      assert_eq!(sorted, rows(&expected), "Failed for type: {type_name}, {order:?}");
+      unsort_rows(&mut sorted, &mut positions);
+      assert_eq!(sorted, rows(&["10", NULL, "2", "-1"]));
+    }
+  }
+
+  #[test]
+  fn test_compare_numbers() {
+    assert_eq!(compare_numbers("9007199254740993", "9007199254740992"), Ordering::Greater);
+    assert_eq!(compare_numbers("1.5", "12"), Ordering::Less);
+    assert_eq!(compare_numbers("_ERROR_", "12"), Ordering::Greater);
+    // equal as floats, but not as numerics
+    assert_eq!(compare_numbers("0.10000000000000000001", "0.1"), Ordering::Greater);
+    assert_eq!(compare_numbers("-12345678901234567890123456789012345678901.5", "-1.5"), Ordering::Less);
+    assert_eq!(compare_numbers("-0.50", "-0.5"), Ordering::Equal);
+    assert_eq!(compare_numbers("-0.0", "0"), Ordering::Equal);
+    assert_eq!(compare_numbers("1.45", "1.5"), Ordering::Less);
+    assert_eq!(compare_numbers("1e3", "999"), Ordering::Greater);
+  }
+}
@@ -0,0 +1,309 @@
+use std::{collections::HashMap, ffi::OsStr, path::Path};
+
+use color_eyre::eyre::{Result, eyre};
+use ratatui::{
+  buffer::Buffer,
+  style::{Color, Style, Stylize},
+};
+use serde::Deserialize;
+
+use crate::config::parse_style;
+
+const THEMES_DIR: &str = "themes";
+
+/// The `[theme]` section of the config: the name of a built-in theme or of a
+/// theme file, and styles for any roles that should differ from it.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ThemeConfig {
+  pub name: Option<String>,
+  #[serde(flatten)]
+  pub styles: HashMap<String, String>,
+}
+
+/// The styles of the ui, by what they're used for rather than by component.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+  pub focused_border: Style,
+  pub unfocused_border: Style,
+  // column headers in the results table
+  pub header: Style,
+  // the first line of history and favorite entries, and other labels that
+  // stand out when their pane is focused
+  pub title: Style,
+  pub null_value: Style,
+  // booleans in the results table
+  pub true_value: Style,
+  pub false_value: Style,
+  // the selected rows and cells in the results table
+  pub selection: Style,
+  // the selected entry in the history and favorites lists
+  pub selected_item: Style,
+  pub error: Style,
+  // search matches. the editor shows them reversed, so they stand out from
+  // keyword highlighting
+  pub highlight: Style,
+  // extra details that shouldn't draw attention, like row counts in the menu
+  pub muted: Style,
+  // rows and values that differ from the pinned results being compared with
+  pub added_row: Style,
+  pub removed_row: Style,
+  pub changed_value: Style,
+  // whether anything is drawn in color. without, colors set outside of the
+  // theme (like syntax highlighting) are taken out of each frame as well
+  pub colors: bool,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Self::dark()
+  }
+}
+
+impl Theme {
+  pub fn dark() -> Self {
+    Self {
+      focused_border: Style::new().fg(Color::Green),
+      unfocused_border: Style::new().dim(),
+      header: Style::new(),
+      title: Style::new().fg(Color::Yellow),
+      null_value: Style::new().dim(),
+      true_value: Style::new().fg(Color::Green),
+      false_value: Style::new().fg(Color::Red),
+      selection: Style::new().fg(Color::LightBlue).reversed().bold(),
+      selected_item: Style::new().fg(Color::Blue),
+      error: Style::new().fg(Color::Red),
+      highlight: Style::new().fg(Color::Yellow).bold(),
+      muted: Style::new().fg(Color::DarkGray),
+      added_row: Style::new().fg(Color::Green),
+      removed_row: Style::new().fg(Color::Red).crossed_out(),
+      changed_value: Style::new().fg(Color::Yellow),
+      colors: true,
+    }
+  }
+
+  pub fn light() -> Self {
+    Self {
+      focused_border: Style::new().fg(Color::Blue),
+      unfocused_border: Style::new().fg(Color::Gray),
+      header: Style::new().bold(),
+      title: Style::new().fg(Color::Magenta),
+      null_value: Style::new().fg(Color::Gray),
+      true_value: Style::new().fg(Color::Green),
+      false_value: Style::new().fg(Color::Red),
+      selection: Style::new().fg(Color::Blue).reversed().bold(),
+      selected_item: Style::new().fg(Color::Blue).bold(),
+      error: Style::new().fg(Color::Red),
+      highlight: Style::new().fg(Color::Magenta).bold(),
+      muted: Style::new().fg(Color::Gray),
+      added_row: Style::new().fg(Color::Green),
+      removed_row: Style::new().fg(Color::Red).crossed_out(),
+      changed_value: Style::new().fg(Color::Magenta),
+      colors: true,
+    }
+  }
+
+  pub fn high_contrast() -> Self {
+    Self {
+      focused_border: Style::new().fg(Color::Yellow).bold(),
+      unfocused_border: Style::new().fg(Color::White),
+      header: Style::new().bold().underlined(),
+      title: Style::new().fg(Color::Cyan).bold(),
+      null_value: Style::new().fg(Color::Magenta),
+      true_value: Style::new().fg(Color::LightGreen).bold(),
+      false_value: Style::new().fg(Color::LightRed).bold(),
+      selection: Style::new().fg(Color::Black).bg(Color::Yellow).bold(),
+      selected_item: Style::new().fg(Color::Yellow).bold(),
+      error: Style::new().fg(Color::LightRed).bold(),
+      highlight: Style::new().fg(Color::Cyan).bold().underlined(),
+      muted: Style::new().fg(Color::White),
+      added_row: Style::new().fg(Color::LightGreen).bold(),
+      removed_row: Style::new().fg(Color::LightRed).crossed_out(),
+      changed_value: Style::new().fg(Color::Yellow).bold(),
+      colors: true,
+    }
+  }
+
+  // for NO_COLOR. modifiers are kept so the selection and focus can still be seen
+  pub fn no_color() -> Self {
+    Self {
+      focused_border: Style::new().bold(),
+      unfocused_border: Style::new(),
+      header: Style::new().bold(),
+      title: Style::new().bold(),
+      null_value: Style::new().italic(),
+      true_value: Style::new(),
+      false_value: Style::new(),
+      selection: Style::new().reversed().bold(),
+      selected_item: Style::new().reversed(),
+      error: Style::new().bold(),
+      highlight: Style::new().bold().underlined(),
+      muted: Style::new(),
+      added_row: Style::new().bold(),
+      removed_row: Style::new().crossed_out(),
+      changed_value: Style::new().underlined(),
+      colors: false,
+    }
+  }
+
+  pub fn builtin(name: &str) -> Option<Self> {
+    match name {
+      "dark" => Some(Self::dark()),
+      "light" => Some(Self::light()),
+      "high-contrast" => Some(Self::high_contrast()),
+      "no-color" => Some(Self::no_color()),
+      _ => None,
+    }
+  }
+
+  pub fn border(&self, focused: bool) -> Style {
+    if focused { self.focused_border } else { self.unfocused_border }
+  }
+
+  fn set(&mut self, role: &str, style: Style) -> Result<()> {
+    let field = match role {
+      "focused_border" => &mut self.focused_border,
+      "unfocused_border" => &mut self.unfocused_border,
+      "header" => &mut self.header,
+      "title" => &mut self.title,
+      "null_value" => &mut self.null_value,
+      "true_value" => &mut self.true_value,
+      "false_value" => &mut self.false_value,
+      "selection" => &mut self.selection,
+      "selected_item" => &mut self.selected_item,
+      "error" => &mut self.error,
+      "highlight" => &mut self.highlight,
+      "muted" => &mut self.muted,
+      "added_row" => &mut self.added_row,
+      "removed_row" => &mut self.removed_row,
+      "changed_value" => &mut self.changed_value,
+      _ => return Err(eyre!("Unknown theme role: {role}")),
+    };
+    *field = style;
+    Ok(())
+  }
+
+  fn with_styles(mut self, styles: &HashMap<String, String>) -> Result<Self> {
+    for (role, style) in styles {
+      self.set(role, parse_style(style))?;
+    }
+    Ok(self)
+  }
+
+  /// Takes the colors out of everything drawn in `buf`, for themes without them.
+  pub fn strip_colors(&self, buf: &mut Buffer) {
+    if self.colors {
+      return;
+    }
+    for cell in &mut buf.content {
+      cell.set_fg(Color::Reset).set_bg(Color::Reset);
+    }
+  }
+
+  /// Builds the theme named in the config, which is either built in or a
+  /// `.toml` or `.json` file in the `themes` folder of the config dir. Theme
+  /// files can set `base` to start from a built-in theme other than dark.
+  /// Without a name, the built-in `default_name` theme is used.
+  pub fn load(config: &ThemeConfig, config_dir: &Path, default_name: &str) -> Result<Self> {
+    let name = config.name.as_deref().unwrap_or(default_name);
+    let theme = match Self::builtin(name) {
+      Some(theme) => theme,
+      None => {
+        let mut styles = Self::read_file(&config_dir.join(THEMES_DIR), name)?;
+        let base = match styles.remove("base") {
+          Some(base) => Self::builtin(&base).ok_or_else(|| eyre!("Unknown base theme: {base}"))?,
+          None => Self::dark(),
+        };
+        base.with_styles(&styles)?
+      },
+    };
+    theme.with_styles(&config.styles)
+  }
+
+  fn read_file(themes_dir: &Path, name: &str) -> Result<HashMap<String, String>> {
+    let toml_path = themes_dir.join(format!("{name}.toml"));
+    let json_path = themes_dir.join(format!("{name}.json"));
+    if toml_path.is_file() {
+      Ok(toml::from_str(&std::fs::read_to_string(toml_path)?)?)
+    } else if json_path.is_file() {
+      Ok(serde_json::from_str(&std::fs::read_to_string(json_path)?)?)
+    } else {
+      Err(eyre!("Theme {name} is not built in, and there is no {name}.toml or {name}.json in {}", themes_dir.display()))
+    }
+  }
+}
+
+/// The default theme for the environment rainfrog is running in.
+pub fn detected_name() -> &'static str {
+  default_name(std::env::var_os("NO_COLOR").as_deref(), std::env::var("COLORFGBG").ok().as_deref())
+}
+
+/// The built-in theme to use when the config doesn't name one: none of the
+/// colors when `NO_COLOR` is set (to anything but an empty string, see
+/// https://no-color.org), otherwise light or dark to suit the terminal's
+/// background. Terminals that set `COLORFGBG` say which color their background
+/// is, and anything else is assumed to be dark.
+pub fn default_name(no_color: Option<&OsStr>, colorfgbg: Option<&str>) -> &'static str {
+  if no_color.is_some_and(|value| !value.is_empty()) {
+    return "no-color";
+  }
+  // `foreground;background`, sometimes with a middle value. the background is
+  // one of the 16 terminal colors, of which only white and bright colors are light
+  let background = colorfgbg.and_then(|value| value.rsplit(';').next()).and_then(|bg| bg.trim().parse::<u8>().ok());
+  match background {
+    Some(7 | 9..=15) => "light",
+    _ => "dark",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_load_theme() {
+    let dir = std::env::temp_dir().join(format!("rainfrog_test_theme_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join(THEMES_DIR)).unwrap();
+    std::fs::write(dir.join(THEMES_DIR).join("mine.toml"), "base = \"light\"\nerror = \"bold magenta\"\n").unwrap();
+
+    let theme_config = |name: &str, styles: &[(&str, &str)]| ThemeConfig {
+      name: Some(name.to_owned()),
+      styles: styles.iter().map(|(role, style)| (role.to_string(), style.to_string())).collect(),
+    };
+    assert_eq!(Theme::load(&theme_config("dark", &[]), &dir, "dark").unwrap(), Theme::dark());
+    assert_eq!(Theme::load(&theme_config("high-contrast", &[]), &dir, "dark").unwrap(), Theme::high_contrast());
+    let unnamed = ThemeConfig { name: None, ..theme_config("", &[]) };
+    assert_eq!(Theme::load(&unnamed, &dir, "light").unwrap(), Theme::light());
+
+    let theme = Theme::load(&theme_config("mine", &[("title", "cyan")]), &dir, "dark").unwrap();
+    assert_eq!(theme.error, Style::new().fg(Color::Indexed(5)).bold());
+    assert_eq!(theme.title, Style::new().fg(Color::Indexed(6)));
+    assert_eq!(theme.focused_border, Theme::light().focused_border);
+
+    assert!(Theme::load(&theme_config("missing", &[]), &dir, "dark").is_err());
+    assert!(Theme::load(&theme_config("dark", &[("borders", "red")]), &dir, "dark").is_err());
+    std::fs::remove_dir_all(dir).ok();
+  }
+
+  #[test]
+  fn test_default_name() {
+    assert_eq!(default_name(None, None), "dark");
+    assert_eq!(default_name(Some(OsStr::new("1")), Some("0;15")), "no-color");
+    assert_eq!(default_name(Some(OsStr::new("")), None), "dark");
+    assert_eq!(default_name(None, Some("0;15")), "light");
+    assert_eq!(default_name(None, Some("0;default;7")), "light");
+    assert_eq!(default_name(None, Some("15;0")), "dark");
+    assert_eq!(default_name(None, Some("15;8")), "dark");
+    assert_eq!(default_name(None, Some("default")), "dark");
+  }
+
+  #[test]
+  fn test_strip_colors() {
+    let mut buf = Buffer::empty(ratatui::layout::Rect::new(0, 0, 2, 1));
+    buf.set_string(0, 0, "ab", Style::new().fg(Color::Red).bg(Color::Blue).bold());
+    Theme::dark().strip_colors(&mut buf);
+    assert_eq!(buf.content[0].fg, Color::Red);
+    Theme::no_color().strip_colors(&mut buf);
+    assert_eq!((buf.content[0].fg, buf.content[0].bg), (Color::Reset, Color::Reset));
+    assert_eq!(buf.content[1].style().add_modifier, Style::new().bold().add_modifier);
+  }
+}
@@ -0,0 +1,111 @@
+// showing zoned timestamps in a chosen timezone. the values themselves are
+// left alone, so copies and exports still have them as they were fetched.
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use color_eyre::eyre::{Result, eyre};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayZone {
+  #[default]
+  Utc,
+  Local,
+  Named(Tz),
+}
+
+impl FromStr for DisplayZone {
+  type Err = color_eyre::eyre::Report;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim() {
+      s if s.eq_ignore_ascii_case("utc") => Ok(Self::Utc),
+      s if s.eq_ignore_ascii_case("local") => Ok(Self::Local),
+      s => s.parse::<Tz>().map(Self::Named).map_err(|_| eyre!("Unknown timezone: {s}")),
+    }
+  }
+}
+
+impl fmt::Display for DisplayZone {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Utc => write!(f, "UTC"),
+      Self::Local => write!(f, "local"),
+      Self::Named(tz) => write!(f, "{}", tz.name()),
+    }
+  }
+}
+
+impl DisplayZone {
+  /// Flips between UTC and local time. A named zone flips to UTC.
+  pub fn toggled(self) -> Self {
+    match self {
+      Self::Utc => Self::Local,
+      Self::Local | Self::Named(_) => Self::Utc,
+    }
+  }
+
+  fn convert(self, timestamp: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    match self {
+      Self::Utc => timestamp.with_timezone(&Utc).fixed_offset(),
+      Self::Local => timestamp.with_timezone(&Local).fixed_offset(),
+      Self::Named(tz) => timestamp.with_timezone(&tz).fixed_offset(),
+    }
+  }
+}
+
+pub fn is_timestamp_type(type_name: &str) -> bool {
+  let type_name = type_name.to_uppercase();
+  type_name.contains("TIMESTAMP") || type_name.contains("DATETIME")
+}
+
+// drivers format zoned timestamps the way chrono does, e.g. `2024-01-02 03:04:05.678 UTC`,
+// but an explicit offset is accepted too
+fn parse_zoned(value: &str) -> Option<DateTime<FixedOffset>> {
+  if let Some(naive) = value.strip_suffix(" UTC") {
+    return NaiveDateTime::parse_from_str(naive, "%Y-%m-%d %H:%M:%S%.f").ok().map(|t| t.and_utc().fixed_offset());
+  }
+  DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f %:z")
+    .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z"))
+    .ok()
+}
+
+/// Shows a zoned timestamp in `zone`, with its offset so it's unambiguous.
+/// Returns `None` for anything else, including timestamps without a zone.
+pub fn format_in_zone(value: &str, zone: DisplayZone) -> Option<String> {
+  let timestamp = parse_zoned(value)?;
+  Some(zone.convert(timestamp).format("%Y-%m-%d %H:%M:%S%.f %:z").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_in_zone() {
+    let new_york = DisplayZone::Named(chrono_tz::America::New_York);
+    let test_cases = vec![
+      ("2024-01-02 03:04:05 UTC", DisplayZone::Utc, Some("2024-01-02 03:04:05 +00:00")),
+      ("2024-01-02 03:04:05.678 UTC", new_york, Some("2024-01-01 22:04:05.678 -05:00")),
+      ("2024-07-02 03:04:05 UTC", new_york, Some("2024-07-01 23:04:05 -04:00")),
+      ("2024-01-02 03:04:05 +02:00", DisplayZone::Utc, Some("2024-01-02 01:04:05 +00:00")),
+      ("2024-01-02 03:04:05+02", DisplayZone::Utc, Some("2024-01-02 01:04:05 +00:00")),
+      ("2024-01-02 03:04:05", DisplayZone::Utc, None),
+      ("not a timestamp", DisplayZone::Utc, None),
+    ];
+
+    for (value, zone, expected) in test_cases {
+      assert_eq!(format_in_zone(value, zone).as_deref(), expected, "Failed for value: {value} in {zone}");
+    }
+  }
+
+  #[test]
+  fn test_display_zone() {
+    assert_eq!("utc".parse::<DisplayZone>().unwrap(), DisplayZone::Utc);
+    assert_eq!("Local".parse::<DisplayZone>().unwrap(), DisplayZone::Local);
+    assert_eq!("Europe/Paris".parse::<DisplayZone>().unwrap(), DisplayZone::Named(chrono_tz::Europe::Paris));
+    assert!("Mars/Olympus".parse::<DisplayZone>().is_err());
+    assert_eq!(DisplayZone::Named(chrono_tz::Europe::Paris).toggled(), DisplayZone::Utc);
+    assert_eq!(DisplayZone::Utc.toggled(), DisplayZone::Local);
+  }
+}
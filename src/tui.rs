@@ -1,4 +1,5 @@
 use std::{
+  io::Write,
   ops::{Deref, DerefMut},
   time::Duration,
 };
@@ -8,7 +9,8 @@ use crossterm::{
   cursor,
   event::{
     DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event as CrosstermEvent,
-    KeyEvent, KeyEventKind, MouseEvent,
+    KeyEvent, KeyEventKind, KeyboardEnhancementFlags, MouseEvent, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
   },
   terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -53,6 +55,9 @@ pub struct Tui {
   pub tick_rate: f64,
   pub mouse: bool,
   pub paste: bool,
+  // whether the terminal was asked to report keys like ctrl-enter apart from
+  // enter, which only terminals with keyboard enhancement support can do
+  keyboard_enhancement: bool,
 }
 
 impl Tui {
@@ -65,7 +70,18 @@ impl Tui {
     let task = tokio::spawn(async {});
     let mouse = true;
     let paste = true;
-    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste })
+    Ok(Self {
+      terminal,
+      task,
+      cancellation_token,
+      event_rx,
+      event_tx,
+      frame_rate,
+      tick_rate,
+      mouse,
+      paste,
+      keyboard_enhancement: false,
+    })
   }
 
   pub fn tick_rate(mut self, tick_rate: Option<f64>) -> Self {
@@ -187,6 +203,10 @@ impl Tui {
     if self.paste {
       crossterm::execute!(io(), EnableBracketedPaste)?;
     }
+    self.keyboard_enhancement = matches!(crossterm::terminal::supports_keyboard_enhancement(), Ok(true));
+    if self.keyboard_enhancement {
+      crossterm::execute!(io(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+    }
     self.start();
     Ok(())
   }
@@ -194,15 +214,11 @@ impl Tui {
   pub fn exit(&mut self) -> Result<()> {
     self.stop()?;
     if crossterm::terminal::is_raw_mode_enabled()? {
-      self.flush()?;
-      if self.paste {
-        crossterm::execute!(io(), DisableBracketedPaste)?;
-      }
-      if self.mouse {
-        crossterm::execute!(io(), DisableMouseCapture)?;
-      }
-      crossterm::execute!(io(), LeaveAlternateScreen, cursor::Show)?;
-      crossterm::terminal::disable_raw_mode()?;
+      let flushed = self.flush();
+      let restored = restore_terminal(&mut io(), self.mouse, self.paste, self.keyboard_enhancement);
+      self.keyboard_enhancement = false;
+      flushed?;
+      restored?;
     }
     Ok(())
   }
@@ -235,8 +251,61 @@ impl DerefMut for Tui {
   }
 }
 
+// runs when `App::run` returns early with an error too, so the terminal is
+// restored on every way out
 impl Drop for Tui {
   fn drop(&mut self) {
-    self.exit().unwrap();
+    if let Err(e) = self.exit() {
+      log::error!("Unable to restore the terminal: {e:?}");
+    }
+  }
+}
+
+/// Undoes what `Tui::enter` did to the terminal. Every step is tried even if
+/// an earlier one fails, so a failed write doesn't leave the terminal in raw
+/// mode, and the first error is returned.
+pub fn restore_terminal(out: &mut impl Write, mouse: bool, paste: bool, keyboard_enhancement: bool) -> Result<()> {
+  let steps = [
+    if keyboard_enhancement { crossterm::execute!(out, PopKeyboardEnhancementFlags) } else { Ok(()) },
+    if paste { crossterm::execute!(out, DisableBracketedPaste) } else { Ok(()) },
+    if mouse { crossterm::execute!(out, DisableMouseCapture) } else { Ok(()) },
+    crossterm::execute!(out, LeaveAlternateScreen, cursor::Show),
+    crossterm::terminal::disable_raw_mode(),
+  ];
+  steps.into_iter().collect::<std::io::Result<Vec<()>>>()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // fails the first write and counts the ones after it
+  #[derive(Default)]
+  struct BrokenTerminal {
+    writes: usize,
+  }
+
+  impl Write for BrokenTerminal {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.writes += 1;
+      match self.writes {
+        1 => Err(std::io::Error::other("broken pipe")),
+        _ => Ok(buf.len()),
+      }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_restore_terminal_after_error() {
+    let mut out = BrokenTerminal::default();
+    let restored = restore_terminal(&mut out, true, true, true);
+    assert_eq!(restored.unwrap_err().to_string(), "broken pipe");
+    // the steps after the failed one still ran
+    assert!(out.writes >= 4, "{}", out.writes);
   }
 }
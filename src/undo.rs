@@ -0,0 +1,117 @@
+// undo history for the query editor. tui-textarea keeps one history entry per
+// keystroke, so the editor keeps its own snapshots instead, one per chunk of edits.
+use std::collections::VecDeque;
+
+pub const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+  pub lines: Vec<String>,
+  pub cursor: (usize, usize),
+}
+
+#[derive(Debug)]
+pub struct UndoHistory {
+  undo: VecDeque<Snapshot>,
+  redo: Vec<Snapshot>,
+  max: usize,
+  // whether the next typing edit belongs to the same chunk as the last one
+  coalescing: bool,
+}
+
+impl Default for UndoHistory {
+  fn default() -> Self {
+    Self::new(MAX_HISTORY)
+  }
+}
+
+impl UndoHistory {
+  pub fn new(max: usize) -> Self {
+    Self { undo: VecDeque::new(), redo: vec![], max, coalescing: false }
+  }
+
+  /// Records the state from before an edit. Consecutive typing edits are
+  /// coalesced, so undoing them restores the state before the first one.
+  pub fn record(&mut self, before: Snapshot, typing: bool) {
+    self.redo.clear();
+    if !(typing && self.coalescing) {
+      self.push(before);
+    }
+    self.coalescing = typing;
+  }
+
+  /// Ends the current chunk, e.g. when the cursor moves without editing.
+  pub fn break_chunk(&mut self) {
+    self.coalescing = false;
+  }
+
+  pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+    let snapshot = self.undo.pop_back()?;
+    self.redo.push(current);
+    self.coalescing = false;
+    Some(snapshot)
+  }
+
+  pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+    let snapshot = self.redo.pop()?;
+    self.push(current);
+    self.coalescing = false;
+    Some(snapshot)
+  }
+
+  pub fn clear(&mut self) {
+    self.undo.clear();
+    self.redo.clear();
+    self.coalescing = false;
+  }
+
+  fn push(&mut self, snapshot: Snapshot) {
+    self.undo.push_back(snapshot);
+    if self.undo.len() > self.max {
+      self.undo.pop_front();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn snapshot(text: &str) -> Snapshot {
+    Snapshot { lines: vec![text.to_string()], cursor: (0, text.len()) }
+  }
+
+  #[test]
+  fn test_undo_history() {
+    let mut history = UndoHistory::default();
+    // typing "abc" is one chunk
+    history.record(snapshot(""), true);
+    history.record(snapshot("a"), true);
+    history.record(snapshot("ab"), true);
+    history.break_chunk();
+    history.record(snapshot("abc"), true);
+
+    assert_eq!(history.undo(snapshot("abcd")), Some(snapshot("abc")));
+    assert_eq!(history.undo(snapshot("abc")), Some(snapshot("")));
+    assert_eq!(history.undo(snapshot("")), None);
+    assert_eq!(history.redo(snapshot("")), Some(snapshot("abc")));
+    assert_eq!(history.redo(snapshot("abc")), Some(snapshot("abcd")));
+    assert_eq!(history.redo(snapshot("abcd")), None);
+
+    // a new edit drops the redo stack
+    history.undo(snapshot("abcd"));
+    history.record(snapshot("abc"), false);
+    assert_eq!(history.redo(snapshot("abcx")), None);
+  }
+
+  #[test]
+  fn test_undo_history_is_bounded() {
+    let mut history = UndoHistory::new(2);
+    for text in ["a", "b", "c"] {
+      history.record(snapshot(text), false);
+    }
+    assert_eq!(history.undo(snapshot("d")), Some(snapshot("c")));
+    assert_eq!(history.undo(snapshot("c")), Some(snapshot("b")));
+    assert_eq!(history.undo(snapshot("b")), None);
+  }
+}
@@ -20,6 +20,8 @@ lazy_static! {
     std::env::var(format!("{}_EXPORT", PROJECT_NAME.clone())).ok().map(PathBuf::from);
   pub static ref FAVORITES_FOLDER: Option<PathBuf> =
     std::env::var(format!("{}_FAVORITES", PROJECT_NAME.clone())).ok().map(PathBuf::from);
+  pub static ref SCRIPTS_FOLDER: Option<PathBuf> =
+    std::env::var(format!("{}_SCRIPTS", PROJECT_NAME.clone())).ok().map(PathBuf::from);
   pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
@@ -41,8 +43,12 @@ pub fn initialize_panic_handler() -> Result<()> {
     .into_hooks();
   eyre_hook.install()?;
   std::panic::set_hook(Box::new(move |panic_info| {
-    if let Ok(mut t) = crate::tui::Tui::new()
-      && let Err(r) = t.exit()
+    // a new `Tui` can't be made here, since panics off the runtime's threads
+    // can't spawn its task, so mouse capture and bracketed paste are turned
+    // off whether or not they were on. terminals drop the keyboard
+    // enhancement flags along with the alternate screen.
+    if crossterm::terminal::is_raw_mode_enabled().unwrap_or(true)
+      && let Err(r) = crate::tui::restore_terminal(&mut crate::tui::io(), true, true, false)
     {
       error!("Unable to exit Terminal: {:?}", r);
     }
@@ -121,6 +127,20 @@ pub fn get_export_dir() -> PathBuf {
   }
 }
 
+// .sql files are usually kept alongside a project, so default to wherever rainfrog was started
+pub fn get_scripts_dir() -> PathBuf {
+  if let Some(s) = SCRIPTS_FOLDER.clone() { s } else { std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")) }
+}
+
+/// The name of a connection, or `default`, made safe to use as a file name.
+pub fn connection_file_name(connection_name: Option<&str>) -> String {
+  connection_name
+    .unwrap_or("default")
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+    .collect()
+}
+
 pub fn initialize_logging() -> Result<()> {
   let directory = get_data_dir();
   std::fs::create_dir_all(directory.clone())?;
@@ -179,6 +199,7 @@ pub fn version() -> String {
   let export_dir_path = get_export_dir().display().to_string();
   let data_dir_path = get_data_dir().display().to_string();
   let favorites_dir_path = get_favorites_dir().display().to_string();
+  let scripts_dir_path = get_scripts_dir().display().to_string();
 
   format!(
     "\
@@ -189,6 +210,7 @@ Authors: {author}
 Config directory: {config_dir_path}
 Export directory: {export_dir_path}
 Data directory: {data_dir_path}
-Favorites directory: {favorites_dir_path}"
+Favorites directory: {favorites_dir_path}
+Scripts directory: {scripts_dir_path}"
   )
 }
@@ -78,11 +78,55 @@ impl fmt::Display for Mode {
   }
 }
 
+#[derive(PartialEq, Eq)]
+enum CharClass {
+  Whitespace,
+  Word,
+  Punctuation,
+}
+
+impl CharClass {
+  fn of(c: char) -> Self {
+    if c.is_whitespace() {
+      Self::Whitespace
+    } else if c.is_alphanumeric() || c == '_' {
+      Self::Word
+    } else {
+      Self::Punctuation
+    }
+  }
+}
+
+// The (start, end) char columns, end exclusive, of the word under `col`. Like Vim, a run
+// of whitespace or punctuation counts as a word, and `around` includes the whitespace
+// after the word, or before it if there is none after.
+fn word_object_range(line: &str, col: usize, around: bool) -> (usize, usize) {
+  let chars = line.chars().collect::<Vec<_>>();
+  let Some(current) = chars.get(col) else {
+    return (col, col);
+  };
+  let class = CharClass::of(*current);
+  let start = col - chars[..col].iter().rev().take_while(|c| CharClass::of(**c) == class).count();
+  let end = col + chars[col..].iter().take_while(|c| CharClass::of(**c) == class).count();
+  if !around || class == CharClass::Whitespace {
+    return (start, end);
+  }
+  let trailing = chars[end..].iter().take_while(|c| c.is_whitespace()).count();
+  if trailing > 0 {
+    return (start, end + trailing);
+  }
+  let leading = chars[..start].iter().rev().take_while(|c| c.is_whitespace()).count();
+  (start - leading, end)
+}
+
 // How the Vim emulation state transitions
 pub enum Transition {
   Nop,
   Mode(Mode),
   Pending(Input),
+  // the editor owns the undo history, so these are handled there
+  Undo,
+  Redo,
 }
 
 // State of Vim emulation
@@ -121,6 +165,19 @@ impl Vim {
           Input { key: Key::Char('l'), .. } | Input { key: Key::Right, .. } => {
             textarea.move_cursor(CursorMove::Forward)
           },
+          Input { key: Key::Char('w'), ctrl: false, .. }
+            if matches!(self.mode, Mode::Operator(_))
+              && matches!(self.pending, Input { key: Key::Char('i' | 'a'), ctrl: false, .. }) =>
+          {
+            // `iw` and `aw` text objects
+            let (row, col) = textarea.cursor();
+            let line = textarea.lines().get(row).map_or("", |line| line.as_str());
+            let (start, end) = word_object_range(line, col, self.pending.key == Key::Char('a'));
+            textarea.cancel_selection();
+            textarea.move_cursor(CursorMove::Jump(row as u16, start as u16));
+            textarea.start_selection();
+            textarea.move_cursor(CursorMove::Jump(row as u16, end as u16));
+          },
           Input { key: Key::Char('w'), .. } => textarea.move_cursor(CursorMove::WordForward),
           Input { key: Key::Char('e'), ctrl: false, .. } if matches!(self.mode, Mode::Operator(_)) => {
             textarea.move_cursor(CursorMove::WordForward) // `e` behaves like `w` in operator-pending mode
@@ -152,13 +209,11 @@ impl Vim {
             textarea.paste();
             return Transition::Mode(Mode::Normal);
           },
-          Input { key: Key::Char('u'), ctrl: false, .. } => {
-            textarea.undo();
-            return Transition::Mode(Mode::Normal);
+          Input { key: Key::Char('u'), ctrl: false, .. } | Input { key: Key::Char('z'), ctrl: true, .. } => {
+            return Transition::Undo;
           },
           Input { key: Key::Char('r'), ctrl: true, .. } => {
-            textarea.redo();
-            return Transition::Mode(Mode::Normal);
+            return Transition::Redo;
           },
           Input { key: Key::Char('r'), ctrl: false, .. } => {
             return Transition::Mode(Mode::Replace);
@@ -193,19 +248,14 @@ impl Vim {
             self.send_copy_action_with_text(textarea.yank_text());
             return Transition::Mode(Mode::Normal);
           },
+          Input { key: Key::Char('i' | 'a'), ctrl: false, .. } if matches!(self.mode, Mode::Operator(_)) => {
+            // wait for the text object, e.g. the `w` in `ciw`
+            return Transition::Pending(input);
+          },
           Input { key: Key::Char('i'), .. } => {
             textarea.cancel_selection();
             return Transition::Mode(Mode::Insert);
           },
-          Input { key: Key::Char('a'), ctrl: false, .. }
-            if matches!(self.mode, Mode::Operator('d')) || matches!(self.mode, Mode::Operator('y')) =>
-          {
-            textarea.cancel_selection();
-            textarea.move_cursor(CursorMove::Forward);
-            textarea.move_cursor(CursorMove::WordBack);
-            textarea.start_selection();
-            return Transition::Nop;
-          },
           Input { key: Key::Char('a'), .. } => {
             textarea.cancel_selection();
             textarea.move_cursor(CursorMove::Forward);
@@ -381,3 +431,33 @@ impl Vim {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_word_object_range() {
+    let line = "select  id, name from users";
+    let test_cases = vec![
+      // inner word, from the start, middle and end of the word
+      (0, false, (0, 6)),
+      (3, false, (0, 6)),
+      (5, false, (0, 6)),
+      // whitespace and punctuation are words of their own
+      (6, false, (6, 8)),
+      (10, false, (10, 11)),
+      // around includes trailing whitespace
+      (13, true, (12, 17)),
+      // or leading whitespace, if there is none after
+      (8, true, (6, 10)),
+      (24, true, (21, 27)),
+      // past the end of the line
+      (40, false, (40, 40)),
+    ];
+
+    for (col, around, expected) in test_cases {
+      assert_eq!(word_object_range(line, col, around), expected, "Failed for col {col}, around {around}");
+    }
+  }
+}